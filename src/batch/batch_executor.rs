@@ -24,9 +24,10 @@ use scoped_pool::Pool;
 use crate::batch::BatchRead;
 use crate::cluster::partition::Partition;
 use crate::cluster::{Cluster, Node};
-use crate::commands::BatchReadCommand;
+use crate::commands::{BatchReadCommand, CommandType};
 use crate::errors::{Error, Result};
 use crate::policy::{BatchPolicy, Concurrency};
+use crate::trace;
 use crate::Key;
 
 pub struct BatchExecutor {
@@ -55,30 +56,43 @@ impl BatchExecutor {
                 BatchReadCommand::new(policy, node, batch_reads.clone(), offsets)
             })
             .collect();
-        self.execute_batch_jobs(jobs, &policy.concurrency)?;
+        self.execute_batch_jobs(jobs, policy)?;
         batch_reads.into_inner()
     }
 
     fn execute_batch_jobs(
         &self,
         mut jobs: Vec<BatchReadCommand>,
-        concurrency: &Concurrency,
+        policy: &BatchPolicy,
     ) -> Result<()> {
-        let threads = match *concurrency {
+        let threads = match policy.concurrency {
             Concurrency::Sequential => 1,
             Concurrency::Parallel => jobs.len(),
             Concurrency::MaxThreads(max) => cmp::min(max, jobs.len()),
         };
         let jobs = Arc::new(Mutex::new(jobs.iter_mut()));
         let last_err: Arc<Mutex<Option<Error>>> = Arc::default();
+        let cluster = &self.cluster;
+        let parent = &trace::Span::current();
         self.thread_pool.scoped(|scope| {
             for _ in 0..threads {
                 let last_err = last_err.clone();
                 let jobs = jobs.clone();
                 scope.execute(move || {
+                    let _enter = parent.enter();
                     let next_job = || jobs.lock().next();
                     while let Some(cmd) = next_job() {
-                        if let Err(err) = cmd.execute() {
+                        let node = cmd.node.clone();
+                        let namespace = cmd.namespace().to_owned();
+                        let result = cluster.observe(
+                            CommandType::Batch,
+                            &namespace,
+                            "",
+                            &node,
+                            policy,
+                            || cmd.execute(),
+                        );
+                        if let Err(err) = result {
                             *last_err.lock() = Some(err);
                             jobs.lock().all(|_| true); // consume the remaining jobs
                         };
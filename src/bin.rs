@@ -13,25 +13,83 @@
 // License for the specific language governing permissions and limitations under
 // the License.
 
+use crate::errors::{ErrorKind, Result};
 use crate::value::Value;
 #[cfg(feature = "serialization")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::convert::From;
 
+/// Maximum length of a bin name in bytes, as enforced by the server.
+pub const MAX_BIN_NAME_LENGTH: usize = 15;
+
 /// Container object for a record bin, comprising a name and a value.
+///
+/// The name is either borrowed or owned, so bins can be built from string literals without
+/// allocating, or stored in structs and sent across threads as an `OwnedBin`.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Bin<'a> {
     /// Bin name
-    pub name: &'a str,
+    pub name: Cow<'a, str>,
 
     /// Bin value
     pub value: Value,
 }
 
+/// Bin that owns its name.
+pub type OwnedBin = Bin<'static>;
+
 impl<'a> Bin<'a> {
     /// Construct a new bin given a name and a value.
-    pub const fn new(name: &'a str, val: Value) -> Self {
-        Bin { name, value: val }
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name is longer than `MAX_BIN_NAME_LENGTH` bytes; use `Bin::try_new` for
+    /// names that are not known to be valid.
+    pub fn new<N: Into<Cow<'a, str>>>(name: N, val: Value) -> Self {
+        match Bin::try_new(name, val) {
+            Ok(bin) => bin,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Construct a new bin given a name and a value. Returns an `InvalidArgument` error if the
+    /// name is longer than `MAX_BIN_NAME_LENGTH` bytes.
+    pub fn try_new<N: Into<Cow<'a, str>>>(name: N, val: Value) -> Result<Self> {
+        let bin = Bin {
+            name: name.into(),
+            value: val,
+        };
+        bin.validate()?;
+        Ok(bin)
+    }
+
+    /// Name of the bin.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Convert into a bin that owns its name.
+    pub fn into_owned(self) -> OwnedBin {
+        Bin {
+            name: Cow::Owned(self.name.into_owned()),
+            value: self.value,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        validate_name(&self.name)
+    }
+}
+
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    if name.len() > MAX_BIN_NAME_LENGTH {
+        bail!(ErrorKind::InvalidArgument(format!(
+            "Bin name '{}' is longer than {} bytes",
+            name, MAX_BIN_NAME_LENGTH
+        )));
     }
+    Ok(())
 }
 
 impl<'a> AsRef<Bin<'a>> for Bin<'a> {
@@ -53,7 +111,7 @@ macro_rules! as_bin {
 
 /// Specify which, if any, bins to return in read operations.
 #[derive(Debug, Clone, PartialEq, Eq)]
-#[cfg_attr(feature = "serialization", derive(Serialize))]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub enum Bins {
     /// Read all bins.
     All,
@@ -128,27 +186,32 @@ impl<'a> From<[&'a str; 6]> for Bins {
 
 impl<'a> From<Bin<'a>> for Bins {
     fn from(bins: Bin<'a>) -> Self {
-        Bins::Some(vec![bins.name.to_owned()])
+        Bins::Some(vec![bins.name.into_owned()])
     }
 }
 
 impl<'a> From<Vec<Bin<'a>>> for Bins {
     fn from(bins: Vec<Bin<'a>>) -> Self {
-        let bins = bins.into_iter().map(|x| String::from(x.name)).collect();
+        let bins = bins.into_iter().map(|x| x.name.into_owned()).collect();
         Bins::Some(bins)
     }
 }
 
 impl<'a> From<&'a Vec<Bin<'a>>> for Bins {
     fn from(bins: &'a Vec<Bin<'a>>) -> Self {
-        let bins = bins.clone().into_iter().map(|x| String::from(x.name)).collect();
+        let bins = bins.iter().map(|x| x.name.to_string()).collect();
         Bins::Some(bins)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Bins, From};
+    use std::borrow::Cow;
+    use std::thread;
+
+    use super::{Bin, Bins, From, OwnedBin};
+    use crate::errors::{Error, ErrorKind};
+    use crate::operations;
 
     #[test]
     fn into_bins() {
@@ -157,4 +220,44 @@ mod tests {
 
         assert_eq!(expected, Bins::from(["a", "b", "c"]));
     }
+
+    #[test]
+    fn names() {
+        let name = String::from("owned");
+        let borrowed = Bin::new(name.as_str(), as_val!(1));
+        assert!(matches!(borrowed.name, Cow::Borrowed(_)));
+        let owned: OwnedBin = borrowed.clone().into_owned();
+        assert!(matches!(owned.name, Cow::Owned(_)));
+        assert_eq!(owned, borrowed);
+        drop(name);
+
+        let bin = thread::spawn(move || owned).join().unwrap();
+        assert_eq!(bin.name(), "owned");
+        assert_eq!(Bin::new(String::from("a"), as_val!(1)), as_bin!("a", 1));
+    }
+
+    #[test]
+    fn name_length() {
+        assert!(Bin::try_new("fifteen_bytes__", as_val!(1)).is_ok());
+        match Bin::try_new("sixteen_bytes___", as_val!(1)) {
+            Err(Error(ErrorKind::InvalidArgument(msg), _)) => assert!(msg.contains("15 bytes")),
+            _ => panic!("expected an InvalidArgument error"),
+        }
+
+        // operations check the name too, whether or not they carry a bin
+        assert!(operations::get_bin("sixteen_bytes___")
+            .estimate_size()
+            .is_err());
+        let bin = Bin {
+            name: "sixteen_bytes___".into(),
+            value: as_val!(1),
+        };
+        assert!(operations::put(&bin).estimate_size().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "longer than 15 bytes")]
+    fn new_rejects_long_name() {
+        let _ = as_bin!("sixteen_bytes___", 1);
+    }
 }
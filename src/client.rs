@@ -13,6 +13,7 @@
 // License for the specific language governing permissions and limitations under
 // the License.
 
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::Path;
@@ -26,17 +27,20 @@ use scoped_pool::Pool;
 use crate::batch::BatchExecutor;
 use crate::cluster::{Cluster, Node};
 use crate::commands::{
-    DeleteCommand, ExecuteUDFCommand, ExistsCommand, OperateCommand, QueryCommand, ReadCommand,
-    ScanCommand, TouchCommand, WriteCommand,
+    CommandType, DeleteCommand, ExecuteUDFCommand, ExistsCommand, OperateCommand, QueryCommand,
+    ReadCommand, ScanCommand, TouchCommand, WriteCommand,
 };
 use crate::errors::{ErrorKind, Result, ResultExt};
+use crate::expressions::FilterExpression;
 use crate::net::ToHosts;
-use crate::operations::{Operation, OperationType};
+use crate::operations::exp::{self as exp_ops, ExpReadFlags, ExpWriteFlags};
+use crate::operations::{Operation, OperationBin, OperationType};
 use crate::policy::{BatchPolicy, ClientPolicy, QueryPolicy, ReadPolicy, ScanPolicy, WritePolicy};
 use crate::task::{IndexTask, RegisterTask};
+use crate::trace;
 use crate::{
-    BatchRead, Bin, Bins, CollectionIndexType, IndexType, Key, Record, Recordset, ResultCode,
-    Statement, UDFLang, Value,
+    BatchRead, Bin, Bins, ClusterStats, CollectionIndexType, IndexType, Key, OpResult,
+    OpReturnType, OperateResult, Record, Recordset, ResultCode, Statement, UDFLang, Value,
 };
 
 /// Instantiate a Client instance to access an Aerospike database cluster and perform database
@@ -136,6 +140,43 @@ impl Client {
         self.cluster.nodes()
     }
 
+    /// Returns a snapshot of the connection pool, command and cluster tend statistics. Taking a
+    /// snapshot only reads atomic counters and briefly locks each connection pool, so it is cheap
+    /// enough to be called periodically by a metrics collector.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use aerospike::*;
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let stats = client.stats();
+    /// for node in &stats.nodes {
+    ///     println!("{}: {} of {} connections in use, {} rejected",
+    ///         node.name, node.in_use_connections, node.open_connections,
+    ///         node.connection_rejections);
+    /// }
+    /// println!("tend cycles: {}", stats.tend_count);
+    /// ```
+    pub fn stats(&self) -> ClusterStats {
+        self.cluster.stats()
+    }
+
+    /// Returns the client's metrics in the [OpenMetrics](https://openmetrics.io/) text exposition
+    /// format, ready to be served to a Prometheus compatible scraper.
+    ///
+    /// The output contains the `aerospike_client_command_duration_seconds` latency histogram for
+    /// every command type (`get`, `put`, `delete`, `touch`, `exists`, `operate`, `batch`, `scan`,
+    /// `query`, `udf` and `info`), labelled by `namespace`, `node` and `result`, followed by the
+    /// connection pool, command and tend counters also returned by `stats()`.
+    ///
+    /// Requires the `metrics` feature.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_text(&self) -> String {
+        self.cluster.metrics().render(&self.cluster.stats())
+    }
+
     /// Read record for the specified key. Depending on the bins value provided, all record bins,
     /// only selected record bins or only the record headers will be returned. The policy can be
     /// used to specify timeouts.
@@ -187,7 +228,8 @@ impl Client {
     {
         let bins = bins.into();
         let mut command = ReadCommand::new(policy, self.cluster.clone(), key, bins);
-        command.execute()?;
+        self.cluster
+            .observe_key(CommandType::Get, key, policy, |node| command.execute(node))?;
         Ok(command.record.unwrap())
     }
 
@@ -230,8 +272,16 @@ impl Client {
         policy: &BatchPolicy,
         batch_reads: Vec<BatchRead<'a>>,
     ) -> Result<Vec<BatchRead<'a>>> {
+        let namespace = batch_reads
+            .first()
+            .map_or_else(String::new, |batch_read| batch_read.key.namespace.clone());
+        let span = trace::command_span(CommandType::Batch, &namespace, "", policy);
+        let _enter = span.enter();
+
         let executor = BatchExecutor::new(self.cluster.clone(), self.thread_pool.clone());
-        executor.execute_batch_read(policy, batch_reads)
+        let result = executor.execute_batch_read(policy, batch_reads);
+        trace::record_result(&result);
+        result
     }
 
     /// Write record bin(s). The policy specifies the transaction timeout, record expiration and
@@ -283,7 +333,8 @@ impl Client {
             bins,
             OperationType::Write,
         );
-        command.execute()
+        self.cluster
+            .observe_key(CommandType::Put, key, policy, |node| command.execute(node))
     }
 
     /// Add integer bin values to existing record bin values. The policy specifies the transaction
@@ -316,7 +367,8 @@ impl Client {
     ) -> Result<()> {
         let mut command =
             WriteCommand::new(policy, self.cluster.clone(), key, bins, OperationType::Incr);
-        command.execute()
+        self.cluster
+            .observe_key(CommandType::Put, key, policy, |node| command.execute(node))
     }
 
     /// Append bin string values to existing record bin values. The policy specifies the
@@ -335,7 +387,8 @@ impl Client {
             bins,
             OperationType::Append,
         );
-        command.execute()
+        self.cluster
+            .observe_key(CommandType::Put, key, policy, |node| command.execute(node))
     }
 
     /// Prepend bin string values to existing record bin values. The policy specifies the
@@ -354,7 +407,8 @@ impl Client {
             bins,
             OperationType::Prepend,
         );
-        command.execute()
+        self.cluster
+            .observe_key(CommandType::Put, key, policy, |node| command.execute(node))
     }
 
     /// Delete record for specified key. The policy specifies the transaction timeout.
@@ -378,7 +432,10 @@ impl Client {
     /// ```
     pub fn delete(&self, policy: &WritePolicy, key: &Key) -> Result<bool> {
         let mut command = DeleteCommand::new(policy, self.cluster.clone(), key);
-        command.execute()?;
+        self.cluster
+            .observe_key(CommandType::Delete, key, policy, |node| {
+                command.execute(node)
+            })?;
         Ok(command.existed)
     }
 
@@ -404,13 +461,19 @@ impl Client {
     /// ```
     pub fn touch(&self, policy: &WritePolicy, key: &Key) -> Result<()> {
         let mut command = TouchCommand::new(policy, self.cluster.clone(), key);
-        command.execute()
+        self.cluster
+            .observe_key(CommandType::Touch, key, policy, |node| {
+                command.execute(node)
+            })
     }
 
     /// Determine if a record key exists. The policy can be used to specify timeouts.
     pub fn exists(&self, policy: &WritePolicy, key: &Key) -> Result<bool> {
         let mut command = ExistsCommand::new(policy, self.cluster.clone(), key);
-        command.execute()?;
+        self.cluster
+            .observe_key(CommandType::Exists, key, policy, |node| {
+                command.execute(node)
+            })?;
         Ok(command.exists)
     }
 
@@ -443,10 +506,177 @@ impl Client {
     /// ```
     pub fn operate(&self, policy: &WritePolicy, key: &Key, ops: &[Operation]) -> Result<Record> {
         let mut command = OperateCommand::new(policy, self.cluster.clone(), key, ops);
-        command.execute()?;
+        self.cluster
+            .observe_key(CommandType::Operate, key, policy, |node| {
+                command.execute(node)
+            })?;
         Ok(command.read_command.record.unwrap())
     }
 
+    /// Perform multiple read/write operations on a single key, like `operate`, but return one
+    /// result per operation: `results[i]` holds the result of `ops[i]`, even if several operations
+    /// apply to the same bin. Operations that do not return a value have a `nil` result. The typed
+    /// getters of each `OpResult` check the value against the return type declared by the
+    /// operation.
+    ///
+    /// The policy's `respond_per_each_op` setting is implied. Operations reading all bins
+    /// (`operations::get()`) or only the record header (`operations::get_header()`) cannot be
+    /// mapped to a single result and are rejected with an `InvalidArgument` error.
+    ///
+    /// # Examples
+    ///
+    /// Increment a counter, read it back and select the three highest ranked values of a list bin,
+    /// all in one database call.
+    ///
+    /// ```rust
+    /// # use aerospike::*;
+    /// # use aerospike::operations::lists;
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let bin = as_bin!("a", 1);
+    /// let ops = vec![
+    ///     operations::add(&bin),
+    ///     operations::get_bin("a"),
+    ///     lists::get_by_rank_range("l", -3, lists::ListReturnType::Values),
+    /// ];
+    /// let result = client.operate_typed(&WritePolicy::default(), &key, &ops).unwrap();
+    /// println!("a = {}", result.results[1].as_int().unwrap());
+    /// println!("top 3 = {:?}", result.results[2].as_list().unwrap());
+    /// ```
+    pub fn operate_typed(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        ops: &[Operation],
+    ) -> Result<OperateResult> {
+        for op in ops {
+            if let (OperationType::Read, OperationBin::All | OperationBin::None) = (op.op, &op.bin)
+            {
+                bail!(ErrorKind::InvalidArgument(
+                    "Operations reading all bins or the record header are not supported by \
+                     operate_typed"
+                        .to_string()
+                ));
+            }
+        }
+
+        let mut policy = policy.clone();
+        policy.respond_per_each_op = true;
+        let mut command = OperateCommand::new(&policy, self.cluster.clone(), key, ops);
+        command.read_command.op_results = Some(Vec::with_capacity(ops.len()));
+        self.cluster
+            .observe_key(CommandType::Operate, key, &policy, |node| {
+                command.execute(node)
+            })?;
+
+        let op_results = command.read_command.op_results.take().unwrap_or_default();
+        if op_results.len() != ops.len() {
+            bail!(ErrorKind::BadResponse(format!(
+                "Expected {} operation results, got {}",
+                ops.len(),
+                op_results.len()
+            )));
+        }
+        let results = op_results
+            .into_iter()
+            .zip(ops)
+            .map(|((bin, value), op)| OpResult {
+                bin,
+                value,
+                return_type: OpReturnType::of(op),
+            })
+            .collect();
+        Ok(OperateResult {
+            record: command.read_command.record.take().unwrap(),
+            results,
+        })
+    }
+
+    /// Evaluate expressions against a record on the server and return the results by name, like
+    /// computed columns of a projection. Each `(name, expression)` pair is sent as an
+    /// `operations::exp::read_exp` operation; the returned map holds an entry for every name,
+    /// which is `nil` if the expression evaluated to nil.
+    ///
+    /// Names must be unique; duplicate names or an empty list of expressions are rejected with an
+    /// `InvalidArgument` error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use aerospike::*;
+    /// use aerospike::expressions::{int_bin, int_val, num_add, num_mul};
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let exps = [
+    ///     ("total", num_add(vec![int_bin("a".to_string()), int_bin("b".to_string())])),
+    ///     ("months", num_mul(vec![int_bin("age".to_string()), int_val(12)])),
+    /// ];
+    /// let results = client.get_with_expressions(&WritePolicy::default(), &key, &exps).unwrap();
+    /// println!("total: {}, months: {}", results["total"], results["months"]);
+    /// ```
+    pub fn get_with_expressions(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        exps: &[(&str, FilterExpression)],
+    ) -> Result<HashMap<String, Value>> {
+        check_expression_names(exps)?;
+        let ops: Vec<Operation> = exps
+            .iter()
+            .map(|&(name, ref exp)| exp_ops::read_exp(name, exp, ExpReadFlags::Default))
+            .collect();
+        let result = self.operate_typed(policy, key, &ops)?;
+        Ok(exps
+            .iter()
+            .zip(result.results)
+            .map(|(&(name, _), result)| (name.to_string(), result.value))
+            .collect())
+    }
+
+    /// Write the results of expressions evaluated on the server to the named bins of a record.
+    /// Each `(bin, expression)` pair is sent as an `operations::exp::write_exp` operation with the
+    /// given flags, e.g. `ExpWriteFlags::UpdateOnly` to only update existing bins or
+    /// `ExpWriteFlags::AllowDelete` to delete bins whose expression evaluates to nil.
+    ///
+    /// Bin names must be unique; duplicate names or an empty list of expressions are rejected
+    /// with an `InvalidArgument` error. A write denied by its flags fails with
+    /// `ResultCode::BinExistsError`, `ResultCode::BinNotFound` or `ResultCode::OpNotApplicable`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use aerospike::*;
+    /// use aerospike::expressions::{int_bin, int_val, num_add};
+    /// use aerospike::operations::exp::ExpWriteFlags;
+    ///
+    /// # let hosts = std::env::var("AEROSPIKE_HOSTS").unwrap();
+    /// # let client = Client::new(&ClientPolicy::default(), &hosts).unwrap();
+    /// let key = as_key!("test", "test", "mykey");
+    /// let bins = [("visits", num_add(vec![int_bin("visits".to_string()), int_val(1)]))];
+    /// client
+    ///     .put_computed(&WritePolicy::default(), &key, &bins, ExpWriteFlags::UpdateOnly)
+    ///     .unwrap();
+    /// ```
+    pub fn put_computed(
+        &self,
+        policy: &WritePolicy,
+        key: &Key,
+        bins: &[(&str, FilterExpression)],
+        flags: ExpWriteFlags,
+    ) -> Result<()> {
+        check_expression_names(bins)?;
+        let ops: Vec<Operation> = bins
+            .iter()
+            .map(|&(name, ref exp)| exp_ops::write_exp(name, exp, flags))
+            .collect();
+        self.operate(policy, key, &ops)?;
+        Ok(())
+    }
+
     /// Register a package containing user-defined functions (UDF) with the cluster. This
     /// asynchronous server call will return before the command is complete. The client registers
     /// the UDF package with a single, random cluster node; from there a copy will get distributed
@@ -506,7 +736,11 @@ impl Client {
             language
         );
         let node = self.cluster.get_random_node()?;
-        let response = node.info(policy.base_policy.timeout, &[&cmd])?;
+        let response = self
+            .cluster
+            .observe(CommandType::Info, "", "", &node, policy, || {
+                node.info(policy.base_policy.timeout, &[&cmd])
+            })?;
 
         if let Some(msg) = response.get("error") {
             let msg = base64::decode(msg)?;
@@ -557,7 +791,11 @@ impl Client {
         let cmd = format!("udf-remove:filename={}.{};", udf_name, language);
         let node = self.cluster.get_random_node()?;
         // Sample response: {"udf-remove:filename=file_name.LUA;": "ok"}
-        let response = node.info(policy.base_policy.timeout, &[&cmd])?;
+        let response = self
+            .cluster
+            .observe(CommandType::Info, "", "", &node, policy, || {
+                node.info(policy.base_policy.timeout, &[&cmd])
+            })?;
 
         match response.get(&cmd).map(String::as_str) {
             Some("ok") => Ok(()),
@@ -584,7 +822,8 @@ impl Client {
             args,
         );
 
-        command.execute()?;
+        self.cluster
+            .observe_key(CommandType::Udf, key, policy, |node| command.execute(node))?;
 
         let record = command.read_command.record.unwrap();
 
@@ -642,6 +881,9 @@ impl Client {
     where
         T: Into<Bins>,
     {
+        let span = trace::command_span(CommandType::Scan, namespace, set_name, policy);
+        let _enter = span.enter();
+
         let bins = bins.into();
         let nodes = self.cluster.nodes();
         let recordset = Arc::new(Recordset::new(policy.record_queue_size, nodes.len()));
@@ -653,12 +895,31 @@ impl Client {
             let namespace = namespace.to_owned();
             let set_name = set_name.to_owned();
             let bins = bins.clone();
+            let cluster = self.cluster.clone();
+            let parent = span.clone();
 
             thread::spawn(move || {
+                let _enter = parent.enter();
                 let mut command = ScanCommand::new(
-                    &policy, node, &namespace, &set_name, bins, recordset, partitions,
+                    &policy,
+                    node.clone(),
+                    &namespace,
+                    &set_name,
+                    bins,
+                    recordset.clone(),
+                    partitions,
                 );
-                command.execute().unwrap();
+                if let Err(err) = cluster.observe(
+                    CommandType::Scan,
+                    &namespace,
+                    &set_name,
+                    &node,
+                    &policy,
+                    || command.execute(),
+                ) {
+                    // hand the error to the consumer of the record set
+                    recordset.push_error(err);
+                }
             });
         }
         Ok(recordset)
@@ -680,6 +941,9 @@ impl Client {
     where
         T: Into<Bins>,
     {
+        let span = trace::command_span(CommandType::Scan, namespace, set_name, policy);
+        let _enter = span.enter();
+
         let partitions = self.cluster.node_partitions(node.as_ref(), namespace);
         let bins = bins.into();
         let recordset = Arc::new(Recordset::new(policy.record_queue_size, 1));
@@ -687,18 +951,31 @@ impl Client {
         let policy = policy.to_owned();
         let namespace = namespace.to_owned();
         let set_name = set_name.to_owned();
+        let cluster = self.cluster.clone();
+        let parent = span.clone();
 
         self.thread_pool.spawn(move || {
+            let _enter = parent.enter();
             let mut command = ScanCommand::new(
                 &policy,
-                node,
+                node.clone(),
                 &namespace,
                 &set_name,
                 bins,
-                t_recordset,
+                t_recordset.clone(),
                 partitions,
             );
-            command.execute().unwrap();
+            if let Err(err) = cluster.observe(
+                CommandType::Scan,
+                &namespace,
+                &set_name,
+                &node,
+                &policy,
+                || command.execute(),
+            ) {
+                // hand the error to the consumer of the record set
+                t_recordset.push_error(err);
+            }
         });
 
         Ok(recordset)
@@ -727,6 +1004,14 @@ impl Client {
     /// }
     /// ```
     pub fn query(&self, policy: &QueryPolicy, statement: Statement) -> Result<Arc<Recordset>> {
+        let span = trace::command_span(
+            CommandType::Query,
+            &statement.namespace,
+            &statement.set_name,
+            policy,
+        );
+        let _enter = span.enter();
+
         statement.validate()?;
         let statement = Arc::new(statement);
 
@@ -740,11 +1025,31 @@ impl Client {
             let t_recordset = recordset.clone();
             let policy = policy.to_owned();
             let statement = statement.clone();
+            let cluster = self.cluster.clone();
+            let parent = span.clone();
 
             self.thread_pool.spawn(move || {
-                let mut command =
-                    QueryCommand::new(&policy, node, statement, t_recordset, partitions);
-                command.execute().unwrap();
+                let _enter = parent.enter();
+                let namespace = statement.namespace.clone();
+                let set_name = statement.set_name.clone();
+                let mut command = QueryCommand::new(
+                    &policy,
+                    node.clone(),
+                    statement,
+                    t_recordset.clone(),
+                    partitions,
+                );
+                if let Err(err) = cluster.observe(
+                    CommandType::Query,
+                    &namespace,
+                    &set_name,
+                    &node,
+                    &policy,
+                    || command.execute(),
+                ) {
+                    // hand the error to the consumer of the record set
+                    t_recordset.push_error(err);
+                }
             });
         }
         Ok(recordset)
@@ -759,6 +1064,14 @@ impl Client {
         node: Arc<Node>,
         statement: Statement,
     ) -> Result<Arc<Recordset>> {
+        let span = trace::command_span(
+            CommandType::Query,
+            &statement.namespace,
+            &statement.set_name,
+            policy,
+        );
+        let _enter = span.enter();
+
         statement.validate()?;
 
         let recordset = Arc::new(Recordset::new(policy.record_queue_size, 1));
@@ -768,10 +1081,31 @@ impl Client {
         let partitions = self
             .cluster
             .node_partitions(node.as_ref(), &statement.namespace);
+        let cluster = self.cluster.clone();
+        let parent = span.clone();
 
         self.thread_pool.spawn(move || {
-            let mut command = QueryCommand::new(&policy, node, statement, t_recordset, partitions);
-            command.execute().unwrap();
+            let _enter = parent.enter();
+            let namespace = statement.namespace.clone();
+            let set_name = statement.set_name.clone();
+            let mut command = QueryCommand::new(
+                &policy,
+                node.clone(),
+                statement,
+                t_recordset.clone(),
+                partitions,
+            );
+            if let Err(err) = cluster.observe(
+                CommandType::Query,
+                &namespace,
+                &set_name,
+                &node,
+                &policy,
+                || command.execute(),
+            ) {
+                // hand the error to the consumer of the record set
+                t_recordset.push_error(err);
+            }
         });
 
         Ok(recordset)
@@ -810,7 +1144,7 @@ impl Client {
             cmd.push_str(&format!("{}", before_nanos));
         }
 
-        self.send_info_cmd(&cmd, policy)
+        self.send_info_cmd(&cmd, namespace, policy)
             .chain_err(|| "Error truncating ns/set")
     }
 
@@ -882,7 +1216,7 @@ impl Client {
              priority=normal",
             namespace, set_name, index_name, cit_str, bin_name, index_type
         );
-        self.send_info_cmd(&cmd, policy)
+        self.send_info_cmd(&cmd, namespace, policy)
             .chain_err(|| "Error creating index")
     }
 
@@ -903,13 +1237,17 @@ impl Client {
             "sindex-delete:ns={};{}indexname={}",
             namespace, set_name, index_name
         );
-        self.send_info_cmd(&cmd, policy)
+        self.send_info_cmd(&cmd, namespace, policy)
             .chain_err(|| "Error dropping index")
     }
 
-    fn send_info_cmd(&self, cmd: &str, policy: &WritePolicy) -> Result<()> {
+    fn send_info_cmd(&self, cmd: &str, namespace: &str, policy: &WritePolicy) -> Result<()> {
         let node = self.cluster.get_random_node()?;
-        let response = node.info(policy.base_policy.timeout, &[cmd])?;
+        let response =
+            self.cluster
+                .observe(CommandType::Info, namespace, "", &node, policy, || {
+                    node.info(policy.base_policy.timeout, &[cmd])
+                })?;
 
         if let Some(v) = response.values().next() {
             if v.to_uppercase() == "OK" {
@@ -925,3 +1263,22 @@ impl Client {
         ))
     }
 }
+
+// Expression helpers map each expression to a distinct bin name.
+fn check_expression_names(exps: &[(&str, FilterExpression)]) -> Result<()> {
+    if exps.is_empty() {
+        bail!(ErrorKind::InvalidArgument(
+            "At least one expression is required".to_string()
+        ));
+    }
+    let mut names = HashSet::with_capacity(exps.len());
+    for &(name, _) in exps {
+        if !names.insert(name) {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Duplicate expression name '{}'",
+                name
+            )));
+        }
+    }
+    Ok(())
+}
@@ -19,7 +19,7 @@ pub mod partition;
 pub mod partition_tokenizer;
 
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::mpsc::{Receiver, Sender, TryRecvError};
 use std::sync::Arc;
@@ -35,9 +35,15 @@ use self::node_validator::NodeValidator;
 use self::partition::Partition;
 use self::partition_tokenizer::PartitionTokenizer;
 
+use crate::commands::CommandType;
 use crate::errors::{ErrorKind, Result};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
 use crate::net::Host;
-use crate::policy::ClientPolicy;
+use crate::policy::{ClientPolicy, Policy};
+use crate::stats::ClusterStats;
+use crate::trace;
+use crate::Key;
 
 // Cluster encapsulates the aerospike cluster nodes and manages
 // them.
@@ -62,6 +68,15 @@ pub struct Cluster {
 
     tend_channel: Mutex<Sender<()>>,
     closed: AtomicBool,
+
+    // Tend statistics; durations are kept in microseconds.
+    tend_count: AtomicUsize,
+    tend_errors: AtomicUsize,
+    last_tend_micros: AtomicU64,
+    total_tend_micros: AtomicU64,
+
+    #[cfg(feature = "metrics")]
+    metrics: Metrics,
 }
 
 impl Cluster {
@@ -79,6 +94,14 @@ impl Cluster {
 
             tend_channel: Mutex::new(tx),
             closed: AtomicBool::new(false),
+
+            tend_count: AtomicUsize::new(0),
+            tend_errors: AtomicUsize::new(0),
+            last_tend_micros: AtomicU64::new(0),
+            total_tend_micros: AtomicU64::new(0),
+
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
         });
 
         // try to seed connections for first use
@@ -131,6 +154,24 @@ impl Cluster {
     }
 
     fn tend(&self) -> Result<()> {
+        let span = trace::tend_span();
+        let _enter = span.enter();
+
+        let start = Instant::now();
+        let res = self.tend_nodes();
+        let micros = start.elapsed().as_micros() as u64;
+
+        self.tend_count.fetch_add(1, Ordering::Relaxed);
+        if res.is_err() {
+            self.tend_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last_tend_micros.store(micros, Ordering::Relaxed);
+        self.total_tend_micros.fetch_add(micros, Ordering::Relaxed);
+
+        res
+    }
+
+    fn tend_nodes(&self) -> Result<()> {
         let mut nodes = self.nodes();
 
         // All node additions/deletions are performed in tend thread.
@@ -148,6 +189,9 @@ impl Cluster {
         for node in nodes {
             let old_gen = node.partition_generation();
             if node.is_active() {
+                let span = trace::refresh_span(&node);
+                let _enter = span.enter();
+
                 match node.refresh(self.aliases()) {
                     Ok(friends) => {
                         refresh_count += 1;
@@ -177,6 +221,7 @@ impl Cluster {
         // Remove nodes in a batch.
         let remove_list = self.find_nodes_to_remove(refresh_count);
         self.remove_nodes_and_aliases(remove_list);
+        trace::record_nodes(self.nodes().len());
 
         Ok(())
     }
@@ -479,6 +524,103 @@ impl Cluster {
         self.set_nodes(node_array)
     }
 
+    pub fn stats(&self) -> ClusterStats {
+        ClusterStats {
+            nodes: self.nodes().iter().map(|node| node.stats()).collect(),
+            tend_count: self.tend_count.load(Ordering::Relaxed),
+            tend_errors: self.tend_errors.load(Ordering::Relaxed),
+            last_tend_duration: Duration::from_micros(
+                self.last_tend_micros.load(Ordering::Relaxed),
+            ),
+            total_tend_duration: Duration::from_micros(
+                self.total_tend_micros.load(Ordering::Relaxed),
+            ),
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    pub const fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    // Executes a command within a command span and records its latency in the command metrics.
+    pub fn observe<T, F>(
+        &self,
+        command: CommandType,
+        namespace: &str,
+        set_name: &str,
+        node: &Node,
+        policy: &dyn Policy,
+        f: F,
+    ) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        self.observe_with(
+            command,
+            namespace,
+            set_name,
+            policy,
+            |()| node.name().to_owned(),
+            |()| f(),
+        )
+    }
+
+    // Executes a single-record command within a command span and records its latency in the
+    // command metrics, labelled with the node the command was last sent to. `f` is passed the
+    // slot for that node, see `SingleCommand::execute`.
+    pub fn observe_key<T, F>(
+        &self,
+        command: CommandType,
+        key: &Key,
+        policy: &dyn Policy,
+        f: F,
+    ) -> Result<T>
+    where
+        F: FnOnce(&mut Option<Arc<Node>>) -> Result<T>,
+    {
+        let node = |last_node: &Option<Arc<Node>>| {
+            last_node
+                .as_ref()
+                .map(|node| node.name().to_owned())
+                .unwrap_or_default()
+        };
+        self.observe_with(command, &key.namespace, &key.set_name, policy, node, f)
+    }
+
+    // `f` runs the command with mutable access to a default `state`, from which `node`
+    // determines the node label afterwards.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    fn observe_with<T, S, N, F>(
+        &self,
+        command: CommandType,
+        namespace: &str,
+        set_name: &str,
+        policy: &dyn Policy,
+        node: N,
+        f: F,
+    ) -> Result<T>
+    where
+        S: Default,
+        N: FnOnce(&S) -> String,
+        F: FnOnce(&mut S) -> Result<T>,
+    {
+        let span = trace::command_span(command, namespace, set_name, policy);
+        let _enter = span.enter();
+
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
+        let mut state = S::default();
+        let result = f(&mut state);
+        trace::record_result(&result);
+
+        #[cfg(feature = "metrics")]
+        self.metrics
+            .record(command, namespace, &node(&state), start.elapsed(), &result);
+
+        result
+    }
+
     pub fn is_connected(&self) -> bool {
         let nodes = self.nodes();
         let closed = self.closed.load(Ordering::Relaxed);
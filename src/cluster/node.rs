@@ -29,6 +29,7 @@ use crate::commands::Message;
 use crate::errors::{ErrorKind, Result, ResultExt};
 use crate::net::{ConnectionPool, Host, PooledConnection};
 use crate::policy::ClientPolicy;
+use crate::stats::NodeStats;
 
 pub const PARTITIONS: usize = 4096;
 
@@ -43,6 +44,10 @@ pub struct Node {
     connection_pool: ConnectionPool,
     failures: AtomicUsize,
 
+    commands: AtomicUsize,
+    command_errors: AtomicUsize,
+    command_timeouts: AtomicUsize,
+
     partition_generation: AtomicIsize,
     refresh_count: AtomicUsize,
     reference_count: AtomicUsize,
@@ -68,6 +73,9 @@ impl Node {
             host: nv.aliases[0].clone(),
             connection_pool: ConnectionPool::new(nv.aliases[0].clone(), client_policy),
             failures: AtomicUsize::new(0),
+            commands: AtomicUsize::new(0),
+            command_errors: AtomicUsize::new(0),
+            command_timeouts: AtomicUsize::new(0),
             partition_generation: AtomicIsize::new(-1),
             refresh_count: AtomicUsize::new(0),
             reference_count: AtomicUsize::new(0),
@@ -258,6 +266,37 @@ impl Node {
         self.failures.fetch_add(1, Ordering::Relaxed)
     }
 
+    // Record that a command attempt was sent to this node.
+    pub fn record_command(&self) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Record that a command attempt on this node failed.
+    pub fn record_command_error(&self, timeout: bool) {
+        self.command_errors.fetch_add(1, Ordering::Relaxed);
+        if timeout {
+            self.command_timeouts.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> NodeStats {
+        let conns = self.connection_pool.stats();
+        NodeStats {
+            name: self.name.clone(),
+            address: self.address.clone(),
+            open_connections: conns.open,
+            idle_connections: conns.idle,
+            in_use_connections: conns.open.saturating_sub(conns.idle),
+            connections_opened: conns.opened,
+            connections_closed: conns.closed,
+            connections_failed: conns.failed,
+            connection_rejections: conns.rejected,
+            commands: self.commands.load(Ordering::Relaxed),
+            command_errors: self.command_errors.load(Ordering::Relaxed),
+            command_timeouts: self.command_timeouts.load(Ordering::Relaxed),
+        }
+    }
+
     fn inactivate(&self) {
         self.active.store(false, Ordering::Relaxed);
     }
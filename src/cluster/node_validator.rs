@@ -91,7 +91,7 @@ impl NodeValidator {
     }
 
     fn validate_alias(&mut self, cluster: &Cluster, alias: &Host) -> Result<()> {
-        let mut conn = Connection::new(&alias, &self.client_policy)?;
+        let mut conn = Connection::new(alias, &self.client_policy)?;
         conn.set_timeout(self.client_policy.timeout)?;
         let info_map = Message::info(&mut conn, &["node", "cluster-name", "features"])?;
 
@@ -13,11 +13,7 @@
 // limitations under the License.
 
 use std::fmt;
-use std::io::Cursor;
 
-use byteorder::{LittleEndian, ReadBytesExt};
-
-use crate::cluster::node;
 use crate::Key;
 
 // Validates a Database server node
@@ -36,15 +32,9 @@ impl<'a> Partition<'a> {
     }
 
     pub fn new_by_key(key: &'a Key) -> Self {
-        let mut rdr = Cursor::new(&key.digest[0..4]);
-
         Partition {
             namespace: &key.namespace,
-
-            // CAN'T USE MOD directly - mod will give negative numbers.
-            // First AND makes positive and negative correctly, then mod.
-            // For any x, y : x % 2^y = x & (2^y - 1); the second method is twice as fast
-            partition_id: rdr.read_u32::<LittleEndian>().unwrap() as usize & (node::PARTITIONS - 1),
+            partition_id: key.partition_id(),
         }
     }
 }
@@ -19,10 +19,12 @@ use std::time::{Duration, Instant};
 
 use crate::batch::batch_executor::SharedSlice;
 use crate::cluster::Node;
-use crate::commands::{self, Command};
+use crate::commands::{self, Command, CommandType};
 use crate::errors::{ErrorKind, Result, ResultExt};
+use crate::interceptor::{CommandContext, Interceptor};
 use crate::net::Connection;
 use crate::policy::{BatchPolicy, Policy, PolicyLike};
+use crate::trace;
 use crate::{value, BatchRead, Record, ResultCode, Value};
 
 struct BatchRecord {
@@ -52,12 +54,21 @@ impl<'a, 'b> BatchReadCommand<'a, 'b> {
         }
     }
 
+    // Namespace of the first key in this batch, used to label the command in metrics.
+    pub fn namespace(&self) -> &str {
+        self.offsets
+            .first()
+            .and_then(|&idx| self.batch_reads.get(idx))
+            .map_or("", |batch_read| batch_read.key.namespace.as_str())
+    }
+
     pub fn execute(&mut self) -> Result<()> {
         let mut iterations = 0;
         let base_policy = self.policy.base();
 
         // set timeout outside the loop
         let deadline = base_policy.deadline();
+        let namespace = self.namespace().to_owned();
 
         // Execute command until successful, timed out or maximum iterations have been reached.
         loop {
@@ -93,36 +104,68 @@ impl<'a, 'b> BatchReadCommand<'a, 'b> {
                 Err(_) => continue, // Node is currently inactive. Retry.
             };
 
+            let mut ctx = CommandContext::new(
+                CommandType::Batch,
+                &namespace,
+                None,
+                base_policy,
+                self.interceptors(),
+                Some(Arc::clone(&node)),
+                iterations,
+            );
+            if ctx.before()?.is_some() {
+                bail!(commands::namespace_not_rewritable(CommandType::Batch));
+            }
+
             let mut conn = match node.get_connection(base_policy.timeout()) {
                 Ok(conn) => conn,
                 Err(err) => {
                     warn!("Node {}: {}", node, err);
+                    ctx.after(&Err(err));
                     continue;
                 }
             };
 
-            self.prepare_buffer(&mut conn)
-                .chain_err(|| "Failed to prepare send buffer")?;
-            self.write_timeout(&mut conn, base_policy.timeout())
-                .chain_err(|| "Failed to set timeout for send buffer")?;
+            node.record_command();
+            trace::record_attempt(&node, iterations);
+
+            let prepared = self
+                .prepare_buffer(&mut conn)
+                .chain_err(|| "Failed to prepare send buffer")
+                .and_then(|()| {
+                    self.write_timeout(&mut conn, base_policy.timeout())
+                        .chain_err(|| "Failed to set timeout for send buffer")
+                });
+            if prepared.is_err() {
+                ctx.after(&prepared);
+                return prepared;
+            }
 
             // Send command.
+            let bytes_sent = conn.buffer.data_buffer.len();
+            let bytes_received = conn.bytes_received();
             if let Err(err) = self.write_buffer(&mut conn) {
                 // IO errors are considered temporary anomalies. Retry.
                 // Close socket to flush out possible garbage. Do not put back in pool.
                 conn.invalidate();
+                node.record_command_error(commands::is_timeout(&err));
                 warn!("Node {}: {}", node, err);
+                ctx.after(&Err(err));
                 continue;
             }
 
             // Parse results.
-            if let Err(err) = self.parse_result(&mut conn) {
+            let result = self.parse_result(&mut conn);
+            trace::record_bytes(bytes_sent, conn.bytes_received() - bytes_received);
+            ctx.after(&result);
+            if let Err(err) = result {
                 // close the connection
                 // cancelling/closing the batch/multi commands will return an error, which will
                 // close the connection to throw away its data and signal the server about the
                 // situation. We will not put back the connection in the buffer.
                 if !commands::keep_connection(&err) {
                     conn.invalidate();
+                    node.record_command_error(commands::is_timeout(&err));
                 }
                 return Err(err);
             }
@@ -225,6 +268,10 @@ impl<'a, 'b> commands::Command for BatchReadCommand<'a, 'b> {
         Ok(self.node.clone())
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.node.client_policy().interceptors.clone()
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         loop {
             conn.read_buffer(8)?;
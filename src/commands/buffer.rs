@@ -33,37 +33,37 @@ use crate::{BatchRead, Bin, Bins, CollectionIndexType, Key, Statement, Value};
 const INFO1_READ: u8 = 1;
 
 // Get all bins.
-const INFO1_GET_ALL: u8 = 1 << 1;
+pub const INFO1_GET_ALL: u8 = 1 << 1;
 
 // Batch read or exists.
-const INFO1_BATCH: u8 = 1 << 3;
+pub const INFO1_BATCH: u8 = 1 << 3;
 
 // Do not read the bins
-const INFO1_NOBINDATA: u8 = 1 << 5;
+pub const INFO1_NOBINDATA: u8 = 1 << 5;
 
 // Involve all replicas in read operation.
 const INFO1_CONSISTENCY_ALL: u8 = 1 << 6;
 
 // Create or update record
-const INFO2_WRITE: u8 = 1;
+pub const INFO2_WRITE: u8 = 1;
 
 // Fling a record into the belly of Moloch.
-const INFO2_DELETE: u8 = 1 << 1;
+pub const INFO2_DELETE: u8 = 1 << 1;
 
 // Update if expected generation == old.
-const INFO2_GENERATION: u8 = 1 << 2;
+pub const INFO2_GENERATION: u8 = 1 << 2;
 
 // Update if new generation >= old, good for restore.
-const INFO2_GENERATION_GT: u8 = 1 << 3;
+pub const INFO2_GENERATION_GT: u8 = 1 << 3;
 
 // Transaction resulting in record deletion leaves tombstone (Enterprise only).
 const INFO2_DURABLE_DELETE: u8 = 1 << 4;
 
 // Create only. Fail if record already exists.
-const INFO2_CREATE_ONLY: u8 = 1 << 5;
+pub const INFO2_CREATE_ONLY: u8 = 1 << 5;
 
 // Return a result for every operation.
-const INFO2_RESPOND_ALL_OPS: u8 = 1 << 7;
+pub const INFO2_RESPOND_ALL_OPS: u8 = 1 << 7;
 
 // This is the last of a multi-part message.
 pub const INFO3_LAST: u8 = 1;
@@ -75,13 +75,13 @@ const INFO3_COMMIT_MASTER: u8 = 1 << 1;
 pub const _INFO3_PARTITION_DONE: u8 = 1 << 2;
 
 // Update only. Merge bins.
-const INFO3_UPDATE_ONLY: u8 = 1 << 3;
+pub const INFO3_UPDATE_ONLY: u8 = 1 << 3;
 
 // Create or completely replace record.
-const INFO3_CREATE_OR_REPLACE: u8 = 1 << 4;
+pub const INFO3_CREATE_OR_REPLACE: u8 = 1 << 4;
 
 // Completely replace existing record only.
-const INFO3_REPLACE_ONLY: u8 = 1 << 5;
+pub const INFO3_REPLACE_ONLY: u8 = 1 << 5;
 
 pub const MSG_TOTAL_HEADER_SIZE: u8 = 30;
 const FIELD_HEADER_SIZE: u8 = 5;
@@ -919,6 +919,8 @@ impl Buffer {
 
     fn estimate_filter_size(&mut self, filter: &Option<FilterExpression>) -> Result<usize> {
         if let Some(filter) = filter {
+            #[cfg(debug_assertions)]
+            filter.validate_filter()?;
             let filter_size = filter.pack(&mut None)?;
             self.data_offset += filter_size + FIELD_HEADER_SIZE as usize;
             Ok(filter_size)
@@ -1174,7 +1176,7 @@ impl Buffer {
         self.write_u8(bin.value.particle_type() as u8)?;
         self.write_u8(0)?;
         self.write_u8(name_length as u8)?;
-        self.write_str(bin.name)?;
+        self.write_str(&bin.name)?;
         bin.value.write_to(self)?;
 
         Ok(())
@@ -29,8 +29,9 @@ pub mod stream_command;
 pub mod touch_command;
 pub mod write_command;
 
-mod field_type;
+pub mod field_type;
 
+use std::io;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -51,23 +52,116 @@ pub use self::write_command::WriteCommand;
 
 use crate::cluster::Node;
 use crate::errors::{Error, ErrorKind, Result};
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::ResultCode;
 
+/// Type of a client command, as reported to interceptors and used to label command metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CommandType {
+    /// Read a single record.
+    Get,
+    /// Write a single record, incl. add, append and prepend.
+    Put,
+    /// Delete a single record.
+    Delete,
+    /// Reset the expiration of a single record.
+    Touch,
+    /// Check for the existence of a single record.
+    Exists,
+    /// Execute multiple operations on a single record.
+    Operate,
+    /// Read multiple records from one node.
+    Batch,
+    /// Scan a namespace or set on one node.
+    Scan,
+    /// Query a secondary index on one node.
+    Query,
+    /// Execute a user-defined function on a single record.
+    Udf,
+    /// Send an info command to one node.
+    Info,
+}
+
+impl CommandType {
+    /// Returns the lower-case name of the command type, e.g. `"get"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            CommandType::Get => "get",
+            CommandType::Put => "put",
+            CommandType::Delete => "delete",
+            CommandType::Touch => "touch",
+            CommandType::Exists => "exists",
+            CommandType::Operate => "operate",
+            CommandType::Batch => "batch",
+            CommandType::Scan => "scan",
+            CommandType::Query => "query",
+            CommandType::Udf => "udf",
+            CommandType::Info => "info",
+        }
+    }
+}
+
 // Command interface describes all commands available
 pub trait Command {
     fn write_timeout(&mut self, conn: &mut Connection, timeout: Option<Duration>) -> Result<()>;
     fn prepare_buffer(&mut self, conn: &mut Connection) -> Result<()>;
     fn get_node(&self) -> Result<Arc<Node>>;
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>>;
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()>;
     fn write_buffer(&mut self, conn: &mut Connection) -> Result<()>;
+
+    // Sends the command to another namespace, as requested by an interceptor. Returns false for
+    // commands that are not routed by a single key.
+    fn set_namespace(&mut self, _namespace: &str) -> bool {
+        false
+    }
+}
+
+// Error for an interceptor that changed the namespace of a command that can not be rewritten.
+pub fn namespace_not_rewritable(command: CommandType) -> Error {
+    ErrorKind::InvalidArgument(format!(
+        "The namespace of {} commands can not be rewritten",
+        command.as_str()
+    ))
+    .into()
 }
 
 pub const fn keep_connection(err: &Error) -> bool {
     match *err {
         Error(ErrorKind::ServerError(result_code), _) => {
-            matches!(result_code, ResultCode::KeyNotFoundError)
+            matches!(
+                result_code,
+                ResultCode::KeyNotFoundError
+                    | ResultCode::GenerationError
+                    | ResultCode::KeyExistsError
+                    | ResultCode::FilteredOut
+            )
         }
         _ => false,
     }
 }
+
+pub fn is_timeout(err: &Error) -> bool {
+    match *err {
+        Error(ErrorKind::Io(ref err), _) => matches!(
+            err.kind(),
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+        ),
+        _ => false,
+    }
+}
+
+// Label used for the outcome of a command: the server result code, or the kind of client-side
+// failure.
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+pub fn result_label<T>(result: &Result<T>) -> String {
+    match *result {
+        Ok(_) => "Ok".to_string(),
+        Err(Error(ErrorKind::ServerError(rc), _)) => format!("{:?}", rc),
+        Err(Error(ErrorKind::NoMoreConnections, _)) => "NoMoreConnections".to_string(),
+        Err(ref err) if is_timeout(err) => "Timeout".to_string(),
+        Err(Error(ErrorKind::Connection(_), _)) => "ConnectionError".to_string(),
+        Err(_) => "ClientError".to_string(),
+    }
+}
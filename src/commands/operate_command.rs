@@ -16,8 +16,9 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::cluster::{Cluster, Node};
-use crate::commands::{Command, ReadCommand, SingleCommand};
+use crate::commands::{Command, CommandType, ReadCommand, SingleCommand};
 use crate::errors::Result;
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::operations::Operation;
 use crate::policy::WritePolicy;
@@ -43,8 +44,16 @@ impl<'a> OperateCommand<'a> {
         }
     }
 
-    pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+    pub fn execute(&mut self, node: &mut Option<Arc<Node>>) -> Result<()> {
+        let key = self.read_command.single_command.key;
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Operate,
+            &key.namespace,
+            Some(key),
+            self,
+            node,
+        )
     }
 }
 
@@ -61,7 +70,7 @@ impl<'a> Command for OperateCommand<'a> {
     fn prepare_buffer(&mut self, conn: &mut Connection) -> Result<()> {
         conn.buffer.set_operate(
             self.policy,
-            self.read_command.single_command.key,
+            self.read_command.single_command.key(),
             self.operations,
         )
     }
@@ -70,6 +79,14 @@ impl<'a> Command for OperateCommand<'a> {
         self.read_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.read_command.interceptors()
+    }
+
+    fn set_namespace(&mut self, namespace: &str) -> bool {
+        self.read_command.single_command.set_namespace(namespace)
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         self.read_command.parse_result(conn)
     }
@@ -16,8 +16,9 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::cluster::Node;
-use crate::commands::{Command, SingleCommand, StreamCommand};
+use crate::commands::{Command, CommandType, SingleCommand, StreamCommand};
 use crate::errors::Result;
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::policy::QueryPolicy;
 use crate::{Recordset, Statement};
@@ -46,7 +47,15 @@ impl<'a> QueryCommand<'a> {
     }
 
     pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+        let statement = self.statement.clone();
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Query,
+            &statement.namespace,
+            None,
+            self,
+            &mut None,
+        )
     }
 }
 
@@ -74,6 +83,10 @@ impl<'a> Command for QueryCommand<'a> {
         self.stream_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.stream_command.interceptors()
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         StreamCommand::parse_result(&mut self.stream_command, conn)
     }
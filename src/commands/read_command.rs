@@ -19,8 +19,9 @@ use std::time::Duration;
 
 use crate::cluster::{Cluster, Node};
 use crate::commands::buffer;
-use crate::commands::{Command, SingleCommand};
+use crate::commands::{Command, CommandType, SingleCommand};
 use crate::errors::{ErrorKind, Result};
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::policy::ReadPolicy;
 use crate::value::bytes_to_particle;
@@ -29,6 +30,9 @@ use crate::{Bins, Key, Record, ResultCode, Value};
 pub struct ReadCommand<'a> {
     pub single_command: SingleCommand<'a>,
     pub record: Option<Record>,
+    // When set, collects every (bin, value) pair of the response in order, including nil values
+    // and repeated bins.
+    pub op_results: Option<Vec<(String, Value)>>,
     policy: &'a ReadPolicy,
     bins: Bins,
 }
@@ -40,11 +44,20 @@ impl<'a> ReadCommand<'a> {
             bins,
             policy,
             record: None,
+            op_results: None,
         }
     }
 
-    pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+    pub fn execute(&mut self, node: &mut Option<Arc<Node>>) -> Result<()> {
+        let key = self.single_command.key;
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Get,
+            &key.namespace,
+            Some(key),
+            self,
+            node,
+        )
     }
 
     fn parse_record(
@@ -56,6 +69,9 @@ impl<'a> ReadCommand<'a> {
         expiration: u32,
     ) -> Result<Record> {
         let mut bins: HashMap<String, Value> = HashMap::with_capacity(op_count);
+        if let Some(ref mut op_results) = self.op_results {
+            op_results.clear();
+        }
 
         // There can be fields in the response (setname etc). For now, ignore them. Expose them to
         // the API if needed in the future.
@@ -75,6 +91,10 @@ impl<'a> ReadCommand<'a> {
             let particle_bytes_size = op_size - (4 + name_size);
             let value = bytes_to_particle(particle_type, &mut conn.buffer, particle_bytes_size)?;
 
+            if let Some(ref mut op_results) = self.op_results {
+                op_results.push((name.clone(), value.clone()));
+            }
+
             if !value.is_nil() {
                 // list/map operations may return multiple values for the same bin.
                 match bins.entry(name) {
@@ -107,13 +127,21 @@ impl<'a> Command for ReadCommand<'a> {
 
     fn prepare_buffer(&mut self, conn: &mut Connection) -> Result<()> {
         conn.buffer
-            .set_read(self.policy, self.single_command.key, &self.bins)
+            .set_read(self.policy, self.single_command.key(), &self.bins)
     }
 
     fn get_node(&self) -> Result<Arc<Node>> {
         self.single_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.single_command.interceptors()
+    }
+
+    fn set_namespace(&mut self, namespace: &str) -> bool {
+        self.single_command.set_namespace(namespace)
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         if let Err(err) = conn.read_buffer(buffer::MSG_TOTAL_HEADER_SIZE as usize) {
             warn!("Parse result error: {}", err);
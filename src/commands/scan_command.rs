@@ -17,8 +17,9 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use crate::cluster::Node;
-use crate::commands::{Command, SingleCommand, StreamCommand};
+use crate::commands::{Command, CommandType, SingleCommand, StreamCommand};
 use crate::errors::Result;
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::policy::ScanPolicy;
 use crate::{Bins, Recordset};
@@ -53,7 +54,15 @@ impl<'a> ScanCommand<'a> {
     }
 
     pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+        let namespace = self.namespace;
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Scan,
+            namespace,
+            None,
+            self,
+            &mut None,
+        )
     }
 }
 
@@ -82,6 +91,10 @@ impl<'a> Command for ScanCommand<'a> {
         self.stream_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.stream_command.interceptors()
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         StreamCommand::parse_result(&mut self.stream_command, conn)
     }
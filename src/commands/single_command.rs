@@ -18,30 +18,50 @@ use std::time::Instant;
 
 use crate::cluster::partition::Partition;
 use crate::cluster::{Cluster, Node};
-use crate::commands::{self};
+use crate::commands::{self, CommandType};
 use crate::errors::{ErrorKind, Result, ResultExt};
+use crate::interceptor::{CommandContext, Interceptor};
 use crate::net::Connection;
 use crate::policy::Policy;
+use crate::trace;
 use crate::Key;
 
 pub struct SingleCommand<'a> {
     cluster: Arc<Cluster>,
     pub key: &'a Key,
-    partition: Partition<'a>,
+    // Copy of the key in the namespace set by an interceptor.
+    rewritten: Option<Key>,
 }
 
 impl<'a> SingleCommand<'a> {
-    pub fn new(cluster: Arc<Cluster>, key: &'a Key) -> Self {
-        let partition = Partition::new_by_key(key);
+    pub const fn new(cluster: Arc<Cluster>, key: &'a Key) -> Self {
         SingleCommand {
             cluster,
             key,
-            partition,
+            rewritten: None,
         }
     }
 
+    // The key the command is sent with, in the namespace set by interceptors.
+    pub fn key(&self) -> &Key {
+        self.rewritten.as_ref().unwrap_or(self.key)
+    }
+
+    pub fn set_namespace(&mut self, namespace: &str) -> bool {
+        if self.key().namespace != namespace {
+            let mut key = self.key.clone();
+            key.namespace = namespace.to_string();
+            self.rewritten = Some(key);
+        }
+        true
+    }
+
     pub fn get_node(&self) -> Result<Arc<Node>> {
-        self.cluster.get_node(&self.partition)
+        self.cluster.get_node(&Partition::new_by_key(self.key()))
+    }
+
+    pub fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.cluster.client_policy().interceptors.clone()
     }
 
     pub fn empty_socket(conn: &mut Connection) -> Result<()> {
@@ -63,7 +83,16 @@ impl<'a> SingleCommand<'a> {
     // EXECUTE
     //
 
-    pub fn execute(policy: &dyn Policy, cmd: &'a mut dyn commands::Command) -> Result<()> {
+    // Executes the command with retries; `last_node` is set to the node of each attempt, so it
+    // holds the node the command ran on last when this returns.
+    pub fn execute(
+        policy: &dyn Policy,
+        command: CommandType,
+        namespace: &str,
+        key: Option<&Key>,
+        cmd: &'a mut dyn commands::Command,
+        last_node: &mut Option<Arc<Node>>,
+    ) -> Result<()> {
         let mut iterations = 0;
 
         // set timeout outside the loop
@@ -97,42 +126,85 @@ impl<'a> SingleCommand<'a> {
                 }
             }
 
+            let mut route = cmd.get_node();
+            let mut ctx = CommandContext::new(
+                command,
+                namespace,
+                key,
+                policy,
+                cmd.interceptors(),
+                route.as_ref().ok().cloned(),
+                iterations,
+            );
+            if let Some(namespace) = ctx.before()? {
+                // route the attempt by the namespace set by the interceptors
+                if !cmd.set_namespace(&namespace) {
+                    bail!(commands::namespace_not_rewritable(command));
+                }
+                route = cmd.get_node();
+                ctx.set_node(route.as_ref().ok().cloned());
+            }
+
             // set command node, so when you return a record it has the node
-            let node = match cmd.get_node() {
+            let node = match route {
                 Ok(node) => node,
-                Err(_) => continue, // Node is currently inactive. Retry.
+                Err(err) => {
+                    // Node is currently inactive. Retry.
+                    ctx.after(&Err(err));
+                    continue;
+                }
             };
+            *last_node = Some(Arc::clone(&node));
 
             let mut conn = match node.get_connection(policy.timeout()) {
                 Ok(conn) => conn,
                 Err(err) => {
                     warn!("Node {}: {}", node, err);
+                    ctx.after(&Err(err));
                     continue;
                 }
             };
 
-            cmd.prepare_buffer(&mut conn)
-                .chain_err(|| "Failed to prepare send buffer")?;
-            cmd.write_timeout(&mut conn, policy.timeout())
-                .chain_err(|| "Failed to set timeout for send buffer")?;
+            node.record_command();
+            trace::record_attempt(&node, iterations);
+
+            let prepared = cmd
+                .prepare_buffer(&mut conn)
+                .chain_err(|| "Failed to prepare send buffer")
+                .and_then(|()| {
+                    cmd.write_timeout(&mut conn, policy.timeout())
+                        .chain_err(|| "Failed to set timeout for send buffer")
+                });
+            if prepared.is_err() {
+                ctx.after(&prepared);
+                return prepared;
+            }
 
             // Send command.
+            let bytes_sent = conn.buffer.data_buffer.len();
+            let bytes_received = conn.bytes_received();
             if let Err(err) = cmd.write_buffer(&mut conn) {
                 // IO errors are considered temporary anomalies. Retry.
                 // Close socket to flush out possible garbage. Do not put back in pool.
                 conn.invalidate();
+                node.record_command_error(commands::is_timeout(&err));
                 warn!("Node {}: {}", node, err);
+                ctx.after(&Err(err));
                 continue;
             }
 
             // Parse results.
-            if let Err(err) = cmd.parse_result(&mut conn) {
+            let result = cmd.parse_result(&mut conn);
+            trace::record_bytes(bytes_sent, conn.bytes_received() - bytes_received);
+            ctx.after(&result);
+            if let Err(err) = result {
                 // close the connection
                 // cancelling/closing the batch/multi commands will return an error, which will
                 // close the connection to throw away its data and signal the server about the
                 // situation. We will not put back the connection in the buffer.
                 if !commands::keep_connection(&err) {
                     conn.invalidate();
+                    node.record_command_error(commands::is_timeout(&err));
                 }
                 return Err(err);
             }
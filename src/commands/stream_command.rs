@@ -22,6 +22,7 @@ use crate::commands::buffer;
 use crate::commands::field_type::FieldType;
 use crate::commands::Command;
 use crate::errors::{ErrorKind, Result};
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::query::Recordset;
 use crate::value::bytes_to_particle;
@@ -123,7 +124,7 @@ impl StreamCommand {
                 },
                 Ok((None, cont)) => return Ok(cont),
                 Err(err) => {
-                    self.recordset.push(Err(err));
+                    self.recordset.push_error(err);
                     return Ok(false);
                 }
             };
@@ -196,6 +197,10 @@ impl Command for StreamCommand {
         Ok(self.node.clone())
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.node.client_policy().interceptors.clone()
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         let mut status = true;
 
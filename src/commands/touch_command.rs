@@ -17,8 +17,9 @@ use std::time::Duration;
 
 use crate::cluster::{Cluster, Node};
 use crate::commands::buffer;
-use crate::commands::{Command, SingleCommand};
+use crate::commands::{Command, CommandType, SingleCommand};
 use crate::errors::{ErrorKind, Result};
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::policy::WritePolicy;
 use crate::{Key, ResultCode};
@@ -36,8 +37,16 @@ impl<'a> TouchCommand<'a> {
         }
     }
 
-    pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+    pub fn execute(&mut self, node: &mut Option<Arc<Node>>) -> Result<()> {
+        let key = self.single_command.key;
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Touch,
+            &key.namespace,
+            Some(key),
+            self,
+            node,
+        )
     }
 }
 
@@ -52,13 +61,22 @@ impl<'a> Command for TouchCommand<'a> {
     }
 
     fn prepare_buffer(&mut self, conn: &mut Connection) -> Result<()> {
-        conn.buffer.set_touch(self.policy, self.single_command.key)
+        conn.buffer
+            .set_touch(self.policy, self.single_command.key())
     }
 
     fn get_node(&self) -> Result<Arc<Node>> {
         self.single_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.single_command.interceptors()
+    }
+
+    fn set_namespace(&mut self, namespace: &str) -> bool {
+        self.single_command.set_namespace(namespace)
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Read header.
         if let Err(err) = conn.read_buffer(buffer::MSG_TOTAL_HEADER_SIZE as usize) {
@@ -17,8 +17,9 @@ use std::time::Duration;
 
 use crate::cluster::{Cluster, Node};
 use crate::commands::buffer;
-use crate::commands::{Command, SingleCommand};
+use crate::commands::{Command, CommandType, SingleCommand};
 use crate::errors::{ErrorKind, Result};
+use crate::interceptor::Interceptor;
 use crate::net::Connection;
 use crate::operations::OperationType;
 use crate::policy::WritePolicy;
@@ -47,8 +48,20 @@ impl<'a, 'b, A: AsRef<Bin<'b>>> WriteCommand<'a, A> {
         }
     }
 
-    pub fn execute(&mut self) -> Result<()> {
-        SingleCommand::execute(self.policy, self)
+    pub fn execute(&mut self, node: &mut Option<Arc<Node>>) -> Result<()> {
+        for bin in self.bins {
+            bin.as_ref().validate()?;
+        }
+
+        let key = self.single_command.key;
+        SingleCommand::execute(
+            self.policy,
+            CommandType::Put,
+            &key.namespace,
+            Some(key),
+            self,
+            node,
+        )
     }
 }
 
@@ -66,7 +79,7 @@ impl<'a, 'b, A: AsRef<Bin<'b>>> Command for WriteCommand<'a, A> {
         conn.buffer.set_write(
             self.policy,
             self.operation,
-            self.single_command.key,
+            self.single_command.key(),
             self.bins,
         )
     }
@@ -75,6 +88,14 @@ impl<'a, 'b, A: AsRef<Bin<'b>>> Command for WriteCommand<'a, A> {
         self.single_command.get_node()
     }
 
+    fn interceptors(&self) -> Vec<Arc<dyn Interceptor>> {
+        self.single_command.interceptors()
+    }
+
+    fn set_namespace(&mut self, namespace: &str) -> bool {
+        self.single_command.set_namespace(namespace)
+    }
+
     fn parse_result(&mut self, conn: &mut Connection) -> Result<()> {
         // Read header.
         if let Err(err) = conn.read_buffer(buffer::MSG_TOTAL_HEADER_SIZE as usize) {
@@ -111,7 +111,7 @@ pub trait Entity<'a>: Set + IntoKey + IntoBins<'a> + FromRecord {
     }
 
     fn update_field(client: &Client, policy: &WritePolicy, key: Self::KeyType, name: &str, val: Value) -> Result<Self> {
-        let bin = Bin::new(name, val);
+        let bin = Bin::try_new(name, val)?;
         let ops = &vec![operations::put(&bin), operations::get()];
         client.operate(
             policy, 
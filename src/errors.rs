@@ -52,7 +52,9 @@
 
 #![allow(missing_docs)]
 
-use crate::ResultCode;
+use std::convert::Infallible;
+
+use crate::{ParticleType, ResultCode};
 
 error_chain! {
 
@@ -121,6 +123,46 @@ error_chain! {
             display("UDF Bad Response: {}", details)
         }
 
+/// A value could not be converted into the requested type, e.g. by `TryFrom<Value>` or
+/// `Record::get`. Contains the name of the bin (if any), the requested type and the particle type
+/// of the value.
+        InvalidType(bin: Option<String>, expected: String, actual: ParticleType) {
+            description("Invalid Type")
+            display("Invalid type{}: expected {}, found {:?}",
+                    bin.as_ref().map(|bin| format!(" for bin '{}'", bin)).unwrap_or_default(),
+                    expected, actual)
+        }
+
+/// A text expression could not be parsed by `FilterExpression::parse`, or a packed expression
+/// could not be decoded by `FilterExpression::from_bytes`. Contains the byte offset of the error
+/// in the expression text or data and a description of the problem.
+        InvalidExpression(position: usize, details: String) {
+            description("Invalid Expression")
+            display("Invalid expression at position {}: {}", position, details)
+        }
+
+/// Client-side evaluation of a filter expression resolved to "unknown", e.g. because a bin does
+/// not exist or has a different type than the expression expects. The server would treat the
+/// expression as not matching.
+        UnknownExpressionValue(details: String) {
+            description("Unknown Expression Value")
+            display("Expression evaluated to unknown: {}", details)
+        }
+
+/// The filter expression uses an operation that can not be evaluated on the client.
+        UnsupportedExpression(details: String) {
+            description("Unsupported Expression")
+            display("Unsupported expression: {}", details)
+        }
+
+/// A filter expression tree is malformed, e.g. an operation has the wrong number of operands or
+/// operands of the wrong type. Contains the path to the offending sub-expression and a
+/// description of the problem.
+        InvalidExpressionTree(path: String, details: String) {
+            description("Invalid Expression Tree")
+            display("Invalid expression tree at {}: {}", path, details)
+        }
+
 /// Error returned when a tasked timeed out before it could be completed.
         Timeout(details: String) {
             description("Timeout")
@@ -141,3 +183,9 @@ macro_rules! log_error_chain {
         }
     };
 }
+
+impl From<Infallible> for Error {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
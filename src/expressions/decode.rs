@@ -0,0 +1,772 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Decoding of packed filter expressions, see `FilterExpression::from_bytes`.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use crate::commands::ParticleType;
+use crate::errors::{ErrorKind, Result};
+use crate::expressions::eval::{list_op, map_op, Select};
+use crate::expressions::validate::{bit_signature, cdt_signature, BIT_MODULE, CDT_MODULE, MAP_OPS};
+use crate::expressions::{ExpOp, ExpType, ExpressionArgument, FilterExpression, MODIFY};
+use crate::operations::cdt_context::CdtContext;
+use crate::Value;
+
+const OPS: &[ExpOp] = &[
+    ExpOp::Unknown,
+    ExpOp::EQ,
+    ExpOp::NE,
+    ExpOp::GT,
+    ExpOp::GE,
+    ExpOp::LT,
+    ExpOp::LE,
+    ExpOp::Regex,
+    ExpOp::Geo,
+    ExpOp::And,
+    ExpOp::Or,
+    ExpOp::Not,
+    ExpOp::Xor,
+    ExpOp::Add,
+    ExpOp::Sub,
+    ExpOp::Mul,
+    ExpOp::Div,
+    ExpOp::Pow,
+    ExpOp::Log,
+    ExpOp::Mod,
+    ExpOp::Abs,
+    ExpOp::Floor,
+    ExpOp::Ceil,
+    ExpOp::ToInt,
+    ExpOp::ToFloat,
+    ExpOp::IntAnd,
+    ExpOp::IntOr,
+    ExpOp::IntXor,
+    ExpOp::IntNot,
+    ExpOp::IntLshift,
+    ExpOp::IntRshift,
+    ExpOp::IntARshift,
+    ExpOp::IntCount,
+    ExpOp::IntLscan,
+    ExpOp::IntRscan,
+    ExpOp::Min,
+    ExpOp::Max,
+    ExpOp::DigestModulo,
+    ExpOp::DeviceSize,
+    ExpOp::LastUpdate,
+    ExpOp::SinceUpdate,
+    ExpOp::VoidTime,
+    ExpOp::TTL,
+    ExpOp::SetName,
+    ExpOp::KeyExists,
+    ExpOp::IsTombstone,
+    ExpOp::Key,
+    ExpOp::Bin,
+    ExpOp::BinType,
+    ExpOp::Cond,
+    ExpOp::Var,
+    ExpOp::Let,
+    ExpOp::Quoted,
+    ExpOp::Call,
+];
+
+const TYPES: &[ExpType] = &[
+    ExpType::NIL,
+    ExpType::BOOL,
+    ExpType::INT,
+    ExpType::STRING,
+    ExpType::LIST,
+    ExpType::MAP,
+    ExpType::BLOB,
+    ExpType::FLOAT,
+    ExpType::GEO,
+    ExpType::HLL,
+];
+
+/// The context of a CDT call is marked by this operation code in the argument list.
+const CONTEXT: i64 = 0xff;
+
+/// Decodes the wire format written by `FilterExpression::pack`.
+pub(super) fn decode(bytes: &[u8]) -> Result<FilterExpression> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    let exp = decoder.expression()?;
+    if decoder.pos < bytes.len() {
+        return decoder.fail(
+            decoder.pos,
+            "unexpected data after the expression".to_string(),
+        );
+    }
+    Ok(exp)
+}
+
+/// Returns the expression type with the given wire code.
+pub(super) fn exp_type(code: i64) -> Option<ExpType> {
+    TYPES.iter().copied().find(|ty| *ty as i64 == code)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn fail<T>(&self, pos: usize, details: String) -> Result<T> {
+        Err(ErrorKind::InvalidExpression(pos, details).into())
+    }
+
+    fn take(&mut self, count: usize) -> Result<&[u8]> {
+        match self.bytes.get(self.pos..self.pos.saturating_add(count)) {
+            Some(bytes) => {
+                self.pos += count;
+                Ok(bytes)
+            }
+            None => self.fail(self.bytes.len(), "unexpected end of data".to_string()),
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn uint(&mut self, size: usize) -> Result<u64> {
+        Ok(self
+            .take(size)?
+            .iter()
+            .fold(0, |acc, byte| acc << 8 | u64::from(*byte)))
+    }
+
+    fn arity(&self, pos: usize, len: usize, count: usize) -> Result<()> {
+        if len == count {
+            Ok(())
+        } else {
+            self.fail(pos, format!("expected {} elements, found {}", count, len))
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Reads an array header if the next value is an array.
+    fn array(&mut self) -> Result<Option<usize>> {
+        let len = match self.peek() {
+            Some(marker @ 0x90..=0x9f) => {
+                self.pos += 1;
+                usize::from(marker & 0x0f)
+            }
+            Some(0xdc) => {
+                self.pos += 1;
+                self.uint(2)? as usize
+            }
+            Some(0xdd) => {
+                self.pos += 1;
+                self.uint(4)? as usize
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(len))
+    }
+
+    fn expect_array(&mut self) -> Result<usize> {
+        let pos = self.pos;
+        self.array()?
+            .map_or_else(|| self.fail(pos, "expected an array".to_string()), Ok)
+    }
+
+    /// Reads a byte string without particle type, as used for bin and variable names.
+    fn raw_string(&mut self) -> Result<String> {
+        let pos = self.pos;
+        let len = match self.byte()? {
+            marker @ 0xa0..=0xbf => usize::from(marker & 0x1f),
+            0xd9 => self.uint(1)? as usize,
+            0xda => self.uint(2)? as usize,
+            0xdb => self.uint(4)? as usize,
+            _ => return self.fail(pos, "expected a string".to_string()),
+        };
+        String::from_utf8(self.take(len)?.to_vec())
+            .or_else(|_| self.fail(pos, "invalid UTF-8 string".to_string()))
+    }
+
+    fn integer(&mut self) -> Result<i64> {
+        let pos = self.pos;
+        match self.value()? {
+            Value::Int(val) => Ok(val),
+            val => self.fail(pos, format!("expected an integer, found {:?}", val)),
+        }
+    }
+
+    fn exp_type(&mut self) -> Result<ExpType> {
+        let pos = self.pos;
+        let code = self.integer()?;
+        exp_type(code).map_or_else(
+            || self.fail(pos, format!("unknown expression type {}", code)),
+            Ok,
+        )
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        let pos = self.pos;
+        let marker = self.byte()?;
+        let val = match marker {
+            0x00..=0x7f => Value::Int(i64::from(marker)),
+            0x80..=0x8f => self.map(usize::from(marker & 0x0f))?,
+            0x90..=0x9f => self.list(usize::from(marker & 0x0f))?,
+            0xa0..=0xbf => self.bytes(usize::from(marker & 0x1f), pos)?,
+            0xc0 => Value::Nil,
+            0xc2 => Value::Bool(false),
+            0xc3 => Value::Bool(true),
+            0xc4 | 0xd9 => {
+                let len = self.uint(1)? as usize;
+                self.bytes(len, pos)?
+            }
+            0xc5 | 0xda => {
+                let len = self.uint(2)? as usize;
+                self.bytes(len, pos)?
+            }
+            0xc6 | 0xdb => {
+                let len = self.uint(4)? as usize;
+                self.bytes(len, pos)?
+            }
+            0xca => Value::from(f32::from_bits(self.uint(4)? as u32)),
+            0xcb => Value::from(f64::from_bits(self.uint(8)?)),
+            0xcc => Value::Int(self.uint(1)? as i64),
+            0xcd => Value::Int(self.uint(2)? as i64),
+            0xce => Value::Int(self.uint(4)? as i64),
+            0xcf => {
+                let val = self.uint(8)?;
+                val.try_into().map_or(Value::UInt(val), Value::Int)
+            }
+            0xd0 => Value::Int(i64::from(self.uint(1)? as u8 as i8)),
+            0xd1 => Value::Int(i64::from(self.uint(2)? as u16 as i16)),
+            0xd2 => Value::Int(i64::from(self.uint(4)? as u32 as i32)),
+            0xd3 => Value::Int(self.uint(8)? as i64),
+            0xd4 => match (self.byte()?, self.byte()?) {
+                (0xff, 0x01) => Value::Infinity,
+                (0xff, 0x00) => Value::Wildcard,
+                _ => return self.fail(pos, "unsupported extension type".to_string()),
+            },
+            0xdc => {
+                let len = self.uint(2)? as usize;
+                self.list(len)?
+            }
+            0xdd => {
+                let len = self.uint(4)? as usize;
+                self.list(len)?
+            }
+            0xde => {
+                let len = self.uint(2)? as usize;
+                self.map(len)?
+            }
+            0xdf => {
+                let len = self.uint(4)? as usize;
+                self.map(len)?
+            }
+            0xe0..=0xff => Value::Int(i64::from(marker as i8)),
+            _ => return self.fail(pos, format!("unsupported type marker {:#04x}", marker)),
+        };
+        Ok(val)
+    }
+
+    /// Byte strings are prefixed with their particle type.
+    fn bytes(&mut self, len: usize, pos: usize) -> Result<Value> {
+        let data = self.take(len)?;
+        let (particle, data) = match data.split_first() {
+            Some((particle, data)) => (*particle, data.to_vec()),
+            None => return self.fail(pos, "missing particle type".to_string()),
+        };
+        let text = |data: Vec<u8>| {
+            String::from_utf8(data).or_else(|_| self.fail(pos, "invalid UTF-8 string".to_string()))
+        };
+        match particle {
+            p if p == ParticleType::STRING as u8 => Ok(Value::String(text(data)?)),
+            p if p == ParticleType::BLOB as u8 => Ok(Value::Blob(data)),
+            p if p == ParticleType::GEOJSON as u8 => Ok(Value::GeoJSON(text(data)?)),
+            p => self.fail(pos, format!("unsupported particle type {}", p)),
+        }
+    }
+
+    fn list(&mut self, len: usize) -> Result<Value> {
+        let mut list = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            list.push(self.value()?);
+        }
+        Ok(Value::List(list))
+    }
+
+    /// Maps starting with the key-ordered flag are decoded as `Value::OrderedMap`.
+    fn map(&mut self, len: usize) -> Result<Value> {
+        let ordered = self.bytes.get(self.pos..self.pos + 4) == Some(&[0xc7, 0x00, 0x01, 0xc0]);
+        if ordered {
+            self.pos += 4;
+            let mut map = Vec::with_capacity(len.min(self.bytes.len()));
+            for _ in 1..len {
+                map.push((self.value()?, self.value()?));
+            }
+            Ok(Value::OrderedMap(map))
+        } else {
+            let mut map = HashMap::with_capacity(len.min(self.bytes.len()));
+            for _ in 0..len {
+                let pos = self.pos;
+                let key = self.value()?;
+                if !key.is_valid_map_key() {
+                    return self.fail(pos, "map keys can not be maps".to_string());
+                }
+                let val = self.value()?;
+                map.insert(key, val);
+            }
+            Ok(Value::HashMap(map))
+        }
+    }
+
+    /// Reads an operation node, or a value if the next element is not an array.
+    fn expression(&mut self) -> Result<FilterExpression> {
+        let pos = self.pos;
+        let len = if let Some(len) = self.array()? {
+            len
+        } else {
+            let val = self.value()?;
+            return Ok(FilterExpression::new(
+                None,
+                Some(val),
+                None,
+                None,
+                None,
+                None,
+            ));
+        };
+        if len == 0 {
+            return self.fail(pos, "empty expression".to_string());
+        }
+        let code = self.integer()?;
+        let op = match OPS.iter().copied().find(|op| *op as i64 == code) {
+            Some(op) => op,
+            None => return self.fail(pos, format!("unknown operation {}", code)),
+        };
+
+        let exp = match op {
+            ExpOp::Regex => {
+                self.arity(pos, len, 4)?;
+                let flags = self.integer()?;
+                let pattern = self.raw_string()?;
+                let bin = self.expression()?;
+                FilterExpression::new(
+                    Some(op),
+                    Some(Value::String(pattern)),
+                    Some(bin),
+                    Some(flags),
+                    None,
+                    None,
+                )
+            }
+            ExpOp::Call => {
+                self.arity(pos, len, 5)?;
+                let return_type = self.exp_type()?;
+                let flags = self.integer()?;
+                let arguments = self.arguments(flags)?;
+                let bin = self.expression()?;
+                let mut exp = FilterExpression::new(
+                    Some(op),
+                    None,
+                    Some(bin),
+                    Some(flags),
+                    Some(return_type),
+                    None,
+                );
+                exp.arguments = Some(arguments);
+                exp
+            }
+            ExpOp::Bin => {
+                self.arity(pos, len, 3)?;
+                let ty = self.exp_type()?;
+                let name = self.raw_string()?;
+                FilterExpression::new(
+                    Some(op),
+                    Some(Value::String(name)),
+                    None,
+                    None,
+                    Some(ty),
+                    None,
+                )
+            }
+            ExpOp::BinType | ExpOp::Var => {
+                self.arity(pos, len, 2)?;
+                let name = self.raw_string()?;
+                FilterExpression::new(Some(op), Some(Value::String(name)), None, None, None, None)
+            }
+            ExpOp::DigestModulo | ExpOp::Key | ExpOp::Quoted => {
+                self.arity(pos, len, 2)?;
+                let val = self.value()?;
+                FilterExpression::new(Some(op), Some(val), None, None, None, None)
+            }
+            ExpOp::Unknown
+            | ExpOp::DeviceSize
+            | ExpOp::LastUpdate
+            | ExpOp::SinceUpdate
+            | ExpOp::VoidTime
+            | ExpOp::TTL
+            | ExpOp::SetName
+            | ExpOp::KeyExists
+            | ExpOp::IsTombstone => {
+                self.arity(pos, len, 1)?;
+                FilterExpression::new(Some(op), None, None, None, None, None)
+            }
+            ExpOp::Let => {
+                // LET <name1>, <exp1>, ..., <nameN>, <expN>, <scope exp>
+                if len % 2 != 0 {
+                    return self.fail(
+                        pos,
+                        format!("expected an even number of elements, found {}", len),
+                    );
+                }
+                let mut exps = Vec::with_capacity((len / 2).min(self.bytes.len()));
+                for _ in 0..len / 2 - 1 {
+                    let name = self.raw_string()?;
+                    let value = self.expression()?;
+                    exps.push(FilterExpression::new(
+                        None,
+                        Some(Value::String(name)),
+                        None,
+                        None,
+                        None,
+                        Some(vec![value]),
+                    ));
+                }
+                exps.push(self.expression()?);
+                FilterExpression::new(Some(op), None, None, None, None, Some(exps))
+            }
+            _ => {
+                let mut exps = Vec::with_capacity((len - 1).min(self.bytes.len()));
+                for _ in 1..len {
+                    exps.push(self.expression()?);
+                }
+                FilterExpression::new(Some(op), None, None, None, None, Some(exps))
+            }
+        };
+        Ok(exp)
+    }
+
+    /// Reads the arguments of a module call. The operation code and return type of reads and any
+    /// trailing flags are values, the operands of the operation are expressions. As both are
+    /// packed the same way if the operand is a constant, the read signatures decide which is
+    /// which; operands of writes are only decoded as expressions if they are operations.
+    fn arguments(&mut self, flags: i64) -> Result<Vec<ExpressionArgument>> {
+        let pos = self.pos;
+        let mut len = self.expect_array()?;
+        let mut context = vec![];
+
+        let start = self.pos;
+        if len == 3 && matches!(self.integer(), Ok(CONTEXT)) {
+            let ctx_pos = self.pos;
+            let ctx_len = self.expect_array()?;
+            if ctx_len % 2 != 0 {
+                return self.fail(ctx_pos, "context has an odd number of elements".to_string());
+            }
+            for _ in 0..ctx_len / 2 {
+                let id_pos = self.pos;
+                let id = self.integer()?;
+                let id = match id.try_into() {
+                    Ok(id) => id,
+                    Err(_) => return self.fail(id_pos, format!("invalid context type {}", id)),
+                };
+                let value = self.value()?;
+                context.push(CdtContext {
+                    id,
+                    flags: 0,
+                    value,
+                });
+            }
+            len = self.expect_array()?;
+        } else {
+            self.pos = start;
+        }
+
+        if len == 0 {
+            return self.fail(pos, "missing operation code".to_string());
+        }
+        let op = self.integer()?;
+        let mut arguments = vec![ExpressionArgument::Value(Value::Int(op))];
+
+        let read = flags & MODIFY == 0;
+        let signature = match flags & !MODIFY {
+            CDT_MODULE if read => {
+                let select = if op < MAP_OPS {
+                    list_op(op)
+                } else {
+                    map_op(op)
+                };
+                select.map(|select| {
+                    let values = usize::from(select != Select::Size);
+                    (values, cdt_signature(select).0.len())
+                })
+            }
+            BIT_MODULE if read => bit_signature(op).map(|(types, _)| (0, types.len())),
+            _ => None,
+        };
+
+        match signature {
+            Some((values, params)) => {
+                for idx in 1..len {
+                    if idx <= values || idx > values + params {
+                        arguments.push(ExpressionArgument::Value(self.value()?));
+                    } else {
+                        arguments.push(ExpressionArgument::FilterExpression(self.expression()?));
+                    }
+                }
+            }
+            None => {
+                for _ in 1..len {
+                    let arg = match self.peek() {
+                        Some(0x90..=0x9f | 0xdc | 0xdd) => {
+                            ExpressionArgument::FilterExpression(self.expression()?)
+                        }
+                        _ => ExpressionArgument::Value(self.value()?),
+                    };
+                    arguments.push(arg);
+                }
+            }
+        }
+
+        arguments.push(ExpressionArgument::Context(context));
+        Ok(arguments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{Error, ErrorKind};
+    use crate::expressions::{bitwise, hll, lists, maps, regex_flag::RegexFlag, *};
+    use crate::operations::bitwise::{BitPolicy, BitwiseResizeFlags};
+    use crate::operations::cdt_context::{ctx_list_index, ctx_map_key};
+    use crate::operations::hll::HLLPolicy;
+    use crate::operations::lists::{ListPolicy, ListReturnType};
+    use crate::operations::maps::{MapPolicy, MapReturnType};
+    use crate::Value;
+    use std::collections::HashMap;
+
+    fn round_trip(exp: &FilterExpression) {
+        let bytes = exp.to_bytes().unwrap();
+        let decoded =
+            FilterExpression::from_bytes(&bytes).unwrap_or_else(|err| panic!("{:?}: {}", exp, err));
+        // Hash map entries are encoded in iteration order, so only the length is stable.
+        assert_eq!(decoded.to_bytes().unwrap().len(), bytes.len(), "{:?}", exp);
+        assert_eq!(decoded.to_string(), exp.to_string());
+    }
+
+    #[test]
+    fn round_trips() {
+        let ctx = [ctx_map_key(as_val!("a")), ctx_list_index(-1)];
+        let mut map = HashMap::new();
+        map.insert(as_val!("b"), as_val!(2));
+        map.insert(as_val!(1), as_list!(1.5));
+        let cases = vec![
+            and(vec![
+                gt(int_bin("age".to_string()), int_val(21)),
+                eq(
+                    string_bin("country".to_string()),
+                    string_val("NL".to_string()),
+                ),
+                lt(ttl(), int_val(3600)),
+                not(key_exists()),
+            ]),
+            or(vec![
+                eq(float_bin("f".to_string()), float_val(-1.5)),
+                eq(blob_bin("b".to_string()), blob_val(vec![1, 2, 3])),
+                eq(key(ExpType::STRING), string_val("k".to_string())),
+                eq(digest_modulo(3), int_val(-100_000)),
+                ne(bin_type("a".to_string()), int_val(0)),
+            ]),
+            regex_compare(
+                "^a.*".to_string(),
+                RegexFlag::ICASE as i64,
+                string_bin("name".to_string()),
+            ),
+            geo_compare(geo_bin("loc".to_string()), geo_val("{\"type\":\"Point\"}")),
+            exp_let(vec![
+                def(
+                    "x".to_string(),
+                    num_add(vec![int_bin("a".to_string()), int_val(1)]),
+                ),
+                def("y".to_string(), int_val(70_000)),
+                cond(vec![
+                    gt(var("x".to_string()), var("y".to_string())),
+                    string_val("big".to_string()),
+                    string_val("small".to_string()),
+                ]),
+            ]),
+            eq(
+                list_val(vec![as_val!(1), as_val!("a"), as_list!(true, Value::Nil)]),
+                list_bin("l".to_string()),
+            ),
+            eq(map_val(map), map_bin("m".to_string())),
+            gt(
+                lists::get_by_value_range(
+                    ListReturnType::Count,
+                    Some(int_val(1)),
+                    Some(inf()),
+                    list_bin("l".to_string()),
+                    &ctx,
+                ),
+                int_val(0),
+            ),
+            eq(
+                maps::size(
+                    maps::put(
+                        &MapPolicy::default(),
+                        string_val("k".to_string()),
+                        int_val(1),
+                        map_bin("m".to_string()),
+                        &[],
+                    ),
+                    &[],
+                ),
+                int_val(1),
+            ),
+            eq(
+                maps::get_by_key(
+                    MapReturnType::Value,
+                    ExpType::INT,
+                    string_val("k".to_string()),
+                    map_bin("m".to_string()),
+                    &ctx[..1],
+                ),
+                lists::size(
+                    lists::append(
+                        ListPolicy::default(),
+                        int_val(5),
+                        list_bin("l".to_string()),
+                        &[],
+                    ),
+                    &[],
+                ),
+            ),
+            eq(
+                bitwise::get_int(
+                    int_val(0),
+                    int_val(8),
+                    true,
+                    bitwise::resize(
+                        &BitPolicy::default(),
+                        int_val(4),
+                        BitwiseResizeFlags::Default,
+                        blob_bin("b".to_string()),
+                    ),
+                ),
+                int_val(1),
+            ),
+            gt(
+                hll::get_count(hll::add(
+                    HLLPolicy::default(),
+                    list_val(vec![as_val!("a")]),
+                    hll_bin("h".to_string()),
+                )),
+                int_val(1),
+            ),
+            lt(
+                num_abs(num_sub(vec![float_bin("a".to_string()), float_val(0.5)])),
+                num_pow(float_val(2.0), float_val(3.0)),
+            ),
+            eq(
+                int_lscan(int_bin("a".to_string()), bool_val(true)),
+                unknown(),
+            ),
+        ];
+        for exp in &cases {
+            round_trip(exp);
+        }
+    }
+
+    #[test]
+    fn decoded_tree() {
+        let exp = FilterExpression::from_base64("kwOTUQKjYWdlFQ==").unwrap();
+        assert_eq!(exp.to_string(), "$.age > 21");
+        assert_eq!(exp.validate().unwrap(), ExpType::BOOL);
+
+        let filter = and(vec![
+            gt(
+                lists::get_by_index(
+                    ListReturnType::Values,
+                    ExpType::INT,
+                    int_val(0),
+                    list_bin("scores".to_string()),
+                    &[],
+                ),
+                int_val(5),
+            ),
+            regex_compare("^a".to_string(), 0, string_bin("name".to_string())),
+        ]);
+        let decoded = FilterExpression::from_base64(&filter.base64().unwrap()).unwrap();
+        assert_eq!(decoded.to_string(), filter.to_string());
+        assert_eq!(
+            decoded.to_string(),
+            "$.scores.[0] > 5 and regex($.name, '^a')"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |bytes: &[u8]| match FilterExpression::from_bytes(bytes) {
+            Err(Error(ErrorKind::InvalidExpression(pos, details), _)) => (pos, details),
+            result => panic!("unexpected result for {:?}: {:?}", bytes, result),
+        };
+
+        assert_eq!(error(&[]), (0, "unexpected end of data".to_string()));
+        assert_eq!(
+            error(&[0x93, 0x03, 0x90]),
+            (2, "empty expression".to_string())
+        );
+        assert_eq!(
+            error(&[0x93, 0x03, 0x01]),
+            (3, "unexpected end of data".to_string())
+        );
+        assert_eq!(
+            error(&[0x91, 0x60]),
+            (0, "unknown operation 96".to_string())
+        );
+        assert_eq!(
+            error(&[0x92, 0x45, 0x01]),
+            (0, "expected 1 elements, found 2".to_string())
+        );
+        assert_eq!(
+            error(&[0x93, 0x51, 0x0a, 0xa1, 0x61]),
+            (2, "unknown expression type 10".to_string())
+        );
+        assert_eq!(
+            error(&[0x93, 0x51, 0x02, 0x01]),
+            (3, "expected a string".to_string())
+        );
+        assert_eq!(
+            error(&[0x91, 0x45, 0x01]),
+            (2, "unexpected data after the expression".to_string())
+        );
+        assert_eq!(
+            error(&[0xa2, 0x09, 0x61]),
+            (0, "unsupported particle type 9".to_string())
+        );
+        assert_eq!(
+            error(&[0x81, 0x81, 0x01, 0x02, 0x03]),
+            (1, "map keys can not be maps".to_string())
+        );
+        assert!(FilterExpression::from_base64("not base64!").is_err());
+    }
+
+    #[test]
+    fn large_integers() {
+        for val in &[1 << 32, i64::from(i32::MAX), i64::MAX, i64::MIN] {
+            let exp = FilterExpression::from_bytes(&int_val(*val).to_bytes().unwrap()).unwrap();
+            assert_eq!(exp.to_string(), val.to_string());
+        }
+    }
+}
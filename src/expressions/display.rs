@@ -0,0 +1,1169 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Infix formatting of filter expressions, in the syntax accepted by `FilterExpression::parse`.
+
+use std::fmt::{self, Write};
+
+use crate::expressions::decode::exp_type;
+use crate::expressions::parser::{
+    function_name, module_bin_type, return_type_name, takes_return_type, CONTEXTS,
+};
+use crate::expressions::regex_flag::RegexFlag;
+use crate::expressions::validate::{infer, op_name};
+use crate::expressions::{ExpOp, ExpType, ExpressionArgument, FilterExpression};
+use crate::operations::cdt_context::{CdtContext, CtxType};
+use crate::operations::lists::{CdtListOpType, ListReturnType};
+use crate::operations::maps::CdtMapOpType;
+use crate::{MapReturnType, Value};
+
+// Operator precedence, from the loosest to the tightest binding.
+const OR: u8 = 1;
+const AND: u8 = 2;
+const NOT: u8 = 3;
+const COMPARISON: u8 = 4;
+const INT_OR: u8 = 5;
+const INT_XOR: u8 = 6;
+const INT_AND: u8 = 7;
+const SHIFT: u8 = 8;
+const SUM: u8 = 9;
+const PRODUCT: u8 = 10;
+const UNARY: u8 = 11;
+const PRIMARY: u8 = 12;
+
+impl fmt::Display for FilterExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (text, _) = Printer::default().print(self, ExpType::BOOL);
+        f.write_str(&text)
+    }
+}
+
+/// The formatted text of an expression and the precedence of its outermost operator.
+type Printed = (String, u8);
+
+/// Formats expressions so that parsing the text yields the same expression. Bins are only
+/// printed with an explicit type, as in `$.name.get(type: STRING)`, if the parser would infer a
+/// different type from the context they are used in, given as `hint`.
+#[derive(Default)]
+struct Printer {
+    vars: Vec<(String, Option<ExpType>)>,
+}
+
+impl Printer {
+    fn print(&mut self, exp: &FilterExpression, hint: ExpType) -> Printed {
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+        let cmd = match exp.cmd {
+            Some(cmd) => cmd,
+            None => {
+                return match (&exp.val, exps) {
+                    (Some(Value::String(name)), [value]) => {
+                        let value = self.print(value, ExpType::INT).0;
+                        (format!("def({}, {})", name, value), PRIMARY)
+                    }
+                    (Some(val), _) => literal(val),
+                    (None, _) => ("nil".to_string(), PRIMARY),
+                };
+            }
+        };
+
+        match cmd {
+            ExpOp::EQ | ExpOp::NE | ExpOp::GT | ExpOp::GE | ExpOp::LT | ExpOp::LE
+                if exps.len() == 2 =>
+            {
+                if let Some(text) = self.method(exp) {
+                    return (text, PRIMARY);
+                }
+                let sym = match cmd {
+                    ExpOp::EQ => "==",
+                    ExpOp::NE => "!=",
+                    ExpOp::GT => ">",
+                    ExpOp::GE => ">=",
+                    ExpOp::LT => "<",
+                    _ => "<=",
+                };
+                let hint = self.unify(exps, ExpType::INT);
+                self.infix(sym, COMPARISON, exps, hint)
+            }
+            ExpOp::And | ExpOp::Or if exps.len() > 1 => {
+                let (sym, prec) = if matches!(cmd, ExpOp::And) {
+                    ("and", AND)
+                } else {
+                    ("or", OR)
+                };
+                self.infix(sym, prec, exps, ExpType::BOOL)
+            }
+            ExpOp::Not if exps.len() == 1 => {
+                let operand = self.operand(&exps[0], ExpType::BOOL, NOT);
+                (format!("not {}", operand), NOT)
+            }
+            ExpOp::Add | ExpOp::Mul | ExpOp::Div if exps.len() > 1 => {
+                let (sym, prec) = match cmd {
+                    ExpOp::Add => ("+", SUM),
+                    ExpOp::Mul => ("*", PRODUCT),
+                    _ => ("/", PRODUCT),
+                };
+                let hint = self.unify(exps, hint);
+                self.infix(sym, prec, exps, hint)
+            }
+            ExpOp::Sub if exps.len() > 1 => {
+                let hint = self.unify(exps, hint);
+                self.infix("-", SUM, exps, hint)
+            }
+            ExpOp::Sub if exps.len() == 1 => {
+                let hint = self.unify(exps, hint);
+                let (operand, prec) = self.print(&exps[0], hint);
+                // A numeric literal would be folded into a negative literal.
+                if prec < UNARY || exps[0].cmd.is_none() {
+                    (format!("-({})", operand), UNARY)
+                } else {
+                    (format!("-{}", operand), UNARY)
+                }
+            }
+            ExpOp::Mod if exps.len() == 2 => {
+                let hint = self.unify(exps, hint);
+                self.infix("%", PRODUCT, exps, hint)
+            }
+            ExpOp::IntAnd | ExpOp::IntOr | ExpOp::IntXor if exps.len() > 1 => {
+                let (sym, prec) = match cmd {
+                    ExpOp::IntAnd => ("&", INT_AND),
+                    ExpOp::IntOr => ("|", INT_OR),
+                    _ => ("^", INT_XOR),
+                };
+                self.infix(sym, prec, exps, ExpType::INT)
+            }
+            ExpOp::IntLshift | ExpOp::IntRshift | ExpOp::IntARshift if exps.len() == 2 => {
+                let sym = match cmd {
+                    ExpOp::IntLshift => "<<",
+                    ExpOp::IntRshift => ">>>",
+                    _ => ">>",
+                };
+                self.infix(sym, SHIFT, exps, ExpType::INT)
+            }
+            ExpOp::IntNot if exps.len() == 1 => {
+                let operand = self.operand(&exps[0], ExpType::INT, UNARY);
+                (format!("~{}", operand), UNARY)
+            }
+            ExpOp::Abs | ExpOp::Min | ExpOp::Max if !exps.is_empty() => {
+                let name = match cmd {
+                    ExpOp::Abs => "abs",
+                    ExpOp::Min => "min",
+                    _ => "max",
+                };
+                let hint = self.unify(exps, hint);
+                self.function(name, exps, &[hint])
+            }
+            ExpOp::Floor | ExpOp::Ceil | ExpOp::Pow | ExpOp::Log | ExpOp::ToInt => {
+                let name = match cmd {
+                    ExpOp::Floor => "floor",
+                    ExpOp::Ceil => "ceil",
+                    ExpOp::Pow => "pow",
+                    ExpOp::Log => "log",
+                    _ => "to_int",
+                };
+                self.function(name, exps, &[ExpType::FLOAT])
+            }
+            ExpOp::ToFloat => self.function("to_float", exps, &[ExpType::INT]),
+            ExpOp::IntCount => self.function("count", exps, &[ExpType::INT]),
+            ExpOp::IntLscan | ExpOp::IntRscan => {
+                let name = if matches!(cmd, ExpOp::IntLscan) {
+                    "lscan"
+                } else {
+                    "rscan"
+                };
+                self.function(name, exps, &[ExpType::INT, ExpType::BOOL])
+            }
+            ExpOp::Geo => self.function("geo_compare", exps, &[ExpType::GEO]),
+            ExpOp::Regex => {
+                let bin = exp
+                    .bin
+                    .as_ref()
+                    .map(|bin| self.print(bin, ExpType::STRING).0)
+                    .unwrap_or_default();
+                let pattern = exp
+                    .val
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                let flags = regex_flags(exp.flags.unwrap_or_default());
+                let text = if flags.is_empty() {
+                    format!("regex({}, {})", bin, quote(&pattern))
+                } else {
+                    format!("regex({}, {}, {})", bin, quote(&pattern), quote(&flags))
+                };
+                (text, PRIMARY)
+            }
+            ExpOp::DigestModulo | ExpOp::Key => {
+                let val = match (cmd, &exp.val) {
+                    (ExpOp::Key, Some(Value::Int(ty))) => type_name(*ty),
+                    (_, Some(val)) => val.to_string(),
+                    (_, None) => String::new(),
+                };
+                (format!("{}({})", op_name(cmd), val), PRIMARY)
+            }
+            ExpOp::Bin => {
+                let name = exp
+                    .val
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                (
+                    typed(format!("$.{}", path_key(&name)), exp.module, hint),
+                    PRIMARY,
+                )
+            }
+            ExpOp::BinType => {
+                let name = exp
+                    .val
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                (format!("$.{}.type()", path_key(&name)), PRIMARY)
+            }
+            ExpOp::Var => {
+                let name = exp
+                    .val
+                    .as_ref()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                (format!("${{{}}}", name), PRIMARY)
+            }
+            ExpOp::Quoted => exp
+                .val
+                .as_ref()
+                .map_or_else(|| ("[]".to_string(), PRIMARY), literal),
+            ExpOp::Cond => (self.when(exps, hint), PRIMARY),
+            ExpOp::Let => (self.exp_let(exps, hint), PRIMARY),
+            ExpOp::Call => {
+                let text = self.method(exp).unwrap_or_else(|| self.call(exp, hint));
+                (text, PRIMARY)
+            }
+            ExpOp::Unknown
+            | ExpOp::DeviceSize
+            | ExpOp::LastUpdate
+            | ExpOp::SinceUpdate
+            | ExpOp::VoidTime
+            | ExpOp::TTL
+            | ExpOp::SetName
+            | ExpOp::KeyExists
+            | ExpOp::IsTombstone
+                if exps.is_empty() =>
+            {
+                (format!("{}()", op_name(cmd)), PRIMARY)
+            }
+            // Operand counts the parser does not produce and operations without infix syntax.
+            _ => {
+                let hint = self.unify(exps, hint);
+                self.function(op_name(cmd), exps, &[hint])
+            }
+        }
+    }
+
+    /// Prints `exp` for a position that requires at least precedence `prec`.
+    fn operand(&mut self, exp: &FilterExpression, hint: ExpType, prec: u8) -> String {
+        match self.print(exp, hint) {
+            (text, operand) if operand < prec => format!("({})", text),
+            (text, _) => text,
+        }
+    }
+
+    /// Operands of the same precedence are parenthesized, so that the parser builds the same
+    /// tree instead of merging them into one operation.
+    fn infix(&mut self, sym: &str, prec: u8, exps: &[FilterExpression], hint: ExpType) -> Printed {
+        let operands: Vec<String> = exps
+            .iter()
+            .map(|exp| self.operand(exp, hint, prec + 1))
+            .collect();
+        (operands.join(&format!(" {} ", sym)), prec)
+    }
+
+    /// Prints a function call; the last hint applies to all remaining arguments.
+    fn function(&mut self, name: &str, exps: &[FilterExpression], hints: &[ExpType]) -> Printed {
+        let args: Vec<String> = exps
+            .iter()
+            .enumerate()
+            .map(|(idx, exp)| {
+                let hint = hints[idx.min(hints.len() - 1)];
+                self.print(exp, hint).0
+            })
+            .collect();
+        (format!("{}({})", name, args.join(", ")), PRIMARY)
+    }
+
+    /// The type the parser infers for operands that have to share one type: the type of the
+    /// first operand whose type does not depend on the context, or `default`.
+    fn unify(&self, exps: &[FilterExpression], default: ExpType) -> ExpType {
+        exps.iter()
+            .filter(|exp| !self.deferred(exp))
+            .filter_map(|exp| infer(exp, &self.vars))
+            .find(|ty| *ty != ExpType::NIL)
+            .unwrap_or(default)
+    }
+
+    /// Whether the parser infers the type of `exp` from the context it is used in.
+    fn deferred(&self, exp: &FilterExpression) -> bool {
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+        let untyped = |exp: &FilterExpression| {
+            self.deferred(exp) || matches!((exp.cmd, &exp.val), (None, Some(Value::Nil)))
+        };
+        match exp.cmd {
+            Some(ExpOp::Bin | ExpOp::Unknown) => true,
+            Some(ExpOp::Call) => self.method(exp).is_none(),
+            Some(
+                ExpOp::Add
+                | ExpOp::Sub
+                | ExpOp::Mul
+                | ExpOp::Div
+                | ExpOp::Abs
+                | ExpOp::Min
+                | ExpOp::Max,
+            ) => exps.iter().all(untyped),
+            Some(ExpOp::Cond) => exps
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| idx % 2 == 1 || *idx == exps.len() - 1)
+                .all(|(_, exp)| untyped(exp)),
+            Some(ExpOp::Let) => {
+                let (body, defs) = match exps.split_last() {
+                    Some(split) => split,
+                    None => return false,
+                };
+                let mut printer = Printer {
+                    vars: self.vars.clone(),
+                };
+                printer.define(defs);
+                printer.deferred(body)
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds the variables of the `def` expressions of a `let` to the scope.
+    fn define(&mut self, defs: &[FilterExpression]) {
+        for def in defs {
+            if let (Some(Value::String(name)), Some([value])) = (&def.val, def.exps.as_deref()) {
+                let ty = infer(value, &self.vars).or(Some(ExpType::INT));
+                self.vars.push((name.clone(), ty));
+            }
+        }
+    }
+
+    fn exp_let(&mut self, exps: &[FilterExpression], hint: ExpType) -> String {
+        let scope = self.vars.len();
+        let (body, defs) = match exps.split_last() {
+            Some(split) => split,
+            None => return "let () then ()".to_string(),
+        };
+        let mut vars = vec![];
+        for def in defs {
+            match (&def.val, def.exps.as_deref()) {
+                (Some(Value::String(name)), Some([value])) => {
+                    let value = self.print(value, ExpType::INT).0;
+                    vars.push(format!("{} = {}", name, value));
+                }
+                _ => vars.push(self.print(def, ExpType::INT).0),
+            }
+            self.define(std::slice::from_ref(def));
+        }
+        let body = self.print(body, hint).0;
+        self.vars.truncate(scope);
+        format!("let ({}) then ({})", vars.join(", "), body)
+    }
+
+    fn when(&mut self, exps: &[FilterExpression], hint: ExpType) -> String {
+        let actions: Vec<FilterExpression> = exps
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| idx % 2 == 1 || *idx == exps.len() - 1)
+            .map(|(_, exp)| exp.clone())
+            .collect();
+        let hint = self.unify(&actions, hint);
+
+        let mut cases = vec![];
+        for pair in exps.chunks(2) {
+            match pair {
+                [condition, action] => cases.push(format!(
+                    "{} => {}",
+                    self.print(condition, ExpType::BOOL).0,
+                    self.print(action, hint).0
+                )),
+                [default] => cases.push(format!("default => {}", self.print(default, hint).0)),
+                _ => unreachable!(),
+            }
+        }
+        format!("when ({})", cases.join(", "))
+    }
+
+    /// Prints bin paths with a method, as created by the parser for `$.name.exists()`,
+    /// `$.list.[].count()`, `$.map.{}.count()`, `$.list.[].contains(x)` and
+    /// `$.map.{}.contains(x)`.
+    fn method(&self, exp: &FilterExpression) -> Option<String> {
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+        match (exp.cmd, exps) {
+            (Some(ExpOp::NE), [bin_type, zero]) if is_int(zero, 0) => {
+                match (bin_type.cmd, &bin_type.val) {
+                    (Some(ExpOp::BinType), Some(Value::String(name))) => {
+                        Some(format!("$.{}.exists()", path_key(name)))
+                    }
+                    _ => None,
+                }
+            }
+            (Some(ExpOp::GT), [count, zero]) if is_int(zero, 0) => {
+                let read = cdt_read(count)?;
+                let list = match (read.op, read.return_type) {
+                    (op, Some(rt))
+                        if op == CdtListOpType::GetByValue as i64
+                            && rt == ListReturnType::Count as i64 =>
+                    {
+                        true
+                    }
+                    (op, Some(rt))
+                        if op == CdtMapOpType::GetByKey as i64
+                            && rt == MapReturnType::Count as i64 =>
+                    {
+                        false
+                    }
+                    _ => return None,
+                };
+                match read.params.as_slice() {
+                    [item] if read.starts_with_list(list) => {
+                        let mut printer = Printer {
+                            vars: self.vars.clone(),
+                        };
+                        let hint = printer.unify(std::slice::from_ref(*item), ExpType::INT);
+                        let item = printer.print(item, hint).0;
+                        Some(format!(
+                            "{}.{}.contains({})",
+                            read.path,
+                            container(list),
+                            item
+                        ))
+                    }
+                    _ => None,
+                }
+            }
+            (Some(ExpOp::Call), _) => {
+                let read = cdt_read(exp)?;
+                let list = read.op == CdtListOpType::Size as i64;
+                let map = read.op == CdtMapOpType::Size as i64;
+                if (list || map) && read.params.is_empty() && read.starts_with_list(list) {
+                    Some(format!("{}.{}.count()", read.path, container(list)))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Prints list, map, bitwise and HLL operations by name, e.g.
+    /// `list_get_by_rank(VALUES, -1, $.scores)`, and operations without a name as
+    /// `call(<flags>, <op>, ...)`.
+    fn call(&mut self, exp: &FilterExpression, hint: ExpType) -> String {
+        if let Some(path) = read_path(exp) {
+            return typed(path, exp.module, hint);
+        }
+
+        let flags = exp.flags.unwrap_or_default();
+        let mut operands = vec![];
+        let mut ctx: &[CdtContext] = &[];
+        for arg in exp.arguments.as_deref().unwrap_or(&[]) {
+            match arg {
+                ExpressionArgument::Context(context) => ctx = context,
+                arg => operands.push(arg),
+            }
+        }
+        let op = operands.first().and_then(|arg| int_literal(arg));
+
+        let mut args = vec![];
+        let named = op.and_then(|op| {
+            let name = function_name(flags, op)?;
+            if !takes_return_type(flags, op) {
+                return Some((name, 1));
+            }
+            let return_type = operands.get(1).and_then(|arg| int_literal(arg))?;
+            args.push(return_type_name(op, return_type).unwrap_or_else(|| return_type.to_string()));
+            Some((name, 2))
+        });
+        let (name, skip) = named.unwrap_or_else(|| {
+            args.push(flags.to_string());
+            ("call", 0)
+        });
+        for arg in &operands[skip..] {
+            match arg {
+                ExpressionArgument::Value(val) => args.push(literal(val).0),
+                ExpressionArgument::FilterExpression(exp) => {
+                    args.push(self.print(exp, ExpType::INT).0);
+                }
+                ExpressionArgument::Context(_) => (),
+            }
+        }
+        if let Some(ref bin) = exp.bin {
+            let bin_type = module_bin_type(flags, op.unwrap_or_default(), ctx);
+            args.push(self.print(bin, bin_type).0);
+        }
+        if !ctx.is_empty() {
+            args.push(format!("ctx: {}", context_list(ctx)));
+        }
+        match exp.module {
+            Some(ty) if ty != hint => args.push(format!("type: {:?}", ty)),
+            _ => (),
+        }
+        format!("{}({})", name, args.join(", "))
+    }
+}
+
+/// A CDT read expression on a bin, whose context only consists of list indexes and map keys.
+struct CdtRead<'a> {
+    op: i64,
+    return_type: Option<i64>,
+    params: Vec<&'a FilterExpression>,
+    /// The bin and context as a path, e.g. `$.address.city`
+    path: String,
+    bin_type: Option<ExpType>,
+    ctx: &'a [CdtContext],
+}
+
+impl CdtRead<'_> {
+    /// Whether the bin is a list if the first path element, which is the element read by the
+    /// expression itself if there is no context, is a list element.
+    fn starts_with_list(&self, list: bool) -> bool {
+        let list = self
+            .ctx
+            .first()
+            .map_or(list, |ctx| ctx.id == CtxType::ListIndex as u8);
+        self.bin_type == Some(if list { ExpType::LIST } else { ExpType::MAP })
+    }
+}
+
+fn cdt_read(exp: &FilterExpression) -> Option<CdtRead<'_>> {
+    if !matches!(exp.cmd, Some(ExpOp::Call)) || exp.flags != Some(0) {
+        return None;
+    }
+    let bin = exp.bin.as_ref()?;
+    let name = match (bin.cmd, &bin.val) {
+        (Some(ExpOp::Bin), Some(Value::String(name))) => name,
+        _ => return None,
+    };
+
+    let mut values = vec![];
+    let mut params = vec![];
+    let mut ctx: &[CdtContext] = &[];
+    for arg in exp.arguments.as_deref().unwrap_or(&[]) {
+        match arg {
+            ExpressionArgument::Value(Value::Int(val)) => values.push(*val),
+            ExpressionArgument::Value(_) => return None,
+            ExpressionArgument::FilterExpression(exp) => params.push(exp),
+            ExpressionArgument::Context(context) => ctx = context,
+        }
+    }
+
+    let mut path = format!("$.{}", path_key(name));
+    for step in ctx {
+        path.push('.');
+        match (step.id, &step.value) {
+            (id, Value::Int(index)) if id == CtxType::ListIndex as u8 => {
+                let _ = write!(path, "[{}]", index);
+            }
+            (id, Value::String(key)) if id == CtxType::MapKey as u8 => {
+                path.push_str(&path_key(key));
+            }
+            (id, Value::Int(key)) if id == CtxType::MapKey as u8 && *key >= 0 => {
+                path.push_str(&key.to_string());
+            }
+            _ => return None,
+        }
+    }
+
+    Some(CdtRead {
+        op: *values.first()?,
+        return_type: values.get(1).copied(),
+        params,
+        path,
+        bin_type: bin.module,
+        ctx,
+    })
+}
+
+/// The path of a list element or map value read as created by the parser, e.g. `$.scores.[0]`
+/// or `$.address.city`.
+fn read_path(exp: &FilterExpression) -> Option<String> {
+    let read = cdt_read(exp)?;
+    let (list, step) = match (read.op, read.return_type, read.params.as_slice()) {
+        (op, Some(rt), [index])
+            if op == CdtListOpType::GetByIndex as i64 && rt == ListReturnType::Values as i64 =>
+        {
+            match (index.cmd, &index.val) {
+                (None, Some(Value::Int(index))) => (true, format!("[{}]", index)),
+                _ => return None,
+            }
+        }
+        (op, Some(rt), [key])
+            if op == CdtMapOpType::GetByKey as i64 && rt == MapReturnType::Value as i64 =>
+        {
+            match (key.cmd, &key.val) {
+                (None, Some(Value::String(key))) => (false, path_key(key)),
+                (None, Some(Value::Int(key))) if *key >= 0 => (false, key.to_string()),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    if read.starts_with_list(list) {
+        Some(format!("{}.{}", read.path, step))
+    } else {
+        None
+    }
+}
+
+/// Adds the type to a bin path if the parser would not infer it from the context.
+fn typed(path: String, ty: Option<ExpType>, hint: ExpType) -> String {
+    match ty {
+        Some(ty) if ty != hint => format!("{}.get(type: {:?})", path, ty),
+        _ => path,
+    }
+}
+
+const fn container(list: bool) -> &'static str {
+    if list {
+        "[]"
+    } else {
+        "{}"
+    }
+}
+
+const fn is_int(exp: &FilterExpression, val: i64) -> bool {
+    matches!((exp.cmd, &exp.val), (None, Some(Value::Int(v))) if *v == val)
+}
+
+const fn int_literal(arg: &ExpressionArgument) -> Option<i64> {
+    match arg {
+        ExpressionArgument::Value(Value::Int(val))
+        | ExpressionArgument::FilterExpression(FilterExpression {
+            cmd: None,
+            val: Some(Value::Int(val)),
+            ..
+        }) => Some(*val),
+        _ => None,
+    }
+}
+
+/// A bin name or map key in a path, quoted unless it is an identifier.
+fn path_key(key: &str) -> String {
+    let mut bytes = key.bytes();
+    let ident = matches!(bytes.next(), Some(c) if c.is_ascii_alphabetic() || c == b'_')
+        && bytes.all(|c| c.is_ascii_alphanumeric() || c == b'_');
+    if ident {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn type_name(ty: i64) -> String {
+    exp_type(ty).map_or_else(|| ty.to_string(), |ty| format!("{:?}", ty))
+}
+
+fn regex_flags(flags: i64) -> String {
+    [
+        (RegexFlag::EXTENDED as i64, 'x'),
+        (RegexFlag::ICASE as i64, 'i'),
+        (RegexFlag::NEWLINE as i64, 'n'),
+    ]
+    .iter()
+    .filter(|(flag, _)| flags & *flag != 0)
+    .map(|(_, c)| *c)
+    .collect()
+}
+
+fn context_list(ctx: &[CdtContext]) -> String {
+    let steps: Vec<String> = ctx
+        .iter()
+        .map(|step| {
+            let value = literal(&step.value).0;
+            match CONTEXTS.iter().find(|(_, id)| *id == step.id) {
+                Some((name, _)) => format!("{}({})", name, value),
+                None => format!("ctx({}, {})", step.id, value),
+            }
+        })
+        .collect();
+    format!("[{}]", steps.join(", "))
+}
+
+/// Prints a constant; negative numbers bind like a unary minus.
+fn literal(val: &Value) -> Printed {
+    let prec = match *val {
+        Value::Int(val) if val < 0 => UNARY,
+        Value::Float(ref val) if f64::from(val) < 0.0 => UNARY,
+        _ => PRIMARY,
+    };
+    (value(val), prec)
+}
+
+fn value(val: &Value) -> String {
+    match *val {
+        Value::Nil => "nil".to_string(),
+        Value::Bool(val) => val.to_string(),
+        Value::Int(val) => val.to_string(),
+        Value::UInt(val) => val.to_string(),
+        Value::Float(ref val) => format!("{:?}", f64::from(val)),
+        Value::String(ref val) => quote(val),
+        Value::Blob(ref val) => format!("blob({})", quote(&hex(val))),
+        Value::HLL(ref val) => format!("hll({})", quote(&hex(val))),
+        Value::GeoJSON(ref val) => format!("geo({})", quote(val)),
+        Value::List(ref list) => {
+            let items: Vec<String> = list.iter().map(value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::HashMap(_) | Value::OrderedMap(_) => {
+            let entries: Vec<String> = val
+                .sorted_entries()
+                .into_iter()
+                .map(|(key, val)| format!("{}: {}", value(key), value(val)))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        Value::Infinity => "inf()".to_string(),
+        Value::Wildcard => "wildcard()".to_string(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expressions::*;
+    use crate::operations::bitwise::{BitPolicy, BitwiseOverflowActions, BitwiseResizeFlags};
+    use crate::operations::cdt_context::{ctx_list_index, ctx_list_rank, ctx_map_key};
+    use crate::operations::hll::HLLPolicy;
+    use crate::operations::lists::{ListPolicy, ListReturnType, ListSortFlags};
+    use crate::operations::MapOrder;
+    use crate::{MapPolicy, MapReturnType, MapWriteMode, Value};
+
+    /// Formats the parsed expression and checks that parsing the text yields the same
+    /// expression.
+    fn round_trip(text: &str) -> String {
+        let exp = FilterExpression::parse(text).unwrap_or_else(|err| panic!("{}: {}", text, err));
+        let formatted = exp.to_string();
+        let reparsed = FilterExpression::parse(&formatted)
+            .unwrap_or_else(|err| panic!("{} -> {}: {}", text, formatted, err));
+        assert_eq!(
+            reparsed.to_bytes().unwrap(),
+            exp.to_bytes().unwrap(),
+            "{} -> {}",
+            text,
+            formatted
+        );
+        formatted
+    }
+
+    #[test]
+    fn canonical_form() {
+        let cases = [
+            (
+                "$.age > 21 && $.country == \"NL\" && ttl() < 3600",
+                "$.age > 21 and $.country == 'NL' and ttl() < 3600",
+            ),
+            ("not ($.a == 1 or $.b == 2)", "not ($.a == 1 or $.b == 2)"),
+            ("($.a + $.b) * 2 >= -3", "($.a + $.b) * 2 >= -3"),
+            ("$.a - ($.b - 1) > 0", "$.a - ($.b - 1) > 0"),
+            ("-$.f > 1.5", "-$.f > 1.5"),
+            ("-(2) == $.a", "-(2) == $.a"),
+            ("$.a & 0xff | 1 << 2 == 5", "$.a & 255 | 1 << 2 == 5"),
+            ("~$.a >>> 1 == $.b >> 2", "~$.a >>> 1 == $.b >> 2"),
+            (
+                "$.name.get(type: STRING) == $.nick.get(type: STRING)",
+                "$.name.get(type: STRING) == $.nick.get(type: STRING)",
+            ),
+            ("$.score == $.limit", "$.score == $.limit"),
+            ("$.flag", "$.flag"),
+            (
+                "regex($.name, '^a\\'b', 'i')",
+                "regex($.name, '^a\\'b', 'i')",
+            ),
+            (
+                "let (x = $.a * 2, y = $.f.get(type: FLOAT)) then (${x} > 10 and ${y} < 1.5)",
+                "let (x = $.a * 2, y = $.f.get(type: FLOAT)) then (${x} > 10 and ${y} < 1.5)",
+            ),
+            (
+                "when ($.a > 1 => 'big', $.a > 0 => 'small', default => 'none') == $.size",
+                "when ($.a > 1 => 'big', $.a > 0 => 'small', default => 'none') == $.size",
+            ),
+            (
+                "$.scores.[0] > 5 and $.address.city == 'Berlin'",
+                "$.scores.[0] > 5 and $.address.city == 'Berlin'",
+            ),
+            ("$.m.'my key'.[-1] == 'x'", "$.m.'my key'.[-1] == 'x'"),
+            (
+                "$.tags.[].count() > 2 and $.attrs.{}.contains('k')",
+                "$.tags.[].count() > 2 and $.attrs.{}.contains('k')",
+            ),
+            (
+                "$.a.exists() and $.b.type() == 3",
+                "$.a.exists() and $.b.type() == 3",
+            ),
+            (
+                "digest_modulo(3) == 1 and key(STRING) == 'k'",
+                "digest_modulo(3) == 1 and key(STRING) == 'k'",
+            ),
+            (
+                "max($.a, 3) > abs($.b) and pow($.f, 2.0) > floor($.g)",
+                "max($.a, 3) > abs($.b) and pow($.f, 2.0) > floor($.g)",
+            ),
+            (
+                "$.l == [1, 'a', [true, nil]]",
+                "$.l == [1, 'a', [true, nil]]",
+            ),
+        ];
+        for (text, expected) in &cases {
+            assert_eq!(round_trip(text), *expected);
+        }
+
+        // Hash map entries are encoded in iteration order, so compare the text only.
+        let exp = FilterExpression::parse("$.m == {'b': 2, 'a': 1}").unwrap();
+        assert_eq!(exp.to_string(), "$.m == {'a': 1, 'b': 2}");
+        let reparsed = FilterExpression::parse(&exp.to_string()).unwrap();
+        assert_eq!(reparsed.to_string(), exp.to_string());
+    }
+
+    #[test]
+    fn builder_expressions() {
+        let exp = and(vec![
+            eq(float_bin("score".into()), float_bin("best".into())),
+            geo_compare(geo_bin("loc".into()), geo_val("{\"type\":\"Point\"}")),
+        ]);
+        assert_eq!(
+            exp.to_string(),
+            "$.score.get(type: FLOAT) == $.best.get(type: FLOAT) and \
+             geo_compare($.loc, geo('{\"type\":\"Point\"}'))"
+        );
+
+        let exp = gt(
+            lists::get_by_rank(
+                ListReturnType::Values,
+                ExpType::INT,
+                int_val(-1),
+                list_bin("scores".into()),
+                &[],
+            ),
+            int_val(100),
+        );
+        assert_eq!(
+            exp.to_string(),
+            "list_get_by_rank(VALUES, -1, $.scores) > 100"
+        );
+    }
+
+    /// Checks that parsing the text of a builder expression yields the same expression.
+    fn reparse(exp: &FilterExpression) -> String {
+        let text = exp.to_string();
+        let reparsed =
+            FilterExpression::parse(&text).unwrap_or_else(|err| panic!("{}: {}", text, err));
+        assert_eq!(
+            reparsed.to_bytes().unwrap(),
+            exp.to_bytes().unwrap(),
+            "{}",
+            text
+        );
+        assert_eq!(reparsed.to_string(), text);
+        text
+    }
+
+    #[test]
+    fn module_calls() {
+        let cases = [
+            (
+                lists::size(list_bin("l".into()), &[ctx_list_rank(-1)]),
+                "list_size($.l, ctx: [list_rank(-1)], type: INT)",
+            ),
+            (
+                lists::get_by_value_range(
+                    ListReturnType::Count,
+                    None,
+                    Some(int_val(5)),
+                    list_bin("l".into()),
+                    &[],
+                ),
+                "list_get_by_value_range(COUNT, nil, 5, $.l, type: INT)",
+            ),
+            (
+                maps::put(
+                    &MapPolicy::default(),
+                    string_val("k".into()),
+                    int_val(1),
+                    map_bin("m".into()),
+                    &[ctx_map_key(Value::from("a"))],
+                ),
+                "map_put('k', 1, 0, $.m, ctx: [map_key('a')], type: MAP)",
+            ),
+            (
+                bitwise::get_int(int_val(0), int_val(8), true, blob_bin("b".into())),
+                "bit_get_int(0, 8, 1, $.b, type: INT)",
+            ),
+            (
+                hll::get_union(
+                    list_val(vec![Value::HLL(vec![0x01, 0xab])]),
+                    hll_bin("h".into()),
+                ),
+                "hll_get_union([hll('01ab')], $.h, type: HLL)",
+            ),
+        ];
+        for (exp, text) in &cases {
+            assert_eq!(reparse(exp), *text);
+        }
+
+        let list = || list_bin("l".into());
+        let map = || map_bin("m".into());
+        let blob = || blob_bin("b".into());
+        let hll = || hll_bin("h".into());
+        let items = || list_val(vec![Value::from(1), Value::from("a")]);
+        let list_policy = ListPolicy::default();
+        let bit_policy = BitPolicy::default();
+        let list_ctx = [ctx_list_index(0), ctx_list_rank(-1)];
+        let map_ctx = [ctx_map_key(Value::from("a")), ctx_list_index(2)];
+        let rt = ListReturnType::Values;
+        let map_rt = MapReturnType::KeyValue;
+
+        let exps = vec![
+            lists::append(list_policy, int_val(1), list(), &[]),
+            lists::append_items(list_policy, items(), list(), &list_ctx),
+            lists::insert(
+                list_policy,
+                int_val(0),
+                string_val("x".into()),
+                map(),
+                &map_ctx,
+            ),
+            lists::insert_items(list_policy, int_val(0), items(), list(), &[]),
+            lists::increment(list_policy, int_val(0), float_val(1.5), list(), &[]),
+            lists::set(list_policy, int_val(-1), nil(), list(), &[]),
+            lists::clear(list(), &[]),
+            lists::sort(ListSortFlags::Descending, list(), &[]),
+            lists::remove_by_value(int_val(1), list(), &[]),
+            lists::remove_by_value_list(items(), list(), &[]),
+            lists::remove_by_value_range(Some(int_val(1)), None, list(), &[]),
+            lists::remove_by_value_relative_rank_range(int_val(1), int_val(0), list(), &[]),
+            lists::remove_by_value_relative_rank_range_count(
+                int_val(1),
+                int_val(0),
+                int_val(2),
+                list(),
+                &[],
+            ),
+            lists::remove_by_index(int_val(0), list(), &[]),
+            lists::remove_by_index_range(int_val(1), list(), &[]),
+            lists::remove_by_index_range_count(int_val(1), int_val(2), list(), &[]),
+            lists::remove_by_rank(int_val(0), list(), &[]),
+            lists::remove_by_rank_range(int_val(1), list(), &[]),
+            lists::remove_by_rank_range_count(int_val(1), int_val(2), list(), &[]),
+            lists::size(lists::append(list_policy, int_val(1), list(), &[]), &[]),
+            lists::get_by_value(rt, int_val(1), list(), &list_ctx),
+            lists::get_by_value_range(rt, Some(int_val(1)), Some(inf()), list(), &[]),
+            lists::get_by_value_list(ListReturnType::Index, items(), list(), &[]),
+            lists::get_by_value_relative_rank_range(rt, int_val(1), int_val(-1), list(), &[]),
+            lists::get_by_value_relative_rank_range_count(
+                ListReturnType::ReverseRank,
+                int_val(1),
+                int_val(-1),
+                int_val(2),
+                list(),
+                &[],
+            ),
+            lists::get_by_index(rt, ExpType::STRING, int_val(0), list(), &list_ctx),
+            lists::get_by_index_range(rt, int_val(1), list(), &[]),
+            lists::get_by_index_range_count(rt, int_val(1), int_val(2), list(), &[]),
+            lists::get_by_rank(ListReturnType::Rank, ExpType::INT, int_val(0), list(), &[]),
+            lists::get_by_rank_range(rt, int_val(1), list(), &[]),
+            lists::get_by_rank_range_count(rt, int_val(1), int_val(2), list(), &[]),
+            maps::put(
+                &MapPolicy::new(MapOrder::KeyOrdered, MapWriteMode::UpdateOnly),
+                int_val(1),
+                string_val("x".into()),
+                map(),
+                &[],
+            ),
+            maps::put(
+                &MapPolicy::new(MapOrder::KeyOrdered, MapWriteMode::CreateOnly),
+                int_val(1),
+                blob_val(vec![0xff]),
+                map(),
+                &[],
+            ),
+            maps::put_items(
+                &MapPolicy::default(),
+                map_val(
+                    vec![(Value::from("k"), Value::from(1))]
+                        .into_iter()
+                        .collect(),
+                ),
+                map(),
+                &map_ctx,
+            ),
+            maps::increment(&MapPolicy::default(), int_val(1), int_val(2), map(), &[]),
+            maps::clear(map(), &[]),
+            maps::remove_by_key(string_val("k".into()), map(), &[]),
+            maps::remove_by_key_list(items(), map(), &[]),
+            maps::remove_by_key_range(None, Some(int_val(5)), map(), &[]),
+            maps::remove_by_key_relative_index_range(int_val(1), int_val(0), map(), &[]),
+            maps::remove_by_key_relative_index_range_count(
+                int_val(1),
+                int_val(0),
+                int_val(2),
+                map(),
+                &[],
+            ),
+            maps::remove_by_value(int_val(1), map(), &[]),
+            maps::remove_by_value_list(items(), map(), &[]),
+            maps::remove_by_value_range(Some(int_val(1)), Some(int_val(5)), map(), &[]),
+            maps::remove_by_value_relative_rank_range(int_val(1), int_val(0), map(), &[]),
+            maps::remove_by_value_relative_rank_range_count(
+                int_val(1),
+                int_val(0),
+                int_val(2),
+                map(),
+                &[],
+            ),
+            maps::remove_by_index(int_val(0), map(), &[]),
+            maps::remove_by_index_range(int_val(1), map(), &[]),
+            maps::remove_by_index_range_count(int_val(1), int_val(2), map(), &[]),
+            maps::remove_by_rank(int_val(0), map(), &[]),
+            maps::remove_by_rank_range(int_val(1), map(), &[]),
+            maps::remove_by_rank_range_count(int_val(1), int_val(2), map(), &[]),
+            maps::size(map(), &[ctx_list_rank(0)]),
+            maps::get_by_key(map_rt, ExpType::MAP, int_val(1), map(), &map_ctx),
+            maps::get_by_key_range(map_rt, Some(int_val(1)), None, map(), &[]),
+            maps::get_by_key_list(MapReturnType::Key, items(), map(), &[]),
+            maps::get_by_key_relative_index_range(map_rt, int_val(1), int_val(0), map(), &[]),
+            maps::get_by_key_relative_index_range_count(
+                map_rt,
+                int_val(1),
+                int_val(0),
+                int_val(2),
+                map(),
+                &[],
+            ),
+            maps::get_by_value(MapReturnType::Count, int_val(1), map(), &[]),
+            maps::get_by_value_range(map_rt, Some(wildcard()), None, map(), &[]),
+            maps::get_by_value_list(map_rt, items(), map(), &[]),
+            maps::get_by_value_relative_rank_range(map_rt, int_val(1), int_val(0), map(), &[]),
+            maps::get_by_value_relative_rank_range_count(
+                map_rt,
+                int_val(1),
+                int_val(0),
+                int_val(2),
+                map(),
+                &[],
+            ),
+            maps::get_by_index(MapReturnType::Value, ExpType::FLOAT, int_val(0), map(), &[]),
+            maps::get_by_index_range(map_rt, int_val(1), map(), &[]),
+            maps::get_by_index_range_count(map_rt, int_val(1), int_val(2), map(), &[]),
+            maps::get_by_rank(MapReturnType::None, ExpType::NIL, int_val(0), map(), &[]),
+            maps::get_by_rank_range(map_rt, int_val(1), map(), &[]),
+            maps::get_by_rank_range_count(map_rt, int_val(1), int_val(2), map(), &[]),
+            bitwise::resize(
+                &bit_policy,
+                int_val(4),
+                BitwiseResizeFlags::GrowOnly,
+                blob(),
+            ),
+            bitwise::insert(&bit_policy, int_val(1), blob_val(vec![0xff, 0x00]), blob()),
+            bitwise::remove(&bit_policy, int_val(1), int_val(2), blob()),
+            bitwise::set(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                blob_val(vec![0x80]),
+                blob(),
+            ),
+            bitwise::or(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                blob_val(vec![0x01]),
+                blob(),
+            ),
+            bitwise::xor(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                blob_val(vec![0x01]),
+                blob(),
+            ),
+            bitwise::and(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                blob_val(vec![0x01]),
+                blob(),
+            ),
+            bitwise::not(&bit_policy, int_val(0), int_val(8), blob()),
+            bitwise::lshift(&bit_policy, int_val(0), int_val(8), int_val(2), blob()),
+            bitwise::rshift(&bit_policy, int_val(0), int_val(8), int_val(2), blob()),
+            bitwise::add(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                int_val(1),
+                true,
+                BitwiseOverflowActions::Saturate,
+                blob(),
+            ),
+            bitwise::subtract(
+                &bit_policy,
+                int_val(0),
+                int_val(8),
+                int_val(1),
+                false,
+                BitwiseOverflowActions::Fail,
+                blob(),
+            ),
+            bitwise::set_int(&bit_policy, int_val(0), int_val(8), int_val(7), blob()),
+            bitwise::get(int_val(0), int_val(8), blob()),
+            bitwise::count(int_val(0), int_val(8), blob()),
+            bitwise::lscan(int_val(0), int_val(8), bool_val(true), blob()),
+            bitwise::rscan(int_val(0), int_val(8), bool_val(false), blob()),
+            bitwise::get_int(int_val(0), int_val(8), false, blob()),
+            hll::init(HLLPolicy::default(), int_val(10), hll()),
+            hll::init_with_min_hash(HLLPolicy::default(), int_val(10), int_val(4), hll()),
+            hll::add(HLLPolicy::default(), items(), hll()),
+            hll::add_with_index(HLLPolicy::default(), items(), int_val(10), hll()),
+            hll::add_with_index_and_min_hash(
+                HLLPolicy::default(),
+                items(),
+                int_val(10),
+                int_val(4),
+                hll(),
+            ),
+            hll::get_count(hll()),
+            hll::get_union_count(list_val(vec![Value::HLL(vec![0x01])]), hll()),
+            hll::get_intersect_count(list_val(vec![Value::HLL(vec![0x01])]), hll()),
+            hll::get_similarity(list_val(vec![Value::HLL(vec![0x01])]), hll()),
+            hll::describe(hll()),
+            hll::may_contain(items(), hll()),
+        ];
+        for exp in &exps {
+            reparse(exp);
+            reparse(&eq(exp.clone(), exp.clone()));
+        }
+    }
+}
@@ -0,0 +1,1244 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Client-side evaluation of filter expressions, see `FilterExpression::evaluate`.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::expressions::bitwise::BitExpOp;
+use crate::expressions::{ExpOp, ExpType, ExpressionArgument, FilterExpression, MODIFY};
+use crate::operations::cdt_context::{CdtContext, CtxType};
+use crate::operations::lists::{CdtListOpType, ListReturnType};
+use crate::operations::maps::CdtMapOpType;
+use crate::record::CITRUSLEAF_EPOCH;
+use crate::value::FloatValue;
+use crate::{Key, MapReturnType, ParticleType, Record, Value};
+
+const CDT_MODULE: i64 = 0;
+const BIT_MODULE: i64 = 1;
+const HLL_MODULE: i64 = 2;
+
+// List and map return types share their numeric values.
+const RETURN_NONE: i64 = ListReturnType::None as i64;
+const RETURN_INDEX: i64 = ListReturnType::Index as i64;
+const RETURN_REVERSE_INDEX: i64 = ListReturnType::ReverseIndex as i64;
+const RETURN_RANK: i64 = ListReturnType::Rank as i64;
+const RETURN_REVERSE_RANK: i64 = ListReturnType::ReverseRank as i64;
+const RETURN_COUNT: i64 = ListReturnType::Count as i64;
+const RETURN_KEY: i64 = MapReturnType::Key as i64;
+const RETURN_VALUE: i64 = ListReturnType::Values as i64;
+const RETURN_KEY_VALUE: i64 = MapReturnType::KeyValue as i64;
+const RETURN_INVERTED: i64 = ListReturnType::Inverted as i64;
+
+const BIT_GET: i64 = BitExpOp::Get as i64;
+const BIT_COUNT: i64 = BitExpOp::Count as i64;
+const BIT_LSCAN: i64 = BitExpOp::LScan as i64;
+const BIT_RSCAN: i64 = BitExpOp::RScan as i64;
+const BIT_GET_INT: i64 = BitExpOp::GetInt as i64;
+
+/// Evaluates `exp` against `record`, whose key is `key`.
+pub fn evaluate(exp: &FilterExpression, record: &Record, key: &Key) -> Result<Value> {
+    Evaluator {
+        record,
+        key,
+        vars: vec![],
+    }
+    .eval(exp)
+}
+
+fn unknown<S: Into<String>>(details: S) -> Error {
+    ErrorKind::UnknownExpressionValue(details.into()).into()
+}
+
+fn unsupported<S: Into<String>>(details: S) -> Error {
+    ErrorKind::UnsupportedExpression(details.into()).into()
+}
+
+fn operand(exps: &[FilterExpression], idx: usize) -> Result<&FilterExpression> {
+    exps.get(idx)
+        .ok_or_else(|| unsupported(format!("missing operand {}", idx + 1)))
+}
+
+pub(super) const fn exp_type(value: &Value) -> ExpType {
+    match value {
+        Value::Bool(_) => ExpType::BOOL,
+        Value::Int(_) | Value::UInt(_) => ExpType::INT,
+        Value::Float(_) => ExpType::FLOAT,
+        Value::String(_) => ExpType::STRING,
+        Value::Blob(_) => ExpType::BLOB,
+        Value::List(_) => ExpType::LIST,
+        Value::HashMap(_) | Value::OrderedMap(_) => ExpType::MAP,
+        Value::GeoJSON(_) => ExpType::GEO,
+        Value::HLL(_) => ExpType::HLL,
+        Value::Nil | Value::Infinity | Value::Wildcard => ExpType::NIL,
+    }
+}
+
+fn as_int(value: &Value) -> Result<i64> {
+    match *value {
+        Value::Int(val) => Ok(val),
+        Value::UInt(val) => i64::try_from(val).map_err(|_| unknown("integer out of range")),
+        _ => Err(unknown(format!(
+            "expected INT, found {:?}",
+            exp_type(value)
+        ))),
+    }
+}
+
+fn as_bool(value: &Value) -> Result<bool> {
+    match *value {
+        Value::Bool(val) => Ok(val),
+        _ => Err(unknown(format!(
+            "expected BOOL, found {:?}",
+            exp_type(value)
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+fn as_number(value: &Value) -> Result<Number> {
+    match *value {
+        Value::Float(FloatValue::F64(bits)) => Ok(Number::Float(f64::from_bits(bits))),
+        Value::Float(FloatValue::F32(bits)) => Ok(Number::Float(f64::from(f32::from_bits(bits)))),
+        _ => as_int(value).map(Number::Int),
+    }
+}
+
+/// Splits numeric operands into integers or floats; mixing both is not allowed.
+fn numbers(values: &[Value]) -> Result<std::result::Result<Vec<i64>, Vec<f64>>> {
+    let numbers = values.iter().map(as_number).collect::<Result<Vec<_>>>()?;
+    if numbers.iter().all(|num| matches!(num, Number::Int(_))) {
+        Ok(Ok(numbers
+            .into_iter()
+            .filter_map(|num| match num {
+                Number::Int(val) => Some(val),
+                Number::Float(_) => None,
+            })
+            .collect()))
+    } else if numbers.iter().all(|num| matches!(num, Number::Float(_))) {
+        Ok(Err(numbers
+            .into_iter()
+            .filter_map(|num| match num {
+                Number::Float(val) => Some(val),
+                Number::Int(_) => None,
+            })
+            .collect()))
+    } else {
+        Err(unknown("mixed INT and FLOAT operands"))
+    }
+}
+
+fn compare(op: ExpOp, left: &Value, right: &Value) -> Result<Value> {
+    let ordering = left.cmp(right);
+    let result = match op {
+        ExpOp::EQ => ordering.is_eq(),
+        ExpOp::NE => ordering.is_ne(),
+        _ if exp_type(left) != exp_type(right) => {
+            return Err(unknown(format!(
+                "can not compare {:?} with {:?}",
+                exp_type(left),
+                exp_type(right)
+            )))
+        }
+        ExpOp::GT => ordering.is_gt(),
+        ExpOp::GE => ordering.is_ge(),
+        ExpOp::LT => ordering.is_lt(),
+        _ => ordering.is_le(),
+    };
+    Ok(Value::Bool(result))
+}
+
+fn arithmetic(op: ExpOp, values: &[Value]) -> Result<Value> {
+    match numbers(values)? {
+        Ok(ints) => {
+            let (first, rest) = ints
+                .split_first()
+                .ok_or_else(|| unsupported("no operands"))?;
+            let result = match (op, rest.is_empty()) {
+                (ExpOp::Sub, true) => first.wrapping_neg(),
+                (ExpOp::Div, true) => 1_i64
+                    .checked_div(*first)
+                    .ok_or_else(|| unknown("division by zero"))?,
+                _ => rest.iter().try_fold(*first, |acc, val| match op {
+                    ExpOp::Add => Ok(acc.wrapping_add(*val)),
+                    ExpOp::Sub => Ok(acc.wrapping_sub(*val)),
+                    ExpOp::Mul => Ok(acc.wrapping_mul(*val)),
+                    _ => acc
+                        .checked_div(*val)
+                        .ok_or_else(|| unknown("division by zero")),
+                })?,
+            };
+            Ok(Value::from(result))
+        }
+        Err(floats) => {
+            let (first, rest) = floats
+                .split_first()
+                .ok_or_else(|| unsupported("no operands"))?;
+            let result = match (op, rest.is_empty()) {
+                (ExpOp::Sub, true) => -first,
+                (ExpOp::Div, true) => 1.0 / first,
+                _ => rest.iter().fold(*first, |acc, val| match op {
+                    ExpOp::Add => acc + val,
+                    ExpOp::Sub => acc - val,
+                    ExpOp::Mul => acc * val,
+                    _ => acc / val,
+                }),
+            };
+            Ok(Value::from(result))
+        }
+    }
+}
+
+fn min_max(op: ExpOp, values: &[Value]) -> Result<Value> {
+    let result = match numbers(values)? {
+        Ok(ints) => {
+            let result = if matches!(op, ExpOp::Min) {
+                ints.into_iter().min()
+            } else {
+                ints.into_iter().max()
+            };
+            result.map(Value::from)
+        }
+        Err(floats) => {
+            let result = floats.into_iter().reduce(|acc, val| {
+                if matches!(op, ExpOp::Min) {
+                    acc.min(val)
+                } else {
+                    acc.max(val)
+                }
+            });
+            result.map(Value::from)
+        }
+    };
+    result.ok_or_else(|| unsupported("no operands"))
+}
+
+fn shift(op: ExpOp, value: i64, shift: i64) -> i64 {
+    match u32::try_from(shift).ok().filter(|shift| *shift < 64) {
+        Some(shift) => match op {
+            ExpOp::IntLshift => value << shift,
+            ExpOp::IntRshift => ((value as u64) >> shift) as i64,
+            _ => value >> shift,
+        },
+        None if matches!(op, ExpOp::IntARshift) && value < 0 => -1,
+        None => 0,
+    }
+}
+
+/// Bit index (from the most significant bit) of the first or last bit equal to `search`.
+fn scan(value: i64, search: bool, from_left: bool) -> i64 {
+    let bit = |idx: i64| ((value >> (63 - idx)) & 1 == 1) == search;
+    let found = if from_left {
+        (0..64).find(|idx| bit(*idx))
+    } else {
+        (0..64).rev().find(|idx| bit(*idx))
+    };
+    found.unwrap_or(-1)
+}
+
+struct Evaluator<'a> {
+    record: &'a Record,
+    key: &'a Key,
+    vars: Vec<(String, Value)>,
+}
+
+impl Evaluator<'_> {
+    fn eval(&mut self, exp: &FilterExpression) -> Result<Value> {
+        let cmd = match exp.cmd {
+            Some(cmd) => cmd,
+            None if exp.exps.is_some() => {
+                return Err(unsupported("variable definition outside of let"))
+            }
+            None => {
+                return exp
+                    .val
+                    .clone()
+                    .ok_or_else(|| unsupported("empty expression"))
+            }
+        };
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+
+        match cmd {
+            ExpOp::Unknown => Err(unknown("unknown() was evaluated")),
+            ExpOp::EQ | ExpOp::NE | ExpOp::GT | ExpOp::GE | ExpOp::LT | ExpOp::LE => {
+                let left = self.eval(operand(exps, 0)?)?;
+                let right = self.eval(operand(exps, 1)?)?;
+                compare(cmd, &left, &right)
+            }
+            ExpOp::Regex => Err(unsupported(
+                "regular expressions can not be evaluated on the client",
+            )),
+            ExpOp::Geo => Err(unsupported(
+                "geospatial comparisons can not be evaluated on the client",
+            )),
+            ExpOp::And => {
+                for exp in exps {
+                    if !self.eval_bool(exp)? {
+                        return Ok(Value::Bool(false));
+                    }
+                }
+                Ok(Value::Bool(true))
+            }
+            ExpOp::Or => {
+                for exp in exps {
+                    if self.eval_bool(exp)? {
+                        return Ok(Value::Bool(true));
+                    }
+                }
+                Ok(Value::Bool(false))
+            }
+            ExpOp::Not => Ok(Value::Bool(!self.eval_bool(operand(exps, 0)?)?)),
+            ExpOp::Xor => {
+                let mut count = 0;
+                for exp in exps {
+                    if self.eval_bool(exp)? {
+                        count += 1;
+                    }
+                }
+                Ok(Value::Bool(count == 1))
+            }
+            ExpOp::Add | ExpOp::Sub | ExpOp::Mul | ExpOp::Div => {
+                arithmetic(cmd, &self.eval_all(exps)?)
+            }
+            ExpOp::Pow | ExpOp::Log => {
+                let values = self.eval_all(exps)?;
+                match numbers(&values)? {
+                    Err(floats) if floats.len() == 2 => {
+                        Ok(Value::from(if matches!(cmd, ExpOp::Pow) {
+                            floats[0].powf(floats[1])
+                        } else {
+                            floats[0].log(floats[1])
+                        }))
+                    }
+                    _ => Err(unknown(format!("{:?} requires two FLOAT operands", cmd))),
+                }
+            }
+            ExpOp::Mod => {
+                let left = as_int(&self.eval(operand(exps, 0)?)?)?;
+                let right = as_int(&self.eval(operand(exps, 1)?)?)?;
+                left.checked_rem(right)
+                    .map(Value::from)
+                    .ok_or_else(|| unknown("division by zero"))
+            }
+            ExpOp::Abs => match as_number(&self.eval(operand(exps, 0)?)?)? {
+                Number::Int(val) => Ok(Value::from(val.wrapping_abs())),
+                Number::Float(val) => Ok(Value::from(val.abs())),
+            },
+            ExpOp::Floor | ExpOp::Ceil | ExpOp::ToInt => {
+                match as_number(&self.eval(operand(exps, 0)?)?)? {
+                    Number::Float(val) => Ok(match cmd {
+                        ExpOp::Floor => Value::from(val.floor()),
+                        ExpOp::Ceil => Value::from(val.ceil()),
+                        _ => Value::from(val as i64),
+                    }),
+                    Number::Int(_) => Err(unknown(format!("{:?} requires a FLOAT operand", cmd))),
+                }
+            }
+            ExpOp::ToFloat => {
+                let val = as_int(&self.eval(operand(exps, 0)?)?)?;
+                Ok(Value::from(val as f64))
+            }
+            ExpOp::IntAnd | ExpOp::IntOr | ExpOp::IntXor => {
+                let mut result = None;
+                for exp in exps {
+                    let val = as_int(&self.eval(exp)?)?;
+                    result = Some(match (result, cmd) {
+                        (None, _) => val,
+                        (Some(acc), ExpOp::IntAnd) => acc & val,
+                        (Some(acc), ExpOp::IntOr) => acc | val,
+                        (Some(acc), _) => acc ^ val,
+                    });
+                }
+                result
+                    .map(Value::from)
+                    .ok_or_else(|| unsupported("no operands"))
+            }
+            ExpOp::IntNot => Ok(Value::from(!as_int(&self.eval(operand(exps, 0)?)?)?)),
+            ExpOp::IntLshift | ExpOp::IntRshift | ExpOp::IntARshift => {
+                let value = as_int(&self.eval(operand(exps, 0)?)?)?;
+                let bits = as_int(&self.eval(operand(exps, 1)?)?)?;
+                Ok(Value::from(shift(cmd, value, bits)))
+            }
+            ExpOp::IntCount => {
+                let value = as_int(&self.eval(operand(exps, 0)?)?)?;
+                Ok(Value::from(i64::from(value.count_ones())))
+            }
+            ExpOp::IntLscan | ExpOp::IntRscan => {
+                let value = as_int(&self.eval(operand(exps, 0)?)?)?;
+                let search = self.eval_bool(operand(exps, 1)?)?;
+                Ok(Value::from(scan(
+                    value,
+                    search,
+                    matches!(cmd, ExpOp::IntLscan),
+                )))
+            }
+            ExpOp::Min | ExpOp::Max => min_max(cmd, &self.eval_all(exps)?),
+            ExpOp::DigestModulo => {
+                let modulo = exp.val.as_ref().map_or(Ok(0), as_int)?;
+                if modulo <= 0 {
+                    return Err(unknown("digest modulo must be positive"));
+                }
+                // The server takes the modulo of the digest's bytes 16 to 19.
+                let mut bytes = [0; 4];
+                bytes.copy_from_slice(&self.key.digest[16..20]);
+                Ok(Value::from(i64::from(u32::from_le_bytes(bytes)) % modulo))
+            }
+            ExpOp::DeviceSize | ExpOp::LastUpdate | ExpOp::SinceUpdate => Err(unsupported(
+                format!("{:?} is not available on the client", cmd),
+            )),
+            ExpOp::VoidTime => Ok(Value::from(match self.record.expiration() {
+                0 => -1,
+                expiration => {
+                    let void_time = *CITRUSLEAF_EPOCH + Duration::from_secs(u64::from(expiration));
+                    let nanos = void_time
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_nanos();
+                    i64::try_from(nanos).unwrap_or(i64::MAX)
+                }
+            })),
+            ExpOp::TTL => {
+                Ok(Value::from(self.record.time_to_live().map_or(-1, |ttl| {
+                    i64::try_from(ttl.as_secs()).unwrap_or(i64::MAX)
+                })))
+            }
+            ExpOp::SetName => Ok(Value::from(self.key.set_name.clone())),
+            ExpOp::KeyExists => Ok(Value::Bool(self.key.user_key.is_some())),
+            ExpOp::IsTombstone => Ok(Value::Bool(false)),
+            ExpOp::Key => {
+                let ty = exp.val.as_ref().map_or(Ok(-1), as_int)?;
+                match self.key.user_key {
+                    Some(ref key) if exp_type(key) as i64 == ty => Ok(key.clone()),
+                    Some(ref key) => Err(unknown(format!(
+                        "record key is of type {:?}",
+                        exp_type(key)
+                    ))),
+                    None => Err(unknown("record key is not stored")),
+                }
+            }
+            ExpOp::Bin => {
+                let name = exp.val.as_ref().map(Value::to_string).unwrap_or_default();
+                let value = self
+                    .record
+                    .bins
+                    .get(&name)
+                    .ok_or_else(|| unknown(format!("bin '{}' does not exist", name)))?;
+                match exp.module {
+                    Some(ty) if ty != exp_type(value) => Err(unknown(format!(
+                        "bin '{}' is of type {:?}, expected {:?}",
+                        name,
+                        exp_type(value),
+                        ty
+                    ))),
+                    _ => Ok(value.clone()),
+                }
+            }
+            ExpOp::BinType => {
+                let name = exp.val.as_ref().map(Value::to_string).unwrap_or_default();
+                let particle_type = match self.record.bins.get(&name) {
+                    None => ParticleType::NULL,
+                    Some(Value::UInt(_)) => ParticleType::INTEGER,
+                    Some(value) => value.particle_type(),
+                };
+                Ok(Value::from(particle_type as i64))
+            }
+            ExpOp::Cond => {
+                let mut pairs = exps.chunks_exact(2);
+                for pair in &mut pairs {
+                    if self.eval_bool(&pair[0])? {
+                        return self.eval(&pair[1]);
+                    }
+                }
+                match pairs.remainder() {
+                    [default] => self.eval(default),
+                    _ => Err(unknown("no condition matched")),
+                }
+            }
+            ExpOp::Let => {
+                let (scope, defs) = exps
+                    .split_last()
+                    .ok_or_else(|| unsupported("empty let expression"))?;
+                let len = self.vars.len();
+                let result = self.eval_let(defs, scope);
+                self.vars.truncate(len);
+                result
+            }
+            ExpOp::Var => {
+                let name = exp.val.as_ref().map(Value::to_string).unwrap_or_default();
+                self.vars
+                    .iter()
+                    .rev()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.clone())
+                    .ok_or_else(|| unknown(format!("undefined variable '{}'", name)))
+            }
+            ExpOp::Quoted => exp
+                .val
+                .clone()
+                .ok_or_else(|| unsupported("empty expression")),
+            ExpOp::Call => self.call(exp),
+        }
+    }
+
+    fn eval_bool(&mut self, exp: &FilterExpression) -> Result<bool> {
+        as_bool(&self.eval(exp)?)
+    }
+
+    fn eval_all(&mut self, exps: &[FilterExpression]) -> Result<Vec<Value>> {
+        exps.iter().map(|exp| self.eval(exp)).collect()
+    }
+
+    fn eval_let(&mut self, defs: &[FilterExpression], scope: &FilterExpression) -> Result<Value> {
+        for def in defs {
+            let name = def.val.as_ref().map(Value::to_string).unwrap_or_default();
+            let value = self.eval(operand(def.exps.as_deref().unwrap_or(&[]), 0)?)?;
+            self.vars.push((name, value));
+        }
+        self.eval(scope)
+    }
+
+    fn call(&mut self, exp: &FilterExpression) -> Result<Value> {
+        let flags = exp.flags.unwrap_or_default();
+        if flags & MODIFY != 0 {
+            return Err(unsupported(
+                "modify expressions can not be evaluated on the client",
+            ));
+        } else if flags == HLL_MODULE {
+            return Err(unsupported(
+                "HLL expressions can not be evaluated on the client",
+            ));
+        }
+
+        let mut ctx: &[CdtContext] = &[];
+        let mut params = vec![];
+        for arg in exp.arguments.as_deref().unwrap_or(&[]) {
+            match arg {
+                ExpressionArgument::Context(context) => ctx = context,
+                ExpressionArgument::Value(value) => params.push(value.clone()),
+                ExpressionArgument::FilterExpression(exp) => params.push(self.eval(exp)?),
+            }
+        }
+        if params.is_empty() {
+            return Err(unsupported("missing operation code"));
+        }
+        let op = as_int(&params.remove(0))?;
+        let bin = match exp.bin {
+            Some(ref bin) => self.eval(bin)?,
+            None => return Err(unsupported("missing bin expression")),
+        };
+
+        match flags {
+            CDT_MODULE => {
+                let (select, map) = list_op(op)
+                    .map(|select| (select, false))
+                    .or_else(|| map_op(op).map(|select| (select, true)))
+                    .ok_or_else(|| unsupported(format!("unsupported CDT operation {}", op)))?;
+                cdt_read(navigate(bin, ctx)?, map, select, &params)
+            }
+            BIT_MODULE => bit_read(op, bin, &params),
+            _ => Err(unsupported(format!("unsupported module {}", flags))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Select {
+    Size,
+    Index,
+    IndexRange,
+    Rank,
+    RankRange,
+    Value,
+    ValueList,
+    ValueInterval,
+    ValueRelRankRange,
+    Key,
+    KeyList,
+    KeyInterval,
+    KeyRelIndexRange,
+}
+
+pub(super) fn list_op(op: i64) -> Option<Select> {
+    [
+        (CdtListOpType::Size, Select::Size),
+        (CdtListOpType::GetByIndex, Select::Index),
+        (CdtListOpType::GetByIndexRange, Select::IndexRange),
+        (CdtListOpType::GetByRank, Select::Rank),
+        (CdtListOpType::GetByRankRange, Select::RankRange),
+        (CdtListOpType::GetByValue, Select::Value),
+        (CdtListOpType::GetByValueList, Select::ValueList),
+        (CdtListOpType::GetByValueInterval, Select::ValueInterval),
+        (
+            CdtListOpType::GetByValueRelRankRange,
+            Select::ValueRelRankRange,
+        ),
+    ]
+    .iter()
+    .find(|(code, _)| *code as i64 == op)
+    .map(|(_, select)| *select)
+}
+
+pub(super) fn map_op(op: i64) -> Option<Select> {
+    [
+        (CdtMapOpType::Size, Select::Size),
+        (CdtMapOpType::GetByKey, Select::Key),
+        (CdtMapOpType::GetByKeyList, Select::KeyList),
+        (CdtMapOpType::GetByKeyInterval, Select::KeyInterval),
+        (
+            CdtMapOpType::GetByKeyRelIndexRange,
+            Select::KeyRelIndexRange,
+        ),
+        (CdtMapOpType::GetByIndex, Select::Index),
+        (CdtMapOpType::GetByIndexRange, Select::IndexRange),
+        (CdtMapOpType::GetByRank, Select::Rank),
+        (CdtMapOpType::GetByRankRange, Select::RankRange),
+        (CdtMapOpType::GetByValue, Select::Value),
+        (CdtMapOpType::GetByValueList, Select::ValueList),
+        (CdtMapOpType::GetByValueInterval, Select::ValueInterval),
+        (
+            CdtMapOpType::GetByValueRelRankRange,
+            Select::ValueRelRankRange,
+        ),
+    ]
+    .iter()
+    .find(|(code, _)| *code as i64 == op)
+    .map(|(_, select)| *select)
+}
+
+/// Whether `value` matches `pattern`, where wildcards in the pattern match any value, including
+/// all remaining elements of a list if the wildcard is the pattern's last element.
+fn matches(pattern: &Value, value: &Value) -> bool {
+    match (pattern, value) {
+        (Value::Wildcard, _) => true,
+        (Value::List(pattern), Value::List(list)) => match pattern.split_last() {
+            Some((Value::Wildcard, head)) if list.len() >= head.len() => head
+                .iter()
+                .zip(list)
+                .all(|(pattern, value)| matches(pattern, value)),
+            _ => {
+                pattern.len() == list.len()
+                    && pattern
+                        .iter()
+                        .zip(list)
+                        .all(|(pattern, value)| matches(pattern, value))
+            }
+        },
+        _ => pattern.cmp(value).is_eq(),
+    }
+}
+
+/// Resolves a possibly negative index or rank against a collection of `len` elements.
+fn resolve_index(index: i64, len: usize) -> Result<usize> {
+    let len = i64::try_from(len).unwrap_or(i64::MAX);
+    let resolved = if index < 0 { len + index } else { index };
+    if (0..len).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(unknown(format!("index {} out of bounds", index)))
+    }
+}
+
+/// The part of `start..start + count` that lies within `0..len`.
+fn window(start: i64, count: Option<i64>, len: usize) -> Range<usize> {
+    let len = i64::try_from(len).unwrap_or(i64::MAX);
+    let end = count.map_or(len, |count| start.saturating_add(count.max(0)));
+    let clamp = |idx: i64| idx.max(0).min(len) as usize;
+    clamp(start)..clamp(end).max(clamp(start))
+}
+
+fn index_window(index: i64, count: Option<i64>, len: usize) -> Range<usize> {
+    let start = if index < 0 {
+        i64::try_from(len).unwrap_or(i64::MAX) + index
+    } else {
+        index
+    };
+    window(start, count, len)
+}
+
+fn navigate(mut value: Value, ctx: &[CdtContext]) -> Result<Value> {
+    for context in ctx {
+        let id = context.id & 0x3f;
+        let map = id & CtxType::MapIndex as u8 != 0;
+        let elements = Elements::new(value, map)?;
+        let pos = if id == CtxType::ListIndex as u8 || id == CtxType::MapIndex as u8 {
+            resolve_index(as_int(&context.value)?, elements.len())?
+        } else if id == CtxType::ListRank as u8 || id == CtxType::MapRank as u8 {
+            elements.by_rank()[resolve_index(as_int(&context.value)?, elements.len())?]
+        } else if id == CtxType::ListValue as u8 || id == CtxType::MapValue as u8 {
+            elements
+                .values
+                .iter()
+                .position(|value| matches(&context.value, value))
+                .ok_or_else(|| unknown("context value not found"))?
+        } else if id == CtxType::MapKey as u8 {
+            elements
+                .keys
+                .iter()
+                .position(|key| key.cmp(&context.value).is_eq())
+                .ok_or_else(|| unknown("context key not found"))?
+        } else {
+            return Err(unsupported(format!("unsupported context type {}", id)));
+        };
+        value = elements.values.into_iter().nth(pos).unwrap_or_default();
+    }
+    Ok(value)
+}
+
+/// The elements of a list, or the entries of a map in key order.
+struct Elements {
+    keys: Vec<Value>,
+    values: Vec<Value>,
+    map: bool,
+}
+
+impl Elements {
+    fn new(value: Value, map: bool) -> Result<Self> {
+        match value {
+            Value::List(values) if !map => Ok(Elements {
+                keys: vec![],
+                values,
+                map,
+            }),
+            Value::HashMap(_) | Value::OrderedMap(_) if map => {
+                let (keys, values) = value
+                    .sorted_entries()
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .unzip();
+                Ok(Elements { keys, values, map })
+            }
+            value => Err(unknown(format!(
+                "expected {}, found {:?}",
+                if map { "MAP" } else { "LIST" },
+                exp_type(&value)
+            ))),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Element positions in value order.
+    fn by_rank(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by(|a, b| self.values[*a].cmp(&self.values[*b]));
+        order
+    }
+
+    fn items(&self, select: Select) -> &[Value] {
+        match select {
+            Select::Key | Select::KeyList | Select::KeyInterval | Select::KeyRelIndexRange => {
+                &self.keys
+            }
+            _ => &self.values,
+        }
+    }
+}
+
+fn param(params: &[Value], idx: usize) -> Result<&Value> {
+    params
+        .get(idx)
+        .ok_or_else(|| unsupported(format!("missing parameter {}", idx + 1)))
+}
+
+fn optional_int(params: &[Value], idx: usize) -> Result<Option<i64>> {
+    params.get(idx).map(as_int).transpose()
+}
+
+fn cdt_read(target: Value, map: bool, select: Select, params: &[Value]) -> Result<Value> {
+    let elements = Elements::new(target, map)?;
+    let len = elements.len();
+    if select == Select::Size {
+        return Ok(Value::from(len as i64));
+    }
+
+    let return_type = as_int(param(params, 0)?)?;
+    let params = &params[1..];
+    let items = elements.items(select);
+    let single = matches!(select, Select::Index | Select::Rank | Select::Key);
+    let selected: Vec<usize> = match select {
+        Select::Size => vec![],
+        Select::Index => vec![resolve_index(as_int(param(params, 0)?)?, len)?],
+        Select::IndexRange => {
+            index_window(as_int(param(params, 0)?)?, optional_int(params, 1)?, len).collect()
+        }
+        Select::Rank => {
+            vec![elements.by_rank()[resolve_index(as_int(param(params, 0)?)?, len)?]]
+        }
+        Select::RankRange => {
+            let window = index_window(as_int(param(params, 0)?)?, optional_int(params, 1)?, len);
+            elements.by_rank()[window].to_vec()
+        }
+        Select::Value | Select::Key => {
+            let pattern = param(params, 0)?;
+            (0..len)
+                .filter(|pos| matches(pattern, &items[*pos]))
+                .collect()
+        }
+        Select::ValueList | Select::KeyList => {
+            let patterns = match param(params, 0)? {
+                Value::List(patterns) => patterns,
+                value => {
+                    return Err(unknown(format!(
+                        "expected LIST, found {:?}",
+                        exp_type(value)
+                    )))
+                }
+            };
+            (0..len)
+                .filter(|pos| {
+                    patterns
+                        .iter()
+                        .any(|pattern| matches(pattern, &items[*pos]))
+                })
+                .collect()
+        }
+        Select::ValueInterval | Select::KeyInterval => {
+            let begin = param(params, 0)?;
+            let end = params.get(1).unwrap_or(&Value::Infinity);
+            let mut selected: Vec<usize> = (0..len)
+                .filter(|pos| begin <= &items[*pos] && &items[*pos] < end)
+                .collect();
+            if select == Select::ValueInterval {
+                selected.sort_by(|a, b| items[*a].cmp(&items[*b]));
+            }
+            selected
+        }
+        Select::ValueRelRankRange | Select::KeyRelIndexRange => {
+            let anchor = param(params, 0)?;
+            let offset = as_int(param(params, 1)?)?;
+            let below = items.iter().filter(|item| *item < anchor).count() as i64;
+            let window = window(below.saturating_add(offset), optional_int(params, 2)?, len);
+            if select == Select::ValueRelRankRange {
+                elements.by_rank()[window].to_vec()
+            } else {
+                window.collect()
+            }
+        }
+    };
+    cdt_result(&elements, selected, single, return_type)
+}
+
+fn cdt_result(
+    elements: &Elements,
+    mut selected: Vec<usize>,
+    single: bool,
+    return_type: i64,
+) -> Result<Value> {
+    let len = elements.len();
+    if return_type & RETURN_INVERTED != 0 && !single {
+        let mut keep = vec![true; len];
+        for pos in &selected {
+            keep[*pos] = false;
+        }
+        selected = (0..len).filter(|pos| keep[*pos]).collect();
+    }
+
+    let mut ranks = vec![0; len];
+    if matches!(
+        return_type & !RETURN_INVERTED,
+        RETURN_RANK | RETURN_REVERSE_RANK
+    ) {
+        for (rank, pos) in elements.by_rank().into_iter().enumerate() {
+            ranks[pos] = rank;
+        }
+    }
+
+    let last = len as i64 - 1;
+    let item = |pos: usize| -> Result<Value> {
+        Ok(match return_type & !RETURN_INVERTED {
+            RETURN_INDEX => Value::from(pos as i64),
+            RETURN_REVERSE_INDEX => Value::from(last - pos as i64),
+            RETURN_RANK => Value::from(ranks[pos] as i64),
+            RETURN_REVERSE_RANK => Value::from(last - ranks[pos] as i64),
+            RETURN_KEY if elements.map => elements.keys[pos].clone(),
+            RETURN_VALUE => elements.values[pos].clone(),
+            other => return Err(unsupported(format!("unsupported return type {}", other))),
+        })
+    };
+
+    match return_type & !RETURN_INVERTED {
+        RETURN_NONE => Ok(Value::Nil),
+        RETURN_COUNT => Ok(Value::from(selected.len() as i64)),
+        RETURN_KEY_VALUE if elements.map => Ok(Value::HashMap(
+            selected
+                .into_iter()
+                .map(|pos| (elements.keys[pos].clone(), elements.values[pos].clone()))
+                .collect::<HashMap<_, _>>(),
+        )),
+        _ if single => selected.first().map_or(Ok(Value::Nil), |pos| item(*pos)),
+        _ => selected
+            .into_iter()
+            .map(item)
+            .collect::<Result<Vec<_>>>()
+            .map(Value::List),
+    }
+}
+
+fn bit_read(op: i64, bin: Value, params: &[Value]) -> Result<Value> {
+    let bytes = match bin {
+        Value::Blob(bytes) => bytes,
+        value => {
+            return Err(unknown(format!(
+                "expected BLOB, found {:?}",
+                exp_type(&value)
+            )))
+        }
+    };
+    let total = bytes.len() as i64 * 8;
+    let offset = as_int(param(params, 0)?)?;
+    let size = as_int(param(params, 1)?)?;
+    let start = if offset < 0 { total + offset } else { offset };
+    if start < 0 || size < 0 || size > total - start {
+        return Err(unknown("bit range out of bounds"));
+    }
+    let (start, size) = (start as usize, size as usize);
+    let bit = |idx: usize| bytes[idx / 8] & (0x80 >> (idx % 8)) != 0;
+
+    match op {
+        BIT_GET => {
+            let mut result = vec![0_u8; (size + 7) / 8];
+            for idx in (0..size).filter(|idx| bit(start + idx)) {
+                result[idx / 8] |= 0x80 >> (idx % 8);
+            }
+            Ok(Value::Blob(result))
+        }
+        BIT_COUNT => Ok(Value::from(
+            (start..start + size).filter(|idx| bit(*idx)).count() as i64,
+        )),
+        BIT_LSCAN | BIT_RSCAN => {
+            let search = as_bool(param(params, 2)?)?;
+            let found = if op == BIT_LSCAN {
+                (0..size).find(|idx| bit(start + idx) == search)
+            } else {
+                (0..size).rev().find(|idx| bit(start + idx) == search)
+            };
+            Ok(Value::from(found.map_or(-1, |idx| idx as i64)))
+        }
+        BIT_GET_INT => {
+            if size > 64 {
+                return Err(unknown("integers can be at most 64 bits"));
+            }
+            let signed = optional_int(params, 2)?.unwrap_or(0) & 1 != 0;
+            let mut value =
+                (0..size).fold(0_u64, |acc, idx| (acc << 1) | u64::from(bit(start + idx)));
+            if signed && size > 0 && size < 64 && value >> (size - 1) & 1 == 1 {
+                value |= !0_u64 << size;
+            }
+            Ok(Value::from(value as i64))
+        }
+        _ => Err(unsupported(format!("unsupported bitwise operation {}", op))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::*;
+    use crate::operations::cdt_context::{ctx_list_index, ctx_map_key};
+
+    fn record() -> (Record, Key) {
+        let key = Key::new("test", "users", as_val!("alice")).unwrap();
+        let mut bins = HashMap::new();
+        bins.insert("age".to_string(), as_val!(42));
+        bins.insert("name".to_string(), as_val!("Alice"));
+        bins.insert("score".to_string(), as_val!(7.5));
+        bins.insert("active".to_string(), as_val!(true));
+        bins.insert("tags".to_string(), as_list!("b", "a", "c", "a"));
+        bins.insert(
+            "attrs".to_string(),
+            as_map!("x" => 3, "y" => 1, "z" => as_list!(10, 20)),
+        );
+        bins.insert("bits".to_string(), Value::Blob(vec![0b0101_0000, 0xff]));
+        (Record::new(None, bins, 1, 0), key)
+    }
+
+    fn eval(exp: &FilterExpression) -> Result<Value> {
+        let (record, key) = record();
+        exp.evaluate(&record, &key)
+    }
+
+    fn eval_text(text: &str) -> Value {
+        eval(&FilterExpression::parse(text).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn scalar_expressions() {
+        assert_eq!(eval_text("$.age > 21 and $.name == 'Alice'"), as_val!(true));
+        assert_eq!(eval_text("$.age >= 43 or not $.active"), as_val!(false));
+        assert_eq!(eval_text("($.age + 8) * 2 - 100 / 4"), as_val!(75));
+        assert_eq!(eval_text("$.score * 2.0 == 15.0"), as_val!(true));
+        assert_eq!(eval_text("-$.age % 5 == -2"), as_val!(true));
+        assert_eq!(eval_text("max($.age, 50, 7)"), as_val!(50));
+        assert_eq!(eval_text("to_int(floor($.score)) + 1"), as_val!(8));
+        assert_eq!(eval_text("pow(2.0, 10.0)"), as_val!(1024.0));
+        assert_eq!(eval_text("0xf0 | 0x0f ^ 0xff & 0x0f"), as_val!(0xf0));
+        assert_eq!(eval_text("-16 >> 2"), as_val!(-4));
+        assert_eq!(eval_text("-16 >>> 60"), as_val!(15));
+        assert_eq!(
+            eval_text("count($.age) + lscan(1, true) + rscan(6, false)"),
+            as_val!(3 + 63 + 63)
+        );
+        assert_eq!(
+            eval_text("let (x = $.age / 2) then (when (${x} > 30 => 'old', ${x} > 20 => 'mid', default => 'young'))"),
+            as_val!("mid")
+        );
+        assert_eq!(
+            eval_text(
+                "key(STRING) == 'alice' and key_exists() and ttl() == -1 and not is_tombstone()"
+            ),
+            as_val!(true)
+        );
+        assert_eq!(eval(&bin_exists("age".into())).unwrap(), as_val!(true));
+        assert_eq!(eval(&bin_exists("missing".into())).unwrap(), as_val!(false));
+        assert_eq!(eval(&set_name()).unwrap(), as_val!("users"));
+
+        let (record, key) = record();
+        let modulo = eval(&digest_modulo(3)).unwrap();
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&key.digest[16..20]);
+        assert_eq!(modulo, as_val!(i64::from(u32::from_le_bytes(bytes)) % 3));
+        assert_eq!(
+            key_exists()
+                .evaluate(&record, &Key::from_digest("test", "users", key.digest))
+                .unwrap(),
+            as_val!(false)
+        );
+    }
+
+    #[test]
+    fn cdt_reads() {
+        assert_eq!(eval_text("$.tags.[1].get(type: STRING)"), as_val!("a"));
+        assert_eq!(eval_text("$.tags.[].count()"), as_val!(4));
+        assert_eq!(eval_text("$.tags.[].contains('a')"), as_val!(true));
+        assert_eq!(eval_text("$.attrs.y"), as_val!(1));
+        assert_eq!(eval_text("$.attrs.z.[-1]"), as_val!(20));
+        assert_eq!(eval_text("$.attrs.{}.contains('q')"), as_val!(false));
+
+        let tags = list_bin("tags".into());
+        let attrs = map_bin("attrs".into());
+        let cases = vec![
+            (
+                lists::get_by_value(
+                    ListReturnType::Index,
+                    string_val("a".into()),
+                    tags.clone(),
+                    &[],
+                ),
+                as_list!(1, 3),
+            ),
+            (
+                lists::get_by_rank_range_count(
+                    ListReturnType::Values,
+                    int_val(0),
+                    int_val(2),
+                    tags.clone(),
+                    &[],
+                ),
+                as_list!("a", "a"),
+            ),
+            (
+                lists::get_by_index_range(ListReturnType::Rank, int_val(-2), tags.clone(), &[]),
+                as_list!(3, 1),
+            ),
+            (
+                lists::get_by_value_range(
+                    ListReturnType::Count,
+                    Some(string_val("b".into())),
+                    None,
+                    tags.clone(),
+                    &[],
+                ),
+                as_val!(2),
+            ),
+            (
+                lists::get_by_value_relative_rank_range_count(
+                    ListReturnType::Values,
+                    string_val("b".into()),
+                    int_val(-1),
+                    int_val(2),
+                    tags,
+                    &[],
+                ),
+                as_list!("a", "b"),
+            ),
+            (
+                maps::get_by_key_list(
+                    MapReturnType::Value,
+                    list_val(vec![as_val!("x"), as_val!("q")]),
+                    attrs.clone(),
+                    &[],
+                ),
+                as_list!(3),
+            ),
+            (
+                maps::get_by_rank(
+                    MapReturnType::Key,
+                    ExpType::STRING,
+                    int_val(0),
+                    attrs.clone(),
+                    &[],
+                ),
+                as_val!("y"),
+            ),
+            (
+                maps::get_by_index_range_count(
+                    MapReturnType::KeyValue,
+                    int_val(0),
+                    int_val(2),
+                    attrs.clone(),
+                    &[],
+                ),
+                as_map!("x" => 3, "y" => 1),
+            ),
+            (
+                maps::get_by_key_relative_index_range(
+                    MapReturnType::Key,
+                    string_val("y".into()),
+                    int_val(0),
+                    attrs.clone(),
+                    &[],
+                ),
+                as_list!("y", "z"),
+            ),
+            (
+                maps::get_by_key_relative_index_range_count(
+                    MapReturnType::Key,
+                    string_val("y".into()),
+                    int_val(i64::MAX),
+                    int_val(1),
+                    attrs.clone(),
+                    &[],
+                ),
+                as_list!(),
+            ),
+            (
+                lists::get_by_index(
+                    ListReturnType::Values,
+                    ExpType::INT,
+                    int_val(0),
+                    attrs,
+                    &[ctx_map_key(as_val!("z"))],
+                ),
+                as_val!(10),
+            ),
+        ];
+        for (exp, expected) in cases {
+            assert_eq!(eval(&exp).unwrap(), expected, "{:?}", exp);
+        }
+        assert!(eval(&lists::get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            int_val(9),
+            list_bin("tags".into()),
+            &[ctx_list_index(0)],
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn bit_reads() {
+        let bits = blob_bin("bits".into());
+        assert_eq!(
+            eval(&bitwise::count(int_val(0), int_val(16), bits.clone())).unwrap(),
+            as_val!(10)
+        );
+        assert_eq!(
+            eval(&bitwise::get(int_val(1), int_val(4), bits.clone())).unwrap(),
+            Value::Blob(vec![0b1010_0000])
+        );
+        assert_eq!(
+            eval(&bitwise::get_int(
+                int_val(1),
+                int_val(3),
+                true,
+                bits.clone()
+            ))
+            .unwrap(),
+            as_val!(-3)
+        );
+        assert_eq!(
+            eval(&bitwise::lscan(
+                int_val(0),
+                int_val(8),
+                bool_val(true),
+                bits.clone()
+            ))
+            .unwrap(),
+            as_val!(1)
+        );
+        assert_eq!(
+            eval(&bitwise::rscan(
+                int_val(0),
+                int_val(8),
+                bool_val(true),
+                bits
+            ))
+            .unwrap(),
+            as_val!(3)
+        );
+    }
+
+    #[test]
+    fn failures() {
+        let unknown_value = |exp: FilterExpression| match eval(&exp) {
+            Err(Error(ErrorKind::UnknownExpressionValue(_), _)) => (),
+            other => panic!("expected unknown value, got {:?}", other),
+        };
+        unknown_value(eq(int_bin("missing".into()), int_val(1)));
+        unknown_value(eq(int_bin("name".into()), int_val(1)));
+        unknown_value(gt(string_bin("name".into()), int_val(1)));
+        unknown_value(num_div(vec![int_bin("age".into()), int_val(0)]));
+        unknown_value(num_add(vec![int_bin("age".into()), float_val(1.0)]));
+        unknown_value(crate::expressions::unknown());
+        unknown_value(bitwise::count(
+            int_val(i64::MAX),
+            int_val(8),
+            blob_bin("bits".into()),
+        ));
+        unknown_value(bitwise::get(
+            int_val(8),
+            int_val(i64::MAX),
+            blob_bin("bits".into()),
+        ));
+
+        let unsupported = |exp: FilterExpression| match eval(&exp) {
+            Err(Error(ErrorKind::UnsupportedExpression(_), _)) => (),
+            other => panic!("expected unsupported expression, got {:?}", other),
+        };
+        unsupported(regex_compare("^A".into(), 0, string_bin("name".into())));
+        unsupported(gt(last_update(), int_val(0)));
+        unsupported(hll::get_count(hll_bin("h".into())));
+        unsupported(lists::size(
+            lists::append(
+                crate::operations::lists::ListPolicy::default(),
+                int_val(1),
+                list_bin("tags".into()),
+                &[],
+            ),
+            &[],
+        ));
+    }
+}
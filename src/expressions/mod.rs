@@ -16,20 +16,26 @@
 //! Functions used for Filter Expressions. This module requires Aerospike Server version >= 5.2
 
 pub mod bitwise;
+mod decode;
+mod display;
+mod eval;
 pub mod hll;
 pub mod lists;
 pub mod maps;
+mod parser;
 pub mod regex_flag;
+pub mod typed;
+mod validate;
 use crate::commands::buffer::Buffer;
 use crate::errors::Result;
 use crate::msgpack::encoder::{pack_array_begin, pack_integer, pack_raw_string, pack_value};
 use crate::operations::cdt_context::CdtContext;
-use crate::{ParticleType, Value};
+use crate::{Key, ParticleType, Record, Value};
 use std::collections::HashMap;
 use std::fmt::Debug;
 
 /// Expression Data Types for usage in some `FilterExpressions` on for example Map and List
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExpType {
     /// NIL Expression Type
     NIL = 0,
@@ -126,6 +132,9 @@ pub enum ExpressionArgument {
 /// Filter expression, which can be applied to most commands, to control which records are
 /// affected by the command. Filter expression are created using the functions in the
 /// [expressions](crate::expressions) module and its submodules.
+///
+/// Expressions are displayed in the text syntax accepted by `FilterExpression::parse`, e.g.
+/// `$.age > 21 and ttl() < 3600`.
 #[derive(Debug, Clone)]
 pub struct FilterExpression {
     /// The Operation code
@@ -320,6 +329,148 @@ impl FilterExpression {
     }
 }
 
+impl FilterExpression {
+    /// Parse a filter expression from its text representation.
+    ///
+    /// Bins are referenced as `$.name`; their type is inferred from the expression they are used
+    /// in, or given explicitly with `$.name.get(type: STRING)`. List elements and map values are
+    /// addressed by extending the path, e.g. `$.scores.[0]` or `$.address.city`, while
+    /// `$.tags.[].count()` and `$.attrs.{}.contains('key')` operate on the list or map itself.
+    /// Record metadata is available through functions like `ttl()`, `last_update()` or
+    /// `key(INT)`. Further supported are arithmetic and integer operators, `regex(exp,
+    /// 'pattern', 'flags')`, `let (x = exp) then (${x} > 0)` and `when (cond => exp, default =>
+    /// exp)`.
+    ///
+    /// The operations in [lists], [maps], [bitwise] and [hll] are functions named after their
+    /// builders, taking the bin as the last positional argument, e.g.
+    /// `list_get_by_rank(VALUES, -1, $.scores) > 100`, `bit_count(0, 8, $.flags) > 1` or
+    /// `map_size($.m, ctx: [map_key('a')], type: INT)`. Blobs and HLLs are written as hex
+    /// strings, `blob('ff00')` and `hll('0102')`.
+    ///
+    /// Parse errors are reported as `ErrorKind::InvalidExpression`, which includes the byte
+    /// offset of the error in `text`.
+    ///
+    /// ```
+    /// use aerospike::expressions::FilterExpression;
+    ///
+    /// // Same as and(vec![gt(int_bin("age"), int_val(21)), eq(string_bin("country"),
+    /// // string_val("NL")), lt(ttl(), int_val(3600))])
+    /// let filter = FilterExpression::parse("$.age > 21 and $.country == 'NL' and ttl() < 3600");
+    /// assert!(filter.is_ok());
+    /// ```
+    pub fn parse(text: &str) -> Result<FilterExpression> {
+        parser::parse(text)
+    }
+
+    /// Evaluate the expression against a record on the client, e.g. to unit-test filters or to
+    /// apply them to cached records. Returns the value the expression resolves to, e.g.
+    /// `Value::Bool` for a filter.
+    ///
+    /// Comparisons, boolean logic, arithmetic and integer operators, `cond`/`exp_let`, record
+    /// metadata and the read expressions in [lists], [maps] and [bitwise] are supported. If the
+    /// server would resolve the expression to "unknown", e.g. because a bin is missing or has a
+    /// different type, `ErrorKind::UnknownExpressionValue` is returned. Regular expressions,
+    /// geospatial comparisons, HLL and modify expressions as well as `device_size()`,
+    /// `last_update()` and `since_update()` return `ErrorKind::UnsupportedExpression`.
+    ///
+    /// ```
+    /// use aerospike::{as_val, Key, Record};
+    /// use aerospike::expressions::FilterExpression;
+    /// # use std::collections::HashMap;
+    ///
+    /// let key = Key::new("test", "users", as_val!("alice")).unwrap();
+    /// let mut bins = HashMap::new();
+    /// bins.insert("age".to_string(), as_val!(42));
+    /// let record = Record::new(None, bins, 1, 0);
+    ///
+    /// let filter = FilterExpression::parse("$.age > 21").unwrap();
+    /// assert_eq!(filter.evaluate(&record, &key).unwrap(), as_val!(true));
+    /// ```
+    pub fn evaluate(&self, record: &Record, key: &Key) -> Result<Value> {
+        eval::evaluate(self, record, key)
+    }
+
+    /// Check the expression tree for errors that the server would otherwise reject with a generic
+    /// `ParameterError`, and return the type the expression resolves to. The number and types of
+    /// the operands of every operation, including the list, map, bitwise and HLL expressions, are
+    /// checked, e.g. comparing an integer bin to a string or a `cond` with an even number of
+    /// operands is reported as `ErrorKind::InvalidExpressionTree`, together with the path to the
+    /// offending sub-expression. Returns `ExpType::NIL` if the type can only be determined at
+    /// runtime, e.g. for `unknown()`.
+    ///
+    /// In debug builds, the filter expression of a policy is validated before every command.
+    ///
+    /// ```
+    /// use aerospike::expressions::{and, eq, gt, int_bin, int_val, string_val, ExpType};
+    ///
+    /// let filter = and(vec![
+    ///     gt(int_bin("age".to_string()), int_val(21)),
+    ///     eq(int_bin("country".to_string()), string_val("NL".to_string())),
+    /// ]);
+    /// let err = filter.validate().unwrap_err();
+    /// assert_eq!(
+    ///     err.to_string(),
+    ///     "Invalid expression tree at and[1].eq: operand 1 is STRING, but operand 0 is INT"
+    /// );
+    ///
+    /// let filter = gt(int_bin("age".to_string()), int_val(21));
+    /// assert_eq!(filter.validate().unwrap(), ExpType::BOOL);
+    /// ```
+    pub fn validate(&self) -> Result<ExpType> {
+        validate::validate(self)
+    }
+
+    /// Validate the expression for use as the filter expression of a policy, which has to resolve
+    /// to a boolean.
+    #[cfg(debug_assertions)]
+    pub(crate) fn validate_filter(&self) -> Result<()> {
+        validate::validate_filter(self)
+    }
+
+    /// Return the packed wire format of the expression, as sent to the server.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let size = self.pack(&mut None)?;
+        let mut buf = Buffer::new(size);
+        buf.resize_buffer(size)?;
+        self.pack(&mut Some(&mut buf))?;
+        Ok(buf.data_buffer[..size].to_vec())
+    }
+
+    /// Return the packed wire format of the expression as base64 string, the form in which
+    /// expressions are logged by the server and stored in configuration.
+    pub fn base64(&self) -> Result<String> {
+        Ok(base64::encode(&self.to_bytes()?))
+    }
+
+    /// Decode an expression from its packed wire format, as returned by `to_bytes`. Packing the
+    /// decoded expression yields the same bytes again. Malformed input is reported as
+    /// `ErrorKind::InvalidExpression` with the byte offset of the error.
+    ///
+    /// Constant operands of list, map and bit reads are decoded as value expressions, e.g.
+    /// `int_val(1)`, while other constant arguments of module calls are decoded as plain values,
+    /// since the wire format does not distinguish between the two.
+    pub fn from_bytes(bytes: &[u8]) -> Result<FilterExpression> {
+        decode::decode(bytes)
+    }
+
+    /// Decode an expression from the base64 encoding of its packed wire format, e.g. as found in
+    /// a server log.
+    ///
+    /// ```
+    /// use aerospike::expressions::{gt, int_bin, int_val, FilterExpression};
+    ///
+    /// let filter = FilterExpression::from_base64("kwOTUQKjYWdlFQ==").unwrap();
+    /// assert_eq!(filter.to_string(), "$.age > 21");
+    ///
+    /// let filter = gt(int_bin("age".to_string()), int_val(21));
+    /// assert_eq!(filter.base64().unwrap(), "kwOTUQKjYWdlFQ==");
+    /// ```
+    pub fn from_base64(encoded: &str) -> Result<FilterExpression> {
+        let bytes = base64::decode(encoded.trim())?;
+        decode::decode(&bytes)
+    }
+}
+
 /// Create a record key expression of specified type.
 /// ```
 /// use aerospike::expressions::{ExpType, ge, int_val, key};
@@ -701,15 +852,34 @@ pub fn map_val(val: HashMap<Value, Value>) -> FilterExpression {
     FilterExpression::new(None, Some(Value::from(val)), None, None, None, None)
 }
 
-/// Create geospatial json string value.
-pub fn geo_val(val: String) -> FilterExpression {
-    FilterExpression::new(None, Some(Value::from(val)), None, None, None, None)
+/// Create geospatial json value from a `GeoJSON` string or one of the `geo` geometry types.
+pub fn geo_val<T: ToString>(val: T) -> FilterExpression {
+    FilterExpression::new(
+        None,
+        Some(Value::GeoJSON(val.to_string())),
+        None,
+        None,
+        None,
+        None,
+    )
 }
 
 /// Create a Nil Value
 pub fn nil() -> FilterExpression {
     FilterExpression::new(None, Some(Value::Nil), None, None, None, None)
 }
+
+/// Create an Infinity value, which sorts after all other values. Used as the end of open-ended
+/// ranges in CDT range expressions.
+pub fn inf() -> FilterExpression {
+    FilterExpression::new(None, Some(Value::Infinity), None, None, None, None)
+}
+
+/// Create a Wildcard value, which matches any other value. Used in list values of CDT value
+/// expressions to match list-of-tuples elements.
+pub fn wildcard() -> FilterExpression {
+    FilterExpression::new(None, Some(Value::Wildcard), None, None, None, None)
+}
 /// Create "not" operator expression.
 /// ```
 /// // ! (a == 0 || a == 10)
@@ -1135,6 +1305,26 @@ pub const fn int_and(exps: Vec<FilterExpression>) -> FilterExpression {
     }
 }
 
+/// Create integer "or" (|) operator that is applied to two or more integers.
+/// All arguments must resolve to integers.
+/// Requires server version 5.6.0+.
+/// ```
+/// // a | 0x10 != 0
+/// use aerospike::expressions::{ne, int_val, int_or, int_bin};
+/// ne(int_or(vec![int_bin("a".to_string()), int_val(0x10)]), int_val(0));
+/// ```
+pub const fn int_or(exps: Vec<FilterExpression>) -> FilterExpression {
+    FilterExpression {
+        cmd: Some(ExpOp::IntOr),
+        val: None,
+        bin: None,
+        flags: None,
+        module: None,
+        exps: Some(exps),
+        arguments: None,
+    }
+}
+
 /// Create integer "xor" (^) operator that is applied to two or more integers.
 /// All arguments must resolve to integers.
 /// Requires server version 5.6.0+.
@@ -0,0 +1,2866 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Parser for the text expression language accepted by `FilterExpression::parse`.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::expressions::bitwise::BitExpOp;
+use crate::expressions::hll::HllExpOp;
+use crate::expressions::regex_flag::RegexFlag;
+use crate::expressions::validate::{BIT_MODULE, CDT_MODULE, HLL_MODULE, MAP_OPS};
+use crate::expressions::{
+    self as exp, lists, maps, ExpOp, ExpType, ExpressionArgument, FilterExpression, MODIFY,
+};
+use crate::geo::Geometry;
+use crate::operations::cdt_context::{ctx_list_index, ctx_map_key, CdtContext, CtxType};
+use crate::operations::lists::{CdtListOpType, ListReturnType};
+use crate::operations::maps::CdtMapOpType;
+use crate::{MapReturnType, Value};
+
+// Multi-character symbols have to come before their prefixes.
+const SYMBOLS: &[&str] = &[
+    ">>>", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "=>", "(", ")", "[", "]", "{", "}", ",",
+    ":", ".", "<", ">", "+", "-", "*", "/", "%", "&", "|", "^", "~", "!", "=",
+];
+
+type Variadic = fn(Vec<FilterExpression>) -> FilterExpression;
+type Binary = fn(FilterExpression, FilterExpression) -> FilterExpression;
+
+const CDT_MODIFY: i64 = CDT_MODULE | MODIFY;
+const BIT_MODIFY: i64 = BIT_MODULE | MODIFY;
+const HLL_MODIFY: i64 = HLL_MODULE | MODIFY;
+
+/// List, map, bitwise and HLL functions: the name, the module flags, incl. `MODIFY` for
+/// functions that return the modified bin, and the operation code.
+const FUNCTIONS: &[(&str, i64, i64)] = &[
+    ("list_append", CDT_MODIFY, CdtListOpType::Append as i64),
+    (
+        "list_append_items",
+        CDT_MODIFY,
+        CdtListOpType::AppendItems as i64,
+    ),
+    ("list_insert", CDT_MODIFY, CdtListOpType::Insert as i64),
+    (
+        "list_insert_items",
+        CDT_MODIFY,
+        CdtListOpType::InsertItems as i64,
+    ),
+    ("list_set", CDT_MODIFY, CdtListOpType::Set as i64),
+    ("list_clear", CDT_MODIFY, CdtListOpType::Clear as i64),
+    (
+        "list_increment",
+        CDT_MODIFY,
+        CdtListOpType::Increment as i64,
+    ),
+    ("list_sort", CDT_MODIFY, CdtListOpType::Sort as i64),
+    ("list_size", CDT_MODULE, CdtListOpType::Size as i64),
+    (
+        "list_get_by_index",
+        CDT_MODULE,
+        CdtListOpType::GetByIndex as i64,
+    ),
+    (
+        "list_get_by_rank",
+        CDT_MODULE,
+        CdtListOpType::GetByRank as i64,
+    ),
+    (
+        "list_get_by_value",
+        CDT_MODULE,
+        CdtListOpType::GetByValue as i64,
+    ),
+    (
+        "list_get_by_value_list",
+        CDT_MODULE,
+        CdtListOpType::GetByValueList as i64,
+    ),
+    (
+        "list_get_by_index_range",
+        CDT_MODULE,
+        CdtListOpType::GetByIndexRange as i64,
+    ),
+    (
+        "list_get_by_value_range",
+        CDT_MODULE,
+        CdtListOpType::GetByValueInterval as i64,
+    ),
+    (
+        "list_get_by_rank_range",
+        CDT_MODULE,
+        CdtListOpType::GetByRankRange as i64,
+    ),
+    (
+        "list_get_by_value_relative_rank_range",
+        CDT_MODULE,
+        CdtListOpType::GetByValueRelRankRange as i64,
+    ),
+    (
+        "list_remove_by_index",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByIndex as i64,
+    ),
+    (
+        "list_remove_by_rank",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByRank as i64,
+    ),
+    (
+        "list_remove_by_value",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByValue as i64,
+    ),
+    (
+        "list_remove_by_value_list",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByValueList as i64,
+    ),
+    (
+        "list_remove_by_index_range",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByIndexRange as i64,
+    ),
+    (
+        "list_remove_by_value_range",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByValueInterval as i64,
+    ),
+    (
+        "list_remove_by_rank_range",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByRankRange as i64,
+    ),
+    (
+        "list_remove_by_value_relative_rank_range",
+        CDT_MODIFY,
+        CdtListOpType::RemoveByValueRelRankRange as i64,
+    ),
+    ("map_add", CDT_MODIFY, CdtMapOpType::Add as i64),
+    ("map_add_items", CDT_MODIFY, CdtMapOpType::AddItems as i64),
+    ("map_put", CDT_MODIFY, CdtMapOpType::Put as i64),
+    ("map_put_items", CDT_MODIFY, CdtMapOpType::PutItems as i64),
+    ("map_replace", CDT_MODIFY, CdtMapOpType::Replace as i64),
+    (
+        "map_replace_items",
+        CDT_MODIFY,
+        CdtMapOpType::ReplaceItems as i64,
+    ),
+    ("map_increment", CDT_MODIFY, CdtMapOpType::Increment as i64),
+    ("map_clear", CDT_MODIFY, CdtMapOpType::Clear as i64),
+    (
+        "map_remove_by_key",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByKey as i64,
+    ),
+    (
+        "map_remove_by_index",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByIndex as i64,
+    ),
+    (
+        "map_remove_by_rank",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByRank as i64,
+    ),
+    (
+        "map_remove_by_key_list",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveKeyList as i64,
+    ),
+    (
+        "map_remove_by_value",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByValue as i64,
+    ),
+    (
+        "map_remove_by_value_list",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveValueList as i64,
+    ),
+    (
+        "map_remove_by_key_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByKeyInterval as i64,
+    ),
+    (
+        "map_remove_by_index_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByIndexRange as i64,
+    ),
+    (
+        "map_remove_by_value_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByValueInterval as i64,
+    ),
+    (
+        "map_remove_by_rank_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByRankRange as i64,
+    ),
+    (
+        "map_remove_by_key_relative_index_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByKeyRelIndexRange as i64,
+    ),
+    (
+        "map_remove_by_value_relative_rank_range",
+        CDT_MODIFY,
+        CdtMapOpType::RemoveByValueRelRankRange as i64,
+    ),
+    ("map_size", CDT_MODULE, CdtMapOpType::Size as i64),
+    ("map_get_by_key", CDT_MODULE, CdtMapOpType::GetByKey as i64),
+    (
+        "map_get_by_index",
+        CDT_MODULE,
+        CdtMapOpType::GetByIndex as i64,
+    ),
+    (
+        "map_get_by_rank",
+        CDT_MODULE,
+        CdtMapOpType::GetByRank as i64,
+    ),
+    (
+        "map_get_by_value",
+        CDT_MODULE,
+        CdtMapOpType::GetByValue as i64,
+    ),
+    (
+        "map_get_by_key_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByKeyInterval as i64,
+    ),
+    (
+        "map_get_by_index_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByIndexRange as i64,
+    ),
+    (
+        "map_get_by_value_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByValueInterval as i64,
+    ),
+    (
+        "map_get_by_rank_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByRankRange as i64,
+    ),
+    (
+        "map_get_by_key_list",
+        CDT_MODULE,
+        CdtMapOpType::GetByKeyList as i64,
+    ),
+    (
+        "map_get_by_value_list",
+        CDT_MODULE,
+        CdtMapOpType::GetByValueList as i64,
+    ),
+    (
+        "map_get_by_key_relative_index_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByKeyRelIndexRange as i64,
+    ),
+    (
+        "map_get_by_value_relative_rank_range",
+        CDT_MODULE,
+        CdtMapOpType::GetByValueRelRankRange as i64,
+    ),
+    ("bit_resize", BIT_MODIFY, BitExpOp::Resize as i64),
+    ("bit_insert", BIT_MODIFY, BitExpOp::Insert as i64),
+    ("bit_remove", BIT_MODIFY, BitExpOp::Remove as i64),
+    ("bit_set", BIT_MODIFY, BitExpOp::Set as i64),
+    ("bit_or", BIT_MODIFY, BitExpOp::Or as i64),
+    ("bit_xor", BIT_MODIFY, BitExpOp::Xor as i64),
+    ("bit_and", BIT_MODIFY, BitExpOp::And as i64),
+    ("bit_not", BIT_MODIFY, BitExpOp::Not as i64),
+    ("bit_lshift", BIT_MODIFY, BitExpOp::LShift as i64),
+    ("bit_rshift", BIT_MODIFY, BitExpOp::RShift as i64),
+    ("bit_add", BIT_MODIFY, BitExpOp::Add as i64),
+    ("bit_subtract", BIT_MODIFY, BitExpOp::Subtract as i64),
+    ("bit_set_int", BIT_MODIFY, BitExpOp::SetInt as i64),
+    ("bit_get", BIT_MODULE, BitExpOp::Get as i64),
+    ("bit_count", BIT_MODULE, BitExpOp::Count as i64),
+    ("bit_lscan", BIT_MODULE, BitExpOp::LScan as i64),
+    ("bit_rscan", BIT_MODULE, BitExpOp::RScan as i64),
+    ("bit_get_int", BIT_MODULE, BitExpOp::GetInt as i64),
+    ("hll_init", HLL_MODIFY, HllExpOp::Init as i64),
+    ("hll_add", HLL_MODIFY, HllExpOp::Add as i64),
+    ("hll_get_count", HLL_MODULE, HllExpOp::Count as i64),
+    ("hll_get_union", HLL_MODULE, HllExpOp::Union as i64),
+    (
+        "hll_get_union_count",
+        HLL_MODULE,
+        HllExpOp::UnionCount as i64,
+    ),
+    (
+        "hll_get_intersect_count",
+        HLL_MODULE,
+        HllExpOp::IntersectCount as i64,
+    ),
+    (
+        "hll_get_similarity",
+        HLL_MODULE,
+        HllExpOp::Similarity as i64,
+    ),
+    ("hll_describe", HLL_MODULE, HllExpOp::Describe as i64),
+    ("hll_may_contain", HLL_MODULE, HllExpOp::MayContain as i64),
+];
+
+const LIST_RETURN_TYPES: &[(&str, i64)] = &[
+    ("NONE", ListReturnType::None as i64),
+    ("INDEX", ListReturnType::Index as i64),
+    ("REVERSE_INDEX", ListReturnType::ReverseIndex as i64),
+    ("RANK", ListReturnType::Rank as i64),
+    ("REVERSE_RANK", ListReturnType::ReverseRank as i64),
+    ("COUNT", ListReturnType::Count as i64),
+    ("VALUES", ListReturnType::Values as i64),
+];
+
+const MAP_RETURN_TYPES: &[(&str, i64)] = &[
+    ("NONE", MapReturnType::None as i64),
+    ("INDEX", MapReturnType::Index as i64),
+    ("REVERSE_INDEX", MapReturnType::ReverseIndex as i64),
+    ("RANK", MapReturnType::Rank as i64),
+    ("REVERSE_RANK", MapReturnType::ReverseRank as i64),
+    ("COUNT", MapReturnType::Count as i64),
+    ("KEY", MapReturnType::Key as i64),
+    ("VALUE", MapReturnType::Value as i64),
+    ("KEY_VALUE", MapReturnType::KeyValue as i64),
+];
+
+const INVERTED: i64 = ListReturnType::Inverted as i64;
+
+/// Context steps of list and map functions, e.g. `ctx: [map_key('a'), list_index(0)]`.
+pub(super) const CONTEXTS: &[(&str, u8)] = &[
+    ("list_index", CtxType::ListIndex as u8),
+    ("list_rank", CtxType::ListRank as u8),
+    ("list_value", CtxType::ListValue as u8),
+    ("map_index", CtxType::MapIndex as u8),
+    ("map_rank", CtxType::MapRank as u8),
+    ("map_key", CtxType::MapKey as u8),
+    ("map_value", CtxType::MapValue as u8),
+];
+
+/// Name of the list, map, bitwise or HLL function with the given module flags and operation code.
+pub(super) fn function_name(flags: i64, op: i64) -> Option<&'static str> {
+    FUNCTIONS
+        .iter()
+        .find(|(_, f, o)| *f == flags && *o == op)
+        .map(|(name, _, _)| *name)
+}
+
+/// Whether the first operand of a list or map operation is its return type.
+pub(super) fn takes_return_type(flags: i64, op: i64) -> bool {
+    flags & !MODIFY == CDT_MODULE
+        && ((CdtListOpType::GetByIndex as i64..=CdtListOpType::RemoveByValueRelRankRange as i64)
+            .contains(&op)
+            || (CdtMapOpType::RemoveByKey as i64..=CdtMapOpType::RemoveByValueRelRankRange as i64)
+                .contains(&op)
+            || (CdtMapOpType::GetByKey as i64..=CdtMapOpType::GetByValueRelRankRange as i64)
+                .contains(&op))
+}
+
+const fn return_types(op: i64) -> &'static [(&'static str, i64)] {
+    if op < MAP_OPS {
+        LIST_RETURN_TYPES
+    } else {
+        MAP_RETURN_TYPES
+    }
+}
+
+/// Name of a list or map return type, e.g. `VALUES` or `COUNT | INVERTED`.
+pub(super) fn return_type_name(op: i64, return_type: i64) -> Option<String> {
+    let name = return_types(op)
+        .iter()
+        .find(|(_, code)| *code == return_type & !INVERTED)?
+        .0;
+    if return_type & INVERTED == 0 {
+        Some(name.to_string())
+    } else {
+        Some(format!("{} | INVERTED", name))
+    }
+}
+
+/// The type of the bin a function operates on: bitwise functions operate on blobs, HLL functions
+/// on HLLs, and list and map functions on the container of the first context step.
+pub(super) fn module_bin_type(flags: i64, op: i64, ctx: &[CdtContext]) -> ExpType {
+    match flags & !MODIFY {
+        BIT_MODULE => ExpType::BLOB,
+        HLL_MODULE => ExpType::HLL,
+        _ => {
+            let list = ctx
+                .first()
+                .map_or(op < MAP_OPS, |ctx| ctx.id & CtxType::ListIndex as u8 != 0);
+            if list {
+                ExpType::LIST
+            } else {
+                ExpType::MAP
+            }
+        }
+    }
+}
+
+fn error<S: Into<String>>(pos: usize, details: S) -> Error {
+    ErrorKind::InvalidExpression(pos, details.into()).into()
+}
+
+/// Parses `input` into a filter expression. Expressions whose type can not be inferred from
+/// their context, e.g. a bin that is not compared to anything, are resolved as booleans.
+pub fn parse(input: &str) -> Result<FilterExpression> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        idx: 0,
+        vars: vec![],
+        depth: 0,
+    };
+    let exp = parser.parse_expr()?;
+    if parser.peek() != &Tok::End {
+        return Err(parser.unexpected("end of expression"));
+    }
+    Ok(exp.resolve(ExpType::BOOL))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Ident(String),
+    /// `$.`, the start of a bin path.
+    Bin,
+    /// `${name}`, a variable reference.
+    Var(String),
+    Sym(&'static str),
+    End,
+}
+
+impl Tok {
+    fn describe(&self) -> String {
+        match self {
+            Tok::Int(val) => format!("integer {}", val),
+            Tok::Float(val) => format!("float {}", val),
+            Tok::Str(val) => format!("string '{}'", val),
+            Tok::Ident(name) => format!("'{}'", name),
+            Tok::Bin => "'$.'".to_string(),
+            Tok::Var(name) => format!("variable '{}'", name),
+            Tok::Sym(sym) => format!("'{}'", sym),
+            Tok::End => "end of expression".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    pos: usize,
+}
+
+const fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_'
+}
+
+const fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let bytes = input.as_bytes();
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let c = bytes[pos];
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        let start = pos;
+        let tok = if c == b'$' {
+            match bytes.get(pos + 1) {
+                Some(b'.') => {
+                    pos += 2;
+                    Tok::Bin
+                }
+                Some(b'{') => {
+                    let end = input[pos..]
+                        .find('}')
+                        .map(|end| pos + end)
+                        .ok_or_else(|| error(start, "unterminated variable reference"))?;
+                    let name = &input[pos + 2..end];
+                    if name.is_empty()
+                        || !is_ident_start(name.as_bytes()[0])
+                        || !name.bytes().all(is_ident_char)
+                    {
+                        return Err(error(start, format!("invalid variable name '{}'", name)));
+                    }
+                    pos = end + 1;
+                    Tok::Var(name.to_string())
+                }
+                _ => return Err(error(start, "expected '$.' or '${'")),
+            }
+        } else if c == b'\'' || c == b'"' {
+            pos += 1;
+            let mut val = String::new();
+            loop {
+                match input[pos..].chars().next() {
+                    None => return Err(error(start, "unterminated string literal")),
+                    Some(ch) if ch == char::from(c) => {
+                        pos += 1;
+                        break;
+                    }
+                    Some('\\') => {
+                        pos += 1;
+                        let escaped = input[pos..]
+                            .chars()
+                            .next()
+                            .ok_or_else(|| error(start, "unterminated string literal"))?;
+                        val.push(escaped);
+                        pos += escaped.len_utf8();
+                    }
+                    Some(ch) => {
+                        val.push(ch);
+                        pos += ch.len_utf8();
+                    }
+                }
+            }
+            Tok::Str(val)
+        } else if c.is_ascii_digit() {
+            if c == b'0' && matches!(bytes.get(pos + 1), Some(b'x' | b'X')) {
+                pos += 2;
+                while pos < bytes.len() && bytes[pos].is_ascii_hexdigit() {
+                    pos += 1;
+                }
+                let val = i64::from_str_radix(&input[start + 2..pos], 16)
+                    .map_err(|_| error(start, "invalid hexadecimal literal"))?;
+                Tok::Int(val)
+            } else {
+                let mut float = false;
+                while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                    pos += 1;
+                }
+                if bytes.get(pos) == Some(&b'.')
+                    && matches!(bytes.get(pos + 1), Some(c) if c.is_ascii_digit())
+                {
+                    float = true;
+                    pos += 1;
+                    while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                        pos += 1;
+                    }
+                }
+                if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+                    let mut end = pos + 1;
+                    if matches!(bytes.get(end), Some(b'+' | b'-')) {
+                        end += 1;
+                    }
+                    if matches!(bytes.get(end), Some(c) if c.is_ascii_digit()) {
+                        float = true;
+                        pos = end;
+                        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                            pos += 1;
+                        }
+                    }
+                }
+                let literal = &input[start..pos];
+                if float {
+                    Tok::Float(
+                        literal
+                            .parse()
+                            .map_err(|_| error(start, "invalid float literal"))?,
+                    )
+                } else {
+                    Tok::Int(
+                        literal
+                            .parse()
+                            .map_err(|_| error(start, "integer literal out of range"))?,
+                    )
+                }
+            }
+        } else if is_ident_start(c) {
+            while pos < bytes.len() && is_ident_char(bytes[pos]) {
+                pos += 1;
+            }
+            Tok::Ident(input[start..pos].to_string())
+        } else if let Some(sym) = SYMBOLS.iter().find(|sym| input[pos..].starts_with(**sym)) {
+            pos += sym.len();
+            Tok::Sym(sym)
+        } else {
+            let ch = input[pos..].chars().next().unwrap_or_default();
+            return Err(error(start, format!("unexpected character '{}'", ch)));
+        };
+        tokens.push(Token { tok, pos: start });
+    }
+    tokens.push(Token {
+        tok: Tok::End,
+        pos: input.len(),
+    });
+    Ok(tokens)
+}
+
+/// A parsed expression whose type may not be known yet. Bins and CDT lookups are only typed by
+/// the expressions they are used in, so building them is deferred until the type is known.
+struct Exp {
+    pos: usize,
+    ty: Option<ExpType>,
+    build: Build,
+}
+
+enum Build {
+    Built(FilterExpression),
+    Deferred(Box<dyn FnOnce(ExpType) -> FilterExpression>),
+}
+
+impl Exp {
+    const fn typed(pos: usize, ty: ExpType, exp: FilterExpression) -> Self {
+        Exp {
+            pos,
+            ty: Some(ty),
+            build: Build::Built(exp),
+        }
+    }
+
+    fn deferred<F>(pos: usize, build: F) -> Self
+    where
+        F: FnOnce(ExpType) -> FilterExpression + 'static,
+    {
+        Exp {
+            pos,
+            ty: None,
+            build: Build::Deferred(Box::new(build)),
+        }
+    }
+
+    /// Typed if `ty` is known, deferred otherwise; `build` receives the resolved type.
+    fn with_type<F>(pos: usize, ty: Option<ExpType>, build: F) -> Self
+    where
+        F: FnOnce(ExpType) -> FilterExpression + 'static,
+    {
+        match ty {
+            Some(ty) => Exp::typed(pos, ty, build(ty)),
+            None => Exp::deferred(pos, build),
+        }
+    }
+
+    fn resolve(self, default: ExpType) -> FilterExpression {
+        match self.build {
+            Build::Built(exp) => exp,
+            Build::Deferred(build) => build(self.ty.unwrap_or(default)),
+        }
+    }
+
+    fn expect(&self, allowed: &[ExpType]) -> Result<()> {
+        match self.ty {
+            Some(ty) if !allowed.contains(&ty) => Err(error(
+                self.pos,
+                format!(
+                    "expected {} expression, found {:?}",
+                    type_names(allowed),
+                    ty
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn type_names(types: &[ExpType]) -> String {
+    types
+        .iter()
+        .map(|ty| format!("{:?}", ty))
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+fn type_from_name(name: &str) -> Option<ExpType> {
+    Some(match name.to_ascii_uppercase().as_str() {
+        "NIL" => ExpType::NIL,
+        "BOOL" => ExpType::BOOL,
+        "INT" => ExpType::INT,
+        "STRING" => ExpType::STRING,
+        "LIST" => ExpType::LIST,
+        "MAP" => ExpType::MAP,
+        "BLOB" => ExpType::BLOB,
+        "FLOAT" => ExpType::FLOAT,
+        "GEO" => ExpType::GEO,
+        "HLL" => ExpType::HLL,
+        _ => return None,
+    })
+}
+
+/// Element of a bin path: `[1]` is a list index, `key` or `'key'` a map key, and `[]` or `{}`
+/// refer to the list or map itself.
+enum Step {
+    Index(i64),
+    Key(Value),
+    List,
+    Map,
+}
+
+fn bin(name: String, ty: ExpType) -> FilterExpression {
+    FilterExpression::new(
+        Some(ExpOp::Bin),
+        Some(Value::from(name)),
+        None,
+        None,
+        Some(ty),
+        None,
+    )
+}
+
+fn value(val: Value) -> FilterExpression {
+    FilterExpression::new(None, Some(val), None, None, None, None)
+}
+
+#[derive(Clone, Copy)]
+enum Operator {
+    Variadic(Variadic),
+    Binary(Binary),
+}
+
+/// Binary operator precedence levels, from the loosest to the tightest binding. Operators on the
+/// first three levels only apply to integers, the others to integers or floats.
+const LEVELS: &[&[(&str, Operator)]] = &[
+    &[("|", Operator::Variadic(exp::int_or))],
+    &[("^", Operator::Variadic(exp::int_xor))],
+    &[("&", Operator::Variadic(exp::int_and))],
+    &[
+        ("<<", Operator::Binary(exp::int_lshift)),
+        (">>", Operator::Binary(exp::int_arshift)),
+        (">>>", Operator::Binary(exp::int_rshift)),
+    ],
+    &[
+        ("+", Operator::Variadic(exp::num_add)),
+        ("-", Operator::Variadic(exp::num_sub)),
+    ],
+    &[
+        ("*", Operator::Variadic(exp::num_mul)),
+        ("/", Operator::Variadic(exp::num_div)),
+        ("%", Operator::Binary(exp::num_mod)),
+    ],
+];
+
+const INTEGER_LEVELS: usize = 4;
+
+// Maximum nesting of sub-expressions and literals, which keeps deeply nested input from
+// overflowing the stack.
+const MAX_DEPTH: usize = 32;
+
+const NUMERIC: &[ExpType] = &[ExpType::INT, ExpType::FLOAT];
+
+struct Parser {
+    tokens: Vec<Token>,
+    idx: usize,
+    vars: Vec<(String, ExpType)>,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.idx].tok
+    }
+
+    fn pos(&self) -> usize {
+        self.tokens[self.idx].pos
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.idx].clone();
+        if token.tok != Tok::End {
+            self.idx += 1;
+        }
+        token
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        error(
+            self.pos(),
+            format!("expected {}, found {}", expected, self.peek().describe()),
+        )
+    }
+
+    fn eat_sym(&mut self, sym: &'static str) -> bool {
+        if self.peek() == &Tok::Sym(sym) {
+            self.idx += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_sym(&mut self, sym: &'static str) -> Result<()> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", sym)))
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Tok::Ident(name) if name == keyword => {
+                self.idx += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", keyword)))
+        }
+    }
+
+    fn expect_ident(&mut self, expected: &str) -> Result<String> {
+        match self.peek().clone() {
+            Tok::Ident(name) => {
+                self.idx += 1;
+                Ok(name)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn expect_string(&mut self, expected: &str) -> Result<String> {
+        match self.peek().clone() {
+            Tok::Str(val) => {
+                self.idx += 1;
+                Ok(val)
+            }
+            _ => Err(self.unexpected(expected)),
+        }
+    }
+
+    fn expect_int(&mut self) -> Result<i64> {
+        let negative = self.eat_sym("-");
+        match self.peek().clone() {
+            Tok::Int(val) => {
+                self.idx += 1;
+                Ok(if negative { -val } else { val })
+            }
+            _ => Err(self.unexpected("integer")),
+        }
+    }
+
+    fn expect_type(&mut self) -> Result<ExpType> {
+        let pos = self.pos();
+        let name = self.expect_ident("type name")?;
+        type_from_name(&name).ok_or_else(|| error(pos, format!("unknown type '{}'", name)))
+    }
+
+    /// Runs `parse` one nesting level deeper, failing if the input is nested too deeply.
+    fn nested<T, F>(&mut self, parse: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.depth == MAX_DEPTH {
+            return Err(error(self.pos(), "expression is nested too deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn parse_expr(&mut self) -> Result<Exp> {
+        self.nested(Self::parse_or)
+    }
+
+    fn parse_or(&mut self) -> Result<Exp> {
+        let mut operands = vec![self.parse_and()?];
+        while self.eat_keyword("or") || self.eat_sym("||") {
+            operands.push(self.parse_and()?);
+        }
+        logical(operands, exp::or)
+    }
+
+    fn parse_and(&mut self) -> Result<Exp> {
+        let mut operands = vec![self.parse_not()?];
+        while self.eat_keyword("and") || self.eat_sym("&&") {
+            operands.push(self.parse_not()?);
+        }
+        logical(operands, exp::and)
+    }
+
+    fn parse_not(&mut self) -> Result<Exp> {
+        let pos = self.pos();
+        if self.eat_keyword("not") || self.eat_sym("!") {
+            let operand = self.nested(Self::parse_not)?;
+            operand.expect(&[ExpType::BOOL])?;
+            return Ok(Exp::typed(
+                pos,
+                ExpType::BOOL,
+                exp::not(operand.resolve(ExpType::BOOL)),
+            ));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Exp> {
+        let left = self.parse_level(0)?;
+        let op: Binary = match self.peek() {
+            Tok::Sym("==") => exp::eq,
+            Tok::Sym("!=") => exp::ne,
+            Tok::Sym("<") => exp::lt,
+            Tok::Sym("<=") => exp::le,
+            Tok::Sym(">") => exp::gt,
+            Tok::Sym(">=") => exp::ge,
+            _ => return Ok(left),
+        };
+        self.idx += 1;
+        let right = self.parse_level(0)?;
+        let ty = match (left.ty, right.ty) {
+            (Some(l), Some(r)) if l != r && l != ExpType::NIL && r != ExpType::NIL => {
+                return Err(error(
+                    right.pos,
+                    format!("can not compare {:?} with {:?}", l, r),
+                ))
+            }
+            (Some(ExpType::NIL) | None, Some(ty)) | (Some(ty), _) => ty,
+            (None, None) => ExpType::INT,
+        };
+        let pos = left.pos;
+        Ok(Exp::typed(
+            pos,
+            ExpType::BOOL,
+            op(left.resolve(ty), right.resolve(ty)),
+        ))
+    }
+
+    /// Parses operators on `min_level` or a tighter binding level. Each operand is parsed by
+    /// one nested call for the next level only, rather than one call per level, which keeps the
+    /// stack shallow for nested sub-expressions.
+    fn parse_level(&mut self, min_level: usize) -> Result<Exp> {
+        let mut left = self.parse_unary()?;
+        while let Tok::Sym(sym) = self.peek() {
+            let sym = *sym;
+            let (level, op) =
+                match LEVELS
+                    .iter()
+                    .enumerate()
+                    .skip(min_level)
+                    .find_map(|(level, ops)| {
+                        ops.iter()
+                            .find(|(s, _)| *s == sym)
+                            .map(|(_, op)| (level, *op))
+                    }) {
+                    Some(found) => found,
+                    None => break,
+                };
+            self.idx += 1;
+
+            let mut operands = vec![left, self.parse_level(level + 1)?];
+            if let Operator::Variadic(_) = op {
+                while self.eat_sym(sym) {
+                    operands.push(self.parse_level(level + 1)?);
+                }
+            }
+            left = if level < INTEGER_LEVELS {
+                integer_op(op, operands)?
+            } else {
+                numeric_op(operands, move |exps| match op {
+                    Operator::Variadic(op) => op(exps),
+                    Operator::Binary(op) => {
+                        let mut exps = exps.into_iter();
+                        op(exps.next().unwrap(), exps.next().unwrap())
+                    }
+                })?
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<Exp> {
+        let pos = self.pos();
+        if self.eat_sym("-") {
+            match self.peek().clone() {
+                Tok::Int(val) => {
+                    self.idx += 1;
+                    return Ok(Exp::typed(pos, ExpType::INT, exp::int_val(-val)));
+                }
+                Tok::Float(val) => {
+                    self.idx += 1;
+                    return Ok(Exp::typed(pos, ExpType::FLOAT, exp::float_val(-val)));
+                }
+                _ => {
+                    let operand = self.nested(Self::parse_unary)?;
+                    return numeric_op(vec![operand], exp::num_sub);
+                }
+            }
+        }
+        if self.eat_sym("~") {
+            let operand = self.nested(Self::parse_unary)?;
+            return integer_op(Operator::Binary(|exp, _| exp::int_not(exp)), vec![operand]);
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Exp> {
+        let Token { tok, pos } = self.next();
+        match tok {
+            Tok::Int(val) => Ok(Exp::typed(pos, ExpType::INT, exp::int_val(val))),
+            Tok::Float(val) => Ok(Exp::typed(pos, ExpType::FLOAT, exp::float_val(val))),
+            Tok::Str(val) => Ok(Exp::typed(pos, ExpType::STRING, exp::string_val(val))),
+            Tok::Bin => self.parse_bin(pos),
+            Tok::Var(name) => match self.vars.iter().rev().find(|(var, _)| *var == name) {
+                Some((_, ty)) => Ok(Exp::typed(pos, *ty, exp::var(name))),
+                None => Err(error(pos, format!("undefined variable '{}'", name))),
+            },
+            Tok::Sym("(") => {
+                let exp = self.parse_expr()?;
+                self.expect_sym(")")?;
+                Ok(exp)
+            }
+            Tok::Sym("[" | "{") => {
+                self.idx -= 1;
+                match self.parse_value()? {
+                    Value::List(list) => Ok(Exp::typed(pos, ExpType::LIST, exp::list_val(list))),
+                    Value::HashMap(map) => Ok(Exp::typed(pos, ExpType::MAP, exp::map_val(map))),
+                    _ => unreachable!(),
+                }
+            }
+            Tok::Ident(name) => match name.as_str() {
+                "true" => Ok(Exp::typed(pos, ExpType::BOOL, exp::bool_val(true))),
+                "false" => Ok(Exp::typed(pos, ExpType::BOOL, exp::bool_val(false))),
+                "nil" | "null" => Ok(Exp::typed(pos, ExpType::NIL, exp::nil())),
+                "let" => self.parse_let(pos),
+                "when" => self.parse_when(pos),
+                _ if self.peek() == &Tok::Sym("(") => {
+                    self.idx += 1;
+                    self.parse_call(&name, pos)
+                }
+                _ => Err(error(pos, format!("unknown identifier '{}'", name))),
+            },
+            tok => Err(error(
+                pos,
+                format!("expected expression, found {}", tok.describe()),
+            )),
+        }
+    }
+
+    /// Parses a constant value, as used in list and map literals.
+    fn parse_value(&mut self) -> Result<Value> {
+        let pos = self.pos();
+        let negative = self.eat_sym("-");
+        match (self.next().tok, negative) {
+            (Tok::Int(val), _) => Ok(Value::from(if negative { -val } else { val })),
+            (Tok::Float(val), _) => Ok(Value::from(if negative { -val } else { val })),
+            (Tok::Str(val), false) => Ok(Value::from(val)),
+            (Tok::Ident(name), false) if name == "true" || name == "false" => {
+                Ok(Value::from(name == "true"))
+            }
+            (Tok::Ident(name), false) if name == "nil" || name == "null" => Ok(Value::Nil),
+            (Tok::Ident(name), false) if self.eat_sym("(") => match name.as_str() {
+                "blob" | "hll" | "inf" | "wildcard" => self.parse_constant(&name),
+                "geo" => {
+                    let json_pos = self.pos();
+                    let json = self.expect_string("GeoJSON string")?;
+                    self.expect_sym(")")?;
+                    json.parse::<Geometry>()
+                        .map_err(|err| error(json_pos, err.to_string()))?;
+                    Ok(Value::GeoJSON(json))
+                }
+                _ => Err(error(pos, "expected constant value")),
+            },
+            (Tok::Sym("["), false) => {
+                let mut list = vec![];
+                if !self.eat_sym("]") {
+                    loop {
+                        list.push(self.nested(Self::parse_value)?);
+                        if !self.eat_sym(",") {
+                            self.expect_sym("]")?;
+                            break;
+                        }
+                    }
+                }
+                Ok(Value::from(list))
+            }
+            (Tok::Sym("{"), false) => {
+                let mut map = HashMap::new();
+                if !self.eat_sym("}") {
+                    loop {
+                        let key_pos = self.pos();
+                        let key = self.nested(Self::parse_value)?;
+                        if !key.is_valid_map_key() {
+                            return Err(error(key_pos, "map keys can not be maps"));
+                        }
+                        self.expect_sym(":")?;
+                        map.insert(key, self.nested(Self::parse_value)?);
+                        if !self.eat_sym(",") {
+                            self.expect_sym("}")?;
+                            break;
+                        }
+                    }
+                }
+                Ok(Value::from(map))
+            }
+            _ => Err(error(pos, "expected constant value")),
+        }
+    }
+
+    /// Parses a bin path following `$.`, e.g. `$.name`, `$.scores.[0]`, `$.address.city`,
+    /// `$.tags.[].count()` or `$.name.get(type: STRING)`.
+    fn parse_bin(&mut self, pos: usize) -> Result<Exp> {
+        let name = match self.next().tok {
+            Tok::Ident(name) | Tok::Str(name) => name,
+            _ => return Err(error(pos, "expected bin name after '$.'")),
+        };
+        let mut steps = vec![];
+        while self.eat_sym(".") {
+            let Token { tok, pos } = self.next();
+            let step = match tok {
+                Tok::Sym("[") => {
+                    if self.eat_sym("]") {
+                        Step::List
+                    } else {
+                        let index = self.expect_int()?;
+                        self.expect_sym("]")?;
+                        Step::Index(index)
+                    }
+                }
+                Tok::Sym("{") => {
+                    self.expect_sym("}")?;
+                    Step::Map
+                }
+                Tok::Ident(method) if self.eat_sym("(") => {
+                    return self.parse_method(name, steps, &method, pos);
+                }
+                Tok::Ident(key) | Tok::Str(key) => Step::Key(Value::from(key)),
+                Tok::Int(key) => Step::Key(Value::from(key)),
+                tok => {
+                    return Err(error(
+                        pos,
+                        format!("expected path element, found {}", tok.describe()),
+                    ))
+                }
+            };
+            steps.push((pos, step));
+        }
+        path(pos, name, steps, None)
+    }
+
+    fn parse_method(
+        &mut self,
+        name: String,
+        steps: Vec<(usize, Step)>,
+        method: &str,
+        pos: usize,
+    ) -> Result<Exp> {
+        let container = match steps.last() {
+            Some((_, Step::List)) => Some(ExpType::LIST),
+            Some((_, Step::Map)) => Some(ExpType::MAP),
+            _ => None,
+        };
+        match (method, container) {
+            ("exists" | "type", None) if steps.is_empty() => {
+                self.expect_sym(")")?;
+                Ok(if method == "exists" {
+                    Exp::typed(pos, ExpType::BOOL, exp::bin_exists(name))
+                } else {
+                    Exp::typed(pos, ExpType::INT, exp::bin_type(name))
+                })
+            }
+            ("get", _) => {
+                self.expect_keyword("type")?;
+                self.expect_sym(":")?;
+                let ty = self.expect_type()?;
+                self.expect_sym(")")?;
+                path(pos, name, steps, Some(ty))
+            }
+            ("count", Some(container)) => {
+                self.expect_sym(")")?;
+                let (bin, ctx) = cdt_target(name, &steps)?;
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::INT,
+                    if container == ExpType::LIST {
+                        lists::size(bin, &ctx)
+                    } else {
+                        maps::size(bin, &ctx)
+                    },
+                ))
+            }
+            ("contains", Some(container)) => {
+                let item = self.parse_expr()?;
+                self.expect_sym(")")?;
+                let (bin, ctx) = cdt_target(name, &steps)?;
+                let item = item.resolve(ExpType::INT);
+                let count = if container == ExpType::LIST {
+                    lists::get_by_value(ListReturnType::Count, item, bin, &ctx)
+                } else {
+                    maps::get_by_key(MapReturnType::Count, ExpType::INT, item, bin, &ctx)
+                };
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::BOOL,
+                    exp::gt(count, exp::int_val(0)),
+                ))
+            }
+            _ => Err(error(
+                pos,
+                format!("method '{}' can not be used here", method),
+            )),
+        }
+    }
+
+    fn parse_args(&mut self) -> Result<Vec<Exp>> {
+        let mut args = vec![];
+        if !self.eat_sym(")") {
+            loop {
+                args.push(self.parse_expr()?);
+                if !self.eat_sym(",") {
+                    self.expect_sym(")")?;
+                    break;
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    fn parse_call(&mut self, name: &str, pos: usize) -> Result<Exp> {
+        match name {
+            "digest_modulo" => {
+                let modulo = self.expect_int()?;
+                self.expect_sym(")")?;
+                return Ok(Exp::typed(pos, ExpType::INT, exp::digest_modulo(modulo)));
+            }
+            "key" => {
+                if self.eat_sym(")") {
+                    return Ok(Exp::deferred(pos, exp::key));
+                }
+                let ty = self.expect_type()?;
+                self.expect_sym(")")?;
+                return Ok(Exp::typed(pos, ty, exp::key(ty)));
+            }
+            "regex" => {
+                let bin = self.parse_expr()?;
+                bin.expect(&[ExpType::STRING])?;
+                self.expect_sym(",")?;
+                let regex = self.expect_string("regular expression string")?;
+                let mut flags = RegexFlag::NONE as i64;
+                if self.eat_sym(",") {
+                    let flags_pos = self.pos();
+                    for flag in self.expect_string("regular expression flags")?.chars() {
+                        flags |= match flag {
+                            'x' | 'e' => RegexFlag::EXTENDED,
+                            'i' => RegexFlag::ICASE,
+                            's' => RegexFlag::NOSUB,
+                            'n' => RegexFlag::NEWLINE,
+                            _ => {
+                                return Err(error(
+                                    flags_pos,
+                                    format!("unknown regular expression flag '{}'", flag),
+                                ))
+                            }
+                        } as i64;
+                    }
+                }
+                self.expect_sym(")")?;
+                return Ok(Exp::typed(
+                    pos,
+                    ExpType::BOOL,
+                    exp::regex_compare(regex, flags, bin.resolve(ExpType::STRING)),
+                ));
+            }
+            "geo" => {
+                let json_pos = self.pos();
+                let json = self.expect_string("GeoJSON string")?;
+                self.expect_sym(")")?;
+                let geometry: Geometry = json
+                    .parse()
+                    .map_err(|err: Error| error(json_pos, err.to_string()))?;
+                return Ok(Exp::typed(pos, ExpType::GEO, exp::geo_val(geometry)));
+            }
+            "blob" | "hll" | "inf" | "wildcard" => {
+                let val = self.parse_constant(name)?;
+                let constant = |val| FilterExpression::new(None, Some(val), None, None, None, None);
+                return Ok(match val {
+                    Value::Blob(_) => Exp::typed(pos, ExpType::BLOB, constant(val)),
+                    Value::HLL(_) => Exp::typed(pos, ExpType::HLL, constant(val)),
+                    _ => Exp::deferred(pos, move |_| constant(val)),
+                });
+            }
+            "call" => return self.parse_module_call(name, None, pos),
+            _ => (),
+        }
+        if let Some((_, flags, op)) = FUNCTIONS.iter().find(|(function, _, _)| *function == name) {
+            return self.parse_module_call(name, Some((*flags, *op)), pos);
+        }
+
+        let args = self.parse_args()?;
+        let arity = |count: usize| {
+            if args.len() == count {
+                Ok(())
+            } else {
+                Err(error(
+                    pos,
+                    format!(
+                        "function '{}' takes {} argument(s), found {}",
+                        name,
+                        count,
+                        args.len()
+                    ),
+                ))
+            }
+        };
+
+        let metadata = match name {
+            "ttl" => Some((ExpType::INT, exp::ttl as fn() -> FilterExpression)),
+            "void_time" => Some((ExpType::INT, exp::void_time as _)),
+            "last_update" => Some((ExpType::INT, exp::last_update as _)),
+            "since_update" => Some((ExpType::INT, exp::since_update as _)),
+            "device_size" => Some((ExpType::INT, exp::device_size as _)),
+            "set_name" => Some((ExpType::STRING, exp::set_name as _)),
+            "key_exists" => Some((ExpType::BOOL, exp::key_exists as _)),
+            "is_tombstone" => Some((ExpType::BOOL, exp::is_tombstone as _)),
+            _ => None,
+        };
+        if let Some((ty, build)) = metadata {
+            arity(0)?;
+            return Ok(Exp::typed(pos, ty, build()));
+        }
+
+        match name {
+            "unknown" => {
+                arity(0)?;
+                Ok(Exp::deferred(pos, |_| exp::unknown()))
+            }
+            "geo_compare" => {
+                arity(2)?;
+                let mut args = args.into_iter();
+                let (left, right) = (args.next().unwrap(), args.next().unwrap());
+                left.expect(&[ExpType::GEO])?;
+                right.expect(&[ExpType::GEO])?;
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::BOOL,
+                    exp::geo_compare(left.resolve(ExpType::GEO), right.resolve(ExpType::GEO)),
+                ))
+            }
+            "abs" => {
+                arity(1)?;
+                numeric_op(args, |mut exps| exp::num_abs(exps.remove(0)))
+            }
+            "min" | "max" => {
+                if args.is_empty() {
+                    return Err(error(
+                        pos,
+                        format!("function '{}' takes at least one argument", name),
+                    ));
+                }
+                numeric_op(args, if name == "min" { exp::min } else { exp::max })
+            }
+            "floor" | "ceil" | "to_int" => {
+                arity(1)?;
+                let arg = args.into_iter().next().unwrap();
+                arg.expect(&[ExpType::FLOAT])?;
+                let arg = arg.resolve(ExpType::FLOAT);
+                Ok(match name {
+                    "floor" => Exp::typed(pos, ExpType::FLOAT, exp::num_floor(arg)),
+                    "ceil" => Exp::typed(pos, ExpType::FLOAT, exp::num_ceil(arg)),
+                    _ => Exp::typed(pos, ExpType::INT, exp::to_int(arg)),
+                })
+            }
+            "to_float" => {
+                arity(1)?;
+                let arg = args.into_iter().next().unwrap();
+                arg.expect(&[ExpType::INT])?;
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::FLOAT,
+                    exp::to_float(arg.resolve(ExpType::INT)),
+                ))
+            }
+            "pow" | "log" => {
+                arity(2)?;
+                let mut args = args.into_iter();
+                let (left, right) = (args.next().unwrap(), args.next().unwrap());
+                left.expect(&[ExpType::FLOAT])?;
+                right.expect(&[ExpType::FLOAT])?;
+                let (left, right) = (left.resolve(ExpType::FLOAT), right.resolve(ExpType::FLOAT));
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::FLOAT,
+                    if name == "pow" {
+                        exp::num_pow(left, right)
+                    } else {
+                        exp::num_log(left, right)
+                    },
+                ))
+            }
+            "count" => {
+                arity(1)?;
+                integer_op(Operator::Binary(|exp, _| exp::int_count(exp)), args)
+            }
+            "lscan" | "rscan" => {
+                arity(2)?;
+                let mut args = args.into_iter();
+                let (value, search) = (args.next().unwrap(), args.next().unwrap());
+                value.expect(&[ExpType::INT])?;
+                search.expect(&[ExpType::BOOL])?;
+                let (value, search) = (value.resolve(ExpType::INT), search.resolve(ExpType::BOOL));
+                Ok(Exp::typed(
+                    pos,
+                    ExpType::INT,
+                    if name == "lscan" {
+                        exp::int_lscan(value, search)
+                    } else {
+                        exp::int_rscan(value, search)
+                    },
+                ))
+            }
+            _ => Err(error(pos, format!("unknown function '{}'", name))),
+        }
+    }
+
+    /// Parses the arguments of `blob('<hex>')`, `hll('<hex>')`, `inf()` or `wildcard()`.
+    fn parse_constant(&mut self, name: &str) -> Result<Value> {
+        let val = match name {
+            "blob" | "hll" => {
+                let hex_pos = self.pos();
+                let bytes = from_hex(&self.expect_string("hex string")?)
+                    .ok_or_else(|| error(hex_pos, "invalid hex string"))?;
+                if name == "blob" {
+                    Value::Blob(bytes)
+                } else {
+                    Value::HLL(bytes)
+                }
+            }
+            "inf" => Value::Infinity,
+            _ => Value::Wildcard,
+        };
+        self.expect_sym(")")?;
+        Ok(val)
+    }
+
+    /// Parses a list, map, bitwise or HLL function, e.g. `list_get_by_rank(VALUES, -1, $.scores)`
+    /// or `map_size($.m, ctx: [map_key('a')], type: INT)`, or `call(<flags>, <op>, ...)` for
+    /// operations without a name. The bin is the last positional argument; list and map
+    /// selectors start with the return type. Like bins, the function is typed by the expression
+    /// it is used in unless `type` is given.
+    fn parse_module_call(
+        &mut self,
+        name: &str,
+        function: Option<(i64, i64)>,
+        pos: usize,
+    ) -> Result<Exp> {
+        let (flags, op, mut first) = match function {
+            Some((flags, op)) => (flags, op, true),
+            None => {
+                let flags = self.expect_int()?;
+                self.expect_sym(",")?;
+                (flags, self.expect_int()?, false)
+            }
+        };
+        let mut arguments = vec![ExpressionArgument::Value(Value::from(op))];
+        if first && takes_return_type(flags, op) {
+            arguments.push(ExpressionArgument::Value(Value::from(
+                self.parse_return_type(op)?,
+            )));
+            first = false;
+        }
+
+        let mut operands = vec![];
+        let mut ctx = vec![];
+        let mut ty = None;
+        let mut named = false;
+        loop {
+            if first {
+                first = false;
+                if self.eat_sym(")") {
+                    break;
+                }
+            } else if !self.eat_sym(",") {
+                self.expect_sym(")")?;
+                break;
+            }
+            let arg = match (self.peek(), self.tokens.get(self.idx + 1).map(|t| &t.tok)) {
+                (Tok::Ident(arg), Some(Tok::Sym(":"))) => arg.clone(),
+                _ if named => return Err(self.unexpected("named argument")),
+                _ => {
+                    operands.push(self.parse_expr()?);
+                    continue;
+                }
+            };
+            let arg_pos = self.pos();
+            self.idx += 2;
+            named = true;
+            match arg.as_str() {
+                "ctx" => ctx = self.parse_context()?,
+                "type" => ty = Some(self.expect_type()?),
+                _ => return Err(error(arg_pos, format!("unknown argument '{}'", arg))),
+            }
+        }
+
+        let bin = operands
+            .pop()
+            .ok_or_else(|| error(pos, format!("function '{}' takes a bin argument", name)))?;
+        let bin_type = module_bin_type(flags, op, &ctx);
+        if flags & MODIFY != 0 && ty.is_none() {
+            // Modify operations return the modified bin.
+            ty = Some(bin_type);
+        }
+        let bin = bin.resolve(bin_type);
+        arguments.extend(
+            operands
+                .into_iter()
+                .map(|operand| ExpressionArgument::FilterExpression(operand.resolve(ExpType::INT))),
+        );
+        arguments.push(ExpressionArgument::Context(ctx));
+
+        Ok(Exp::with_type(pos, ty, move |ty| {
+            let mut call = FilterExpression::new(
+                Some(ExpOp::Call),
+                None,
+                Some(bin),
+                Some(flags),
+                Some(ty),
+                None,
+            );
+            call.arguments = Some(arguments);
+            call
+        }))
+    }
+
+    /// `VALUES`, `COUNT | INVERTED` or the numeric return type.
+    fn parse_return_type(&mut self, op: i64) -> Result<i64> {
+        let pos = self.pos();
+        let name = match self.peek().clone() {
+            Tok::Ident(name) => name,
+            _ => return self.expect_int(),
+        };
+        self.idx += 1;
+        let mut return_type = return_types(op)
+            .iter()
+            .find(|(return_type, _)| *return_type == name)
+            .ok_or_else(|| error(pos, format!("unknown return type '{}'", name)))?
+            .1;
+        if self.eat_sym("|") {
+            self.expect_keyword("INVERTED")?;
+            return_type |= INVERTED;
+        }
+        Ok(return_type)
+    }
+
+    /// `[list_index(0), map_key('a'), ctx(<id>, <value>)]`
+    fn parse_context(&mut self) -> Result<Vec<CdtContext>> {
+        self.expect_sym("[")?;
+        let mut ctx = vec![];
+        if self.eat_sym("]") {
+            return Ok(ctx);
+        }
+        loop {
+            let pos = self.pos();
+            let name = self.expect_ident("context")?;
+            self.expect_sym("(")?;
+            let id = if name == "ctx" {
+                let id_pos = self.pos();
+                let id = self.expect_int()?;
+                self.expect_sym(",")?;
+                u8::try_from(id)
+                    .map_err(|_| error(id_pos, format!("invalid context type {}", id)))?
+            } else {
+                CONTEXTS
+                    .iter()
+                    .find(|(context, _)| *context == name)
+                    .ok_or_else(|| error(pos, format!("unknown context '{}'", name)))?
+                    .1
+            };
+            let value = self.parse_value()?;
+            self.expect_sym(")")?;
+            ctx.push(CdtContext {
+                id,
+                flags: 0,
+                value,
+            });
+            if !self.eat_sym(",") {
+                self.expect_sym("]")?;
+                return Ok(ctx);
+            }
+        }
+    }
+
+    /// `let (x = <exp>, y = <exp>) then (<exp>)`
+    fn parse_let(&mut self, pos: usize) -> Result<Exp> {
+        self.expect_sym("(")?;
+        let scope = self.vars.len();
+        let mut defs = vec![];
+        loop {
+            let name = self.expect_ident("variable name")?;
+            self.expect_sym("=")?;
+            let value = self.parse_expr()?;
+            let ty = value.ty.unwrap_or(ExpType::INT);
+            defs.push(exp::def(name.clone(), value.resolve(ty)));
+            self.vars.push((name, ty));
+            if !self.eat_sym(",") {
+                self.expect_sym(")")?;
+                break;
+            }
+        }
+        self.expect_keyword("then")?;
+        self.expect_sym("(")?;
+        let body = self.parse_expr();
+        self.vars.truncate(scope);
+        let body = body?;
+        self.expect_sym(")")?;
+
+        Ok(Exp::with_type(pos, body.ty, move |ty| {
+            defs.push(body.resolve(ty));
+            exp::exp_let(defs)
+        }))
+    }
+
+    /// `when (<condition> => <exp>, ..., default => <exp>)`
+    fn parse_when(&mut self, pos: usize) -> Result<Exp> {
+        self.expect_sym("(")?;
+        let mut conditions = vec![];
+        let mut actions = vec![];
+        loop {
+            if self.eat_keyword("default") {
+                self.expect_sym("=>")?;
+                actions.push(self.parse_expr()?);
+                self.expect_sym(")")?;
+                break;
+            }
+            let condition = self.parse_expr()?;
+            condition.expect(&[ExpType::BOOL])?;
+            conditions.push(condition.resolve(ExpType::BOOL));
+            self.expect_sym("=>")?;
+            actions.push(self.parse_expr()?);
+            self.expect_sym(",")?;
+        }
+
+        let ty = common_type(&actions, None)?;
+        Ok(Exp::with_type(pos, ty, move |ty| {
+            let mut exps = vec![];
+            let mut actions = actions.into_iter();
+            for condition in conditions {
+                exps.push(condition);
+                exps.push(actions.next().unwrap().resolve(ty));
+            }
+            exps.extend(actions.map(|action| action.resolve(ty)));
+            exp::cond(exps)
+        }))
+    }
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn logical(mut operands: Vec<Exp>, op: Variadic) -> Result<Exp> {
+    if operands.len() == 1 {
+        return Ok(operands.pop().unwrap());
+    }
+    for operand in &operands {
+        operand.expect(&[ExpType::BOOL])?;
+    }
+    let pos = operands[0].pos;
+    Ok(Exp::typed(
+        pos,
+        ExpType::BOOL,
+        op(operands
+            .into_iter()
+            .map(|operand| operand.resolve(ExpType::BOOL))
+            .collect()),
+    ))
+}
+
+/// Returns the type shared by all typed `exps`, or an error pointing at the first one that
+/// differs. Nil is compatible with every type.
+fn common_type(exps: &[Exp], allowed: Option<&[ExpType]>) -> Result<Option<ExpType>> {
+    let mut common: Option<ExpType> = None;
+    for exp in exps {
+        if let Some(allowed) = allowed {
+            exp.expect(allowed)?;
+        }
+        match (common, exp.ty) {
+            (_, None | Some(ExpType::NIL)) => (),
+            (None, ty) => common = ty,
+            (Some(common), Some(ty)) if common != ty => {
+                return Err(error(
+                    exp.pos,
+                    format!("expected {:?} expression, found {:?}", common, ty),
+                ))
+            }
+            _ => (),
+        }
+    }
+    Ok(common)
+}
+
+fn numeric_op<F>(operands: Vec<Exp>, op: F) -> Result<Exp>
+where
+    F: FnOnce(Vec<FilterExpression>) -> FilterExpression + 'static,
+{
+    let ty = common_type(&operands, Some(NUMERIC))?;
+    let pos = operands[0].pos;
+    Ok(Exp::with_type(pos, ty, move |ty| {
+        op(operands
+            .into_iter()
+            .map(|operand| operand.resolve(ty))
+            .collect())
+    }))
+}
+
+fn integer_op(op: Operator, operands: Vec<Exp>) -> Result<Exp> {
+    for operand in &operands {
+        operand.expect(&[ExpType::INT])?;
+    }
+    let pos = operands[0].pos;
+    let mut exps: Vec<_> = operands
+        .into_iter()
+        .map(|operand| operand.resolve(ExpType::INT))
+        .collect();
+    let exp = match op {
+        Operator::Variadic(op) => op(exps),
+        Operator::Binary(op) => {
+            let left = exps.remove(0);
+            let right = exps.pop().unwrap_or_else(exp::nil);
+            op(left, right)
+        }
+    };
+    Ok(Exp::typed(pos, ExpType::INT, exp))
+}
+
+/// Splits a bin path into the top-level bin and the context leading to its last element.
+fn cdt_target(
+    name: String,
+    steps: &[(usize, Step)],
+) -> Result<(FilterExpression, Vec<CdtContext>)> {
+    let bin = match steps[0].1 {
+        Step::Index(_) | Step::List => exp::list_bin(name),
+        Step::Key(_) | Step::Map => exp::map_bin(name),
+    };
+    let mut ctx = vec![];
+    for (pos, step) in &steps[..steps.len() - 1] {
+        ctx.push(match step {
+            Step::Index(index) => ctx_list_index(*index),
+            Step::Key(key) => ctx_map_key(key.clone()),
+            Step::List | Step::Map => {
+                return Err(error(
+                    *pos,
+                    "'[]' and '{}' can only be used at the end of a path",
+                ))
+            }
+        });
+    }
+    Ok((bin, ctx))
+}
+
+/// Reads the element addressed by a bin path, with the given type or the type inferred later.
+fn path(
+    pos: usize,
+    name: String,
+    mut steps: Vec<(usize, Step)>,
+    ty: Option<ExpType>,
+) -> Result<Exp> {
+    let container = match steps.last() {
+        None => return Ok(Exp::with_type(pos, ty, move |ty| bin(name, ty))),
+        Some((_, Step::List)) => ExpType::LIST,
+        Some((_, Step::Map)) => ExpType::MAP,
+        Some(_) => {
+            let (bin, ctx) = cdt_target(name, &steps)?;
+            let step = steps.pop().unwrap().1;
+            return Ok(Exp::with_type(pos, ty, move |ty| match step {
+                Step::Index(index) => {
+                    lists::get_by_index(ListReturnType::Values, ty, exp::int_val(index), bin, &ctx)
+                }
+                Step::Key(key) => maps::get_by_key(MapReturnType::Value, ty, value(key), bin, &ctx),
+                Step::List | Step::Map => unreachable!(),
+            }));
+        }
+    };
+
+    // `[]` and `{}` address the container itself.
+    if let Some(ty) = ty.filter(|ty| *ty != container) {
+        return Err(error(
+            pos,
+            format!("expected {:?} for this path, found {:?}", container, ty),
+        ));
+    }
+    steps.pop();
+    path(pos, name, steps, Some(container))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::buffer::Buffer;
+    use crate::expressions::*;
+    use crate::operations::bitwise::BitPolicy;
+    use crate::operations::hll::HLLPolicy;
+    use crate::operations::lists::ListPolicy;
+    use crate::MapPolicy;
+
+    fn packed(exp: &FilterExpression) -> Vec<u8> {
+        let mut buf = Buffer::new(1024);
+        let size = exp.pack(&mut None).unwrap();
+        buf.resize_buffer(size).unwrap();
+        exp.pack(&mut Some(&mut buf)).unwrap();
+        buf.data_buffer[..size].to_vec()
+    }
+
+    fn assert_parses(text: &str, expected: FilterExpression) {
+        let parsed = FilterExpression::parse(text).unwrap();
+        assert_eq!(packed(&parsed), packed(&expected), "{}", text);
+    }
+
+    fn assert_error(text: &str, position: usize, details: &str) {
+        match FilterExpression::parse(text) {
+            Err(Error(ErrorKind::InvalidExpression(pos, msg), _)) => {
+                assert_eq!((pos, msg.as_str()), (position, details), "{}", text);
+            }
+            other => panic!("unexpected result for {}: {:?}", text, other),
+        }
+    }
+
+    #[test]
+    fn comparisons() {
+        assert_parses(
+            "$.age > 21 and $.country == 'NL' and ttl() < 3600",
+            and(vec![
+                gt(int_bin("age".into()), int_val(21)),
+                eq(string_bin("country".into()), string_val("NL".into())),
+                lt(ttl(), int_val(3600)),
+            ]),
+        );
+        assert_parses(
+            "not ($.a == 1.5 || $.b != -2) && $.flag",
+            and(vec![
+                not(or(vec![
+                    eq(float_bin("a".into()), float_val(1.5)),
+                    ne(int_bin("b".into()), int_val(-2)),
+                ])),
+                bin("flag".into(), ExpType::BOOL),
+            ]),
+        );
+        assert_parses(
+            "$.'bin name'.get(type: BLOB) == $.other",
+            eq(blob_bin("bin name".into()), blob_bin("other".into())),
+        );
+        assert_parses(
+            "$.a.exists() and $.a.type() == 1 and key(STRING) == 'k' and key_exists()",
+            and(vec![
+                bin_exists("a".into()),
+                eq(bin_type("a".into()), int_val(1)),
+                eq(key(ExpType::STRING), string_val("k".into())),
+                key_exists(),
+            ]),
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let a = || int_bin("a".into());
+        let b = || int_bin("b".into());
+        assert_parses(
+            "$.a or $.b and not $.c",
+            or(vec![
+                bin("a".into(), ExpType::BOOL),
+                and(vec![
+                    bin("b".into(), ExpType::BOOL),
+                    not(bin("c".into(), ExpType::BOOL)),
+                ]),
+            ]),
+        );
+        assert_parses(
+            "!$.a && $.b || $.c",
+            or(vec![
+                and(vec![
+                    not(bin("a".into(), ExpType::BOOL)),
+                    bin("b".into(), ExpType::BOOL),
+                ]),
+                bin("c".into(), ExpType::BOOL),
+            ]),
+        );
+        assert_parses(
+            "not $.a == 1 and $.b > 2 or $.a < 0",
+            or(vec![
+                and(vec![not(eq(a(), int_val(1))), gt(b(), int_val(2))]),
+                lt(a(), int_val(0)),
+            ]),
+        );
+        assert_parses(
+            "1 | 2 ^ 3 & 4 << 5 + 6 * 7 == $.a",
+            eq(
+                int_or(vec![
+                    int_val(1),
+                    int_xor(vec![
+                        int_val(2),
+                        int_and(vec![
+                            int_val(3),
+                            int_lshift(
+                                int_val(4),
+                                num_add(vec![int_val(5), num_mul(vec![int_val(6), int_val(7)])]),
+                            ),
+                        ]),
+                    ]),
+                ]),
+                a(),
+            ),
+        );
+        assert_parses(
+            "$.a * 2 + 3 & 4 ^ 5 | 6 == $.b",
+            eq(
+                int_or(vec![
+                    int_xor(vec![
+                        int_and(vec![
+                            num_add(vec![num_mul(vec![a(), int_val(2)]), int_val(3)]),
+                            int_val(4),
+                        ]),
+                        int_val(5),
+                    ]),
+                    int_val(6),
+                ]),
+                b(),
+            ),
+        );
+        assert_parses(
+            "$.a << 1 >> 2 >>> 3 == 0",
+            eq(
+                int_rshift(
+                    int_arshift(int_lshift(a(), int_val(1)), int_val(2)),
+                    int_val(3),
+                ),
+                int_val(0),
+            ),
+        );
+        assert_parses(
+            "1 + 2 + 3 - 4 - 5 == $.a * 2 / 3 % 4",
+            eq(
+                num_sub(vec![
+                    num_add(vec![int_val(1), int_val(2), int_val(3)]),
+                    int_val(4),
+                    int_val(5),
+                ]),
+                num_mod(
+                    num_div(vec![num_mul(vec![a(), int_val(2)]), int_val(3)]),
+                    int_val(4),
+                ),
+            ),
+        );
+        assert_parses(
+            "$.a % 3 % 2 == 1 - (2 - 3)",
+            eq(
+                num_mod(num_mod(a(), int_val(3)), int_val(2)),
+                num_sub(vec![int_val(1), num_sub(vec![int_val(2), int_val(3)])]),
+            ),
+        );
+        assert_parses(
+            "-$.a * -2 == ~$.b & ~~1",
+            eq(
+                num_mul(vec![num_sub(vec![a()]), int_val(-2)]),
+                int_and(vec![int_not(b()), int_not(int_not(int_val(1)))]),
+            ),
+        );
+        assert_parses(
+            "- 1 == --1 and -1.5 < -(1.5)",
+            and(vec![
+                eq(int_val(-1), num_sub(vec![int_val(-1)])),
+                lt(float_val(-1.5), num_sub(vec![float_val(1.5)])),
+            ]),
+        );
+        assert_parses(
+            "(1 | 2) & (($.a)) == 3",
+            eq(
+                int_and(vec![int_or(vec![int_val(1), int_val(2)]), a()]),
+                int_val(3),
+            ),
+        );
+        assert_error("1 < 2 < 3", 6, "expected end of expression, found '<'");
+        assert_error(
+            "1 == 2 == true",
+            7,
+            "expected end of expression, found '=='",
+        );
+    }
+
+    #[test]
+    fn literals() {
+        assert_parses(
+            "$.a == 0xff or $.a == 0X1F or $.a == 9223372036854775807",
+            or(vec![
+                eq(int_bin("a".into()), int_val(255)),
+                eq(int_bin("a".into()), int_val(31)),
+                eq(int_bin("a".into()), int_val(i64::MAX)),
+            ]),
+        );
+        assert_parses(
+            "$.f == 1e3 or $.f == 2.5E-1 or $.f == 0.5e+1",
+            or(vec![
+                eq(float_bin("f".into()), float_val(1000.0)),
+                eq(float_bin("f".into()), float_val(0.25)),
+                eq(float_bin("f".into()), float_val(5.0)),
+            ]),
+        );
+        assert_parses(
+            r#"$.s == 'it\'s' or $.s == "say \"hi\"" or $.s == 'a\\b' or $.s == 'ü'"#,
+            or(vec![
+                eq(string_bin("s".into()), string_val("it's".into())),
+                eq(string_bin("s".into()), string_val("say \"hi\"".into())),
+                eq(string_bin("s".into()), string_val("a\\b".into())),
+                eq(string_bin("s".into()), string_val("ü".into())),
+            ]),
+        );
+        assert_parses(
+            "$.b == true or $.b == false or $.n == nil or $.n == null",
+            or(vec![
+                eq(bin("b".into(), ExpType::BOOL), bool_val(true)),
+                eq(bin("b".into(), ExpType::BOOL), bool_val(false)),
+                eq(bin("n".into(), ExpType::NIL), nil()),
+                eq(bin("n".into(), ExpType::NIL), nil()),
+            ]),
+        );
+        assert_parses(
+            "$.b == blob('00ff') and $.h == hll('ABcd') and $.e == blob('')",
+            and(vec![
+                eq(blob_bin("b".into()), blob_val(vec![0x00, 0xff])),
+                eq(
+                    hll_bin("h".into()),
+                    FilterExpression::new(
+                        None,
+                        Some(Value::HLL(vec![0xab, 0xcd])),
+                        None,
+                        None,
+                        None,
+                        None,
+                    ),
+                ),
+                eq(blob_bin("e".into()), blob_val(vec![])),
+            ]),
+        );
+        assert_parses(
+            "$.l == [] and $.m == {} and $.l == [-1, -2.5, 'x', [[]], {'k': [nil]}]",
+            and(vec![
+                eq(list_bin("l".into()), list_val(vec![])),
+                eq(map_bin("m".into()), map_val(HashMap::new())),
+                eq(
+                    list_bin("l".into()),
+                    list_val(vec![
+                        Value::from(-1),
+                        Value::from(-2.5),
+                        Value::from("x"),
+                        Value::from(vec![Value::from(Vec::<Value>::new())]),
+                        Value::from(
+                            vec![(Value::from("k"), Value::from(vec![Value::Nil]))]
+                                .into_iter()
+                                .collect::<HashMap<_, _>>(),
+                        ),
+                    ]),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.l == [blob('01'), hll('02'), inf(), wildcard(), geo('{\"type\":\"Point\",\"coordinates\":[1,2]}')]",
+            eq(
+                list_bin("l".into()),
+                list_val(vec![
+                    Value::Blob(vec![1]),
+                    Value::HLL(vec![2]),
+                    Value::Infinity,
+                    Value::Wildcard,
+                    Value::GeoJSON("{\"type\":\"Point\",\"coordinates\":[1,2]}".into()),
+                ]),
+            ),
+        );
+        assert_parses(
+            "list_get_by_value_range(VALUES, 1, inf(), $.l) == [wildcard()]",
+            eq(
+                lists::get_by_value_range(
+                    ListReturnType::Values,
+                    Some(int_val(1)),
+                    Some(inf()),
+                    list_bin("l".into()),
+                    &[],
+                ),
+                list_val(vec![Value::Wildcard]),
+            ),
+        );
+    }
+
+    #[test]
+    fn functions() {
+        assert_parses(
+            "ttl() > 1 and void_time() > 2 and last_update() > 3 and since_update() > 4 and \
+             device_size() > 5 and set_name() == 's' and key_exists() and not is_tombstone()",
+            and(vec![
+                gt(ttl(), int_val(1)),
+                gt(void_time(), int_val(2)),
+                gt(last_update(), int_val(3)),
+                gt(since_update(), int_val(4)),
+                gt(device_size(), int_val(5)),
+                eq(set_name(), string_val("s".into())),
+                key_exists(),
+                not(is_tombstone()),
+            ]),
+        );
+        assert_parses(
+            "digest_modulo(3) == -1 and key() == 1 and key(BLOB) == blob('01') and key() == 'k'",
+            and(vec![
+                eq(digest_modulo(3), int_val(-1)),
+                eq(key(ExpType::INT), int_val(1)),
+                eq(key(ExpType::BLOB), blob_val(vec![1])),
+                eq(key(ExpType::STRING), string_val("k".into())),
+            ]),
+        );
+        assert_parses(
+            "when ($.a > 0 => $.a, default => unknown()) > 1",
+            gt(
+                cond(vec![
+                    gt(int_bin("a".into()), int_val(0)),
+                    int_bin("a".into()),
+                    unknown(),
+                ]),
+                int_val(1),
+            ),
+        );
+        assert_parses(
+            "abs($.f) > abs(-2.5) and min($.a) < max(1, $.b, 3)",
+            and(vec![
+                gt(num_abs(float_bin("f".into())), num_abs(float_val(-2.5))),
+                lt(
+                    min(vec![int_bin("a".into())]),
+                    max(vec![int_val(1), int_bin("b".into()), int_val(3)]),
+                ),
+            ]),
+        );
+        assert_parses(
+            "floor($.f) <= ceil($.g) and to_int($.f) == 1 and to_float($.a) == 1.0",
+            and(vec![
+                le(
+                    num_floor(float_bin("f".into())),
+                    num_ceil(float_bin("g".into())),
+                ),
+                eq(to_int(float_bin("f".into())), int_val(1)),
+                eq(to_float(int_bin("a".into())), float_val(1.0)),
+            ]),
+        );
+        assert_parses(
+            "pow($.f, 2.0) >= log($.g, 10.0)",
+            ge(
+                num_pow(float_bin("f".into()), float_val(2.0)),
+                num_log(float_bin("g".into()), float_val(10.0)),
+            ),
+        );
+        assert_parses(
+            "count($.a) == lscan($.b, true) + rscan(7, $.c)",
+            eq(
+                int_count(int_bin("a".into())),
+                num_add(vec![
+                    int_lscan(int_bin("b".into()), bool_val(true)),
+                    int_rscan(int_val(7), bin("c".into(), ExpType::BOOL)),
+                ]),
+            ),
+        );
+        assert_parses(
+            "regex($.s, 'a') and regex($.s, 'b', 'xisn') and regex(key(), 'c', 'e')",
+            and(vec![
+                regex_compare("a".into(), RegexFlag::NONE as i64, string_bin("s".into())),
+                regex_compare(
+                    "b".into(),
+                    RegexFlag::EXTENDED as i64
+                        | RegexFlag::ICASE as i64
+                        | RegexFlag::NOSUB as i64
+                        | RegexFlag::NEWLINE as i64,
+                    string_bin("s".into()),
+                ),
+                regex_compare("c".into(), RegexFlag::EXTENDED as i64, key(ExpType::STRING)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_parses(
+            "$.a + $.b * 2 - 1 >= 0x10 % 3",
+            ge(
+                num_sub(vec![
+                    num_add(vec![
+                        int_bin("a".into()),
+                        num_mul(vec![int_bin("b".into()), int_val(2)]),
+                    ]),
+                    int_val(1),
+                ]),
+                num_mod(int_val(16), int_val(3)),
+            ),
+        );
+        assert_parses(
+            "($.a + $.b) / 2.0 < floor(pow($.c, 2.0))",
+            lt(
+                num_div(vec![
+                    num_add(vec![float_bin("a".into()), float_bin("b".into())]),
+                    float_val(2.0),
+                ]),
+                num_floor(num_pow(float_bin("c".into()), float_val(2.0))),
+            ),
+        );
+        assert_parses(
+            "$.flags & 0xff | 1 << 2 == ~$.mask ^ count($.x)",
+            eq(
+                int_or(vec![
+                    int_and(vec![int_bin("flags".into()), int_val(0xff)]),
+                    int_lshift(int_val(1), int_val(2)),
+                ]),
+                int_xor(vec![
+                    int_not(int_bin("mask".into())),
+                    int_count(int_bin("x".into())),
+                ]),
+            ),
+        );
+        assert_parses(
+            "max($.a, to_int($.f), -$.b) > 0",
+            gt(
+                max(vec![
+                    int_bin("a".into()),
+                    to_int(float_bin("f".into())),
+                    num_sub(vec![int_bin("b".into())]),
+                ]),
+                int_val(0),
+            ),
+        );
+    }
+
+    #[test]
+    fn control_flow() {
+        assert_parses(
+            "let (x = $.a, y = ${x} * 2) then (${x} > 5 and ${y} < 20)",
+            exp_let(vec![
+                def("x".into(), int_bin("a".into())),
+                def("y".into(), num_mul(vec![var("x".into()), int_val(2)])),
+                and(vec![
+                    gt(var("x".into()), int_val(5)),
+                    lt(var("y".into()), int_val(20)),
+                ]),
+            ]),
+        );
+        assert_parses(
+            "when ($.type == 0 => $.a + $.b, $.type == 1 => $.a - $.b, default => -1) > 0",
+            gt(
+                cond(vec![
+                    eq(int_bin("type".into()), int_val(0)),
+                    num_add(vec![int_bin("a".into()), int_bin("b".into())]),
+                    eq(int_bin("type".into()), int_val(1)),
+                    num_sub(vec![int_bin("a".into()), int_bin("b".into())]),
+                    int_val(-1),
+                ]),
+                int_val(0),
+            ),
+        );
+        assert_parses(
+            "regex($.name, '^ab.*', 'i') or geo_compare($.loc, geo('{\"type\":\"Point\",\"coordinates\":[1.5,2.5]}'))",
+            or(vec![
+                regex_compare(
+                    "^ab.*".into(),
+                    regex_flag::RegexFlag::ICASE as i64,
+                    string_bin("name".into()),
+                ),
+                geo_compare(
+                    geo_bin("loc".into()),
+                    geo_val("{\"type\":\"Point\",\"coordinates\":[1.5,2.5]}"),
+                ),
+            ]),
+        );
+    }
+
+    #[test]
+    fn cdt_paths() {
+        assert_parses(
+            "$.scores.[0] > 10 and $.address.city == 'Amsterdam'",
+            and(vec![
+                gt(
+                    lists::get_by_index(
+                        ListReturnType::Values,
+                        ExpType::INT,
+                        int_val(0),
+                        list_bin("scores".into()),
+                        &[],
+                    ),
+                    int_val(10),
+                ),
+                eq(
+                    maps::get_by_key(
+                        MapReturnType::Value,
+                        ExpType::STRING,
+                        string_val("city".into()),
+                        map_bin("address".into()),
+                        &[],
+                    ),
+                    string_val("Amsterdam".into()),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.m.'a b'.[-1].get(type: FLOAT) == 1.0",
+            eq(
+                lists::get_by_index(
+                    ListReturnType::Values,
+                    ExpType::FLOAT,
+                    int_val(-1),
+                    map_bin("m".into()),
+                    &[ctx_map_key(Value::from("a b"))],
+                ),
+                float_val(1.0),
+            ),
+        );
+        assert_parses(
+            "$.tags.[].count() > 2 and $.tags.[].contains('x') and $.m.1.{}.contains('k')",
+            and(vec![
+                gt(lists::size(list_bin("tags".into()), &[]), int_val(2)),
+                gt(
+                    lists::get_by_value(
+                        ListReturnType::Count,
+                        string_val("x".into()),
+                        list_bin("tags".into()),
+                        &[],
+                    ),
+                    int_val(0),
+                ),
+                gt(
+                    maps::get_by_key(
+                        MapReturnType::Count,
+                        ExpType::INT,
+                        string_val("k".into()),
+                        map_bin("m".into()),
+                        &[ctx_map_key(Value::from(1))],
+                    ),
+                    int_val(0),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.l.[] == [1, 'a', [true, nil]]",
+            eq(
+                list_bin("l".into()),
+                list_val(vec![
+                    Value::from(1),
+                    Value::from("a"),
+                    Value::from(vec![Value::from(true), Value::Nil]),
+                ]),
+            ),
+        );
+    }
+
+    #[test]
+    fn path_forms() {
+        assert_parses(
+            "$.'a.b' == 1 and $.\"c d\" == 2 and $._x1 == 3",
+            and(vec![
+                eq(int_bin("a.b".into()), int_val(1)),
+                eq(int_bin("c d".into()), int_val(2)),
+                eq(int_bin("_x1".into()), int_val(3)),
+            ]),
+        );
+        assert_parses(
+            "$.m.5 == 'x' and $.m.'5' == 'y'",
+            and(vec![
+                eq(
+                    maps::get_by_key(
+                        MapReturnType::Value,
+                        ExpType::STRING,
+                        int_val(5),
+                        map_bin("m".into()),
+                        &[],
+                    ),
+                    string_val("x".into()),
+                ),
+                eq(
+                    maps::get_by_key(
+                        MapReturnType::Value,
+                        ExpType::STRING,
+                        string_val("5".into()),
+                        map_bin("m".into()),
+                        &[],
+                    ),
+                    string_val("y".into()),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.l.[1].a.[-2] == $.m.b.[0].c.get(type: INT)",
+            eq(
+                lists::get_by_index(
+                    ListReturnType::Values,
+                    ExpType::INT,
+                    int_val(-2),
+                    list_bin("l".into()),
+                    &[ctx_list_index(1), ctx_map_key(Value::from("a"))],
+                ),
+                maps::get_by_key(
+                    MapReturnType::Value,
+                    ExpType::INT,
+                    string_val("c".into()),
+                    map_bin("m".into()),
+                    &[ctx_map_key(Value::from("b")), ctx_list_index(0)],
+                ),
+            ),
+        );
+        assert_parses(
+            "$.l.[] == [1] and $.m.{} == {} and $.n.[0].[].get(type: LIST) == [2]",
+            and(vec![
+                eq(list_bin("l".into()), list_val(vec![Value::from(1)])),
+                eq(map_bin("m".into()), map_val(HashMap::new())),
+                eq(
+                    lists::get_by_index(
+                        ListReturnType::Values,
+                        ExpType::LIST,
+                        int_val(0),
+                        list_bin("n".into()),
+                        &[],
+                    ),
+                    list_val(vec![Value::from(2)]),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.l.[0].[].count() == $.m.k.{}.count()",
+            eq(
+                lists::size(list_bin("l".into()), &[ctx_list_index(0)]),
+                maps::size(map_bin("m".into()), &[ctx_map_key(Value::from("k"))]),
+            ),
+        );
+        assert_parses(
+            "$.l.[].contains($.x) and $.m.{}.contains(1 + 2)",
+            and(vec![
+                gt(
+                    lists::get_by_value(
+                        ListReturnType::Count,
+                        int_bin("x".into()),
+                        list_bin("l".into()),
+                        &[],
+                    ),
+                    int_val(0),
+                ),
+                gt(
+                    maps::get_by_key(
+                        MapReturnType::Count,
+                        ExpType::INT,
+                        num_add(vec![int_val(1), int_val(2)]),
+                        map_bin("m".into()),
+                        &[],
+                    ),
+                    int_val(0),
+                ),
+            ]),
+        );
+        assert_parses(
+            "$.'my bin'.exists() and $.a.type() != 0 and $.g.get(type: GEO) == $.h",
+            and(vec![
+                bin_exists("my bin".into()),
+                ne(bin_type("a".into()), int_val(0)),
+                eq(geo_bin("g".into()), geo_bin("h".into())),
+            ]),
+        );
+        for name in &[
+            "NIL", "BOOL", "INT", "STRING", "LIST", "MAP", "BLOB", "FLOAT", "GEO", "HLL", "int",
+        ] {
+            let ty = type_from_name(name).unwrap();
+            assert_parses(
+                &format!("$.a.get(type: {}) == $.b", name),
+                eq(bin("a".into(), ty), bin("b".into(), ty)),
+            );
+        }
+    }
+
+    #[test]
+    fn module_functions() {
+        let list = || list_bin("l".into());
+        let map = || map_bin("m".into());
+        assert_parses(
+            "list_get_by_rank(VALUES, -1, $.l) > 100 and list_size($.l) == 2",
+            and(vec![
+                gt(
+                    lists::get_by_rank(
+                        ListReturnType::Values,
+                        ExpType::INT,
+                        int_val(-1),
+                        list(),
+                        &[],
+                    ),
+                    int_val(100),
+                ),
+                eq(lists::size(list(), &[]), int_val(2)),
+            ]),
+        );
+        assert_parses(
+            "list_append(1, 0, 0, $.l, ctx: [list_index(0), map_key('a')]) == [1]",
+            eq(
+                lists::append(
+                    ListPolicy::default(),
+                    int_val(1),
+                    list(),
+                    &[ctx_list_index(0), ctx_map_key(Value::from("a"))],
+                ),
+                list_val(vec![Value::from(1)]),
+            ),
+        );
+        assert_parses(
+            "map_get_by_key(KEY_VALUE, 'k', $.m, ctx: [map_key('a')], type: MAP) == {}",
+            eq(
+                maps::get_by_key(
+                    MapReturnType::KeyValue,
+                    ExpType::MAP,
+                    string_val("k".into()),
+                    map(),
+                    &[ctx_map_key(Value::from("a"))],
+                ),
+                map_val(HashMap::new()),
+            ),
+        );
+        assert_parses(
+            "map_put('k', $.v, 0, $.m) == $.n and map_size($.m, type: INT) > 0",
+            and(vec![
+                eq(
+                    maps::put(
+                        &MapPolicy::default(),
+                        string_val("k".into()),
+                        int_bin("v".into()),
+                        map(),
+                        &[],
+                    ),
+                    map_bin("n".into()),
+                ),
+                gt(maps::size(map(), &[]), int_val(0)),
+            ]),
+        );
+        assert_parses(
+            "bit_count(0, 8, $.b) == 1 and bit_set(0, 8, blob('ff'), 0, $.b) == blob('ff')",
+            and(vec![
+                eq(
+                    bitwise::count(int_val(0), int_val(8), blob_bin("b".into())),
+                    int_val(1),
+                ),
+                eq(
+                    bitwise::set(
+                        &BitPolicy::default(),
+                        int_val(0),
+                        int_val(8),
+                        blob_val(vec![0xff]),
+                        blob_bin("b".into()),
+                    ),
+                    blob_val(vec![0xff]),
+                ),
+            ]),
+        );
+        assert_parses(
+            "hll_get_count(hll_add(['a'], -1, -1, 0, $.h)) > 0",
+            gt(
+                hll::get_count(hll::add(
+                    HLLPolicy::default(),
+                    list_val(vec![Value::from("a")]),
+                    hll_bin("h".into()),
+                )),
+                int_val(0),
+            ),
+        );
+
+        // Return types are named, may be inverted or given as their numeric value.
+        let count = |rt: i64| {
+            let mut exp = lists::get_by_value(ListReturnType::Count, int_val(1), list(), &[]);
+            exp.arguments.as_mut().unwrap()[1] = ExpressionArgument::Value(Value::from(rt));
+            gt(exp, int_val(0))
+        };
+        assert_parses(
+            "list_get_by_value(COUNT | INVERTED, 1, $.l) > 0",
+            count(ListReturnType::Count as i64 | INVERTED),
+        );
+        assert_parses("list_get_by_value(5, 1, $.l) > 0", count(5));
+        assert_parses("list_get_by_value(-1, 1, $.l) > 0", count(-1));
+
+        // Operations without a name and unknown context types.
+        let mut exp = lists::size(list(), &[]);
+        exp.arguments = Some(vec![
+            ExpressionArgument::Value(Value::from(99)),
+            ExpressionArgument::FilterExpression(int_val(1)),
+            ExpressionArgument::Context(vec![CdtContext {
+                id: 0x7f,
+                flags: 0,
+                value: Value::from("x"),
+            }]),
+        ]);
+        exp.flags = Some(5);
+        assert_parses(
+            "call(5, 99, 1, $.l, ctx: [ctx(127, 'x')], type: INT) > 0",
+            gt(exp, int_val(0)),
+        );
+
+        // Every function is accepted with the operation code of its builder and printed by name.
+        for (name, flags, op) in FUNCTIONS {
+            let text = if takes_return_type(*flags, *op) {
+                format!("{}(COUNT, 1, $.l, type: INT) == 2", name)
+            } else {
+                format!("{}(1, $.l, type: INT) == 2", name)
+            };
+            let exp = FilterExpression::parse(&text).unwrap();
+            let call = &exp.exps.as_ref().unwrap()[0];
+            assert_eq!(call.flags, Some(*flags), "{}", text);
+            assert!(
+                matches!(call.arguments.as_deref(), Some([ExpressionArgument::Value(Value::Int(code)), ..]) if code == op),
+                "{}",
+                text
+            );
+            assert!(
+                exp.to_string().starts_with(&format!("{}(", name)),
+                "{}",
+                exp
+            );
+        }
+        for (name, id) in CONTEXTS {
+            let exp = FilterExpression::parse(&format!("list_size($.l, ctx: [{}(0)]) > 0", name))
+                .unwrap();
+            let call = &exp.exps.as_ref().unwrap()[0];
+            assert!(
+                matches!(call.arguments.as_deref(), Some([.., ExpressionArgument::Context(ctx)]) if ctx[0].id == *id),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_error("$.a >", 5, "expected expression, found end of expression");
+        assert_error(
+            "$.a > 1 and",
+            11,
+            "expected expression, found end of expression",
+        );
+        assert_error("($.a > 1", 8, "expected ')', found end of expression");
+        assert_error("$.a == 'x", 7, "unterminated string literal");
+        assert_error("$.a # 1", 4, "unexpected character '#'");
+        assert_error("foo() > 1", 0, "unknown function 'foo'");
+        assert_error(
+            "ttl(1) > 1",
+            0,
+            "function 'ttl' takes 0 argument(s), found 1",
+        );
+        assert_error("$.a == 1 and 2", 13, "expected BOOL expression, found INT");
+        assert_error("1 + 1.5 > 0", 4, "expected INT expression, found FLOAT");
+        assert_error("1 == 'a'", 5, "can not compare INT with STRING");
+        assert_error("${x} > 1", 0, "undefined variable 'x'");
+        assert_error("$.a.get(type: TEXT)", 14, "unknown type 'TEXT'");
+        assert_error(
+            "$.a.[].[0] > 1",
+            4,
+            "'[]' and '{}' can only be used at the end of a path",
+        );
+        assert_error(
+            "regex($.a, 'x', 'q')",
+            16,
+            "unknown regular expression flag 'q'",
+        );
+        assert_error("$.a > 1 $.b", 8, "expected end of expression, found '$.'");
+        assert_error("$.m == {{1: 2}: 1}", 8, "map keys can not be maps");
+        assert_error("$.m == {[{}]: 1}", 8, "map keys can not be maps");
+        assert_error("$.m == {1: }", 11, "expected constant value");
+        assert_error("$.l == [1, ttl()]", 11, "expected constant value");
+
+        // Tokens
+        assert_error("$x > 1", 0, "expected '$.' or '${'");
+        assert_error("${x > 1", 0, "unterminated variable reference");
+        assert_error("${1x} > 1", 0, "invalid variable name '1x'");
+        assert_error("${} > 1", 0, "invalid variable name ''");
+        assert_error("$.a == 'x\\", 7, "unterminated string literal");
+        assert_error("$.a == 0x", 7, "invalid hexadecimal literal");
+        assert_error(
+            "$.a == 0x10000000000000000",
+            7,
+            "invalid hexadecimal literal",
+        );
+        assert_error(
+            "$.a == 9223372036854775808",
+            7,
+            "integer literal out of range",
+        );
+        assert_error("$.a == 1 @", 9, "unexpected character '@'");
+        assert_error("$.a == 'ü' ü", 12, "unexpected character 'ü'");
+
+        // Paths and methods
+        assert_error("$. == 1", 0, "expected bin name after '$.'");
+        assert_error("$.a.+ == 1", 4, "expected path element, found '+'");
+        assert_error("$.a.[x] == 1", 5, "expected integer, found 'x'");
+        assert_error("$.a.[1 == 1", 7, "expected ']', found '=='");
+        assert_error("$.a.{1} == 1", 5, "expected '}', found integer 1");
+        assert_error("$.a.foo() == 1", 4, "method 'foo' can not be used here");
+        assert_error("$.a.b.exists()", 6, "method 'exists' can not be used here");
+        assert_error("$.a.count() == 1", 4, "method 'count' can not be used here");
+        assert_error("$.a.get(STRING) == 1", 8, "expected 'type', found 'STRING'");
+        assert_error(
+            "$.a.[].get(type: MAP) == {}",
+            7,
+            "expected LIST for this path, found MAP",
+        );
+
+        // Functions and control flow
+        assert_error(
+            "abs(1, 2) > 0",
+            0,
+            "function 'abs' takes 1 argument(s), found 2",
+        );
+        assert_error("min() > 0", 0, "function 'min' takes at least one argument");
+        assert_error(
+            "to_float(1.5) > 0",
+            9,
+            "expected INT expression, found FLOAT",
+        );
+        assert_error("lscan(1, 2) > 0", 9, "expected BOOL expression, found INT");
+        assert_error(
+            "geo_compare($.a, 1)",
+            17,
+            "expected GEO expression, found INT",
+        );
+        assert_error("regex(1, 'a')", 6, "expected STRING expression, found INT");
+        assert_error(
+            "regex($.a, $.b)",
+            11,
+            "expected regular expression string, found '$.'",
+        );
+        assert_error("digest_modulo(x) == 1", 14, "expected integer, found 'x'");
+        assert_error("key(TEXT) == 1", 4, "unknown type 'TEXT'");
+        assert_error("ttl > 1", 0, "unknown identifier 'ttl'");
+        assert_error(
+            "let (x = 1) then (${x} > 0) and ${x} > 0",
+            32,
+            "undefined variable 'x'",
+        );
+        assert_error("let (x = 1) (${x} > 0)", 12, "expected 'then', found '('");
+        assert_error(
+            "let (1 = 1) then (true)",
+            5,
+            "expected variable name, found integer 1",
+        );
+        assert_error(
+            "when ($.a > 0 => 1, default => 'x') > 0",
+            31,
+            "expected INT expression, found STRING",
+        );
+        assert_error("when ($.a => 1) > 0", 14, "expected ',', found ')'");
+        assert_error(
+            "when (1 => 1, default => 2) > 0",
+            6,
+            "expected BOOL expression, found INT",
+        );
+
+        // Constants and module functions
+        assert_error("$.b == blob('f')", 12, "invalid hex string");
+        assert_error("$.b == hll('zz')", 11, "invalid hex string");
+        assert_error("$.b == blob(1)", 12, "expected hex string, found integer 1");
+        assert_error("inf(1) == $.a", 4, "expected ')', found integer 1");
+        assert_error(
+            "list_size() > 0",
+            0,
+            "function 'list_size' takes a bin argument",
+        );
+        assert_error(
+            "list_get_by_rank(FOO, 0, $.l) > 0",
+            17,
+            "unknown return type 'FOO'",
+        );
+        assert_error(
+            "map_get_by_key(VALUES, 0, $.m) > 0",
+            15,
+            "unknown return type 'VALUES'",
+        );
+        assert_error(
+            "list_get_by_rank(COUNT | 1, 0, $.l) > 0",
+            25,
+            "expected 'INVERTED', found integer 1",
+        );
+        assert_error(
+            "list_size($.l, ctx: [list_foo(1)]) > 0",
+            21,
+            "unknown context 'list_foo'",
+        );
+        assert_error(
+            "list_size($.l, ctx: [ctx(300, 1)]) > 0",
+            25,
+            "invalid context type 300",
+        );
+        assert_error(
+            "list_size($.l, ctx: list_index(0)) > 0",
+            20,
+            "expected '[', found 'list_index'",
+        );
+        assert_error("list_size($.l, foo: 1) > 0", 15, "unknown argument 'foo'");
+        assert_error(
+            "list_size(ctx: [], $.l) > 0",
+            19,
+            "expected named argument, found '$.'",
+        );
+        assert_error("list_size($.l, type: TEXT) > 0", 21, "unknown type 'TEXT'");
+        assert_error("call(0, $.l) > 0", 8, "expected integer, found '$.'");
+        assert_error("call(0) > 0", 6, "expected ',', found ')'");
+
+        let deep = format!("{}1{} > 0", "(".repeat(5000), ")".repeat(5000));
+        assert_error(&deep, 32, "expression is nested too deeply");
+    }
+
+    #[test]
+    fn depth_limit() {
+        // The expression itself is the first level.
+        let nested = |depth: usize| format!("{}1{} > 0", "(".repeat(depth), ")".repeat(depth));
+        assert!(FilterExpression::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_error(
+            &nested(MAX_DEPTH),
+            MAX_DEPTH,
+            "expression is nested too deeply",
+        );
+
+        let nested = |depth: usize| format!("{}1{} > 0", "abs(".repeat(depth), ")".repeat(depth));
+        assert!(FilterExpression::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_error(
+            &nested(MAX_DEPTH),
+            4 * MAX_DEPTH,
+            "expression is nested too deeply",
+        );
+
+        let nested = |depth: usize| format!("$.l == {}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(FilterExpression::parse(&nested(MAX_DEPTH)).is_ok());
+        assert_error(
+            &nested(MAX_DEPTH + 1),
+            7 + MAX_DEPTH,
+            "expression is nested too deeply",
+        );
+
+        let nested = |depth: usize| format!("{}true", "not ".repeat(depth));
+        assert!(FilterExpression::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_error(
+            &nested(MAX_DEPTH),
+            4 * MAX_DEPTH,
+            "expression is nested too deeply",
+        );
+
+        for text in &[
+            format!("{}true", "not ".repeat(5000)),
+            format!("{}1 > 0", "-".repeat(5000)),
+            format!("$.l == {}{}", "[".repeat(5000), "]".repeat(5000)),
+        ] {
+            match FilterExpression::parse(text) {
+                Err(Error(ErrorKind::InvalidExpression(_, msg), _)) => {
+                    assert_eq!(msg, "expression is nested too deeply");
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+}
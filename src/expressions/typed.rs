@@ -0,0 +1,444 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Typed builder for filter expressions. An `Exp<T>` is an expression that resolves to a value
+//! of type `T`; bins are referenced with `bin::<T>("name")` and constants are converted with
+//! `val()` or implicitly wherever an `Exp<T>` is expected. Comparisons are available as methods,
+//! boolean and arithmetic operators through the `std::ops` traits, and the result converts into
+//! a `FilterExpression`.
+//!
+//! ```
+//! use aerospike::expressions::typed::bin;
+//! use aerospike::expressions::{and, eq, gt, int_bin, int_val, string_bin, string_val};
+//! use aerospike::expressions::FilterExpression;
+//!
+//! let filter: FilterExpression = (bin::<i64>("age").gt(21) & bin::<String>("cc").eq("NL")).into();
+//! let expected = and(vec![
+//!     gt(int_bin("age".to_string()), int_val(21)),
+//!     eq(string_bin("cc".to_string()), string_val("NL".to_string())),
+//! ]);
+//! assert_eq!(filter.to_bytes().unwrap(), expected.to_bytes().unwrap());
+//! ```
+//!
+//! Operands of different types are rejected by the compiler:
+//!
+//! ```compile_fail
+//! use aerospike::expressions::typed::bin;
+//!
+//! let filter = bin::<i64>("age").gt("21");
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Add, BitAnd, BitOr, Div, Mul, Not, Sub};
+
+use crate::expressions::{
+    and, blob_val, bool_val, eq, float_val, ge, gt, int_and, int_not, int_or, int_val, le,
+    list_val, lt, map_val, ne, not, num_add, num_div, num_mul, num_sub, or, string_val, ExpOp,
+    ExpType, FilterExpression,
+};
+use crate::Value;
+
+/// Rust types that correspond to an expression type: `i64`, `f64`, `String`, `bool`, `Vec<u8>`
+/// (blob), `Vec<Value>` (list) and `HashMap<Value, Value>` (map).
+pub trait ExpValue {
+    /// The expression type of values and bins of this type.
+    const TYPE: ExpType;
+
+    /// Create a constant expression for the value.
+    fn into_exp(self) -> FilterExpression;
+}
+
+/// Expression types that support arithmetic operators.
+pub trait Numeric: ExpValue {}
+
+impl ExpValue for i64 {
+    const TYPE: ExpType = ExpType::INT;
+
+    fn into_exp(self) -> FilterExpression {
+        int_val(self)
+    }
+}
+
+impl ExpValue for f64 {
+    const TYPE: ExpType = ExpType::FLOAT;
+
+    fn into_exp(self) -> FilterExpression {
+        float_val(self)
+    }
+}
+
+impl ExpValue for String {
+    const TYPE: ExpType = ExpType::STRING;
+
+    fn into_exp(self) -> FilterExpression {
+        string_val(self)
+    }
+}
+
+impl ExpValue for bool {
+    const TYPE: ExpType = ExpType::BOOL;
+
+    fn into_exp(self) -> FilterExpression {
+        bool_val(self)
+    }
+}
+
+impl ExpValue for Vec<u8> {
+    const TYPE: ExpType = ExpType::BLOB;
+
+    fn into_exp(self) -> FilterExpression {
+        blob_val(self)
+    }
+}
+
+impl ExpValue for Vec<Value> {
+    const TYPE: ExpType = ExpType::LIST;
+
+    fn into_exp(self) -> FilterExpression {
+        list_val(self)
+    }
+}
+
+#[allow(clippy::implicit_hasher)]
+impl ExpValue for HashMap<Value, Value> {
+    const TYPE: ExpType = ExpType::MAP;
+
+    fn into_exp(self) -> FilterExpression {
+        map_val(self)
+    }
+}
+
+impl Numeric for i64 {}
+impl Numeric for f64 {}
+
+/// Filter expression that resolves to a value of type `T`.
+#[derive(Clone)]
+pub struct Exp<T> {
+    exp: FilterExpression,
+    ty: PhantomData<T>,
+}
+
+/// Create a bin expression of type `T`, e.g. `bin::<i64>("age")`.
+pub fn bin<T: ExpValue>(name: &str) -> Exp<T> {
+    Exp::unchecked(FilterExpression::new(
+        Some(ExpOp::Bin),
+        Some(Value::from(name)),
+        None,
+        None,
+        Some(T::TYPE),
+        None,
+    ))
+}
+
+/// Create a constant expression.
+pub fn val<T: ExpValue>(value: T) -> Exp<T> {
+    Exp::unchecked(value.into_exp())
+}
+
+impl<T> Exp<T> {
+    /// Wrap an untyped expression, e.g. `Exp::<i64>::unchecked(ttl())`. The caller has to make
+    /// sure that the expression resolves to `T`.
+    pub const fn unchecked(exp: FilterExpression) -> Self {
+        Exp {
+            exp,
+            ty: PhantomData,
+        }
+    }
+
+    /// Return the untyped expression.
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_inner(self) -> FilterExpression {
+        self.exp
+    }
+
+    /// Create "equals" (==) expression.
+    pub fn eq<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(eq(self.exp, other.into().exp))
+    }
+
+    /// Create "not equal" (!=) expression.
+    pub fn ne<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(ne(self.exp, other.into().exp))
+    }
+
+    /// Create "greater than" (>) expression.
+    pub fn gt<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(gt(self.exp, other.into().exp))
+    }
+
+    /// Create "greater than or equal" (>=) expression.
+    pub fn ge<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(ge(self.exp, other.into().exp))
+    }
+
+    /// Create "less than" (<) expression.
+    pub fn lt<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(lt(self.exp, other.into().exp))
+    }
+
+    /// Create "less than or equal" (<=) expression.
+    pub fn le<R: Into<Exp<T>>>(self, other: R) -> Exp<bool> {
+        Exp::unchecked(le(self.exp, other.into().exp))
+    }
+}
+
+impl<T> fmt::Debug for Exp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.exp.fmt(f)
+    }
+}
+
+impl<T> fmt::Display for Exp<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.exp, f)
+    }
+}
+
+impl<T> From<Exp<T>> for FilterExpression {
+    fn from(exp: Exp<T>) -> Self {
+        exp.exp
+    }
+}
+
+impl<T: ExpValue> From<T> for Exp<T> {
+    fn from(value: T) -> Self {
+        val(value)
+    }
+}
+
+/// Integer literals default to `i32`.
+impl From<i32> for Exp<i64> {
+    fn from(value: i32) -> Self {
+        val(i64::from(value))
+    }
+}
+
+impl From<&str> for Exp<String> {
+    fn from(value: &str) -> Self {
+        val(value.to_string())
+    }
+}
+
+/// Appends `right` to `left` if `left` is already an `op` expression with at least two operands,
+/// so that `a & b & c` creates a single `and` expression with three operands. Unary expressions
+/// such as `num_sub(vec![x])` (negation) are kept as an operand.
+fn variadic(
+    op: ExpOp,
+    left: FilterExpression,
+    right: FilterExpression,
+    build: fn(Vec<FilterExpression>) -> FilterExpression,
+) -> FilterExpression {
+    match left {
+        FilterExpression {
+            cmd: Some(cmd),
+            exps: Some(mut exps),
+            ..
+        } if cmd as i64 == op as i64 && exps.len() >= 2 => {
+            exps.push(right);
+            build(exps)
+        }
+        left => build(vec![left, right]),
+    }
+}
+
+impl<R: Into<Exp<bool>>> BitAnd<R> for Exp<bool> {
+    type Output = Exp<bool>;
+
+    fn bitand(self, rhs: R) -> Exp<bool> {
+        Exp::unchecked(variadic(ExpOp::And, self.exp, rhs.into().exp, and))
+    }
+}
+
+impl<R: Into<Exp<bool>>> BitOr<R> for Exp<bool> {
+    type Output = Exp<bool>;
+
+    fn bitor(self, rhs: R) -> Exp<bool> {
+        Exp::unchecked(variadic(ExpOp::Or, self.exp, rhs.into().exp, or))
+    }
+}
+
+impl Not for Exp<bool> {
+    type Output = Exp<bool>;
+
+    fn not(self) -> Exp<bool> {
+        Exp::unchecked(not(self.exp))
+    }
+}
+
+impl<R: Into<Exp<i64>>> BitAnd<R> for Exp<i64> {
+    type Output = Exp<i64>;
+
+    fn bitand(self, rhs: R) -> Exp<i64> {
+        Exp::unchecked(variadic(ExpOp::IntAnd, self.exp, rhs.into().exp, int_and))
+    }
+}
+
+impl<R: Into<Exp<i64>>> BitOr<R> for Exp<i64> {
+    type Output = Exp<i64>;
+
+    fn bitor(self, rhs: R) -> Exp<i64> {
+        Exp::unchecked(variadic(ExpOp::IntOr, self.exp, rhs.into().exp, int_or))
+    }
+}
+
+impl Not for Exp<i64> {
+    type Output = Exp<i64>;
+
+    fn not(self) -> Exp<i64> {
+        Exp::unchecked(int_not(self.exp))
+    }
+}
+
+impl<T: Numeric, R: Into<Exp<T>>> Add<R> for Exp<T> {
+    type Output = Exp<T>;
+
+    fn add(self, rhs: R) -> Exp<T> {
+        Exp::unchecked(variadic(ExpOp::Add, self.exp, rhs.into().exp, num_add))
+    }
+}
+
+impl<T: Numeric, R: Into<Exp<T>>> Sub<R> for Exp<T> {
+    type Output = Exp<T>;
+
+    fn sub(self, rhs: R) -> Exp<T> {
+        Exp::unchecked(variadic(ExpOp::Sub, self.exp, rhs.into().exp, num_sub))
+    }
+}
+
+impl<T: Numeric, R: Into<Exp<T>>> Mul<R> for Exp<T> {
+    type Output = Exp<T>;
+
+    fn mul(self, rhs: R) -> Exp<T> {
+        Exp::unchecked(variadic(ExpOp::Mul, self.exp, rhs.into().exp, num_mul))
+    }
+}
+
+impl<T: Numeric, R: Into<Exp<T>>> Div<R> for Exp<T> {
+    type Output = Exp<T>;
+
+    fn div(self, rhs: R) -> Exp<T> {
+        Exp::unchecked(variadic(ExpOp::Div, self.exp, rhs.into().exp, num_div))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bin, val, Exp};
+    use crate::expressions::*;
+
+    fn assert_same<T>(typed: Exp<T>, expected: FilterExpression) {
+        let typed = FilterExpression::from(typed);
+        assert_eq!(
+            typed.to_bytes().unwrap(),
+            expected.to_bytes().unwrap(),
+            "{}",
+            typed
+        );
+    }
+
+    #[test]
+    fn boolean_operators() {
+        assert_same(
+            bin::<i64>("age").gt(21) & bin::<String>("cc").eq("NL") & bin::<bool>("active"),
+            and(vec![
+                gt(int_bin("age".to_string()), int_val(21)),
+                eq(string_bin("cc".to_string()), string_val("NL".to_string())),
+                FilterExpression::new(
+                    Some(ExpOp::Bin),
+                    Some("active".into()),
+                    None,
+                    None,
+                    Some(ExpType::BOOL),
+                    None,
+                ),
+            ]),
+        );
+        assert_same(
+            !(bin::<f64>("score").le(1.5) | bin::<Vec<u8>>("b").ne(vec![1, 2])),
+            not(or(vec![
+                le(float_bin("score".to_string()), float_val(1.5)),
+                ne(blob_bin("b".to_string()), blob_val(vec![1, 2])),
+            ])),
+        );
+        assert_same(
+            (bin::<i64>("a").lt(1) & bin::<i64>("b").ge(2)) | bin::<i64>("c").eq(3),
+            or(vec![
+                and(vec![
+                    lt(int_bin("a".to_string()), int_val(1)),
+                    ge(int_bin("b".to_string()), int_val(2)),
+                ]),
+                eq(int_bin("c".to_string()), int_val(3)),
+            ]),
+        );
+    }
+
+    #[test]
+    fn arithmetic_operators() {
+        assert_same(
+            (bin::<i64>("a") + bin::<i64>("b") + 1) * 2,
+            num_mul(vec![
+                num_add(vec![
+                    int_bin("a".to_string()),
+                    int_bin("b".to_string()),
+                    int_val(1),
+                ]),
+                int_val(2),
+            ]),
+        );
+        assert_same(
+            bin::<f64>("a") - 0.5 - bin::<f64>("b") / 2.0,
+            num_sub(vec![
+                float_bin("a".to_string()),
+                float_val(0.5),
+                num_div(vec![float_bin("b".to_string()), float_val(2.0)]),
+            ]),
+        );
+        assert_same(
+            !(bin::<i64>("flags") & 0xff | 1),
+            int_not(int_or(vec![
+                int_and(vec![int_bin("flags".to_string()), int_val(0xff)]),
+                int_val(1),
+            ])),
+        );
+        assert_same(
+            Exp::<i64>::unchecked(num_sub(vec![int_bin("a".to_string())])) - 1,
+            num_sub(vec![num_sub(vec![int_bin("a".to_string())]), int_val(1)]),
+        );
+        assert_same(
+            Exp::<f64>::unchecked(num_div(vec![float_bin("a".to_string())])) / 2.0,
+            num_div(vec![
+                num_div(vec![float_bin("a".to_string())]),
+                float_val(2.0),
+            ]),
+        );
+        assert_same(
+            Exp::<i64>::unchecked(ttl()).lt(val(3600)),
+            lt(ttl(), int_val(3600)),
+        );
+    }
+
+    #[test]
+    fn validates() {
+        let filter: FilterExpression =
+            (bin::<i64>("age").gt(21) & (bin::<f64>("score") * 2.0).ge(bin::<f64>("limit"))).into();
+        assert_eq!(filter.validate().unwrap(), ExpType::BOOL);
+        assert_eq!(
+            filter.to_string(),
+            "$.age > 21 and $.score * 2.0 >= $.limit"
+        );
+    }
+}
@@ -0,0 +1,774 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Static type checking of filter expressions, see `FilterExpression::validate`.
+
+use crate::errors::{ErrorKind, Result};
+use crate::expressions::bitwise::BitExpOp;
+use crate::expressions::eval::{exp_type, list_op, map_op, Select};
+use crate::expressions::{ExpOp, ExpType, ExpressionArgument, FilterExpression, MODIFY};
+use crate::operations::cdt_context::{CdtContext, CtxType};
+use crate::Value;
+
+pub(super) const CDT_MODULE: i64 = 0;
+pub(super) const BIT_MODULE: i64 = 1;
+pub(super) const HLL_MODULE: i64 = 2;
+
+/// Map operation codes start at 64, list operation codes are below.
+pub(super) const MAP_OPS: i64 = 64;
+
+const NUMBERS: &[ExpType] = &[ExpType::INT, ExpType::FLOAT];
+
+/// The statically inferred type of an expression; `None` if the expression can take any type,
+/// like `unknown()` or `nil()`.
+pub(super) type Type = Option<ExpType>;
+
+/// Checks `exp` and returns the type it resolves to, or `ExpType::NIL` if the type can only be
+/// determined at runtime.
+pub fn validate(exp: &FilterExpression) -> Result<ExpType> {
+    let ty = Validator::default().check(exp)?;
+    Ok(ty.unwrap_or(ExpType::NIL))
+}
+
+/// Checks `exp` for use as the filter expression of a policy, which has to resolve to a boolean.
+#[cfg(debug_assertions)]
+pub fn validate_filter(exp: &FilterExpression) -> Result<()> {
+    let mut validator = Validator::default();
+    let ty = validator.check(exp)?;
+    validator.expect(&node_name(exp), "filter", ty, &[ExpType::BOOL])?;
+    Ok(())
+}
+
+/// Infers the type of `exp`, where `vars` are the variables defined by enclosing `exp_let`
+/// expressions. Returns `None` if the type can only be determined at runtime or `exp` is invalid.
+pub(super) fn infer(exp: &FilterExpression, vars: &[(String, Option<ExpType>)]) -> Option<ExpType> {
+    Validator {
+        path: vec![],
+        vars: vars.to_vec(),
+    }
+    .check(exp)
+    .ok()
+    .flatten()
+}
+
+#[derive(Debug, Clone, Default)]
+struct Validator {
+    /// Operand positions leading to the expression being checked, e.g. `and[1]`.
+    path: Vec<String>,
+    vars: Vec<(String, Type)>,
+}
+
+impl Validator {
+    fn fail<T>(&self, node: &str, details: String) -> Result<T> {
+        let mut path = self.path.clone();
+        path.push(node.to_string());
+        Err(ErrorKind::InvalidExpressionTree(path.join("."), details).into())
+    }
+
+    fn expect(&self, node: &str, what: &str, ty: Type, allowed: &[ExpType]) -> Result<Type> {
+        match ty {
+            Some(ty) if !allowed.contains(&ty) => self.fail(
+                node,
+                format!("{} must be {}, found {:?}", what, type_names(allowed), ty),
+            ),
+            _ => Ok(ty),
+        }
+    }
+
+    fn arity(&self, node: &str, count: usize, min: usize, max: Option<usize>) -> Result<()> {
+        let expected = match max {
+            Some(max) if max == min => format!("{}", min),
+            Some(max) => format!("{} to {}", min, max),
+            None => format!("at least {}", min),
+        };
+        if count < min || matches!(max, Some(max) if count > max) {
+            return self.fail(
+                node,
+                format!("expected {} operands, found {}", expected, count),
+            );
+        }
+        Ok(())
+    }
+
+    fn child(&mut self, segment: String, exp: &FilterExpression) -> Result<Type> {
+        self.path.push(segment);
+        let ty = self.check(exp)?;
+        self.path.pop();
+        Ok(ty)
+    }
+
+    /// Checks the operand at `idx`, which has to be of one of the `allowed` types.
+    fn operand(
+        &mut self,
+        node: &str,
+        idx: usize,
+        exp: &FilterExpression,
+        allowed: &[ExpType],
+    ) -> Result<Type> {
+        let ty = self.child(format!("{}[{}]", node, idx), exp)?;
+        if allowed.is_empty() {
+            return Ok(ty);
+        }
+        self.expect(node, &format!("operand {}", idx), ty, allowed)
+    }
+
+    /// Checks that all operands are of one of the `allowed` types and that they all have the
+    /// same type, which is returned.
+    fn unify(
+        &mut self,
+        node: &str,
+        exps: &[FilterExpression],
+        allowed: &[ExpType],
+    ) -> Result<Type> {
+        let mut result = None;
+        for (idx, exp) in exps.iter().enumerate() {
+            match (result, self.operand(node, idx, exp, allowed)?) {
+                (Some(prev), Some(ty)) if prev != ty => {
+                    return self.fail(
+                        node,
+                        format!("operand {} is {:?}, but operand 0 is {:?}", idx, ty, prev),
+                    )
+                }
+                (None, ty) => result = ty,
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    fn check(&mut self, exp: &FilterExpression) -> Result<Type> {
+        let exps = exp.exps.as_deref().unwrap_or(&[]);
+        let cmd = match exp.cmd {
+            Some(cmd) => cmd,
+            None => return self.value(exp),
+        };
+        let node = op_name(cmd);
+
+        match cmd {
+            ExpOp::Call => return self.call(exp),
+            ExpOp::Let => return self.exp_let(exps),
+            ExpOp::Cond => return self.cond(exps),
+            _ => {}
+        }
+
+        let (operands, max) = match cmd {
+            ExpOp::Unknown
+            | ExpOp::Regex
+            | ExpOp::DigestModulo
+            | ExpOp::DeviceSize
+            | ExpOp::LastUpdate
+            | ExpOp::SinceUpdate
+            | ExpOp::VoidTime
+            | ExpOp::TTL
+            | ExpOp::SetName
+            | ExpOp::KeyExists
+            | ExpOp::IsTombstone
+            | ExpOp::Key
+            | ExpOp::Bin
+            | ExpOp::BinType
+            | ExpOp::Var
+            | ExpOp::Quoted => (0, Some(0)),
+            ExpOp::Not
+            | ExpOp::Abs
+            | ExpOp::Floor
+            | ExpOp::Ceil
+            | ExpOp::ToInt
+            | ExpOp::ToFloat
+            | ExpOp::IntNot
+            | ExpOp::IntCount => (1, Some(1)),
+            ExpOp::EQ
+            | ExpOp::NE
+            | ExpOp::GT
+            | ExpOp::GE
+            | ExpOp::LT
+            | ExpOp::LE
+            | ExpOp::Geo
+            | ExpOp::Pow
+            | ExpOp::Log
+            | ExpOp::Mod
+            | ExpOp::IntLshift
+            | ExpOp::IntRshift
+            | ExpOp::IntARshift
+            | ExpOp::IntLscan
+            | ExpOp::IntRscan => (2, Some(2)),
+            _ => (1, None),
+        };
+        self.arity(node, exps.len(), operands, max)?;
+
+        let int = Some(ExpType::INT);
+        let float = Some(ExpType::FLOAT);
+        match cmd {
+            ExpOp::Unknown => Ok(None),
+            ExpOp::EQ | ExpOp::NE | ExpOp::GT | ExpOp::GE | ExpOp::LT | ExpOp::LE => {
+                self.unify(node, exps, &[])?;
+                Ok(Some(ExpType::BOOL))
+            }
+            ExpOp::Regex => {
+                if !matches!(exp.val, Some(Value::String(_))) {
+                    return self.fail(node, "missing regular expression".to_string());
+                }
+                if exp.flags.is_none() {
+                    return self.fail(node, "missing regular expression flags".to_string());
+                }
+                match exp.bin {
+                    Some(ref bin) => {
+                        let ty = self.child(format!("{}[bin]", node), bin)?;
+                        self.expect(node, "bin", ty, &[ExpType::STRING])?;
+                    }
+                    None => return self.fail(node, "missing bin expression".to_string()),
+                }
+                Ok(Some(ExpType::BOOL))
+            }
+            ExpOp::Geo => {
+                self.unify(node, exps, &[ExpType::GEO])?;
+                Ok(Some(ExpType::BOOL))
+            }
+            ExpOp::And | ExpOp::Or | ExpOp::Not | ExpOp::Xor => {
+                self.unify(node, exps, &[ExpType::BOOL])?;
+                Ok(Some(ExpType::BOOL))
+            }
+            ExpOp::Add
+            | ExpOp::Sub
+            | ExpOp::Mul
+            | ExpOp::Div
+            | ExpOp::Abs
+            | ExpOp::Min
+            | ExpOp::Max => self.unify(node, exps, NUMBERS),
+            ExpOp::Pow | ExpOp::Log | ExpOp::Floor | ExpOp::Ceil => {
+                self.unify(node, exps, &[ExpType::FLOAT])?;
+                Ok(float)
+            }
+            ExpOp::ToInt => {
+                self.unify(node, exps, &[ExpType::FLOAT])?;
+                Ok(int)
+            }
+            ExpOp::ToFloat => {
+                self.unify(node, exps, &[ExpType::INT])?;
+                Ok(float)
+            }
+            ExpOp::Mod
+            | ExpOp::IntAnd
+            | ExpOp::IntOr
+            | ExpOp::IntXor
+            | ExpOp::IntNot
+            | ExpOp::IntLshift
+            | ExpOp::IntRshift
+            | ExpOp::IntARshift
+            | ExpOp::IntCount => {
+                self.unify(node, exps, &[ExpType::INT])?;
+                Ok(int)
+            }
+            ExpOp::IntLscan | ExpOp::IntRscan => {
+                self.operand(node, 0, &exps[0], &[ExpType::INT])?;
+                self.operand(node, 1, &exps[1], &[ExpType::BOOL])?;
+                Ok(int)
+            }
+            ExpOp::DigestModulo => match exp.val {
+                Some(Value::Int(modulo)) if modulo > 0 => Ok(int),
+                _ => self.fail(node, "modulo must be a positive integer".to_string()),
+            },
+            ExpOp::DeviceSize
+            | ExpOp::LastUpdate
+            | ExpOp::SinceUpdate
+            | ExpOp::VoidTime
+            | ExpOp::TTL => Ok(int),
+            ExpOp::SetName => Ok(Some(ExpType::STRING)),
+            ExpOp::KeyExists | ExpOp::IsTombstone => Ok(Some(ExpType::BOOL)),
+            ExpOp::Key => match exp.val {
+                Some(Value::Int(ty)) if key_type(ty).is_some() => Ok(key_type(ty)),
+                _ => self.fail(node, "key type must be INT, STRING or BLOB".to_string()),
+            },
+            ExpOp::Bin => match (&exp.val, exp.module) {
+                (Some(Value::String(_)), Some(ty)) => Ok(Some(ty)),
+                (Some(Value::String(_)), None) => self.fail(node, "missing bin type".to_string()),
+                _ => self.fail(node, "missing bin name".to_string()),
+            },
+            ExpOp::BinType => match exp.val {
+                Some(Value::String(_)) => Ok(int),
+                _ => self.fail(node, "missing bin name".to_string()),
+            },
+            ExpOp::Var => {
+                let name = match exp.val {
+                    Some(Value::String(ref name)) => name,
+                    _ => return self.fail(node, "missing variable name".to_string()),
+                };
+                match self.vars.iter().rev().find(|(var, _)| var == name) {
+                    Some((_, ty)) => Ok(*ty),
+                    None => self.fail(node, format!("variable '{}' is not defined", name)),
+                }
+            }
+            ExpOp::Quoted => match exp.val {
+                Some(Value::List(_)) => Ok(Some(ExpType::LIST)),
+                _ => self.fail(node, "quoted value must be a list".to_string()),
+            },
+            ExpOp::Call | ExpOp::Let | ExpOp::Cond => unreachable!(),
+        }
+    }
+
+    fn value(&self, exp: &FilterExpression) -> Result<Type> {
+        if exp.exps.is_some() {
+            return self.fail("def", "def can only be used in exp_let".to_string());
+        }
+        match exp.val {
+            None => self.fail("value", "missing value".to_string()),
+            Some(Value::List(_)) => self.fail(
+                "value",
+                "list values have to be created with list_val".to_string(),
+            ),
+            Some(Value::Nil | Value::Infinity | Value::Wildcard) => Ok(None),
+            Some(ref value) => Ok(Some(exp_type(value))),
+        }
+    }
+
+    fn cond(&mut self, exps: &[FilterExpression]) -> Result<Type> {
+        let node = op_name(ExpOp::Cond);
+        if exps.len() < 3 || exps.len() % 2 == 0 {
+            return self.fail(
+                node,
+                format!(
+                    "expected an odd number of at least 3 operands, found {}",
+                    exps.len()
+                ),
+            );
+        }
+
+        let mut result = None;
+        for (idx, exp) in exps.iter().enumerate() {
+            if idx % 2 == 0 && idx < exps.len() - 1 {
+                self.operand(node, idx, exp, &[ExpType::BOOL])?;
+                continue;
+            }
+            match (result, self.operand(node, idx, exp, &[])?) {
+                (Some(prev), Some(ty)) if prev != ty => {
+                    return self.fail(
+                        node,
+                        format!(
+                            "operand {} is {:?}, but the first action is {:?}",
+                            idx, ty, prev
+                        ),
+                    )
+                }
+                (None, ty) => result = ty,
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    fn exp_let(&mut self, exps: &[FilterExpression]) -> Result<Type> {
+        let node = op_name(ExpOp::Let);
+        self.arity(node, exps.len(), 2, None)?;
+
+        let scope = self.vars.len();
+        let (scope_exp, defs) = exps.split_last().unwrap();
+        for (idx, def) in defs.iter().enumerate() {
+            let (name, value) = match (def.cmd, &def.val, def.exps.as_deref()) {
+                (None, Some(Value::String(name)), Some([value])) => (name, value),
+                _ => {
+                    return self.fail(
+                        node,
+                        format!("operand {} must be a def, all but the last operand", idx),
+                    )
+                }
+            };
+            let ty = self.child(format!("{}[{}]", node, idx), value)?;
+            self.vars.push((name.clone(), ty));
+        }
+        let ty = self.child(format!("{}[{}]", node, defs.len()), scope_exp)?;
+        self.vars.truncate(scope);
+        Ok(ty)
+    }
+
+    fn call(&mut self, exp: &FilterExpression) -> Result<Type> {
+        let flags = exp.flags.unwrap_or_default();
+        let module = flags & !MODIFY;
+        let node = match module {
+            CDT_MODULE => "cdt",
+            BIT_MODULE => "bitwise",
+            HLL_MODULE => "hll",
+            _ => return self.fail("call", format!("unknown module {}", module)),
+        };
+
+        let mut ctx: &[CdtContext] = &[];
+        let mut values = vec![];
+        let mut params = vec![];
+        for arg in exp.arguments.as_deref().unwrap_or(&[]) {
+            match arg {
+                ExpressionArgument::Context(context) => ctx = context,
+                ExpressionArgument::Value(value) => values.push(value),
+                ExpressionArgument::FilterExpression(exp) => params.push(exp),
+            }
+        }
+        let op = match values.first() {
+            Some(Value::Int(op)) => *op,
+            _ => return self.fail(node, "missing operation code".to_string()),
+        };
+        let return_type = match exp.module {
+            Some(ty) => ty,
+            None => return self.fail(node, "missing return type".to_string()),
+        };
+        let bin = match exp.bin {
+            Some(ref bin) => self.child(format!("{}[bin]", node), bin)?,
+            None => return self.fail(node, "missing bin expression".to_string()),
+        };
+
+        let (bin_type, signature) = match module {
+            CDT_MODULE => {
+                let list = ctx
+                    .first()
+                    .map_or(op < MAP_OPS, |ctx| ctx.id & CtxType::ListIndex as u8 != 0);
+                let bin_type = if list { ExpType::LIST } else { ExpType::MAP };
+                let signature = if flags & MODIFY != 0 {
+                    None
+                } else {
+                    let select = if op < MAP_OPS {
+                        list_op(op)
+                    } else {
+                        map_op(op)
+                    };
+                    match select {
+                        Some(select) => Some(cdt_signature(select)),
+                        None => return self.fail(node, format!("unknown read operation {}", op)),
+                    }
+                };
+                (bin_type, signature)
+            }
+            BIT_MODULE => {
+                let signature = if flags & MODIFY != 0 {
+                    None
+                } else {
+                    match bit_signature(op) {
+                        Some(signature) => Some(signature),
+                        None => return self.fail(node, format!("unknown read operation {}", op)),
+                    }
+                };
+                (ExpType::BLOB, signature)
+            }
+            _ => (ExpType::HLL, None),
+        };
+        self.expect(node, "bin", bin, &[bin_type])?;
+
+        match signature {
+            Some((types, required)) => {
+                self.arity(node, params.len(), required, Some(types.len()))?;
+                for (idx, (param, ty)) in params.iter().zip(types).enumerate() {
+                    let allowed = ty.as_ref().map_or(&[][..], std::slice::from_ref);
+                    self.operand(node, idx, param, allowed)?;
+                }
+            }
+            None => {
+                for (idx, param) in params.iter().enumerate() {
+                    self.operand(node, idx, param, &[])?;
+                }
+            }
+        }
+        Ok(Some(return_type))
+    }
+}
+
+/// Operand types of a read operation and the number of required operands.
+pub(super) type Signature = (&'static [Type], usize);
+
+pub(super) const fn cdt_signature(select: Select) -> Signature {
+    const INT: Type = Some(ExpType::INT);
+    const LIST: Type = Some(ExpType::LIST);
+    match select {
+        Select::Size => (&[], 0),
+        Select::Index | Select::Rank => (&[INT], 1),
+        Select::IndexRange | Select::RankRange => (&[INT, INT], 1),
+        Select::Value | Select::Key => (&[None], 1),
+        Select::ValueList | Select::KeyList => (&[LIST], 1),
+        Select::ValueInterval | Select::KeyInterval => (&[None, None], 1),
+        Select::ValueRelRankRange | Select::KeyRelIndexRange => (&[None, INT, INT], 2),
+    }
+}
+
+pub(super) fn bit_signature(op: i64) -> Option<Signature> {
+    const INT: Type = Some(ExpType::INT);
+    const BOOL: Type = Some(ExpType::BOOL);
+    [
+        (BitExpOp::Get as i64, (&[INT, INT][..], 2)),
+        (BitExpOp::Count as i64, (&[INT, INT], 2)),
+        (BitExpOp::GetInt as i64, (&[INT, INT], 2)),
+        (BitExpOp::LScan as i64, (&[INT, INT, BOOL], 3)),
+        (BitExpOp::RScan as i64, (&[INT, INT, BOOL], 3)),
+    ]
+    .iter()
+    .find(|(code, _)| *code == op)
+    .map(|(_, signature)| *signature)
+}
+
+const fn key_type(ty: i64) -> Option<ExpType> {
+    match ty {
+        2 => Some(ExpType::INT),
+        3 => Some(ExpType::STRING),
+        6 => Some(ExpType::BLOB),
+        _ => None,
+    }
+}
+
+fn type_names(types: &[ExpType]) -> String {
+    let names: Vec<String> = types.iter().map(|ty| format!("{:?}", ty)).collect();
+    names.join(" or ")
+}
+
+/// Name of the function that creates expressions of type `op`, used in error paths.
+pub(super) const fn op_name(op: ExpOp) -> &'static str {
+    match op {
+        ExpOp::Unknown => "unknown",
+        ExpOp::EQ => "eq",
+        ExpOp::NE => "ne",
+        ExpOp::GT => "gt",
+        ExpOp::GE => "ge",
+        ExpOp::LT => "lt",
+        ExpOp::LE => "le",
+        ExpOp::Regex => "regex_compare",
+        ExpOp::Geo => "geo_compare",
+        ExpOp::And => "and",
+        ExpOp::Or => "or",
+        ExpOp::Not => "not",
+        ExpOp::Xor => "xor",
+        ExpOp::Add => "num_add",
+        ExpOp::Sub => "num_sub",
+        ExpOp::Mul => "num_mul",
+        ExpOp::Div => "num_div",
+        ExpOp::Pow => "num_pow",
+        ExpOp::Log => "num_log",
+        ExpOp::Mod => "num_mod",
+        ExpOp::Abs => "num_abs",
+        ExpOp::Floor => "num_floor",
+        ExpOp::Ceil => "num_ceil",
+        ExpOp::ToInt => "to_int",
+        ExpOp::ToFloat => "to_float",
+        ExpOp::IntAnd => "int_and",
+        ExpOp::IntOr => "int_or",
+        ExpOp::IntXor => "int_xor",
+        ExpOp::IntNot => "int_not",
+        ExpOp::IntLshift => "int_lshift",
+        ExpOp::IntRshift => "int_rshift",
+        ExpOp::IntARshift => "int_arshift",
+        ExpOp::IntCount => "int_count",
+        ExpOp::IntLscan => "int_lscan",
+        ExpOp::IntRscan => "int_rscan",
+        ExpOp::Min => "min",
+        ExpOp::Max => "max",
+        ExpOp::DigestModulo => "digest_modulo",
+        ExpOp::DeviceSize => "device_size",
+        ExpOp::LastUpdate => "last_update",
+        ExpOp::SinceUpdate => "since_update",
+        ExpOp::VoidTime => "void_time",
+        ExpOp::TTL => "ttl",
+        ExpOp::SetName => "set_name",
+        ExpOp::KeyExists => "key_exists",
+        ExpOp::IsTombstone => "is_tombstone",
+        ExpOp::Key => "key",
+        ExpOp::Bin => "bin",
+        ExpOp::BinType => "bin_type",
+        ExpOp::Cond => "cond",
+        ExpOp::Var => "var",
+        ExpOp::Let => "exp_let",
+        ExpOp::Quoted => "list_val",
+        ExpOp::Call => "call",
+    }
+}
+
+#[cfg(debug_assertions)]
+fn node_name(exp: &FilterExpression) -> String {
+    exp.cmd.map_or("value", op_name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::lists::{get_by_index, get_by_value_range, size};
+    use crate::expressions::*;
+    use crate::operations::cdt_context::ctx_map_key;
+    use crate::operations::lists::ListReturnType;
+
+    fn error(exp: &FilterExpression) -> (String, String) {
+        match exp.validate() {
+            Err(crate::Error(ErrorKind::InvalidExpressionTree(path, details), _)) => {
+                (path, details)
+            }
+            res => panic!("expected invalid expression tree, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn inferred_types() {
+        let exp = and(vec![
+            gt(int_bin("age".into()), int_val(21)),
+            eq(string_bin("name".into()), string_val("Alice".into())),
+            le(ttl(), num_add(vec![int_val(60), int_val(60)])),
+        ]);
+        assert_eq!(exp.validate().unwrap(), ExpType::BOOL);
+
+        let exp = num_mul(vec![
+            float_bin("score".into()),
+            to_float(int_bin("age".into())),
+        ]);
+        assert_eq!(exp.validate().unwrap(), ExpType::FLOAT);
+
+        let exp = exp_let(vec![
+            def("x".into(), string_bin("name".into())),
+            eq(var("x".into()), string_val("Bob".into())),
+        ]);
+        assert_eq!(exp.validate().unwrap(), ExpType::BOOL);
+
+        let exp = cond(vec![key_exists(), key(ExpType::STRING), unknown()]);
+        assert_eq!(exp.validate().unwrap(), ExpType::STRING);
+
+        assert_eq!(unknown().validate().unwrap(), ExpType::NIL);
+        assert!(eq(int_bin("age".into()), nil()).validate().is_ok());
+    }
+
+    #[test]
+    fn cdt_calls() {
+        let exp = get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            int_val(0),
+            list_bin("scores".into()),
+            &[],
+        );
+        assert_eq!(exp.validate().unwrap(), ExpType::INT);
+
+        let exp = size(map_bin("attrs".into()), &[ctx_map_key(as_val!("tags"))]);
+        assert_eq!(exp.validate().unwrap(), ExpType::INT);
+
+        let exp = get_by_value_range(
+            ListReturnType::Count,
+            None,
+            Some(int_val(10)),
+            list_bin("scores".into()),
+            &[],
+        );
+        assert_eq!(exp.validate().unwrap(), ExpType::INT);
+
+        let exp = get_by_index(
+            ListReturnType::Values,
+            ExpType::INT,
+            string_val("first".into()),
+            list_bin("scores".into()),
+            &[],
+        );
+        assert_eq!(
+            error(&exp),
+            (
+                "cdt".to_string(),
+                "operand 0 must be INT, found STRING".to_string()
+            )
+        );
+
+        let exp = size(map_bin("attrs".into()), &[]);
+        assert_eq!(
+            error(&exp),
+            ("cdt".to_string(), "bin must be LIST, found MAP".to_string())
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let exp = and(vec![
+            key_exists(),
+            eq(int_bin("age".into()), string_val("42".into())),
+        ]);
+        assert_eq!(
+            error(&exp),
+            (
+                "and[1].eq".to_string(),
+                "operand 1 is STRING, but operand 0 is INT".to_string()
+            )
+        );
+
+        let exp = cond(vec![key_exists(), int_val(1), int_val(2), int_val(3)]);
+        assert_eq!(
+            error(&exp),
+            (
+                "cond".to_string(),
+                "expected an odd number of at least 3 operands, found 4".to_string()
+            )
+        );
+
+        let exp = cond(vec![int_val(1), int_val(2), int_val(3)]);
+        assert_eq!(
+            error(&exp),
+            (
+                "cond".to_string(),
+                "operand 0 must be BOOL, found INT".to_string()
+            )
+        );
+
+        let exp = not(num_add(vec![int_val(1), float_val(1.5)]));
+        assert_eq!(
+            error(&exp),
+            (
+                "not[0].num_add".to_string(),
+                "operand 1 is FLOAT, but operand 0 is INT".to_string()
+            )
+        );
+
+        let exp = gt(num_pow(int_val(2), int_val(3)), int_val(7));
+        assert_eq!(
+            error(&exp),
+            (
+                "gt[0].num_pow".to_string(),
+                "operand 0 must be FLOAT, found INT".to_string()
+            )
+        );
+
+        assert_eq!(
+            error(&and(vec![])),
+            (
+                "and".to_string(),
+                "expected at least 1 operands, found 0".to_string()
+            )
+        );
+
+        let exp = exp_let(vec![
+            def("x".into(), int_val(1)),
+            gt(var("y".into()), int_val(0)),
+        ]);
+        assert_eq!(
+            error(&exp),
+            (
+                "exp_let[1].gt[0].var".to_string(),
+                "variable 'y' is not defined".to_string()
+            )
+        );
+
+        let exp = regex_compare("^a".into(), 0, int_bin("age".into()));
+        assert_eq!(
+            error(&exp),
+            (
+                "regex_compare".to_string(),
+                "bin must be STRING, found INT".to_string()
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn filters() {
+        assert!(validate_filter(&gt(int_bin("age".into()), int_val(21))).is_ok());
+        assert!(validate_filter(&unknown()).is_ok());
+
+        let exp = num_add(vec![int_bin("age".into()), int_val(1)]);
+        assert_eq!(
+            validate_filter(&exp).unwrap_err().to_string(),
+            "Invalid expression tree at num_add: filter must be BOOL, found INT"
+        );
+    }
+}
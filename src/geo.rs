@@ -0,0 +1,733 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Typed geometries for `Value::GeoJSON` bins and geospatial queries.
+//!
+//! The geometry types validate their coordinates on construction and format as the `GeoJSON`
+//! strings expected by the server. They convert into `Value::GeoJSON`, and `Value::GeoJSON`
+//! strings read from the database can be parsed back into a `Geometry`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::convert::TryFrom;
+//! use aerospike::geo::{AeroCircle, Geometry, Point};
+//! use aerospike::Value;
+//!
+//! let point = Point::new(-122.0, 37.5).unwrap();
+//! assert_eq!(point.to_string(), r#"{"type":"Point","coordinates":[-122,37.5]}"#);
+//!
+//! let circle = AeroCircle::new(point, 1000.0).unwrap();
+//! let value = Value::from(circle);
+//! assert_eq!(Geometry::try_from(value).unwrap(), Geometry::AeroCircle(circle));
+//! ```
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::value::{invalid_type, Value};
+
+/// Point given by its longitude and latitude in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    lng: f64,
+    lat: f64,
+}
+
+impl Point {
+    /// Create a point. The longitude must be within [-180, 180] and the latitude within
+    /// [-90, 90] degrees.
+    pub fn new(lng: f64, lat: f64) -> Result<Self> {
+        if !(-180.0..=180.0).contains(&lng) {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Longitude {} is out of range",
+                lng
+            )));
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Latitude {} is out of range",
+                lat
+            )));
+        }
+        Ok(Point { lng, lat })
+    }
+
+    /// Longitude in degrees.
+    pub const fn lng(&self) -> f64 {
+        self.lng
+    }
+
+    /// Latitude in degrees.
+    pub const fn lat(&self) -> f64 {
+        self.lat
+    }
+}
+
+/// Polygon given by its exterior ring and optional holes. Each ring must be closed, i.e. its
+/// first and last points must be equal, and have at least four points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon {
+    rings: Vec<Vec<Point>>,
+}
+
+impl Polygon {
+    /// Create a polygon without holes.
+    pub fn new(exterior: Vec<Point>) -> Result<Self> {
+        Polygon::with_holes(exterior, vec![])
+    }
+
+    /// Create a polygon with holes.
+    pub fn with_holes(exterior: Vec<Point>, holes: Vec<Vec<Point>>) -> Result<Self> {
+        let mut rings = Vec::with_capacity(holes.len() + 1);
+        rings.push(exterior);
+        rings.extend(holes);
+        Polygon::from_rings(rings)
+    }
+
+    fn from_rings(rings: Vec<Vec<Point>>) -> Result<Self> {
+        if rings.is_empty() {
+            bail!(ErrorKind::InvalidArgument(
+                "Polygon must have an exterior ring".to_string()
+            ));
+        }
+        for ring in &rings {
+            if ring.len() < 4 {
+                bail!(ErrorKind::InvalidArgument(format!(
+                    "Polygon ring must have at least 4 points, found {}",
+                    ring.len()
+                )));
+            }
+            if ring.first() != ring.last() {
+                bail!(ErrorKind::InvalidArgument(
+                    "Polygon ring is not closed".to_string()
+                ));
+            }
+        }
+        Ok(Polygon { rings })
+    }
+
+    /// Exterior ring of the polygon.
+    pub fn exterior(&self) -> &[Point] {
+        &self.rings[0]
+    }
+
+    /// Holes of the polygon.
+    pub fn holes(&self) -> &[Vec<Point>] {
+        &self.rings[1..]
+    }
+}
+
+/// Collection of one or more polygons.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiPolygon {
+    polygons: Vec<Polygon>,
+}
+
+impl MultiPolygon {
+    /// Create a multi-polygon from at least one polygon.
+    pub fn new(polygons: Vec<Polygon>) -> Result<Self> {
+        if polygons.is_empty() {
+            bail!(ErrorKind::InvalidArgument(
+                "MultiPolygon must have at least one polygon".to_string()
+            ));
+        }
+        Ok(MultiPolygon { polygons })
+    }
+
+    /// Polygons of the collection.
+    pub fn polygons(&self) -> &[Polygon] {
+        &self.polygons
+    }
+}
+
+/// Circle given by its center and radius in meters. This is an Aerospike extension to `GeoJSON`,
+/// used to query points within a distance of the center.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AeroCircle {
+    center: Point,
+    radius: f64,
+}
+
+impl AeroCircle {
+    /// Create a circle. The radius must be a finite, non-negative number of meters.
+    pub fn new(center: Point, radius: f64) -> Result<Self> {
+        if !radius.is_finite() || radius < 0.0 {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Invalid circle radius {}",
+                radius
+            )));
+        }
+        Ok(AeroCircle { center, radius })
+    }
+
+    /// Center of the circle.
+    pub const fn center(&self) -> Point {
+        self.center
+    }
+
+    /// Radius of the circle in meters.
+    pub const fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+/// Any of the geometries supported by the server.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    /// Point geometry.
+    Point(Point),
+
+    /// Polygon geometry.
+    Polygon(Polygon),
+
+    /// Multi-polygon geometry.
+    MultiPolygon(MultiPolygon),
+
+    /// Circle geometry.
+    AeroCircle(AeroCircle),
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, r#"{{"type":"Point","coordinates":"#)?;
+        write_point(f, self)?;
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Polygon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, r#"{{"type":"Polygon","coordinates":"#)?;
+        write_polygon(f, self)?;
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for MultiPolygon {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, r#"{{"type":"MultiPolygon","coordinates":["#)?;
+        for (i, polygon) in self.polygons.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write_polygon(f, polygon)?;
+        }
+        write!(f, "]}}")
+    }
+}
+
+impl fmt::Display for AeroCircle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, r#"{{"type":"AeroCircle","coordinates":["#)?;
+        write_point(f, &self.center)?;
+        write!(f, ",{}]}}", self.radius)
+    }
+}
+
+impl fmt::Display for Geometry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Geometry::Point(ref geo) => geo.fmt(f),
+            Geometry::Polygon(ref geo) => geo.fmt(f),
+            Geometry::MultiPolygon(ref geo) => geo.fmt(f),
+            Geometry::AeroCircle(ref geo) => geo.fmt(f),
+        }
+    }
+}
+
+fn write_point(f: &mut fmt::Formatter, point: &Point) -> fmt::Result {
+    write!(f, "[{},{}]", point.lng, point.lat)
+}
+
+fn write_polygon(f: &mut fmt::Formatter, polygon: &Polygon) -> fmt::Result {
+    write!(f, "[")?;
+    for (i, ring) in polygon.rings.iter().enumerate() {
+        if i > 0 {
+            write!(f, ",")?;
+        }
+        write!(f, "[")?;
+        for (j, point) in ring.iter().enumerate() {
+            if j > 0 {
+                write!(f, ",")?;
+            }
+            write_point(f, point)?;
+        }
+        write!(f, "]")?;
+    }
+    write!(f, "]")
+}
+
+impl FromStr for Geometry {
+    type Err = Error;
+
+    /// Parse a `GeoJSON` string. Members other than `type` and `coordinates` are ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            input: s.as_bytes(),
+            pos: 0,
+            depth: 0,
+        };
+        let json = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos != parser.input.len() {
+            return Err(parser.error("trailing characters"));
+        }
+
+        let members = match json {
+            Json::Object(members) => members,
+            _ => bail!(ErrorKind::InvalidArgument(
+                "GeoJSON must be an object".to_string()
+            )),
+        };
+        let member = |name: &str| {
+            members
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, val)| val)
+                .ok_or_else(|| {
+                    Error::from(ErrorKind::InvalidArgument(format!(
+                        "GeoJSON is missing the `{}` member",
+                        name
+                    )))
+                })
+        };
+
+        let coordinates = member("coordinates")?;
+        match member("type")? {
+            Json::String(ref kind) if kind == "Point" => Ok(Geometry::Point(point(coordinates)?)),
+            Json::String(ref kind) if kind == "Polygon" => {
+                Ok(Geometry::Polygon(polygon(coordinates)?))
+            }
+            Json::String(ref kind) if kind == "MultiPolygon" => {
+                let polygons = array(coordinates)?
+                    .iter()
+                    .map(polygon)
+                    .collect::<Result<_>>()?;
+                Ok(Geometry::MultiPolygon(MultiPolygon::new(polygons)?))
+            }
+            Json::String(ref kind) if kind == "AeroCircle" => match array(coordinates)? {
+                [center, radius] => Ok(Geometry::AeroCircle(AeroCircle::new(
+                    point(center)?,
+                    number(radius)?,
+                )?)),
+                _ => bail!(ErrorKind::InvalidArgument(
+                    "AeroCircle coordinates must be a center and a radius".to_string()
+                )),
+            },
+            kind => bail!(ErrorKind::InvalidArgument(format!(
+                "Unsupported GeoJSON type {:?}",
+                kind
+            ))),
+        }
+    }
+}
+
+fn array(json: &Json) -> Result<&[Json]> {
+    match *json {
+        Json::Array(ref items) => Ok(items),
+        _ => bail!(ErrorKind::InvalidArgument(
+            "GeoJSON coordinates must be arrays".to_string()
+        )),
+    }
+}
+
+fn number(json: &Json) -> Result<f64> {
+    match *json {
+        Json::Number(val) => Ok(val),
+        _ => bail!(ErrorKind::InvalidArgument(
+            "GeoJSON coordinates must be numbers".to_string()
+        )),
+    }
+}
+
+fn point(json: &Json) -> Result<Point> {
+    match array(json)? {
+        [lng, lat] => Point::new(number(lng)?, number(lat)?),
+        _ => bail!(ErrorKind::InvalidArgument(
+            "GeoJSON position must have a longitude and a latitude".to_string()
+        )),
+    }
+}
+
+fn polygon(json: &Json) -> Result<Polygon> {
+    let rings = array(json)?
+        .iter()
+        .map(|ring| array(ring)?.iter().map(point).collect())
+        .collect::<Result<_>>()?;
+    Polygon::from_rings(rings)
+}
+
+// Minimal JSON document model, sufficient to read GeoJSON geometries.
+#[derive(Debug)]
+enum Json {
+    // `true`, `false` or `null`, which never occur in geometries
+    Literal,
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+// Maximum nesting of arrays and objects. Geometries need far less; the limit keeps deeply nested
+// input from overflowing the stack.
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    input: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> Error {
+        ErrorKind::InvalidArgument(format!("Invalid GeoJSON at offset {}: {}", self.pos, msg))
+            .into()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        self.skip_whitespace();
+        if self.input.get(self.pos) != Some(&byte) {
+            return Err(self.error(&format!("expected `{}`", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<Json> {
+        if !self.input[self.pos..].starts_with(literal.as_bytes()) {
+            return Err(self.error("unexpected literal"));
+        }
+        self.pos += literal.len();
+        Ok(Json::Literal)
+    }
+
+    fn parse_value(&mut self) -> Result<Json> {
+        self.skip_whitespace();
+        match self.input.get(self.pos) {
+            Some(b'{' | b'[') if self.depth == MAX_DEPTH => Err(self.error("nested too deeply")),
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b'[') => self.nested(Self::parse_array),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Json>) -> Result<Json> {
+        self.depth += 1;
+        let json = parse(self);
+        self.depth -= 1;
+        json
+    }
+
+    fn parse_object(&mut self) -> Result<Json> {
+        self.expect(b'{')?;
+        let mut members = vec![];
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(Json::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            members.push((key, self.parse_value()?));
+            self.skip_whitespace();
+            match self.input.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Json> {
+        self.expect(b'[')?;
+        let mut items = vec![];
+        self.skip_whitespace();
+        if self.input.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.input.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        if self.input.get(self.pos) != Some(&b'"') {
+            return Err(self.error("expected a string"));
+        }
+        self.pos += 1;
+        let mut bytes = vec![];
+        loop {
+            match self.input.get(self.pos) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    let escaped = match self.input.get(self.pos + 1) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => {
+                            let code = self
+                                .input
+                                .get(self.pos + 2..self.pos + 6)
+                                .and_then(|hex| std::str::from_utf8(hex).ok())
+                                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                                .ok_or_else(|| self.error("invalid unicode escape"))?;
+                            self.pos += 4;
+                            std::char::from_u32(code).unwrap_or('\u{fffd}')
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut utf8 = [0; 4];
+                    bytes.extend_from_slice(escaped.encode_utf8(&mut utf8).as_bytes());
+                    self.pos += 2;
+                }
+                Some(&byte) => {
+                    bytes.push(byte);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8"))
+    }
+
+    fn parse_number(&mut self) -> Result<Json> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.input.get(self.pos) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.pos])
+            .ok()
+            .and_then(|num| num.parse().ok())
+            .map(Json::Number)
+            .ok_or_else(|| self.error("invalid number"))
+    }
+}
+
+macro_rules! geo_conversions {
+    ($($t:ident),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(geo: $t) -> Value {
+                    Value::GeoJSON(geo.to_string())
+                }
+            }
+
+            impl<'a> From<&'a $t> for Value {
+                fn from(geo: &$t) -> Value {
+                    Value::GeoJSON(geo.to_string())
+                }
+            }
+
+            impl From<$t> for Geometry {
+                fn from(geo: $t) -> Geometry {
+                    Geometry::$t(geo)
+                }
+            }
+
+            impl TryFrom<Value> for $t {
+                type Error = Error;
+
+                fn try_from(val: Value) -> Result<$t> {
+                    match Geometry::try_from(val)? {
+                        Geometry::$t(geo) => Ok(geo),
+                        geo => bail!(ErrorKind::InvalidArgument(format!(
+                            "Expected {}, found {}",
+                            stringify!($t),
+                            geo
+                        ))),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+geo_conversions!(Point, Polygon, MultiPolygon, AeroCircle);
+
+impl From<Geometry> for Value {
+    fn from(geo: Geometry) -> Value {
+        Value::GeoJSON(geo.to_string())
+    }
+}
+
+impl<'a> From<&'a Geometry> for Value {
+    fn from(geo: &Geometry) -> Value {
+        Value::GeoJSON(geo.to_string())
+    }
+}
+
+/// Parses `Value::GeoJSON` values.
+impl TryFrom<Value> for Geometry {
+    type Error = Error;
+
+    fn try_from(val: Value) -> Result<Geometry> {
+        match val {
+            Value::GeoJSON(ref json) => json.parse(),
+            _ => Err(invalid_type("GeoJSON", &val)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use super::{AeroCircle, Geometry, MultiPolygon, Point, Polygon};
+    use crate::errors::ErrorKind;
+    use crate::Value;
+
+    fn square(offset: f64) -> Vec<Point> {
+        vec![
+            Point::new(offset, offset).unwrap(),
+            Point::new(offset + 1.0, offset).unwrap(),
+            Point::new(offset + 1.0, offset + 1.0).unwrap(),
+            Point::new(offset, offset).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn to_geo_json() {
+        let point = Point::new(-122.5, 37.0).unwrap();
+        assert_eq!(
+            point.to_string(),
+            r#"{"type":"Point","coordinates":[-122.5,37]}"#
+        );
+
+        let polygon = Polygon::with_holes(square(0.0), vec![square(0.25)]).unwrap();
+        assert_eq!(
+            polygon.to_string(),
+            r#"{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,0]],[[0.25,0.25],[1.25,0.25],[1.25,1.25],[0.25,0.25]]]}"#
+        );
+
+        let multi = MultiPolygon::new(vec![Polygon::new(square(0.0)).unwrap()]).unwrap();
+        assert_eq!(
+            multi.to_string(),
+            r#"{"type":"MultiPolygon","coordinates":[[[[0,0],[1,0],[1,1],[0,0]]]]}"#
+        );
+
+        let circle = AeroCircle::new(point, 1000.5).unwrap();
+        assert_eq!(
+            circle.to_string(),
+            r#"{"type":"AeroCircle","coordinates":[[-122.5,37],1000.5]}"#
+        );
+        assert_eq!(Value::from(circle), as_geo!(circle.to_string()));
+    }
+
+    #[test]
+    fn from_geo_json() {
+        let geometries = vec![
+            Geometry::Point(Point::new(1.5, -2.0).unwrap()),
+            Geometry::Polygon(Polygon::with_holes(square(0.0), vec![square(0.5)]).unwrap()),
+            Geometry::MultiPolygon(
+                MultiPolygon::new(vec![
+                    Polygon::new(square(0.0)).unwrap(),
+                    Polygon::new(square(10.0)).unwrap(),
+                ])
+                .unwrap(),
+            ),
+            Geometry::AeroCircle(AeroCircle::new(Point::new(0.0, 0.0).unwrap(), 5.0).unwrap()),
+        ];
+        for geo in geometries {
+            assert_eq!(geo.to_string().parse::<Geometry>().unwrap(), geo);
+            assert_eq!(Geometry::try_from(Value::from(&geo)).unwrap(), geo);
+        }
+
+        let json = r#" { "coordinates" : [ 1e1, -0.5 ], "bbox": [0, 0], "type": "Point",
+            "properties": { "name": "\"x\"A", "ok": true, "none": null } } "#;
+        assert_eq!(
+            Point::try_from(as_geo!(json)).unwrap(),
+            Point::new(10.0, -0.5).unwrap()
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert!(Point::new(180.5, 0.0).is_err());
+        assert!(Point::new(0.0, -90.5).is_err());
+        assert!(Point::new(std::f64::NAN, 0.0).is_err());
+
+        let mut open = square(0.0);
+        open.pop();
+        open.push(Point::new(0.0, 1.0).unwrap());
+        assert!(Polygon::new(open).is_err());
+        assert!(Polygon::new(square(0.0)[1..].to_vec()).is_err());
+        assert!(Polygon::with_holes(square(0.0), vec![vec![]]).is_err());
+        assert!(MultiPolygon::new(vec![]).is_err());
+        assert!(AeroCircle::new(Point::new(0.0, 0.0).unwrap(), -1.0).is_err());
+        assert!(AeroCircle::new(Point::new(0.0, 0.0).unwrap(), std::f64::INFINITY).is_err());
+
+        for json in &[
+            "",
+            "[]",
+            r#"{"type":"Point"}"#,
+            r#"{"type":"Line","coordinates":[]}"#,
+            r#"{"type":"Point","coordinates":[1]}"#,
+            r#"{"type":"Point","coordinates":[200,0]}"#,
+            r#"{"type":"Point","coordinates":[1,2]"#,
+            r#"{"type":"Point","coordinates":[1,2]} x"#,
+            r#"{"type":"Polygon","coordinates":[[[0,0],[1,0],[1,1],[0,1]]]}"#,
+            r#"{"type":"AeroCircle","coordinates":[[0,0]]}"#,
+        ] {
+            assert!(json.parse::<Geometry>().is_err(), "{}", json);
+        }
+        match "[".repeat(10_000).parse::<Geometry>().unwrap_err().0 {
+            ErrorKind::InvalidArgument(msg) => {
+                assert!(msg.contains("nested too deeply"), "{}", msg)
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+
+        assert!(Geometry::try_from(as_val!("{}")).is_err());
+        let point = Value::from(Point::new(0.0, 0.0).unwrap());
+        assert!(Polygon::try_from(point).is_err());
+    }
+}
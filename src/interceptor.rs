@@ -0,0 +1,190 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Hooks to inject behaviour around every command sent to the cluster.
+//!
+//! Interceptors are registered on the `ClientPolicy` and are invoked for every attempt of every
+//! command, including retries, right before the request is sent to a server node and right after
+//! the attempt has completed. They can be used for auditing, enforcing quotas, injecting
+//! failures for testing or rewriting the namespace of single record commands, e.g. to map
+//! tenants to namespaces.
+//!
+//! # Examples
+//!
+//! Reject all commands against the `restricted` namespace, and send commands for the `tenant`
+//! namespace to `tenant_a`:
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use aerospike::{ClientPolicy, CommandContext, ErrorKind, Interceptor, Result};
+//!
+//! #[derive(Debug)]
+//! struct Tenants;
+//!
+//! impl Interceptor for Tenants {
+//!     fn before_command(&self, ctx: &mut CommandContext) -> Result<()> {
+//!         if ctx.namespace() == "restricted" {
+//!             return Err(ErrorKind::InvalidArgument("Namespace not allowed".to_string()).into());
+//!         }
+//!         if ctx.namespace() == "tenant" {
+//!             ctx.set_namespace("tenant_a");
+//!         }
+//!         Ok(())
+//!     }
+//! }
+//!
+//! let mut policy = ClientPolicy::default();
+//! policy.interceptors.push(Arc::new(Tenants));
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::cluster::Node;
+use crate::commands::CommandType;
+use crate::errors::Result;
+use crate::policy::Policy;
+use crate::Key;
+
+/// Hooks invoked around each command attempt. Both methods have default implementations that do
+/// nothing, so implementors only need to override the hooks they are interested in.
+pub trait Interceptor: fmt::Debug + Send + Sync {
+    /// Called before a command attempt is sent to the server node. Returning an error aborts the
+    /// command without retrying it; the error is returned to the caller and `after_command` is
+    /// not invoked for this attempt. The namespace of the attempt can be changed with
+    /// `CommandContext::set_namespace`.
+    fn before_command(&self, ctx: &mut CommandContext) -> Result<()> {
+        let _ = ctx;
+        Ok(())
+    }
+
+    /// Called after a command attempt has completed, with the result of the attempt. Failed
+    /// attempts that are retried are reported as well.
+    fn after_command(&self, ctx: &CommandContext, result: &Result<()>) {
+        let _ = (ctx, result);
+    }
+}
+
+/// Information about a single command attempt passed to an `Interceptor`.
+pub struct CommandContext<'a> {
+    command: CommandType,
+    namespace: Cow<'a, str>,
+    key: Option<&'a Key>,
+    policy: &'a dyn Policy,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    node: Option<Arc<Node>>,
+    iteration: usize,
+}
+
+impl<'a> CommandContext<'a> {
+    pub(crate) fn new(
+        command: CommandType,
+        namespace: &'a str,
+        key: Option<&'a Key>,
+        policy: &'a dyn Policy,
+        interceptors: Vec<Arc<dyn Interceptor>>,
+        node: Option<Arc<Node>>,
+        iteration: usize,
+    ) -> Self {
+        CommandContext {
+            command,
+            namespace: Cow::Borrowed(namespace),
+            key,
+            policy,
+            interceptors,
+            node,
+            iteration,
+        }
+    }
+
+    /// Type of the command.
+    pub const fn command(&self) -> CommandType {
+        self.command
+    }
+
+    /// Namespace the command operates on, including changes made by interceptors that ran
+    /// before. For batch commands this is the namespace of the first key sent to the node.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Sends the command attempt to `namespace` instead, routing it to the node that owns the
+    /// key in that namespace. Only single record commands can be rewritten; batch, scan and query
+    /// commands fail with an `InvalidArgument` error if their namespace is changed. The rewrite
+    /// applies to one attempt, so it is made again for every retry.
+    pub fn set_namespace<S: Into<String>>(&mut self, namespace: S) {
+        self.namespace = Cow::Owned(namespace.into());
+    }
+
+    /// Key of the record as passed to the client, i.e. in the namespace before any rewrite, for
+    /// single record commands; `None` for batch, scan and query commands.
+    pub const fn key(&self) -> Option<&Key> {
+        self.key
+    }
+
+    /// Policy of the command.
+    pub fn policy(&self) -> &dyn Policy {
+        self.policy
+    }
+
+    /// Server node the command attempt is sent to; `None` if no active node serves the namespace,
+    /// e.g. for a namespace that an interceptor rewrites to one that exists in the cluster.
+    pub fn node(&self) -> Option<&Node> {
+        self.node.as_deref()
+    }
+
+    /// Attempt number, starting at 1; greater than 1 for retries.
+    pub const fn iteration(&self) -> usize {
+        self.iteration
+    }
+
+    // Runs the `before_command` hooks of all interceptors registered on the client policy,
+    // stopping at the first error. Returns the new namespace if an interceptor changed
+    // it.
+    pub(crate) fn before(&mut self) -> Result<Option<String>> {
+        for interceptor in self.interceptors.clone() {
+            interceptor.before_command(self)?;
+        }
+        match self.namespace {
+            Cow::Owned(ref namespace) => Ok(Some(namespace.clone())),
+            Cow::Borrowed(_) => Ok(None),
+        }
+    }
+
+    // The node the attempt is sent to, after the namespace has been rewritten.
+    pub(crate) fn set_node(&mut self, node: Option<Arc<Node>>) {
+        self.node = node;
+    }
+
+    // Runs the `after_command` hooks of all interceptors registered on the client policy.
+    pub(crate) fn after(&self, result: &Result<()>) {
+        for interceptor in &self.interceptors {
+            interceptor.after_command(self, result);
+        }
+    }
+}
+
+impl<'a> fmt::Debug for CommandContext<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CommandContext")
+            .field("command", &self.command)
+            .field("namespace", &self.namespace)
+            .field("key", &self.key)
+            .field("node", &self.node.as_ref().map(|node| node.name()))
+            .field("iteration", &self.iteration)
+            .finish()
+    }
+}
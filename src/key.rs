@@ -15,14 +15,20 @@
 
 use std::fmt;
 use std::result::Result as StdResult;
+use std::str::FromStr;
 
-use crate::errors::Result;
+use crate::cluster::node::PARTITIONS;
+use crate::errors::{Error, ErrorKind, Result};
 use crate::Value;
 
+use byteorder::{ByteOrder, LittleEndian};
 use ripemd160::digest::Digest;
 use ripemd160::Ripemd160;
 #[cfg(feature = "serialization")]
-use serde::Serialize;
+use serde::de::{self, SeqAccess, Visitor};
+#[cfg(feature = "serialization")]
+use serde::{Deserialize, Deserializer, Serialize};
+
 /// Unique record identifier. Records can be identified using a specified namespace, an optional
 /// set name and a user defined key which must be uique within a set. Records can also be
 /// identified by namespace/digest, which is the combination used on the server.
@@ -45,14 +51,22 @@ pub struct Key {
 impl Key {
     /// Construct a new key given a namespace, a set name and a user key value.
     ///
-    /// # Panics
-    ///
-    /// Only integers, strings and blobs (`Vec<u8>`) can be used as user keys. The constructor will
-    /// panic if any other value type is passed.
+    /// Only integers, strings and blobs (`Vec<u8>`, `&[u8]`) can be used as user keys. Unsigned
+    /// integers are accepted as long as they fit into an `i64`. Any other value type returns an
+    /// `InvalidArgument` error.
     pub fn new<S>(namespace: S, set_name: S, key: Value) -> Result<Self>
     where
         S: Into<String>,
     {
+        let key = match key {
+            Value::Int(_) | Value::String(_) | Value::Blob(_) => key,
+            Value::UInt(val) if val <= i64::max_value() as u64 => Value::Int(val as i64),
+            _ => bail!(ErrorKind::InvalidArgument(format!(
+                "{} is not a valid key value; only integers, strings and blobs are supported",
+                key
+            ))),
+        };
+
         let mut key = Key {
             namespace: namespace.into(),
             set_name: set_name.into(),
@@ -64,6 +78,40 @@ impl Key {
         Ok(key)
     }
 
+    /// Construct a key from a digest, e.g. one returned by a scan or received from another
+    /// system. The key has no user key.
+    pub fn from_digest<S>(namespace: S, set_name: S, digest: [u8; 20]) -> Self
+    where
+        S: Into<String>,
+    {
+        Key {
+            namespace: namespace.into(),
+            set_name: set_name.into(),
+            user_key: None,
+            digest,
+        }
+    }
+
+    /// Id of the partition the record belongs to. Records are distributed over 4096 partitions
+    /// based on their digest.
+    pub fn partition_id(&self) -> usize {
+        // CAN'T USE MOD directly - mod will give negative numbers.
+        // First AND makes positive and negative correctly, then mod.
+        // For any x, y : x % 2^y = x & (2^y - 1); the second method is twice as fast
+        LittleEndian::read_u32(&self.digest[0..4]) as usize & (PARTITIONS - 1)
+    }
+
+    /// Digest encoded as a lowercase hex string.
+    pub fn digest_hex(&self) -> String {
+        const HEX: &[u8; 16] = b"0123456789abcdef";
+        let mut hex = String::with_capacity(40);
+        for b in &self.digest {
+            hex.push(HEX[(b >> 4) as usize] as char);
+            hex.push(HEX[(b & 0x0f) as usize] as char);
+        }
+        hex
+    }
+
     fn compute_digest(&mut self) -> Result<()> {
         let mut hash = Ripemd160::new();
         hash.input(self.set_name.as_bytes());
@@ -79,6 +127,44 @@ impl Key {
     }
 }
 
+// Decodes a 40 character hex string into a digest.
+fn digest_from_hex(hex: &str) -> Option<[u8; 20]> {
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let mut digest = [0; 20];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(digest)
+}
+
+/// Parses keys in the form `<namespace>:<set name>:<digest hex>`, e.g.
+/// `test:users:93d943aae37b017ad7e011b0c1d2e2143c2fb37d`. The set name may be empty. The parsed
+/// key has no user key.
+impl FromStr for Key {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(namespace), Some(set_name), Some(digest)) if !namespace.is_empty() => {
+                match digest_from_hex(digest) {
+                    Some(digest) => Ok(Key::from_digest(namespace, set_name, digest)),
+                    None => bail!(ErrorKind::InvalidArgument(format!(
+                        "Invalid key digest '{}'",
+                        digest
+                    ))),
+                }
+            }
+            _ => bail!(ErrorKind::InvalidArgument(format!(
+                "Invalid key '{}', expected <namespace>:<set>:<digest>",
+                s
+            ))),
+        }
+    }
+}
+
 impl fmt::Display for Key {
     fn fmt(&self, f: &mut fmt::Formatter) -> StdResult<(), fmt::Error> {
         match self.user_key {
@@ -89,19 +175,129 @@ impl fmt::Display for Key {
             ),
             None => write!(
                 f,
-                "<Key: ns=\"{}\", set=\"{}\", digest=\"{:?}\">",
-                &self.namespace, &self.set_name, &self.digest
+                "<Key: ns=\"{}\", set=\"{}\", digest=\"{}\">",
+                &self.namespace,
+                &self.set_name,
+                self.digest_hex()
             ),
         }
     }
 }
 
+// Keys are deserialized from the derived `Serialize` representation. The digest may also be
+// given as a hex or base64 encoded string, or omitted, in which case it is computed from the user
+// key.
+#[cfg(feature = "serialization")]
+impl<'de> Deserialize<'de> for Key {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Fields {
+            namespace: String,
+            set_name: String,
+            #[serde(default)]
+            user_key: Option<Value>,
+            #[serde(default)]
+            digest: Option<DigestField>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let user_key = fields.user_key.map(blob_from_bytes);
+        match (fields.digest, user_key) {
+            (Some(DigestField(digest)), user_key) => Ok(Key {
+                namespace: fields.namespace,
+                set_name: fields.set_name,
+                user_key,
+                digest,
+            }),
+            (None, Some(user_key)) => Key::new(fields.namespace, fields.set_name, user_key)
+                .map_err(|err| de::Error::custom(err.to_string())),
+            (None, None) => Err(de::Error::missing_field("digest")),
+        }
+    }
+}
+
+// Blob keys serialized to formats without byte strings, e.g. JSON, come back as lists of
+// integers; lists are not valid keys, so such lists are turned back into blobs.
+#[cfg(feature = "serialization")]
+fn blob_from_bytes(value: Value) -> Value {
+    match value {
+        Value::List(list) => {
+            let bytes: Option<Vec<u8>> = list
+                .iter()
+                .map(|v| match v {
+                    Value::Int(b @ 0..=255) => Some(*b as u8),
+                    _ => None,
+                })
+                .collect();
+            bytes.map_or(Value::List(list), Value::Blob)
+        }
+        value => value,
+    }
+}
+
+#[cfg(feature = "serialization")]
+struct DigestField([u8; 20]);
+
+#[cfg(feature = "serialization")]
+impl<'de> Deserialize<'de> for DigestField {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DigestVisitor).map(DigestField)
+    }
+}
+
+#[cfg(feature = "serialization")]
+struct DigestVisitor;
+
+#[cfg(feature = "serialization")]
+impl<'de> Visitor<'de> for DigestVisitor {
+    type Value = [u8; 20];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a 20 byte digest, or its hex or base64 encoding")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> StdResult<[u8; 20], E> {
+        if let Some(digest) = digest_from_hex(v) {
+            return Ok(digest);
+        }
+        let bytes =
+            base64::decode(v).map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))?;
+        self.visit_bytes(&bytes)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> StdResult<[u8; 20], E> {
+        if v.len() != 20 {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        let mut digest = [0; 20];
+        digest.copy_from_slice(v);
+        Ok(digest)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<[u8; 20], A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(20);
+        while let Some(byte) = seq.next_element::<u8>()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
 /// Construct a new key given a namespace, a set name and a user key.
 ///
 /// # Panics
 ///
 /// Only integers, strings and blobs (`Vec<u8>`) can be used as user keys. The macro will
-/// panic if any other value type is passed.
+/// panic if any other value type is passed; use `Key::new` to handle invalid keys.
 #[macro_export]
 macro_rules! as_key {
     ($ns:expr, $set:expr, $val:expr) => {{
@@ -266,14 +462,81 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Data type is not supported as Key value.")]
+    #[should_panic(expected = "is not a valid key value")]
     fn unsupported_float_key() {
         as_key!("namespace", "set", 4.1415);
     }
 
     #[test]
-    #[should_panic(expected = "Aerospike does not support u64 natively on server-side.")]
+    #[should_panic(expected = "is not a valid key value")]
     fn unsupported_u64_key() {
         as_key!("namespace", "set", u64::max_value());
     }
+
+    #[test]
+    fn fallible_keys() {
+        use super::{Key, Value};
+
+        assert_eq!(digest!(1u64), "82d7213b469812947c109a6d341e3b5b1dedec1f");
+        assert_eq!(
+            as_key!("namespace", "set", 1u64).user_key,
+            Some(Value::Int(1))
+        );
+        assert_eq!(
+            digest!(&b"haha"[..]),
+            hex::encode(as_key!("namespace", "set", b"haha".to_vec()).digest)
+        );
+        for value in [
+            Value::Nil,
+            Value::from(1.5),
+            Value::UInt(u64::max_value()),
+            as_list!(1),
+            as_map!(1 => 1),
+            as_geo!("{}"),
+        ] {
+            assert!(Key::new("namespace", "set", value).is_err());
+        }
+    }
+
+    #[test]
+    fn digest_keys() {
+        use super::Key;
+        use crate::cluster::partition::Partition;
+
+        let key = as_key!("namespace", "set", 0);
+        let digest_hex = "93d943aae37b017ad7e011b0c1d2e2143c2fb37d";
+        assert_eq!(key.digest_hex(), digest_hex);
+
+        let from_digest = Key::from_digest("namespace", "set", key.digest);
+        assert_eq!(from_digest.user_key, None);
+        assert_eq!(from_digest.partition_id(), key.partition_id());
+        assert_eq!(key.partition_id(), Partition::new_by_key(&key).partition_id);
+        assert!(key.partition_id() < 4096);
+        assert_eq!(
+            from_digest.to_string(),
+            format!(
+                "<Key: ns=\"namespace\", set=\"set\", digest=\"{}\">",
+                digest_hex
+            )
+        );
+
+        let parsed: Key = format!("namespace:set:{}", digest_hex).parse().unwrap();
+        assert_eq!(parsed, from_digest);
+        let parsed: Key = format!("namespace::{}", digest_hex).parse().unwrap();
+        assert_eq!(parsed.set_name, "");
+        assert!("namespace:set".parse::<Key>().is_err());
+        assert!(":set:93d943aae37b017ad7e011b0c1d2e2143c2fb37d"
+            .parse::<Key>()
+            .is_err());
+        assert!("namespace:set:93d943".parse::<Key>().is_err());
+        assert!("namespace:set:zzd943aae37b017ad7e011b0c1d2e2143c2fb37d"
+            .parse::<Key>()
+            .is_err());
+    }
+
+    #[test]
+    fn unsupported_infinity_key() {
+        assert!(super::Key::new("namespace", "set", super::Value::Infinity).is_err());
+        assert!(super::Key::new("namespace", "set", super::Value::Wildcard).is_err());
+    }
 }
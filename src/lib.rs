@@ -151,13 +151,16 @@ extern crate rand;
 extern crate scoped_pool;
 
 pub use batch::BatchRead;
-pub use bin::{Bin, Bins};
+pub use bin::{Bin, Bins, OwnedBin, MAX_BIN_NAME_LENGTH};
 pub use client::Client;
+pub use commands::CommandType;
 pub use commands::particle_type::ParticleType;
 pub use errors::{Error, ErrorKind, Result};
 pub use expressions::regex_flag::RegexFlag;
+pub use interceptor::{CommandContext, Interceptor};
 pub use key::Key;
 pub use net::Host;
+pub use operate_result::{OpResult, OpReturnType, OperateResult};
 pub use operations::{MapPolicy, MapReturnType, MapWriteMode};
 pub use policy::{
     BatchPolicy, ClientPolicy, CommitLevel, Concurrency, ConsistencyLevel, Expiration,
@@ -167,6 +170,9 @@ pub use policy::{
 pub use query::{CollectionIndexType, IndexType, Recordset, Statement, UDFLang};
 pub use record::Record;
 pub use result_code::ResultCode;
+#[cfg(feature = "serialization")]
+pub use serialization::to_bins;
+pub use stats::{ClusterStats, NodeStats};
 pub use task::{IndexTask, RegisterTask, Task};
 pub use user::User;
 pub use value::{FloatValue, Value};
@@ -184,14 +190,25 @@ mod client;
 mod cluster;
 mod commands;
 pub mod expressions;
+pub mod geo;
+pub mod interceptor;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod msgpack;
 mod net;
+mod operate_result;
 pub mod operations;
 pub mod policy;
 pub mod query;
 mod record;
 mod result_code;
+#[cfg(feature = "serialization")]
+pub mod serialization;
+pub mod stats;
 pub mod task;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod trace;
 mod user;
 pub mod entity;
 
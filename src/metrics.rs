@@ -0,0 +1,384 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Command latency histograms and their exposition in the OpenMetrics text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+
+use crate::commands::{self, CommandType};
+use crate::errors::Result;
+use crate::stats::ClusterStats;
+
+// Upper bounds of the latency buckets, in seconds. The implicit `+Inf` bucket is not listed.
+const LATENCY_BUCKETS: [f64; 14] = [
+    0.0001, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+];
+
+#[derive(Debug, Default)]
+struct Histogram {
+    // Non-cumulative bucket counts; the last slot counts observations above the largest bound.
+    buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    fn observe(&self, elapsed: Duration) {
+        let secs = elapsed.as_secs_f64();
+        let idx = LATENCY_BUCKETS
+            .iter()
+            .position(|&bound| secs <= bound)
+            .unwrap_or_else(|| LATENCY_BUCKETS.len());
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct SeriesKey {
+    command: CommandType,
+    namespace: String,
+    node: String,
+    result: String,
+}
+
+// Registry of the command latency histograms of a cluster, one series per combination of
+// command type, namespace, node and result code.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    series: RwLock<BTreeMap<SeriesKey, Arc<Histogram>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Metrics::default()
+    }
+
+    pub fn record<T>(
+        &self,
+        command: CommandType,
+        namespace: &str,
+        node: &str,
+        elapsed: Duration,
+        result: &Result<T>,
+    ) {
+        let key = SeriesKey {
+            command,
+            namespace: namespace.to_owned(),
+            node: node.to_owned(),
+            result: commands::result_label(result),
+        };
+
+        let existing = self.series.read().get(&key).cloned();
+        let histogram = match existing {
+            Some(histogram) => histogram,
+            None => self.series.write().entry(key).or_default().clone(),
+        };
+        histogram.observe(elapsed);
+    }
+
+    pub fn render(&self, stats: &ClusterStats) -> String {
+        let mut out = String::with_capacity(4096);
+        self.render_latencies(&mut out);
+        render_stats(&mut out, stats);
+        out.push_str("# EOF\n");
+        out
+    }
+
+    fn render_latencies(&self, out: &mut String) {
+        let name = "aerospike_client_command_duration_seconds";
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let _ = writeln!(out, "# UNIT {} seconds", name);
+        let _ = writeln!(out, "# HELP {} Latency of client commands.", name);
+
+        let series = self.series.read();
+        for (key, histogram) in series.iter() {
+            let labels = format!(
+                "command=\"{}\",namespace=\"{}\",node=\"{}\",result=\"{}\"",
+                key.command.as_str(),
+                escape_label(&key.namespace),
+                escape_label(&key.node),
+                escape_label(&key.result)
+            );
+
+            let mut cumulative = 0;
+            for (idx, bucket) in histogram.buckets.iter().enumerate() {
+                cumulative += bucket.load(Ordering::Relaxed);
+                let bound = LATENCY_BUCKETS
+                    .get(idx)
+                    .map_or_else(|| "+Inf".to_string(), |bound| format!("{:?}", bound));
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{{},le=\"{}\"}} {}",
+                    name, labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_count{{{}}} {}",
+                name,
+                labels,
+                histogram.count.load(Ordering::Relaxed)
+            );
+            let sum = Duration::from_nanos(histogram.sum_nanos.load(Ordering::Relaxed));
+            let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, sum.as_secs_f64());
+        }
+    }
+}
+
+fn render_stats(out: &mut String, stats: &ClusterStats) {
+    let node_metrics: [(&str, &str, &str, fn(&crate::NodeStats) -> usize); 11] = [
+        (
+            "aerospike_client_connections_open",
+            "gauge",
+            "Open connections, idle and in use.",
+            |n| n.open_connections,
+        ),
+        (
+            "aerospike_client_connections_idle",
+            "gauge",
+            "Open connections waiting in the pool.",
+            |n| n.idle_connections,
+        ),
+        (
+            "aerospike_client_connections_in_use",
+            "gauge",
+            "Open connections checked out by a command.",
+            |n| n.in_use_connections,
+        ),
+        (
+            "aerospike_client_connections_opened",
+            "counter",
+            "Connections opened.",
+            |n| n.connections_opened,
+        ),
+        (
+            "aerospike_client_connections_closed",
+            "counter",
+            "Connections closed.",
+            |n| n.connections_closed,
+        ),
+        (
+            "aerospike_client_connections_failed",
+            "counter",
+            "Failed attempts to open a connection.",
+            |n| n.connections_failed,
+        ),
+        (
+            "aerospike_client_connections_rejected",
+            "counter",
+            "Connection requests rejected because the pool was full.",
+            |n| n.connection_rejections,
+        ),
+        (
+            "aerospike_client_commands",
+            "counter",
+            "Command attempts, including retries.",
+            |n| n.commands,
+        ),
+        (
+            "aerospike_client_command_errors",
+            "counter",
+            "Failed command attempts.",
+            |n| n.command_errors,
+        ),
+        (
+            "aerospike_client_command_timeouts",
+            "counter",
+            "Command attempts that timed out.",
+            |n| n.command_timeouts,
+        ),
+        (
+            "aerospike_client_node",
+            "info",
+            "Address of the node.",
+            |_| 1,
+        ),
+    ];
+
+    for &(name, kind, help, value) in &node_metrics {
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let suffix = match kind {
+            "counter" => "_total",
+            "info" => "_info",
+            _ => "",
+        };
+        for node in &stats.nodes {
+            let labels = if kind == "info" {
+                format!(
+                    "node=\"{}\",address=\"{}\"",
+                    escape_label(&node.name),
+                    escape_label(&node.address)
+                )
+            } else {
+                format!("node=\"{}\"", escape_label(&node.name))
+            };
+            let _ = writeln!(out, "{}{}{{{}}} {}", name, suffix, labels, value(node));
+        }
+    }
+
+    let _ = writeln!(out, "# TYPE aerospike_client_tends counter");
+    let _ = writeln!(out, "# HELP aerospike_client_tends Cluster tend cycles.");
+    let _ = writeln!(out, "aerospike_client_tends_total {}", stats.tend_count);
+    let _ = writeln!(out, "# TYPE aerospike_client_tend_errors counter");
+    let _ = writeln!(
+        out,
+        "# HELP aerospike_client_tend_errors Cluster tend cycles that failed."
+    );
+    let _ = writeln!(
+        out,
+        "aerospike_client_tend_errors_total {}",
+        stats.tend_errors
+    );
+    let _ = writeln!(out, "# TYPE aerospike_client_tend_duration_seconds counter");
+    let _ = writeln!(out, "# UNIT aerospike_client_tend_duration_seconds seconds");
+    let _ = writeln!(
+        out,
+        "# HELP aerospike_client_tend_duration_seconds Accumulated duration of cluster tends."
+    );
+    let _ = writeln!(
+        out,
+        "aerospike_client_tend_duration_seconds_total {}",
+        stats.total_tend_duration.as_secs_f64()
+    );
+    let _ = writeln!(
+        out,
+        "# TYPE aerospike_client_last_tend_duration_seconds gauge"
+    );
+    let _ = writeln!(
+        out,
+        "# UNIT aerospike_client_last_tend_duration_seconds seconds"
+    );
+    let _ = writeln!(
+        out,
+        "# HELP aerospike_client_last_tend_duration_seconds Duration of the last cluster tend."
+    );
+    let _ = writeln!(
+        out,
+        "aerospike_client_last_tend_duration_seconds {}",
+        stats.last_tend_duration.as_secs_f64()
+    );
+}
+
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{escape_label, Metrics};
+    use crate::commands::CommandType;
+    use crate::errors::{ErrorKind, Result};
+    use crate::stats::{ClusterStats, NodeStats};
+    use crate::ResultCode;
+
+    #[test]
+    fn render_histogram() {
+        let metrics = Metrics::new();
+        let ok: Result<()> = Ok(());
+        let not_found: Result<()> =
+            Err(ErrorKind::ServerError(ResultCode::KeyNotFoundError).into());
+        metrics.record(
+            CommandType::Get,
+            "test",
+            "A1",
+            Duration::from_micros(50),
+            &ok,
+        );
+        metrics.record(
+            CommandType::Get,
+            "test",
+            "A1",
+            Duration::from_millis(3),
+            &ok,
+        );
+        metrics.record(CommandType::Get, "test", "A1", Duration::from_secs(10), &ok);
+        metrics.record(
+            CommandType::Get,
+            "test",
+            "A1",
+            Duration::from_millis(1),
+            &not_found,
+        );
+
+        let text = metrics.render(&ClusterStats::default());
+        let labels = r#"command="get",namespace="test",node="A1",result="Ok""#;
+        assert!(text.contains(&format!(
+            "aerospike_client_command_duration_seconds_bucket{{{},le=\"0.0001\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "aerospike_client_command_duration_seconds_bucket{{{},le=\"0.005\"}} 2",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "aerospike_client_command_duration_seconds_bucket{{{},le=\"+Inf\"}} 3",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "aerospike_client_command_duration_seconds_count{{{}}} 3",
+            labels
+        )));
+        assert!(text.contains(r#"result="KeyNotFoundError""#));
+        assert!(text.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn render_node_stats() {
+        let stats = ClusterStats {
+            nodes: vec![NodeStats {
+                name: "A1".to_string(),
+                address: "127.0.0.1:3000".to_string(),
+                open_connections: 3,
+                commands: 42,
+                ..NodeStats::default()
+            }],
+            tend_count: 7,
+            ..ClusterStats::default()
+        };
+        let text = Metrics::new().render(&stats);
+        assert!(text.contains("aerospike_client_connections_open{node=\"A1\"} 3\n"));
+        assert!(text.contains("aerospike_client_commands_total{node=\"A1\"} 42\n"));
+        assert!(
+            text.contains("aerospike_client_node_info{node=\"A1\",address=\"127.0.0.1:3000\"} 1\n")
+        );
+        assert!(text.contains("aerospike_client_tends_total 7\n"));
+    }
+
+    #[test]
+    fn escape_label_values() {
+        assert_eq!(r#"a\"b\\c\nd"#, escape_label("a\"b\\c\nd"));
+    }
+}
@@ -162,12 +162,14 @@ fn unpack_value(buf: &mut Buffer) -> Result<Value> {
         0xd1 => Ok(Value::from(buf.read_i16(None)?)),
         0xd2 => Ok(Value::from(buf.read_i32(None)?)),
         0xd3 => Ok(Value::from(buf.read_i64(None)?)),
-        0xd4 => {
-            warn!("Skipping over type extension with 1 byte");
-            let count = (1 + 1) as usize;
-            buf.skip_bytes(count);
-            Ok(Value::Nil)
-        }
+        0xd4 => match (buf.read_u8(None)?, buf.read_u8(None)?) {
+            (0xff, 0x01) => Ok(Value::Infinity),
+            (0xff, 0x00) => Ok(Value::Wildcard),
+            _ => {
+                warn!("Skipping over type extension with 1 byte");
+                Ok(Value::Nil)
+            }
+        },
         0xd5 => {
             warn!("Skipping over type extension with 2 bytes");
             let count = (1 + 2) as usize;
@@ -39,8 +39,10 @@ pub fn pack_value(buf: &mut Option<&mut Buffer>, val: &Value) -> Result<usize> {
         Value::Blob(ref val) | Value::HLL(ref val) => pack_blob(buf, val),
         Value::List(ref val) => pack_array(buf, val),
         Value::HashMap(ref val) => pack_map(buf, val),
-        Value::OrderedMap(_) => panic!("Ordered maps are not supported in this encoder."),
+        Value::OrderedMap(ref val) => pack_ordered_map(buf, val),
         Value::GeoJSON(ref val) => pack_geo_json(buf, val),
+        Value::Infinity => pack_aerospike_ext(buf, MSGPACK_EXT_INFINITY),
+        Value::Wildcard => pack_aerospike_ext(buf, MSGPACK_EXT_WILDCARD),
     }
 }
 
@@ -179,7 +181,27 @@ pub fn pack_map(buf: &mut Option<&mut Buffer>, map: &HashMap<Value, Value>) -> R
     let mut size = 0;
 
     size += pack_map_begin(buf, map.len())?;
-    for (key, val) in map.iter() {
+    for (key, val) in map {
+        size += pack_value(buf, key)?;
+        size += pack_value(buf, val)?;
+    }
+
+    Ok(size)
+}
+
+#[doc(hidden)]
+pub fn pack_ordered_map(buf: &mut Option<&mut Buffer>, map: &[(Value, Value)]) -> Result<usize> {
+    let mut size = 0;
+
+    // The server expects key-ordered maps to be sent sorted; only sort if they are not already.
+    let mut entries: Vec<_> = map.iter().collect();
+    if !map.windows(2).all(|pair| pair[0].0 <= pair[1].0) {
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+
+    size += pack_map_begin(buf, map.len() + 1)?;
+    size += pack_map_flags(buf, MAP_FLAGS_KEY_ORDERED)?;
+    for (key, val) in entries {
         size += pack_value(buf, key)?;
         size += pack_value(buf, val)?;
     }
@@ -190,13 +212,14 @@ pub fn pack_map(buf: &mut Option<&mut Buffer>, map: &HashMap<Value, Value>) -> R
 /// ///////////////////////////////////////////////////////////////////
 
 const MSGPACK_MARKER_NIL: u8 = 0xc0;
+const MSGPACK_MARKER_FIXEXT1: u8 = 0xd4;
 const MSGPACK_MARKER_BOOL_TRUE: u8 = 0xc3;
 const MSGPACK_MARKER_BOOL_FALSE: u8 = 0xc2;
 
 const MSGPACK_MARKER_I8: u8 = 0xcc;
 const MSGPACK_MARKER_I16: u8 = 0xcd;
 const MSGPACK_MARKER_I32: u8 = 0xce;
-// const MSGPACK_MARKER_I64: u8 = 0xd3;
+const MSGPACK_MARKER_I64: u8 = 0xcf;
 
 const MSGPACK_MARKER_NI8: u8 = 0xd0;
 const MSGPACK_MARKER_NI16: u8 = 0xd1;
@@ -238,6 +261,34 @@ pub fn pack_nil(buf: &mut Option<&mut Buffer>) -> Result<usize> {
     Ok(1)
 }
 
+// Infinity and wildcard are encoded as 1-byte extensions of the Aerospike extension type.
+const MSGPACK_EXT_AEROSPIKE: u8 = 0xff;
+const MSGPACK_EXT_WILDCARD: u8 = 0x00;
+const MSGPACK_EXT_INFINITY: u8 = 0x01;
+
+fn pack_aerospike_ext(buf: &mut Option<&mut Buffer>, value: u8) -> Result<usize> {
+    if let Some(ref mut buf) = *buf {
+        buf.write_u8(MSGPACK_MARKER_FIXEXT1)?;
+        buf.write_u8(MSGPACK_EXT_AEROSPIKE)?;
+        buf.write_u8(value)?;
+    }
+    Ok(3)
+}
+
+// The map order is sent as an extension entry with a nil value at the start of the map.
+const MSGPACK_MARKER_EXT8: u8 = 0xc7;
+const MAP_FLAGS_KEY_ORDERED: u8 = 0x01;
+
+fn pack_map_flags(buf: &mut Option<&mut Buffer>, flags: u8) -> Result<usize> {
+    if let Some(ref mut buf) = *buf {
+        buf.write_u8(MSGPACK_MARKER_EXT8)?;
+        buf.write_u8(0)?;
+        buf.write_u8(flags)?;
+        buf.write_u8(MSGPACK_MARKER_NIL)?;
+    }
+    Ok(4)
+}
+
 #[doc(hidden)]
 pub fn pack_bool(buf: &mut Option<&mut Buffer>, value: bool) -> Result<usize> {
     if let Some(ref mut buf) = *buf {
@@ -341,7 +392,7 @@ pub fn pack_integer(buf: &mut Option<&mut Buffer>, val: i64) -> Result<usize> {
         val if val >= i64::from(i16::max_value()) && val < i64::from(i32::max_value()) => {
             pack_i32(buf, MSGPACK_MARKER_I32, val as i32)
         }
-        val if val >= i64::from(i32::max_value()) => pack_i64(buf, MSGPACK_MARKER_I32, val),
+        val if val >= i64::from(i32::max_value()) => pack_i64(buf, MSGPACK_MARKER_I64, val),
 
         // Negative values
         val if val >= -32 && val < 0 => {
@@ -395,7 +446,7 @@ pub fn pack_u64(buf: &mut Option<&mut Buffer>, value: u64) -> Result<usize> {
     }
 
     if let Some(ref mut buf) = *buf {
-        buf.write_u8(0xcf)?;
+        buf.write_u8(MSGPACK_MARKER_I64)?;
         buf.write_u64(value)?;
     }
     Ok(9)
@@ -14,13 +14,15 @@
 // the License.
 
 use std::io::prelude::*;
-use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::net::TcpStream;
 use std::ops::Add;
 use std::time::{Duration, Instant};
 
 use crate::commands::admin_command::AdminCommand;
 use crate::commands::buffer::Buffer;
 use crate::errors::Result;
+use crate::net::transport::Transport;
+use crate::net::Host;
 use crate::policy::ClientPolicy;
 
 #[derive(Debug)]
@@ -32,19 +34,27 @@ pub struct Connection {
     idle_deadline: Option<Instant>,
 
     // connection object
-    conn: TcpStream,
+    conn: Box<dyn Transport>,
 
     bytes_read: usize,
 
+    // total number of bytes received over the life of the connection
+    bytes_received: usize,
+
     pub buffer: Buffer,
 }
 
 impl Connection {
-    pub fn new<T: ToSocketAddrs>(addr: T, policy: &ClientPolicy) -> Result<Self> {
-        let stream = TcpStream::connect(addr)?;
+    pub fn new(host: &Host, policy: &ClientPolicy) -> Result<Self> {
+        let stream = TcpStream::connect(host)?;
+        #[cfg(feature = "testing")]
+        let stream = crate::testing::transport(host, stream, policy);
+        #[cfg(not(feature = "testing"))]
+        let stream: Box<dyn Transport> = Box::new(stream);
         let mut conn = Connection {
             buffer: Buffer::new(policy.buffer_reclaim_threshold),
             bytes_read: 0,
+            bytes_received: 0,
             timeout: policy.timeout,
             conn: stream,
             idle_timeout: policy.idle_timeout,
@@ -59,7 +69,7 @@ impl Connection {
     }
 
     pub fn close(&mut self) {
-        let _ = self.conn.shutdown(Shutdown::Both);
+        self.conn.shutdown();
     }
 
     pub fn flush(&mut self) -> Result<()> {
@@ -72,6 +82,7 @@ impl Connection {
         self.buffer.resize_buffer(size)?;
         self.conn.read_exact(&mut self.buffer.data_buffer)?;
         self.bytes_read += size;
+        self.bytes_received += size;
         self.buffer.reset_offset()?;
         self.refresh();
         Ok(())
@@ -86,13 +97,13 @@ impl Connection {
     pub fn read(&mut self, buf: &mut [u8]) -> Result<()> {
         self.conn.read_exact(buf)?;
         self.bytes_read += buf.len();
+        self.bytes_received += buf.len();
         self.refresh();
         Ok(())
     }
 
     pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
-        self.conn.set_read_timeout(timeout)?;
-        self.conn.set_write_timeout(timeout)?;
+        self.conn.set_timeout(timeout)?;
         Ok(())
     }
 
@@ -131,4 +142,8 @@ impl Connection {
     pub const fn bytes_read(&self) -> usize {
         self.bytes_read
     }
+
+    pub const fn bytes_received(&self) -> usize {
+        self.bytes_received
+    }
 }
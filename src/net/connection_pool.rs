@@ -34,9 +34,18 @@ struct QueueInternals {
     num_conns: usize,
 }
 
+#[derive(Debug, Default)]
+struct QueueCounters {
+    opened: AtomicUsize,
+    closed: AtomicUsize,
+    failed: AtomicUsize,
+    rejected: AtomicUsize,
+}
+
 #[derive(Debug)]
 struct SharedQueue {
     internals: Mutex<QueueInternals>,
+    counters: QueueCounters,
     capacity: usize,
     host: Host,
     policy: ClientPolicy,
@@ -53,6 +62,7 @@ impl Queue {
         };
         let shared = SharedQueue {
             internals: Mutex::new(internals),
+            counters: QueueCounters::default(),
             capacity,
             host,
             policy,
@@ -68,15 +78,21 @@ impl Queue {
                 if conn.is_idle() {
                     internals.num_conns -= 1;
                     conn.close();
+                    self.0.counters.closed.fetch_add(1, Ordering::Relaxed);
                     continue;
                 }
                 connection = conn;
                 break;
             } else {
                 if internals.num_conns >= self.0.capacity {
+                    self.0.counters.rejected.fetch_add(1, Ordering::Relaxed);
                     bail!(ErrorKind::NoMoreConnections);
                 }
-                let conn = Connection::new(&self.0.host, &self.0.policy)?;
+                let conn = Connection::new(&self.0.host, &self.0.policy).map_err(|err| {
+                    self.0.counters.failed.fetch_add(1, Ordering::Relaxed);
+                    err
+                })?;
+                self.0.counters.opened.fetch_add(1, Ordering::Relaxed);
                 internals.num_conns += 1;
                 connection = conn;
                 break;
@@ -84,6 +100,7 @@ impl Queue {
         }
         connection.set_timeout(timeout).map_err(|err| {
             internals.num_conns -= 1;
+            self.0.counters.closed.fetch_add(1, Ordering::Relaxed);
             err
         })?;
 
@@ -100,6 +117,7 @@ impl Queue {
         } else {
             conn.close();
             internals.num_conns -= 1;
+            self.0.counters.closed.fetch_add(1, Ordering::Relaxed);
         }
     }
 
@@ -109,15 +127,33 @@ impl Queue {
             internals.num_conns -= 1;
         }
         conn.close();
+        self.0.counters.closed.fetch_add(1, Ordering::Relaxed);
     }
 
     pub fn clear(&mut self) {
         let mut internals = self.0.internals.lock();
         for mut conn in internals.connections.drain(..) {
             conn.0.close();
+            self.0.counters.closed.fetch_add(1, Ordering::Relaxed);
         }
         internals.num_conns = 0;
     }
+
+    pub fn stats(&self) -> ConnectionStats {
+        let (open, idle) = {
+            let internals = self.0.internals.lock();
+            (internals.num_conns, internals.connections.len())
+        };
+        let counters = &self.0.counters;
+        ConnectionStats {
+            open,
+            idle,
+            opened: counters.opened.load(Ordering::Relaxed),
+            closed: counters.closed.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+        }
+    }
 }
 
 impl Clone for Queue {
@@ -126,6 +162,28 @@ impl Clone for Queue {
     }
 }
 
+// Connection counters of a pool, aggregated over all of its queues.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConnectionStats {
+    pub open: usize,
+    pub idle: usize,
+    pub opened: usize,
+    pub closed: usize,
+    pub failed: usize,
+    pub rejected: usize,
+}
+
+impl ConnectionStats {
+    fn merge(&mut self, other: &ConnectionStats) {
+        self.open += other.open;
+        self.idle += other.idle;
+        self.opened += other.opened;
+        self.closed += other.closed;
+        self.failed += other.failed;
+        self.rejected += other.rejected;
+    }
+}
+
 #[derive(Debug)]
 pub struct ConnectionPool {
     num_queues: usize,
@@ -184,6 +242,14 @@ impl ConnectionPool {
         }
     }
 
+    pub fn stats(&self) -> ConnectionStats {
+        let mut stats = ConnectionStats::default();
+        for queue in &self.queues {
+            stats.merge(&queue.stats());
+        }
+        stats
+    }
+
     pub fn close(&mut self) {
         for mut queue in self.queues.drain(..) {
             queue.clear();
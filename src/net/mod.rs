@@ -23,3 +23,4 @@ mod connection;
 mod connection_pool;
 pub mod host;
 mod parser;
+pub(crate) mod transport;
@@ -0,0 +1,286 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::convert::TryFrom;
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::operations::cdt::CdtArgument;
+use crate::operations::lists::{CdtListOpType, ListReturnType};
+use crate::operations::maps::CdtMapOpType;
+use crate::operations::{MapReturnType, Operation, OperationData};
+use crate::value::invalid_type;
+use crate::{Record, Value};
+
+/// Result of `Client::operate_typed`, with one entry per operation.
+///
+/// Unlike the record returned by `Client::operate`, which collapses the results of several
+/// operations on the same bin into a single list, `results` holds exactly one entry per
+/// operation, in the order the operations were given.
+#[derive(Debug)]
+pub struct OperateResult {
+    /// The record as returned by `Client::operate`, i.e. with the results of operations on the
+    /// same bin collapsed into a list.
+    pub record: Record,
+
+    /// One result per operation; `results[i]` is the result of `ops[i]`.
+    pub results: Vec<OpResult>,
+}
+
+/// Return type declared by an operation, used to check the typed getters of `OpResult`.
+#[derive(Debug, Clone, Copy)]
+pub enum OpReturnType {
+    /// The operation does not select list or map items, e.g. a scalar operation, a list append or
+    /// a map size. Write operations return `nil`.
+    Default,
+
+    /// List operation selecting items by index, rank or value.
+    List(ListReturnType),
+
+    /// Map operation selecting items by key, index, rank or value.
+    Map(MapReturnType),
+}
+
+impl OpReturnType {
+    /// Determine the declared return type of an operation. All list and map operations selecting
+    /// items take the return type as their first argument.
+    pub(crate) fn of(op: &Operation) -> Self {
+        match op.data {
+            OperationData::CdtListOp(ref cdt_op) => match cdt_op.args.first() {
+                Some(&CdtArgument::Byte(rt))
+                    if (CdtListOpType::GetByIndex as u8
+                        ..=CdtListOpType::RemoveByValueRelRankRange as u8)
+                        .contains(&cdt_op.op) =>
+                {
+                    list_return_type(rt).map_or(OpReturnType::Default, OpReturnType::List)
+                }
+                _ => OpReturnType::Default,
+            },
+            OperationData::CdtMapOp(ref cdt_op) => match cdt_op.args.first() {
+                Some(&CdtArgument::Byte(rt))
+                    if (CdtMapOpType::RemoveByKey as u8
+                        ..=CdtMapOpType::RemoveByValueRelRankRange as u8)
+                        .contains(&cdt_op.op)
+                        || (CdtMapOpType::GetByKey as u8
+                            ..=CdtMapOpType::GetByValueRelRankRange as u8)
+                            .contains(&cdt_op.op) =>
+                {
+                    map_return_type(rt).map_or(OpReturnType::Default, OpReturnType::Map)
+                }
+                _ => OpReturnType::Default,
+            },
+            _ => OpReturnType::Default,
+        }
+    }
+}
+
+const fn list_return_type(rt: u8) -> Option<ListReturnType> {
+    match rt {
+        0 => Some(ListReturnType::None),
+        1 => Some(ListReturnType::Index),
+        2 => Some(ListReturnType::ReverseIndex),
+        3 => Some(ListReturnType::Rank),
+        4 => Some(ListReturnType::ReverseRank),
+        5 => Some(ListReturnType::Count),
+        7 => Some(ListReturnType::Values),
+        _ => None,
+    }
+}
+
+const fn map_return_type(rt: u8) -> Option<MapReturnType> {
+    match rt {
+        0 => Some(MapReturnType::None),
+        1 => Some(MapReturnType::Index),
+        2 => Some(MapReturnType::ReverseIndex),
+        3 => Some(MapReturnType::Rank),
+        4 => Some(MapReturnType::ReverseRank),
+        5 => Some(MapReturnType::Count),
+        6 => Some(MapReturnType::Key),
+        7 => Some(MapReturnType::Value),
+        8 => Some(MapReturnType::KeyValue),
+        _ => None,
+    }
+}
+
+/// Result of a single operation.
+#[derive(Debug, Clone)]
+pub struct OpResult {
+    /// Name of the bin the operation applied to; empty for record level operations like `touch`.
+    pub bin: String,
+
+    /// The raw result value; `nil` for operations that do not return a result.
+    pub value: Value,
+
+    /// The return type declared by the operation.
+    pub return_type: OpReturnType,
+}
+
+impl OpResult {
+    /// Returns the result as an integer, e.g. the item count of a `Count` return type or the
+    /// value of a scalar read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error if the operation declared a return type that never
+    /// yields an integer, or an `InvalidType` error if the value is not an integer.
+    pub fn as_int(&self) -> Result<i64> {
+        match self.return_type {
+            OpReturnType::List(ListReturnType::None)
+            | OpReturnType::Map(MapReturnType::None | MapReturnType::KeyValue) => {
+                self.mismatch("an integer")
+            }
+            _ => i64::try_from(self.value.clone()).map_err(|err| self.with_bin(err)),
+        }
+    }
+
+    /// Returns the result as a list, e.g. the values or indexes selected by a range operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error if the operation declared a return type that never
+    /// yields a list, or an `InvalidType` error if the value is not a list.
+    pub fn as_list(&self) -> Result<Vec<Value>> {
+        match self.return_type {
+            OpReturnType::List(ListReturnType::None | ListReturnType::Count)
+            | OpReturnType::Map(
+                MapReturnType::None | MapReturnType::Count | MapReturnType::KeyValue,
+            ) => self.mismatch("a list"),
+            _ => match self.value {
+                Value::List(ref list) => Ok(list.clone()),
+                ref val => Err(self.with_bin(invalid_type("list", val))),
+            },
+        }
+    }
+
+    /// Returns the result of a map operation with the `KeyValue` return type as a list of
+    /// key/value pairs. The server's order is preserved for range results; for unordered maps,
+    /// the order of the entries is unspecified.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error if the operation declared a return type other than
+    /// `MapReturnType::KeyValue`, or an `InvalidType` error if the value is not a map.
+    pub fn as_map_entries(&self) -> Result<Vec<(Value, Value)>> {
+        match self.return_type {
+            OpReturnType::Default | OpReturnType::Map(MapReturnType::KeyValue) => {}
+            _ => return self.mismatch("map entries"),
+        }
+        match self.value {
+            Value::OrderedMap(ref entries) => Ok(entries.clone()),
+            Value::HashMap(ref map) => {
+                Ok(map.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+            }
+            ref val => Err(self.with_bin(invalid_type("map", val))),
+        }
+    }
+
+    fn with_bin(&self, err: Error) -> Error {
+        match err {
+            Error(ErrorKind::InvalidType(None, expected, actual), _) => {
+                ErrorKind::InvalidType(Some(self.bin.clone()), expected, actual).into()
+            }
+            err => err,
+        }
+    }
+
+    fn mismatch<T>(&self, expected: &str) -> Result<T> {
+        bail!(ErrorKind::InvalidArgument(format!(
+            "Operation on bin '{}' with return type {:?} does not return {}",
+            self.bin, self.return_type, expected
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OpResult, OpReturnType};
+    use crate::errors::ErrorKind;
+    use crate::operations::lists::{self, ListReturnType};
+    use crate::operations::{self, maps, MapReturnType};
+    use crate::Value;
+
+    fn result(value: Value, return_type: OpReturnType) -> OpResult {
+        OpResult {
+            bin: "bin".to_string(),
+            value,
+            return_type,
+        }
+    }
+
+    #[test]
+    fn return_types() {
+        let key = as_val!("k");
+        let ops = [
+            operations::get_bin("bin"),
+            lists::size("bin"),
+            lists::get_by_index("bin", 0, ListReturnType::Values),
+            lists::remove_by_rank("bin", 0, ListReturnType::Count),
+            maps::size("bin"),
+            maps::get_by_key("bin", &key, MapReturnType::KeyValue),
+            maps::remove_by_index_range("bin", 0, 2, MapReturnType::Key),
+        ];
+        let types: Vec<String> = ops
+            .iter()
+            .map(|op| format!("{:?}", OpReturnType::of(op)))
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "Default",
+                "Default",
+                "List(Values)",
+                "List(Count)",
+                "Default",
+                "Map(KeyValue)",
+                "Map(Key)"
+            ]
+        );
+    }
+
+    #[test]
+    fn getters() {
+        let count = result(as_val!(3), OpReturnType::List(ListReturnType::Count));
+        assert_eq!(count.as_int().unwrap(), 3);
+        assert!(count.as_list().is_err());
+
+        let values = result(as_list!(1, 2), OpReturnType::List(ListReturnType::Values));
+        assert_eq!(values.as_list().unwrap(), vec![as_val!(1), as_val!(2)]);
+        match values.as_int() {
+            Err(err) => match err.0 {
+                ErrorKind::InvalidType(Some(bin), ..) => assert_eq!(bin, "bin"),
+                kind => panic!("unexpected error: {:?}", kind),
+            },
+            Ok(_) => panic!("list converted to int"),
+        }
+        assert!(values.as_map_entries().is_err());
+
+        let entries = vec![(as_val!("a"), as_val!(1)), (as_val!("b"), as_val!(2))];
+        let kv = result(
+            Value::OrderedMap(entries.clone()),
+            OpReturnType::Map(MapReturnType::KeyValue),
+        );
+        assert_eq!(kv.as_map_entries().unwrap(), entries);
+        match kv.as_int() {
+            Err(err) => match err.0 {
+                ErrorKind::InvalidArgument(_) => (),
+                kind => panic!("unexpected error: {:?}", kind),
+            },
+            Ok(_) => panic!("key/value result converted to int"),
+        }
+
+        let scalar = result(as_val!(42), OpReturnType::Default);
+        assert_eq!(scalar.as_int().unwrap(), 42);
+        assert!(scalar.as_map_entries().is_err());
+    }
+}
@@ -148,7 +148,7 @@ pub fn resize<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Resize as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args,
     };
     Operation {
@@ -178,7 +178,7 @@ pub fn insert<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Insert as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(byte_offset),
             CdtArgument::Value(value),
@@ -213,7 +213,7 @@ pub fn remove<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Remove as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(byte_offset),
             CdtArgument::Int(byte_size),
@@ -250,7 +250,7 @@ pub fn set<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Set as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -288,7 +288,7 @@ pub fn or<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Or as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -326,7 +326,7 @@ pub fn xor<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Xor as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -364,7 +364,7 @@ pub fn and<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::And as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -400,7 +400,7 @@ pub fn not<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Not as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -437,7 +437,7 @@ pub fn lshift<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::LShift as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -474,7 +474,7 @@ pub fn rshift<'a>(
     policy: &'a BitPolicy,
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         op: CdtBitwiseOpType::RShift as u8,
         args: vec![
             CdtArgument::Int(bit_offset),
@@ -523,7 +523,7 @@ pub fn add<'a>(
 
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Add as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -572,7 +572,7 @@ pub fn subtract<'a>(
 
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Subtract as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -611,7 +611,7 @@ pub fn set_int<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::SetInt as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -641,7 +641,7 @@ pub fn set_int<'a>(
 pub fn get(bin: &str, bit_offset: i64, bit_size: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Get as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![CdtArgument::Int(bit_offset), CdtArgument::Int(bit_size)],
     };
 
@@ -666,7 +666,7 @@ pub fn get(bin: &str, bit_offset: i64, bit_size: i64) -> Operation {
 pub fn count(bin: &str, bit_offset: i64, bit_size: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::Count as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![CdtArgument::Int(bit_offset), CdtArgument::Int(bit_size)],
     };
 
@@ -693,7 +693,7 @@ pub fn count(bin: &str, bit_offset: i64, bit_size: i64) -> Operation {
 pub fn lscan(bin: &str, bit_offset: i64, bit_size: i64, value: bool) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::LScan as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -724,7 +724,7 @@ pub fn lscan(bin: &str, bit_offset: i64, bit_size: i64, value: bool) -> Operatio
 pub fn rscan(bin: &str, bit_offset: i64, bit_size: i64, value: bool) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::RScan as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args: vec![
             CdtArgument::Int(bit_offset),
             CdtArgument::Int(bit_size),
@@ -759,7 +759,7 @@ pub fn get_int(bin: &str, bit_offset: i64, bit_size: i64, signed: bool) -> Opera
     }
     let cdt_op = CdtOperation {
         op: CdtBitwiseOpType::GetInt as u8,
-        encoder: Box::new(pack_cdt_bit_op),
+        encoder: pack_cdt_bit_op,
         args,
     };
 
@@ -0,0 +1,254 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Builder for a validated list of operations.
+
+use std::slice;
+
+use crate::errors::{ErrorKind, Result};
+use crate::operations::{Operation, OperationBin, OperationBuf, OperationType};
+
+/// Owned list of operations for the client's `operate()` method, which rejects combinations of
+/// operations the server would refuse or answer ambiguously:
+///
+/// * a `put` together with any other write (`put`, `add`, `append`, CDT, bit, HLL or expression
+///   write) to the same bin;
+/// * `get()`, which reads all bins, together with reads of specific bins, incl. CDT, bit, HLL
+///   and expression reads.
+///
+/// # Examples
+///
+/// ```rust
+/// use aerospike::operations::{self, Operations};
+/// use aerospike::as_bin;
+///
+/// let mut ops = Operations::new();
+/// ops.add(operations::put(&as_bin!("a", 1))).unwrap();
+/// ops.add(operations::get_bin("a")).unwrap();
+/// assert!(ops.add(operations::put(&as_bin!("a", 2))).is_err());
+/// assert!(ops.add(operations::get()).is_err());
+/// assert_eq!(ops.len(), 2);
+///
+/// // client.operate(&WritePolicy::default(), &key, &ops.operations())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Operations {
+    ops: Vec<OperationBuf>,
+}
+
+impl Operations {
+    /// Create an empty list of operations.
+    pub fn new() -> Self {
+        Operations::default()
+    }
+
+    /// Append an operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error, leaving the list unchanged, if the operation cannot be
+    /// combined with the operations added before.
+    pub fn add<O: Into<OperationBuf>>(&mut self, op: O) -> Result<()> {
+        let op = op.into();
+        self.validate(&op.as_operation())?;
+        self.ops.push(op);
+        Ok(())
+    }
+
+    fn validate(&self, new: &Operation) -> Result<()> {
+        for prev in &self.ops {
+            let prev = prev.as_operation();
+            match (&new.bin, &prev.bin) {
+                (&OperationBin::Name(bin), &OperationBin::Name(prev_bin))
+                    if bin == prev_bin
+                        && is_write(new.op)
+                        && is_write(prev.op)
+                        && (matches!(new.op, OperationType::Write)
+                            || matches!(prev.op, OperationType::Write)) =>
+                {
+                    bail!(ErrorKind::InvalidArgument(format!(
+                        "Multiple writes to bin '{}'",
+                        bin
+                    )))
+                }
+                (&OperationBin::All, &OperationBin::Name(_))
+                | (&OperationBin::Name(_), &OperationBin::All)
+                    if is_read(new.op) && is_read(prev.op) =>
+                {
+                    bail!(ErrorKind::InvalidArgument(
+                        "Reading all bins cannot be combined with reads of specific bins"
+                            .to_string()
+                    ))
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    /// Borrow the operations, e.g. to pass them to the client's `operate()` method.
+    pub fn operations(&self) -> Vec<Operation> {
+        self.ops.iter().map(OperationBuf::as_operation).collect()
+    }
+
+    /// Returns an iterator over the operations.
+    pub fn iter(&self) -> slice::Iter<OperationBuf> {
+        self.ops.iter()
+    }
+
+    /// Returns the number of operations.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if no operations have been added.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Operations {
+    type Item = &'a OperationBuf;
+    type IntoIter = slice::Iter<'a, OperationBuf>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ops.iter()
+    }
+}
+
+const fn is_read(op: OperationType) -> bool {
+    matches!(
+        op,
+        OperationType::Read
+            | OperationType::CdtRead
+            | OperationType::BitRead
+            | OperationType::HllRead
+            | OperationType::ExpRead
+    )
+}
+
+const fn is_write(op: OperationType) -> bool {
+    matches!(
+        op,
+        OperationType::Write
+            | OperationType::CdtWrite
+            | OperationType::Incr
+            | OperationType::Append
+            | OperationType::Prepend
+            | OperationType::BitWrite
+            | OperationType::HllWrite
+            | OperationType::ExpWrite
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Operations;
+    use crate::errors::ErrorKind;
+    use crate::expressions::{int_bin, int_val, num_add};
+    use crate::operations;
+    use crate::operations::bitwise::{self, BitPolicy};
+    use crate::operations::exp::{self, ExpReadFlags, ExpWriteFlags};
+    use crate::operations::hll::{self, HLLPolicy};
+    use crate::operations::lists::{self, ListPolicy, ListReturnType};
+    use crate::operations::{Operation, OperationBuf};
+    use crate::Value;
+
+    #[test]
+    fn combinations() {
+        let mut ops = Operations::new();
+        ops.add(operations::put(&as_bin!("a", 1))).unwrap();
+        ops.add(operations::put(&as_bin!("b", 1))).unwrap();
+        ops.add(operations::add(&as_bin!("c", 1))).unwrap();
+        ops.add(operations::add(&as_bin!("c", 1))).unwrap();
+        ops.add(lists::append(&ListPolicy::default(), "l", &as_val!(1)))
+            .unwrap();
+        ops.add(lists::append(&ListPolicy::default(), "l", &as_val!(2)))
+            .unwrap();
+        ops.add(operations::get_bin("a")).unwrap();
+        ops.add(operations::get_bin("a")).unwrap();
+        ops.add(operations::get_header()).unwrap();
+
+        match ops.add(operations::put(&as_bin!("b", 2))) {
+            Err(err) => match err.0 {
+                ErrorKind::InvalidArgument(msg) => assert_eq!(msg, "Multiple writes to bin 'b'"),
+                kind => panic!("unexpected error: {:?}", kind),
+            },
+            Ok(()) => panic!("duplicate write accepted"),
+        }
+        assert!(ops.add(operations::get()).is_err());
+        assert_eq!(ops.len(), 9);
+        assert_eq!(ops.operations().len(), 9);
+
+        let mut ops = Operations::new();
+        ops.add(operations::get()).unwrap();
+        ops.add(operations::touch()).unwrap();
+        assert!(ops.add(operations::get_bin("a")).is_err());
+        assert_eq!(ops.iter().count(), 2);
+    }
+
+    fn rejects(first: OperationBuf, second: OperationBuf) {
+        let mut ops = Operations::new();
+        ops.add(first).unwrap();
+        assert!(ops.add(second).is_err());
+        assert_eq!(ops.len(), 1);
+    }
+
+    #[test]
+    fn read_write_classes() {
+        let exp = num_add(vec![int_bin("a".to_string()), int_val(1)]);
+        let values = [Value::from(1)];
+        let bit_policy = BitPolicy::default();
+        let hll_policy = HLLPolicy::default();
+
+        let reads: Vec<Operation> = vec![
+            operations::get_bin("a"),
+            lists::get_by_index("a", 0, ListReturnType::Values),
+            bitwise::count("a", 0, 8),
+            hll::get_count("a"),
+            exp::read_exp("a", &exp, ExpReadFlags::Default),
+        ];
+        for read in reads {
+            rejects(operations::get().into(), OperationBuf::from(&read));
+            rejects(OperationBuf::from(&read), operations::get().into());
+        }
+
+        let (int, string) = (as_bin!("a", 1), as_bin!("a", "x"));
+        let writes: Vec<Operation> = vec![
+            operations::add(&int),
+            operations::append(&string),
+            operations::prepend(&string),
+            lists::append(&ListPolicy::default(), "a", &values[0]),
+            bitwise::remove("a", 0, 1, &bit_policy),
+            hll::add(&hll_policy, "a", &values),
+            exp::write_exp("a", &exp, ExpWriteFlags::Default),
+        ];
+        let put = as_bin!("a", 2);
+        for write in writes {
+            rejects(operations::put(&put).into(), OperationBuf::from(&write));
+            rejects(OperationBuf::from(&write), operations::put(&put).into());
+        }
+
+        // reads and writes of other bins, and writes other than `put`, still combine
+        let mut ops = Operations::new();
+        ops.add(operations::get()).unwrap();
+        ops.add(operations::put(&as_bin!("a", 1))).unwrap();
+        ops.add(lists::append(&ListPolicy::default(), "b", &values[0]))
+            .unwrap();
+        ops.add(hll::add(&hll_policy, "b", &values)).unwrap();
+        ops.add(operations::touch()).unwrap();
+        assert_eq!(ops.len(), 5);
+    }
+}
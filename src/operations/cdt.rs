@@ -32,7 +32,7 @@ pub enum CdtArgument<'a> {
 }
 
 pub type OperationEncoder =
-    Box<dyn Fn(&mut Option<&mut Buffer>, &CdtOperation, &[CdtContext]) -> Result<usize>>;
+    fn(&mut Option<&mut Buffer>, &CdtOperation, &[CdtContext]) -> Result<usize>;
 
 #[doc(hidden)]
 pub struct CdtOperation<'a> {
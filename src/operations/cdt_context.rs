@@ -13,10 +13,14 @@
 // limitations under the License.
 
 //! Operation Context for nested Operations
+use std::ops::Deref;
+use std::str::FromStr;
+
+use crate::errors::{Error, ErrorKind, Result};
 use crate::operations::lists::{list_order_flag, ListOrderType};
+use crate::operations::maps::map_order_flag;
 use crate::operations::MapOrder;
 use crate::Value;
-use crate::operations::maps::map_order_flag;
 
 #[doc(hidden)]
 // Empty Context for scalar operations
@@ -147,3 +151,339 @@ pub const fn ctx_map_value(key: Value) -> CdtContext {
         value: key,
     }
 }
+
+/// Path to a nested list or map element: the sequence of contexts leading from the bin to the
+/// element. A path is built by chaining the methods named after the `ctx_*` constructors, or
+/// parsed from a string with `CdtPath::parse`.
+///
+/// `CdtPath` dereferences to `[CdtContext]`, so a `&CdtPath` is accepted by
+/// `Operation::set_context` and by every expression helper taking a context.
+///
+/// # Examples
+///
+/// ```rust
+/// use aerospike::operations::cdt_context::CdtPath;
+/// use aerospike::operations::lists::{self, ListOrderType};
+/// use aerospike::operations::MapOrder;
+///
+/// // doc.users[3].tags
+/// let path = CdtPath::parse("users[3].tags").unwrap();
+/// let op = lists::size("doc").set_context(&path);
+///
+/// // Create the user map and the tag list if they do not exist yet.
+/// let path = CdtPath::new()
+///     .map_key("users")
+///     .list_index_create(3, ListOrderType::Unordered, false)
+///     .map_key_create("tags", MapOrder::Unordered);
+/// assert_eq!(path.len(), 3);
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct CdtPath {
+    ctx: Vec<CdtContext>,
+}
+
+impl CdtPath {
+    /// Create an empty path, addressing the bin itself.
+    pub fn new() -> Self {
+        CdtPath::default()
+    }
+
+    /// Parse a path of map keys and list indexes, e.g. `users[3].tags`. A map key is preceded by
+    /// a dot, except at the start of the path. Keys consisting of digits are integer keys; other
+    /// keys may be quoted with `'` or `"`, e.g. `users.'first name'` or `counts.'7'`. List indexes
+    /// are integers in brackets and may be negative, e.g. `scores[-1]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidArgument` error with the offset of the first invalid character.
+    pub fn parse(path: &str) -> Result<Self> {
+        let mut parser = PathParser { path, pos: 0 };
+        let mut result = CdtPath::new();
+        if path.is_empty() {
+            return Ok(result);
+        }
+        if !path.starts_with('[') {
+            result = result.map_key(parser.key()?);
+        }
+        while let Some(c) = parser.peek() {
+            result = match c {
+                '[' => result.list_index(parser.index()?),
+                '.' => {
+                    parser.pos += 1;
+                    if parser.peek() == Some('[') {
+                        result.list_index(parser.index()?)
+                    } else {
+                        result.map_key(parser.key()?)
+                    }
+                }
+                _ => return Err(parser.error("expected '.' or '['")),
+            };
+        }
+        Ok(result)
+    }
+
+    /// Append a lookup of the list item at the given index, see `ctx_list_index`.
+    pub fn list_index(mut self, index: i64) -> Self {
+        self.ctx.push(ctx_list_index(index));
+        self
+    }
+
+    /// Append a lookup of the list item at the given index, creating a list with the given order
+    /// and pad flag if it does not exist, see `ctx_list_index_create`.
+    pub fn list_index_create(mut self, index: i64, order: ListOrderType, pad: bool) -> Self {
+        self.ctx.push(ctx_list_index_create(index, order, pad));
+        self
+    }
+
+    /// Append a lookup of the list item with the given rank, see `ctx_list_rank`.
+    pub fn list_rank(mut self, rank: i64) -> Self {
+        self.ctx.push(ctx_list_rank(rank));
+        self
+    }
+
+    /// Append a lookup of the list item with the given value, see `ctx_list_value`.
+    pub fn list_value<V: Into<Value>>(mut self, value: V) -> Self {
+        self.ctx.push(ctx_list_value(value.into()));
+        self
+    }
+
+    /// Append a lookup of the map item at the given index, see `ctx_map_index`.
+    pub fn map_index(mut self, index: i64) -> Self {
+        self.ctx.push(ctx_map_index(Value::from(index)));
+        self
+    }
+
+    /// Append a lookup of the map item with the given rank, see `ctx_map_rank`.
+    pub fn map_rank(mut self, rank: i64) -> Self {
+        self.ctx.push(ctx_map_rank(rank));
+        self
+    }
+
+    /// Append a lookup of the map item with the given key, see `ctx_map_key`.
+    pub fn map_key<K: Into<Value>>(mut self, key: K) -> Self {
+        self.ctx.push(ctx_map_key(key.into()));
+        self
+    }
+
+    /// Append a lookup of the map item with the given key, creating a map with the given order if
+    /// it does not exist, see `ctx_map_key_create`.
+    pub fn map_key_create<K: Into<Value>>(mut self, key: K, order: MapOrder) -> Self {
+        self.ctx.push(ctx_map_key_create(key.into(), order));
+        self
+    }
+
+    /// Append a lookup of the map item with the given value, see `ctx_map_value`.
+    pub fn map_value<V: Into<Value>>(mut self, value: V) -> Self {
+        self.ctx.push(ctx_map_value(value.into()));
+        self
+    }
+
+    /// Returns the contexts of the path.
+    pub fn into_vec(self) -> Vec<CdtContext> {
+        self.ctx
+    }
+}
+
+impl Deref for CdtPath {
+    type Target = [CdtContext];
+
+    fn deref(&self) -> &[CdtContext] {
+        &self.ctx
+    }
+}
+
+impl AsRef<[CdtContext]> for CdtPath {
+    fn as_ref(&self) -> &[CdtContext] {
+        &self.ctx
+    }
+}
+
+impl From<Vec<CdtContext>> for CdtPath {
+    fn from(ctx: Vec<CdtContext>) -> Self {
+        CdtPath { ctx }
+    }
+}
+
+impl From<CdtPath> for Vec<CdtContext> {
+    fn from(path: CdtPath) -> Self {
+        path.ctx
+    }
+}
+
+impl FromStr for CdtPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self> {
+        CdtPath::parse(path)
+    }
+}
+
+struct PathParser<'a> {
+    path: &'a str,
+    pos: usize,
+}
+
+impl<'a> PathParser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.path[self.pos..].chars().next()
+    }
+
+    fn error(&self, msg: &str) -> Error {
+        ErrorKind::InvalidArgument(format!(
+            "Invalid CDT path '{}' at offset {}: {}",
+            self.path, self.pos, msg
+        ))
+        .into()
+    }
+
+    // Parses a map key, quoted or up to the next '.' or '['.
+    fn key(&mut self) -> Result<Value> {
+        let rest = &self.path[self.pos..];
+        match self.peek() {
+            Some(quote) if quote == '\'' || quote == '"' => {
+                let mut key = String::new();
+                let mut chars = rest.char_indices().skip(1);
+                while let Some((i, c)) = chars.next() {
+                    if c == quote {
+                        self.pos += i + 1;
+                        return Ok(Value::from(key));
+                    }
+                    if c == '\\' {
+                        match chars.next() {
+                            Some((_, c)) => key.push(c),
+                            None => break,
+                        }
+                    } else {
+                        key.push(c);
+                    }
+                }
+                Err(self.error("unterminated quoted key"))
+            }
+            _ => {
+                let len = rest
+                    .find(|c| matches!(c, '.' | '[' | ']' | '\'' | '"'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(self.error("expected map key"));
+                }
+                let key = &rest[..len];
+                self.pos += len;
+                if key.bytes().all(|b| b.is_ascii_digit()) {
+                    if let Ok(key) = key.parse::<i64>() {
+                        return Ok(Value::from(key));
+                    }
+                }
+                Ok(Value::from(key))
+            }
+        }
+    }
+
+    // Parses a list index in brackets.
+    fn index(&mut self) -> Result<i64> {
+        let rest = &self.path[self.pos + 1..];
+        let index = rest
+            .find(']')
+            .and_then(|end| rest[..end].parse::<i64>().ok().map(|index| (index, end)));
+        match index {
+            Some((index, end)) => {
+                self.pos += end + 2;
+                Ok(index)
+            }
+            None => Err(self.error("expected list index followed by ']'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ctx_list_index, ctx_map_key, CdtContext, CdtPath};
+    use crate::errors::ErrorKind;
+    use crate::expressions::{self as exp, lists as exp_lists};
+    use crate::operations::lists::{self, ListOrderType, ListReturnType};
+    use crate::operations::MapOrder;
+    use crate::Value;
+
+    fn contexts(ctx: &[CdtContext]) -> Vec<(u8, u8, Value)> {
+        ctx.iter()
+            .map(|c| (c.id, c.flags, c.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let path = CdtPath::parse("users[3].tags").unwrap();
+        let expected = CdtPath::new()
+            .map_key("users")
+            .list_index(3)
+            .map_key("tags");
+        assert_eq!(contexts(&path), contexts(&expected));
+
+        let path: CdtPath = "[-1].'first name'.7.\"8\".[0]".parse().unwrap();
+        let expected = CdtPath::new()
+            .list_index(-1)
+            .map_key("first name")
+            .map_key(7)
+            .map_key("8")
+            .list_index(0);
+        assert_eq!(contexts(&path), contexts(&expected));
+
+        assert!(CdtPath::parse("").unwrap().is_empty());
+        assert_eq!(
+            contexts(&CdtPath::parse("a.'it\\'s'").unwrap()),
+            contexts(&CdtPath::new().map_key("a").map_key("it's"))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for (path, pos) in &[
+            ("users[x]", 5),
+            ("users[3", 5),
+            ("users..tags", 6),
+            ("users]", 5),
+            ("users.'tags", 6),
+            ("[1]x", 3),
+        ] {
+            match CdtPath::parse(path) {
+                Err(err) => match err.0 {
+                    ErrorKind::InvalidArgument(msg) => {
+                        assert!(msg.contains(&format!("at offset {}:", pos)), "{}", msg);
+                    }
+                    kind => panic!("unexpected error: {:?}", kind),
+                },
+                Ok(_) => panic!("invalid path {} accepted", path),
+            }
+        }
+    }
+
+    #[test]
+    fn create() {
+        let path = CdtPath::new()
+            .map_key_create("users", MapOrder::KeyOrdered)
+            .list_index_create(0, ListOrderType::Unordered, true);
+        assert_eq!(
+            contexts(&path),
+            vec![
+                (0x22, 0x80, Value::from("users")),
+                (0x10, 0x80, Value::from(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn accepted_as_context() {
+        let path = CdtPath::parse("users[3].tags").unwrap();
+        let op = lists::get_by_index("doc", 0, ListReturnType::Values).set_context(&path);
+        assert_eq!(op.ctx.len(), 3);
+
+        let manual = [
+            ctx_map_key(Value::from("users")),
+            ctx_list_index(3),
+            ctx_map_key(Value::from("tags")),
+        ];
+        let filter = exp_lists::size(exp::list_bin("doc".to_string()), &path);
+        let expected = exp_lists::size(exp::list_bin("doc".to_string()), &manual);
+        assert_eq!(filter.to_bytes().unwrap(), expected.to_bytes().unwrap());
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Document operations on JSON-like documents stored as nested map and list bins.
+//!
+//! Elements are addressed by JSONPath-style paths starting with `$.` and the bin name, followed
+//! by map keys and list indexes in the syntax of `CdtPath::parse`, e.g. `$.doc.users[3].name`.
+//! Each function translates the path into the equivalent map or list operation with the
+//! `CdtContext` leading to the element, and returns it as an `OperationBuf`.
+//!
+//! Write operations create missing maps along the path, so `put("$.doc.a.b", v)` works even if
+//! `doc.a` does not exist yet. Missing lists are not created.
+//!
+//! With the `serde_json` feature, `serde_json::Value` documents convert to and from `Value`.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aerospike::operations::document;
+//! use aerospike::as_val;
+//!
+//! let ops = vec![
+//!     document::put("$.doc.user.name", &as_val!("Alice")).unwrap(),
+//!     document::append("$.doc.user.tags", &as_val!("admin")).unwrap(),
+//!     document::get("$.doc.user.tags[0]").unwrap(),
+//!     document::delete("$.doc.user.legacy").unwrap(),
+//! ];
+//! let ops: Vec<_> = ops.iter().map(|op| op.as_operation()).collect();
+//! // client.operate(&WritePolicy::default(), &key, &ops)
+//! ```
+
+use crate::errors::{ErrorKind, Result};
+use crate::operations::cdt_context::{
+    ctx_list_index, ctx_map_key, ctx_map_key_create, CdtContext, CdtPath, CtxType,
+};
+use crate::operations::lists::{self, ListPolicy, ListReturnType};
+use crate::operations::maps::{self, MapOrder, MapPolicy, MapReturnType};
+use crate::operations::{self as ops, OperationBuf};
+use crate::{Bin, Value};
+
+/// Element addressed by the last step of a path.
+enum Target {
+    Bin,
+    Key(Value),
+    Index(i64),
+}
+
+/// A parsed document path: the bin, the context leading to the parent of the target element,
+/// and the target element itself.
+struct DocPath {
+    bin: String,
+    ctx: Vec<CdtContext>,
+    target: Target,
+}
+
+impl DocPath {
+    fn parse(path: &str) -> Result<Self> {
+        if !path.starts_with("$.") {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Invalid document path '{}': expected '$.' followed by the bin name",
+                path
+            )));
+        }
+        let mut ctx = CdtPath::parse(&path[2..])?.into_vec();
+        let bin = match ctx.first() {
+            Some(&CdtContext {
+                id,
+                value: Value::String(ref bin),
+                ..
+            }) if id == CtxType::MapKey as u8 => bin.clone(),
+            _ => bail!(ErrorKind::InvalidArgument(format!(
+                "Invalid document path '{}': expected bin name after '$.'",
+                path
+            ))),
+        };
+        crate::bin::validate_name(&bin)?;
+        ctx.remove(0);
+        let target = match ctx.pop() {
+            None => Target::Bin,
+            Some(last) => match last.value {
+                Value::Int(index) if last.id == CtxType::ListIndex as u8 => Target::Index(index),
+                key => Target::Key(key),
+            },
+        };
+        Ok(DocPath { bin, ctx, target })
+    }
+
+    // Context for write operations: create the maps along the path that are followed by a map
+    // key, if they do not exist.
+    fn write_ctx(&self, target_is_map: bool) -> Vec<CdtContext> {
+        let mut ctx = self.ctx.clone();
+        for i in 0..ctx.len() {
+            let next_is_key = ctx
+                .get(i + 1)
+                .map_or(target_is_map, |next| next.id == CtxType::MapKey as u8);
+            if ctx[i].id == CtxType::MapKey as u8 && next_is_key {
+                ctx[i] = ctx_map_key_create(ctx[i].value.clone(), MapOrder::Unordered);
+            }
+        }
+        ctx
+    }
+}
+
+/// Create an operation reading the element at the given path; the whole bin for `$.bin`.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgument` error if the path is invalid.
+pub fn get(path: &str) -> Result<OperationBuf> {
+    let path = DocPath::parse(path)?;
+    let op = match path.target {
+        Target::Bin => ops::get_bin(&path.bin).into(),
+        Target::Key(ref key) => maps::get_by_key(&path.bin, key, MapReturnType::Value)
+            .set_context(&path.ctx)
+            .into(),
+        Target::Index(index) => lists::get_by_index(&path.bin, index, ListReturnType::Values)
+            .set_context(&path.ctx)
+            .into(),
+    };
+    Ok(op)
+}
+
+/// Create an operation writing the value at the given path: a map put for a map key, a list set
+/// for a list index, or a bin write for `$.bin`.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgument` error if the path is invalid or the value is `nil`; use `delete`
+/// to remove an element.
+pub fn put(path: &str, value: &Value) -> Result<OperationBuf> {
+    if value.is_nil() {
+        bail!(ErrorKind::InvalidArgument(format!(
+            "Cannot put nil at '{}', use document::delete",
+            path
+        )));
+    }
+    let path = DocPath::parse(path)?;
+    let op = match path.target {
+        Target::Bin => ops::put(&Bin::new(path.bin.as_str(), value.clone())).into(),
+        Target::Key(ref key) => {
+            let ctx = path.write_ctx(true);
+            maps::put(&MapPolicy::default(), &path.bin, key, value)
+                .set_context(&ctx)
+                .into()
+        }
+        Target::Index(index) => {
+            let ctx = path.write_ctx(false);
+            lists::set(&path.bin, index, value).set_context(&ctx).into()
+        }
+    };
+    Ok(op)
+}
+
+/// Create an operation appending the value to the list at the given path.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgument` error if the path is invalid.
+pub fn append(path: &str, value: &Value) -> Result<OperationBuf> {
+    let path = DocPath::parse(path)?;
+    let mut ctx = path.write_ctx(matches!(path.target, Target::Key(_)));
+    match path.target {
+        Target::Bin => (),
+        Target::Key(key) => ctx.push(ctx_map_key(key)),
+        Target::Index(index) => ctx.push(ctx_list_index(index)),
+    }
+    Ok(lists::append(&ListPolicy::default(), &path.bin, value)
+        .set_context(&ctx)
+        .into())
+}
+
+/// Create an operation removing the element at the given path: a map key, a list item or, for
+/// `$.bin`, the whole bin.
+///
+/// # Errors
+///
+/// Returns an `InvalidArgument` error if the path is invalid.
+pub fn delete(path: &str) -> Result<OperationBuf> {
+    let path = DocPath::parse(path)?;
+    let op = match path.target {
+        Target::Bin => ops::put(&Bin::new(path.bin.as_str(), Value::Nil)).into(),
+        Target::Key(ref key) => maps::remove_by_key(&path.bin, key, MapReturnType::None)
+            .set_context(&path.ctx)
+            .into(),
+        Target::Index(index) => lists::remove(&path.bin, index)
+            .set_context(&path.ctx)
+            .into(),
+    };
+    Ok(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{append, delete, get, put};
+    use crate::commands::buffer::Buffer;
+    use crate::errors::ErrorKind;
+    use crate::operations::cdt_context::{
+        ctx_list_index, ctx_map_key, ctx_map_key_create, CdtContext,
+    };
+    use crate::operations::lists::{self, ListPolicy, ListReturnType};
+    use crate::operations::maps::{self, MapOrder, MapPolicy, MapReturnType};
+    use crate::operations::{self, Operation, OperationBuf};
+    use crate::{Bin, Value};
+
+    fn encode(op: &Operation) -> Vec<u8> {
+        let size = op.estimate_size().unwrap() + 8;
+        let mut buf = Buffer::new(size);
+        buf.resize_buffer(size).unwrap();
+        let written = op.write_to(&mut buf).unwrap();
+        buf.data_buffer[..written].to_vec()
+    }
+
+    fn assert_op(op: &OperationBuf, expected: &Operation) {
+        assert_eq!(encode(&op.as_operation()), encode(expected));
+    }
+
+    fn key(key: &str) -> CdtContext {
+        ctx_map_key(Value::from(key))
+    }
+
+    #[test]
+    fn reads() {
+        let b = Value::from("b");
+        let ctx = [key("a"), key("b")];
+        assert_op(
+            &get("$.doc.a.b[2]").unwrap(),
+            &lists::get_by_index("doc", 2, ListReturnType::Values).set_context(&ctx),
+        );
+        assert_op(
+            &get("$.doc.a.b").unwrap(),
+            &maps::get_by_key("doc", &b, MapReturnType::Value).set_context(&ctx[..1]),
+        );
+        assert_op(&get("$.doc").unwrap(), &operations::get_bin("doc"));
+    }
+
+    #[test]
+    fn writes() {
+        let val = Value::from(1);
+        let c = Value::from("c");
+        let policy = MapPolicy::default();
+        let created = [
+            ctx_map_key_create(Value::from("a"), MapOrder::Unordered),
+            ctx_map_key_create(Value::from("b"), MapOrder::Unordered),
+        ];
+        assert_op(
+            &put("$.doc.a.b.c", &val).unwrap(),
+            &maps::put(&policy, "doc", &c, &val).set_context(&created),
+        );
+
+        let ctx = [created[0].clone(), key("b")];
+        assert_op(
+            &put("$.doc.a.b[0]", &val).unwrap(),
+            &lists::set("doc", 0, &val).set_context(&ctx),
+        );
+        assert_op(
+            &append("$.doc.a.b", &val).unwrap(),
+            &lists::append(&ListPolicy::default(), "doc", &val).set_context(&ctx),
+        );
+        let ctx = [key("list"), ctx_list_index(-1)];
+        assert_op(
+            &append("$.doc.list[-1]", &val).unwrap(),
+            &lists::append(&ListPolicy::default(), "doc", &val).set_context(&ctx),
+        );
+        assert_op(
+            &put("$.doc", &val).unwrap(),
+            &operations::put(&Bin::new("doc", val.clone())),
+        );
+    }
+
+    #[test]
+    fn deletes() {
+        let x = Value::from("x");
+        let ctx = [key("a")];
+        assert_op(
+            &delete("$.doc.a.x").unwrap(),
+            &maps::remove_by_key("doc", &x, MapReturnType::None).set_context(&ctx),
+        );
+        assert_op(
+            &delete("$.doc.a[3]").unwrap(),
+            &lists::remove("doc", 3).set_context(&ctx),
+        );
+        assert_op(
+            &delete("$.doc").unwrap(),
+            &operations::put(&Bin::new("doc", Value::Nil)),
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for path in &["doc.a", "$.", "$.[0]", "$.7.a", "$.doc.a[x]"] {
+            match get(path) {
+                Err(err) => match err.0 {
+                    ErrorKind::InvalidArgument(_) => (),
+                    kind => panic!("unexpected error: {:?}", kind),
+                },
+                Ok(_) => panic!("invalid path {} accepted", path),
+            }
+        }
+        assert!(put("$.doc.a", &Value::Nil).is_err());
+    }
+}
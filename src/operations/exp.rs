@@ -24,6 +24,7 @@ use crate::operations::{Operation, OperationBin, OperationData, OperationType};
 use crate::ParticleType;
 
 /// Expression write Flags
+#[derive(Debug, Clone, Copy)]
 pub enum ExpWriteFlags {
     /// Default. Allow create or update.
     Default = 0,
@@ -45,7 +46,7 @@ pub enum ExpWriteFlags {
 }
 
 #[doc(hidden)]
-pub type ExpressionEncoder = Box<dyn Fn(&mut Option<&mut Buffer>, &ExpOperation) -> Result<usize>>;
+pub type ExpressionEncoder = fn(&mut Option<&mut Buffer>, &ExpOperation) -> Result<usize>;
 
 #[doc(hidden)]
 pub struct ExpOperation<'a> {
@@ -72,6 +73,7 @@ impl<'a> ExpOperation<'a> {
 }
 
 /// Expression read Flags
+#[derive(Debug, Clone, Copy)]
 pub enum ExpReadFlags {
     /// Default
     Default = 0,
@@ -86,7 +88,7 @@ pub fn write_exp<'a>(
     flags: ExpWriteFlags,
 ) -> Operation<'a> {
     let op = ExpOperation {
-        encoder: Box::new(pack_write_exp),
+        encoder: pack_write_exp,
         policy: flags as i64,
         exp,
     };
@@ -105,7 +107,7 @@ pub fn read_exp<'a>(
     flags: ExpReadFlags,
 ) -> Operation<'a> {
     let op = ExpOperation {
-        encoder: Box::new(pack_read_exp),
+        encoder: pack_read_exp,
         policy: flags as i64,
         exp,
     };
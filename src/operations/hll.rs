@@ -96,7 +96,7 @@ pub fn init_with_min_hash<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::Init as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![
             CdtArgument::Int(index_bit_count),
             CdtArgument::Int(min_hash_bit_count),
@@ -142,7 +142,7 @@ pub fn add_with_index_and_min_hash<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::Add as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![
             CdtArgument::List(list),
             CdtArgument::Int(index_bit_count),
@@ -164,7 +164,7 @@ pub fn add_with_index_and_min_hash<'a>(
 pub fn set_union<'a>(policy: &HLLPolicy, bin: &'a str, list: &'a [Value]) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::SetUnion as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![
             CdtArgument::List(list),
             CdtArgument::Byte(policy.flags as u8),
@@ -183,7 +183,7 @@ pub fn set_union<'a>(policy: &HLLPolicy, bin: &'a str, list: &'a [Value]) -> Ope
 pub fn refresh_count(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: HLLOpType::SetCount as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![],
     };
     Operation {
@@ -201,7 +201,7 @@ pub fn refresh_count(bin: &str) -> Operation {
 pub fn fold(bin: &str, index_bit_count: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: HLLOpType::Fold as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![CdtArgument::Int(index_bit_count)],
     };
     Operation {
@@ -217,7 +217,7 @@ pub fn fold(bin: &str, index_bit_count: i64) -> Operation {
 pub fn get_count(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: HLLOpType::Count as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![],
     };
     Operation {
@@ -234,7 +234,7 @@ pub fn get_count(bin: &str) -> Operation {
 pub fn get_union<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::Union as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![CdtArgument::List(list)],
     };
     Operation {
@@ -251,7 +251,7 @@ pub fn get_union<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
 pub fn get_union_count<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::UnionCount as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![CdtArgument::List(list)],
     };
     Operation {
@@ -268,7 +268,7 @@ pub fn get_union_count<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
 pub fn get_intersect_count<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::IntersectCount as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![CdtArgument::List(list)],
     };
     Operation {
@@ -284,7 +284,7 @@ pub fn get_intersect_count<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a>
 pub fn get_similarity<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: HLLOpType::Similarity as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![CdtArgument::List(list)],
     };
     Operation {
@@ -301,7 +301,7 @@ pub fn get_similarity<'a>(bin: &'a str, list: &'a [Value]) -> Operation<'a> {
 pub fn describe(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: HLLOpType::Describe as u8,
-        encoder: Box::new(pack_hll_op),
+        encoder: pack_hll_op,
         args: vec![],
     };
     Operation {
@@ -192,7 +192,7 @@ pub const fn list_order_flag(order: ListOrderType, pad: bool) -> u8 {
 pub fn create(bin: &str, list_order: ListOrderType, pad: bool) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::SetType as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(list_order_flag(list_order, pad)),
             CdtArgument::Byte(list_order as u8),
@@ -215,7 +215,7 @@ pub fn set_order<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::SetType as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(list_order as u8)],
     };
     Operation {
@@ -230,7 +230,7 @@ pub fn set_order<'a>(
 pub fn append<'a>(policy: &ListPolicy, bin: &'a str, value: &'a Value) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Append as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Value(value),
             CdtArgument::Byte(policy.attributes as u8),
@@ -252,7 +252,7 @@ pub fn append_items<'a>(policy: &ListPolicy, bin: &'a str, values: &'a [Value])
 
     let cdt_op = CdtOperation {
         op: CdtListOpType::AppendItems as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::List(values),
             CdtArgument::Byte(policy.attributes as u8),
@@ -277,7 +277,7 @@ pub fn insert<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Insert as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Int(index),
             CdtArgument::Value(value),
@@ -304,7 +304,7 @@ pub fn insert_items<'a>(
 
     let cdt_op = CdtOperation {
         op: CdtListOpType::InsertItems as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Int(index),
             CdtArgument::List(values),
@@ -324,7 +324,7 @@ pub fn insert_items<'a>(
 pub fn pop(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Pop as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -340,7 +340,7 @@ pub fn pop(bin: &str, index: i64) -> Operation {
 pub fn pop_range(bin: &str, index: i64, count: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::PopRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index), CdtArgument::Int(count)],
     };
     Operation {
@@ -356,7 +356,7 @@ pub fn pop_range(bin: &str, index: i64, count: i64) -> Operation {
 pub fn pop_range_from(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::PopRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -372,7 +372,7 @@ pub fn pop_range_from(bin: &str, index: i64) -> Operation {
 pub fn remove(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Remove as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -388,7 +388,7 @@ pub fn remove(bin: &str, index: i64) -> Operation {
 pub fn remove_range(bin: &str, index: i64, count: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index), CdtArgument::Int(count)],
     };
     Operation {
@@ -404,7 +404,7 @@ pub fn remove_range(bin: &str, index: i64, count: i64) -> Operation {
 pub fn remove_range_from(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -424,7 +424,7 @@ pub fn remove_by_value<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByValue as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -447,7 +447,7 @@ pub fn remove_by_value_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByValueList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(values),
@@ -464,7 +464,7 @@ pub fn remove_by_value_list<'a>(
 /// Creates a list remove operation.
 /// Server removes list items identified by value range (valueBegin inclusive, valueEnd exclusive).
 /// If valueBegin is nil, the range is less than valueEnd.
-/// If valueEnd is nil, the range is greater than equal to valueBegin.
+/// If valueEnd is `Value::Infinity`, the range is greater than equal to valueBegin.
 /// Server returns removed data specified by returnType
 pub fn remove_by_value_range<'a>(
     bin: &'a str,
@@ -474,7 +474,7 @@ pub fn remove_by_value_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByValueInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(begin),
@@ -511,7 +511,7 @@ pub fn remove_by_value_relative_rank_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -549,7 +549,7 @@ pub fn remove_by_value_relative_rank_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -570,7 +570,7 @@ pub fn remove_by_value_relative_rank_range_count<'a>(
 pub fn remove_by_index(bin: &str, index: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByIndex as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -590,7 +590,7 @@ pub fn remove_by_index(bin: &str, index: i64, return_type: ListReturnType) -> Op
 pub fn remove_by_index_range(bin: &str, index: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -614,7 +614,7 @@ pub fn remove_by_index_range_count(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -634,7 +634,7 @@ pub fn remove_by_index_range_count(
 pub fn remove_by_rank(bin: &str, rank: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByRank as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -651,7 +651,7 @@ pub fn remove_by_rank(bin: &str, rank: i64, return_type: ListReturnType) -> Oper
 pub fn remove_by_rank_range(bin: &str, rank: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -672,7 +672,7 @@ pub fn remove_by_rank_range_count(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::RemoveByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(rank),
@@ -694,7 +694,7 @@ pub fn set<'a>(bin: &'a str, index: i64, value: &'a Value) -> Operation<'a> {
 
     let cdt_op = CdtOperation {
         op: CdtListOpType::Set as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index), CdtArgument::Value(value)],
     };
     Operation {
@@ -711,7 +711,7 @@ pub fn set<'a>(bin: &'a str, index: i64, value: &'a Value) -> Operation<'a> {
 pub fn trim(bin: &str, index: i64, count: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Trim as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index), CdtArgument::Int(count)],
     };
     Operation {
@@ -727,7 +727,7 @@ pub fn trim(bin: &str, index: i64, count: i64) -> Operation {
 pub fn clear(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Clear as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![],
     };
     Operation {
@@ -743,7 +743,7 @@ pub fn clear(bin: &str) -> Operation {
 pub fn increment<'a>(policy: &ListPolicy, bin: &'a str, index: i64, value: i64) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Increment as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Int(index),
             CdtArgument::Int(value),
@@ -762,7 +762,7 @@ pub fn increment<'a>(policy: &ListPolicy, bin: &'a str, index: i64, value: i64)
 pub fn size(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Size as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![],
     };
     Operation {
@@ -777,7 +777,7 @@ pub fn size(bin: &str) -> Operation {
 pub fn get(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Get as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -793,7 +793,7 @@ pub fn get(bin: &str, index: i64) -> Operation {
 pub fn get_range(bin: &str, index: i64, count: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index), CdtArgument::Int(count)],
     };
     Operation {
@@ -809,7 +809,7 @@ pub fn get_range(bin: &str, index: i64, count: i64) -> Operation {
 pub fn get_range_from(bin: &str, index: i64) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Int(index)],
     };
     Operation {
@@ -829,7 +829,7 @@ pub fn get_by_value<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByValue as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -853,7 +853,7 @@ pub fn get_by_value_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByValueList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(values),
@@ -870,7 +870,7 @@ pub fn get_by_value_list<'a>(
 /// Creates a list get by value range operation.
 /// Server selects list items identified by value range (valueBegin inclusive, valueEnd exclusive)
 /// If valueBegin is null, the range is less than valueEnd.
-/// If valueEnd is null, the range is greater than equal to valueBegin.
+/// If valueEnd is `Value::Infinity`, the range is greater than equal to valueBegin.
 /// Server returns selected data specified by returnType.
 pub fn get_by_value_range<'a>(
     bin: &'a str,
@@ -880,7 +880,7 @@ pub fn get_by_value_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByValueInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(begin),
@@ -900,7 +900,7 @@ pub fn get_by_value_range<'a>(
 pub fn get_by_index(bin: &str, index: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByIndex as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -921,7 +921,7 @@ pub fn get_by_index(bin: &str, index: i64, return_type: ListReturnType) -> Opera
 pub fn get_by_index_range(bin: &str, index: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -947,7 +947,7 @@ pub fn get_by_index_range_count(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -968,7 +968,7 @@ pub fn get_by_index_range_count(
 pub fn get_by_rank(bin: &str, rank: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByRank as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -985,7 +985,7 @@ pub fn get_by_rank(bin: &str, rank: i64, return_type: ListReturnType) -> Operati
 pub fn get_by_rank_range(bin: &str, rank: i64, return_type: ListReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -1006,7 +1006,7 @@ pub fn get_by_rank_range_count(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(rank),
@@ -1043,7 +1043,7 @@ pub fn get_by_value_relative_rank_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -1081,7 +1081,7 @@ pub fn get_by_value_relative_rank_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtListOpType::GetByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -1103,7 +1103,7 @@ pub fn get_by_value_relative_rank_range_count<'a>(
 pub fn sort(bin: &str, sort_flags: ListSortFlags) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtListOpType::Sort as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(sort_flags as u8)],
     };
     Operation {
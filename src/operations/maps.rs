@@ -242,7 +242,7 @@ pub const fn map_order_flag(order: MapOrder) -> u8 {
 pub fn set_order(bin: &str, map_order: MapOrder) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::SetType as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(map_order as u8)],
     };
     Operation {
@@ -273,7 +273,7 @@ pub fn put<'a>(
     }
     let cdt_op = CdtOperation {
         op: map_write_op(policy, false) as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -301,7 +301,7 @@ pub fn put_items<'a>(
     }
     let cdt_op = CdtOperation {
         op: map_write_op(policy, true) as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -332,7 +332,7 @@ pub fn increment_value<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::Increment as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -363,7 +363,7 @@ pub fn decrement_value<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::Decrement as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -379,7 +379,7 @@ pub fn decrement_value<'a>(
 pub fn clear(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::Clear as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![],
     };
     Operation {
@@ -399,7 +399,7 @@ pub fn remove_by_key<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByKey as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -422,7 +422,7 @@ pub fn remove_by_key_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveKeyList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(keys),
@@ -436,9 +436,9 @@ pub fn remove_by_key_list<'a>(
     }
 }
 
-/// Create map remove operation. Server removes map items identified by the key range
-/// (`begin` inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than
-/// `end`. If `end` is `Value::Nil`, the range is greater than equal to `begin`. Server returns
+/// Create map remove operation. Server removes map items identified by the key range (`begin`
+/// inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than `end`. If `end`
+/// is `Value::Nil` or `Value::Infinity`, the range is greater than equal to `begin`. Server returns
 /// removed data specified by `return_type`.
 pub fn remove_by_key_range<'a>(
     bin: &'a str,
@@ -455,7 +455,7 @@ pub fn remove_by_key_range<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByKeyInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -475,7 +475,7 @@ pub fn remove_by_value<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByValue as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -498,7 +498,7 @@ pub fn remove_by_value_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveValueList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(values),
@@ -513,9 +513,9 @@ pub fn remove_by_value_list<'a>(
 }
 
 /// Create map remove operation. Server removes map items identified by value range (`begin`
-/// inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than `end`. If
-/// `end` is `Value::Nil`, the range is greater than equal to `begin`. Server returns the
-/// removed data specified by `return_type`.
+/// inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than `end`. If `end`
+/// is `Value::Nil` or `Value::Infinity`, the range is greater than equal to `begin`. Server returns
+/// the removed data specified by `return_type`.
 pub fn remove_by_value_range<'a>(
     bin: &'a str,
     begin: &'a Value,
@@ -531,7 +531,7 @@ pub fn remove_by_value_range<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByValueInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -547,7 +547,7 @@ pub fn remove_by_value_range<'a>(
 pub fn remove_by_index(bin: &str, index: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByIndex as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -571,7 +571,7 @@ pub fn remove_by_index_range(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -591,7 +591,7 @@ pub fn remove_by_index_range(
 pub fn remove_by_index_range_from(bin: &str, index: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -610,7 +610,7 @@ pub fn remove_by_index_range_from(bin: &str, index: i64, return_type: MapReturnT
 pub fn remove_by_rank(bin: &str, rank: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByRank as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -631,7 +631,7 @@ pub fn remove_by_rank_range(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(rank),
@@ -651,7 +651,7 @@ pub fn remove_by_rank_range(
 pub fn remove_by_rank_range_from(bin: &str, rank: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -666,7 +666,7 @@ pub fn remove_by_rank_range_from(bin: &str, rank: i64, return_type: MapReturnTyp
 pub fn size(bin: &str) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::Size as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![],
     };
     Operation {
@@ -682,7 +682,7 @@ pub fn size(bin: &str) -> Operation {
 pub fn get_by_key<'a>(bin: &'a str, key: &'a Value, return_type: MapReturnType) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByKey as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -696,9 +696,9 @@ pub fn get_by_key<'a>(bin: &'a str, key: &'a Value, return_type: MapReturnType)
     }
 }
 
-/// Create map get by key range operation. Server selects the map items identified by the key
-/// range (`begin` inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less
-/// than `end`. If `end` is `Value::Nil` the range is greater than equal to `begin`. Server
+/// Create map get by key range operation. Server selects the map items identified by the key range
+/// (`begin` inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than `end`.
+/// If `end` is `Value::Nil` or `Value::Infinity` the range is greater than equal to `begin`. Server
 /// returns the selected data specified by `return_type`.
 pub fn get_by_key_range<'a>(
     bin: &'a str,
@@ -715,7 +715,7 @@ pub fn get_by_key_range<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByKeyInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -735,7 +735,7 @@ pub fn get_by_value<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByValue as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -749,10 +749,10 @@ pub fn get_by_value<'a>(
     }
 }
 
-/// Create map get by value range operation. Server selects the map items identified by the
-/// value range (`begin` inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is
-/// less than `end`. If `end` is `Value::Nil`, the range is greater than equal to `begin`.
-/// Server returns the selected data specified by `return_type`.
+/// Create map get by value range operation. Server selects the map items identified by the value
+/// range (`begin` inclusive, `end` exclusive). If `begin` is `Value::Nil`, the range is less than
+/// `end`. If `end` is `Value::Nil` or `Value::Infinity`, the range is greater than equal to
+/// `begin`. Server returns the selected data specified by `return_type`.
 pub fn get_by_value_range<'a>(
     bin: &'a str,
     begin: &'a Value,
@@ -768,7 +768,7 @@ pub fn get_by_value_range<'a>(
     }
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByValueInterval as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args,
     };
     Operation {
@@ -784,7 +784,7 @@ pub fn get_by_value_range<'a>(
 pub fn get_by_index(bin: &str, index: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByIndex as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -808,7 +808,7 @@ pub fn get_by_index_range(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -829,7 +829,7 @@ pub fn get_by_index_range(
 pub fn get_by_index_range_from(bin: &str, index: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(index),
@@ -848,7 +848,7 @@ pub fn get_by_index_range_from(bin: &str, index: i64, return_type: MapReturnType
 pub fn get_by_rank(bin: &str, rank: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByRank as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -869,7 +869,7 @@ pub fn get_by_rank_range(
 ) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Int(rank),
@@ -890,7 +890,7 @@ pub fn get_by_rank_range(
 pub fn get_by_rank_range_from(bin: &str, rank: i64, return_type: MapReturnType) -> Operation {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![CdtArgument::Byte(return_type as u8), CdtArgument::Int(rank)],
     };
     Operation {
@@ -921,7 +921,7 @@ pub fn remove_by_key_relative_index_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByKeyRelIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -957,7 +957,7 @@ pub fn remove_by_key_relative_index_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByKeyRelIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -990,7 +990,7 @@ pub fn remove_by_value_relative_rank_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -1023,7 +1023,7 @@ pub fn remove_by_value_relative_rank_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::RemoveByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -1048,7 +1048,7 @@ pub fn get_by_key_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByKeyList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(keys),
@@ -1071,7 +1071,7 @@ pub fn get_by_value_list<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByValueList as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::List(values),
@@ -1105,7 +1105,7 @@ pub fn get_by_key_relative_index_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByKeyRelIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -1141,7 +1141,7 @@ pub fn get_by_key_relative_index_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByKeyRelIndexRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(key),
@@ -1174,7 +1174,7 @@ pub fn get_by_value_relative_rank_range<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
@@ -1207,7 +1207,7 @@ pub fn get_by_value_relative_rank_range_count<'a>(
 ) -> Operation<'a> {
     let cdt_op = CdtOperation {
         op: CdtMapOpType::GetByValueRelRankRange as u8,
-        encoder: Box::new(pack_cdt_op),
+        encoder: pack_cdt_op,
         args: vec![
             CdtArgument::Byte(return_type as u8),
             CdtArgument::Value(value),
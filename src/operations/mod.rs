@@ -16,17 +16,22 @@
 //! Functions used to create database operations used in the client's `operate()` method.
 
 pub mod bitwise;
+mod builder;
 #[doc(hidden)]
 pub mod cdt;
 pub mod cdt_context;
+pub mod document;
 pub mod exp;
 pub mod hll;
 pub mod lists;
 pub mod maps;
+mod owned;
 pub mod scalar;
 
+pub use self::builder::Operations;
 use self::cdt::CdtOperation;
 pub use self::maps::{MapOrder, MapPolicy, MapReturnType, MapWriteMode};
+pub use self::owned::OperationBuf;
 pub use self::scalar::*;
 
 use crate::commands::buffer::Buffer;
@@ -36,7 +41,7 @@ use crate::operations::cdt_context::CdtContext;
 use crate::operations::exp::ExpOperation;
 use crate::Value;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 #[doc(hidden)]
 pub enum OperationType {
     Read = 1,
@@ -98,7 +103,10 @@ impl<'a> Operation<'a> {
     pub fn estimate_size(&self) -> Result<usize> {
         let mut size: usize = 0;
         size += match self.bin {
-            OperationBin::Name(bin) => bin.len(),
+            OperationBin::Name(bin) => {
+                crate::bin::validate_name(bin)?;
+                bin.len()
+            }
             OperationBin::None | OperationBin::All => 0,
         };
         size += match self.data {
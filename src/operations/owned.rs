@@ -0,0 +1,299 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Owned database operations.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::expressions::FilterExpression;
+use crate::operations::cdt::{CdtArgument, CdtOperation, OperationEncoder};
+use crate::operations::cdt_context::CdtContext;
+use crate::operations::exp::{ExpOperation, ExpressionEncoder};
+use crate::operations::{Operation, OperationBin, OperationData, OperationType};
+use crate::Value;
+
+/// Owned version of an `Operation`.
+///
+/// An `OperationBuf` owns its bin name, values and CDT context instead of borrowing them, so it
+/// can be returned from helper functions, stored in structs, cloned and sent across threads.
+/// `as_operation()` borrows it as an `Operation` again, e.g. to pass it to the client's
+/// `operate()` method.
+///
+/// Any operation created by the functions in the `operations` module and its submodules can be
+/// converted into an `OperationBuf`.
+///
+/// # Examples
+///
+/// ```rust
+/// use aerospike::as_bin;
+/// use aerospike::operations::{self, lists, Operation, OperationBuf};
+///
+/// fn increment_and_trim(bin: &str, by: i64) -> Vec<OperationBuf> {
+///     let amount = as_bin!(bin, by);
+///     vec![
+///         operations::add(&amount).into(),
+///         lists::trim("history", 0, 10).into(),
+///     ]
+/// }
+///
+/// let bufs = increment_and_trim("count", 2);
+/// let ops: Vec<Operation> = bufs.iter().map(OperationBuf::as_operation).collect();
+/// assert_eq!(ops.len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct OperationBuf {
+    op: OperationType,
+    ctx: Vec<CdtContext>,
+    bin: BinBuf,
+    data: DataBuf,
+}
+
+#[derive(Debug, Clone)]
+enum BinBuf {
+    None,
+    All,
+    Name(String),
+}
+
+#[derive(Debug, Clone)]
+enum DataBuf {
+    None,
+    Value(Value),
+    CdtListOp(CdtOperationBuf),
+    CdtMapOp(CdtOperationBuf),
+    CdtBitOp(CdtOperationBuf),
+    HLLOp(CdtOperationBuf),
+    EXPOp(ExpOperationBuf),
+}
+
+#[derive(Clone)]
+struct CdtOperationBuf {
+    op: u8,
+    encoder: OperationEncoder,
+    args: Vec<ArgumentBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum ArgumentBuf {
+    Byte(u8),
+    Int(i64),
+    Bool(bool),
+    Value(Value),
+    List(Vec<Value>),
+    Map(HashMap<Value, Value>),
+}
+
+#[derive(Clone)]
+struct ExpOperationBuf {
+    encoder: ExpressionEncoder,
+    policy: i64,
+    exp: FilterExpression,
+}
+
+// The encoders are function pointers; leave them out of the debug output.
+impl fmt::Debug for CdtOperationBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CdtOperationBuf")
+            .field("op", &self.op)
+            .field("args", &self.args)
+            .finish_non_exhaustive()
+    }
+}
+
+impl fmt::Debug for ExpOperationBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExpOperationBuf")
+            .field("policy", &self.policy)
+            .field("exp", &self.exp)
+            .finish_non_exhaustive()
+    }
+}
+
+impl CdtOperationBuf {
+    fn new(cdt_op: &CdtOperation) -> Self {
+        let args = cdt_op
+            .args
+            .iter()
+            .map(|arg| match *arg {
+                CdtArgument::Byte(b) => ArgumentBuf::Byte(b),
+                CdtArgument::Int(i) => ArgumentBuf::Int(i),
+                CdtArgument::Bool(b) => ArgumentBuf::Bool(b),
+                CdtArgument::Value(value) => ArgumentBuf::Value(value.clone()),
+                CdtArgument::List(list) => ArgumentBuf::List(list.to_vec()),
+                CdtArgument::Map(map) => ArgumentBuf::Map(map.clone()),
+            })
+            .collect();
+        CdtOperationBuf {
+            op: cdt_op.op,
+            encoder: cdt_op.encoder,
+            args,
+        }
+    }
+
+    fn as_cdt_op(&self) -> CdtOperation {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| match *arg {
+                ArgumentBuf::Byte(b) => CdtArgument::Byte(b),
+                ArgumentBuf::Int(i) => CdtArgument::Int(i),
+                ArgumentBuf::Bool(b) => CdtArgument::Bool(b),
+                ArgumentBuf::Value(ref value) => CdtArgument::Value(value),
+                ArgumentBuf::List(ref list) => CdtArgument::List(list),
+                ArgumentBuf::Map(ref map) => CdtArgument::Map(map),
+            })
+            .collect();
+        CdtOperation {
+            op: self.op,
+            encoder: self.encoder,
+            args,
+        }
+    }
+}
+
+impl OperationBuf {
+    /// Borrow the operation, e.g. to pass it to the client's `operate()` method.
+    pub fn as_operation(&self) -> Operation {
+        let bin = match self.bin {
+            BinBuf::None => OperationBin::None,
+            BinBuf::All => OperationBin::All,
+            BinBuf::Name(ref name) => OperationBin::Name(name),
+        };
+        let data = match self.data {
+            DataBuf::None => OperationData::None,
+            DataBuf::Value(ref value) => OperationData::Value(value),
+            DataBuf::CdtListOp(ref cdt_op) => OperationData::CdtListOp(cdt_op.as_cdt_op()),
+            DataBuf::CdtMapOp(ref cdt_op) => OperationData::CdtMapOp(cdt_op.as_cdt_op()),
+            DataBuf::CdtBitOp(ref cdt_op) => OperationData::CdtBitOp(cdt_op.as_cdt_op()),
+            DataBuf::HLLOp(ref cdt_op) => OperationData::HLLOp(cdt_op.as_cdt_op()),
+            DataBuf::EXPOp(ref exp_op) => OperationData::EXPOp(ExpOperation {
+                encoder: exp_op.encoder,
+                policy: exp_op.policy,
+                exp: &exp_op.exp,
+            }),
+        };
+        Operation {
+            op: self.op,
+            ctx: &self.ctx,
+            bin,
+            data,
+        }
+    }
+}
+
+impl<'a> From<&Operation<'a>> for OperationBuf {
+    fn from(op: &Operation<'a>) -> Self {
+        let bin = match op.bin {
+            OperationBin::None => BinBuf::None,
+            OperationBin::All => BinBuf::All,
+            OperationBin::Name(name) => BinBuf::Name(name.to_string()),
+        };
+        let data = match op.data {
+            OperationData::None => DataBuf::None,
+            OperationData::Value(value) => DataBuf::Value(value.clone()),
+            OperationData::CdtListOp(ref cdt_op) => {
+                DataBuf::CdtListOp(CdtOperationBuf::new(cdt_op))
+            }
+            OperationData::CdtMapOp(ref cdt_op) => DataBuf::CdtMapOp(CdtOperationBuf::new(cdt_op)),
+            OperationData::CdtBitOp(ref cdt_op) => DataBuf::CdtBitOp(CdtOperationBuf::new(cdt_op)),
+            OperationData::HLLOp(ref cdt_op) => DataBuf::HLLOp(CdtOperationBuf::new(cdt_op)),
+            OperationData::EXPOp(ref exp_op) => DataBuf::EXPOp(ExpOperationBuf {
+                encoder: exp_op.encoder,
+                policy: exp_op.policy,
+                exp: exp_op.exp.clone(),
+            }),
+        };
+        OperationBuf {
+            op: op.op,
+            ctx: op.ctx.to_vec(),
+            bin,
+            data,
+        }
+    }
+}
+
+impl<'a> From<Operation<'a>> for OperationBuf {
+    fn from(op: Operation<'a>) -> Self {
+        OperationBuf::from(&op)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OperationBuf;
+    use crate::commands::buffer::Buffer;
+    use crate::expressions::{self as exp, FilterExpression};
+    use crate::operations::cdt_context::ctx_map_key;
+    use crate::operations::exp::{read_exp, ExpReadFlags};
+    use crate::operations::lists::{self, ListReturnType};
+    use crate::operations::{self, bitwise, hll, maps, MapPolicy, MapReturnType, Operation};
+    use crate::Value;
+    use std::collections::HashMap;
+    use std::thread;
+
+    fn encode(op: &Operation) -> Vec<u8> {
+        let size = op.estimate_size().unwrap() + 8;
+        let mut buf = Buffer::new(size);
+        buf.resize_buffer(size).unwrap();
+        let written = op.write_to(&mut buf).unwrap();
+        buf.data_buffer[..written].to_vec()
+    }
+
+    #[test]
+    fn round_trips() {
+        let bin = as_bin!("a", 1);
+        let values = vec![as_val!(1), as_val!("two")];
+        let mut map = HashMap::new();
+        map.insert(as_val!("k"), as_val!(2));
+        let key = as_val!("k");
+        let ctx = [ctx_map_key(as_val!("nested"))];
+        let filter: FilterExpression = exp::int_val(1);
+        let policy = MapPolicy::default();
+        let ops = vec![
+            operations::get(),
+            operations::get_header(),
+            operations::put(&bin),
+            operations::touch(),
+            lists::append_items(&lists::ListPolicy::default(), "l", &values),
+            lists::get_by_index("l", 0, ListReturnType::Values).set_context(&ctx),
+            maps::put_items(&policy, "m", &map),
+            maps::get_by_key("m", &key, MapReturnType::KeyValue),
+            bitwise::count("b", 0, 8),
+            hll::get_count("h"),
+            read_exp("e", &filter, ExpReadFlags::Default),
+        ];
+        for op in &ops {
+            let buf = OperationBuf::from(op);
+            assert_eq!(encode(op), encode(&buf.as_operation()));
+            assert_eq!(encode(op), encode(&buf.clone().as_operation()));
+        }
+    }
+
+    #[test]
+    fn send() {
+        let value = as_list!(1, 2, 3);
+        let buf = OperationBuf::from(operations::put(&as_bin!("a", value)));
+        let expected = encode(&buf.as_operation());
+        let encoded = thread::spawn(move || encode(&buf.as_operation()))
+            .join()
+            .unwrap();
+        assert_eq!(encoded, expected);
+        assert_ne!(
+            expected,
+            encode(&operations::put(&as_bin!("a", Value::Nil)))
+        );
+    }
+}
@@ -13,10 +13,14 @@
 // limitations under the License.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use crate::commands::admin_command::AdminCommand;
 use crate::errors::Result;
+use crate::interceptor::Interceptor;
+#[cfg(feature = "testing")]
+use crate::testing::{FaultInjector, Recorder};
 
 /// `ClientPolicy` encapsulates parameters for client policy command.
 #[derive(Debug, Clone)]
@@ -83,6 +87,20 @@ pub struct ClientPolicy {
     /// to join the client's view of the cluster. Should only be set when connecting to servers
     /// that support the "cluster-name" info command.
     pub cluster_name: Option<String>,
+
+    /// Interceptors invoked around every command attempt, in the order they are registered.
+    /// See the `interceptor` module for details.
+    pub interceptors: Vec<Arc<dyn Interceptor>>,
+
+    /// Injects network faults into the connections opened by the client, to test timeout, retry
+    /// and failover handling. Requires the `testing` feature. Default is no fault injection.
+    #[cfg(feature = "testing")]
+    pub fault_injector: Option<Arc<FaultInjector>>,
+
+    /// Captures the frames exchanged with the cluster to a file, to be served by a
+    /// `testing::ReplayServer`. Requires the `testing` feature. Default is no recording.
+    #[cfg(feature = "testing")]
+    pub recorder: Option<Arc<Recorder>>,
 }
 
 impl Default for ClientPolicy {
@@ -100,6 +118,11 @@ impl Default for ClientPolicy {
             thread_pool_size: 128,
             cluster_name: None,
             buffer_reclaim_threshold: 65536,
+            interceptors: vec![],
+            #[cfg(feature = "testing")]
+            fault_injector: None,
+            #[cfg(feature = "testing")]
+            recorder: None,
         }
     }
 }
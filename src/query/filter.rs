@@ -149,11 +149,13 @@ macro_rules! as_contains_range {
 
 /// Create geospatial "points within region" filter for queries. For queries on a collection index
 /// the collection index type must be specified.
+///
+/// The region can be a `GeoJSON` string or one of the `geo` geometry types.
 #[macro_export]
 macro_rules! as_within_region {
     ($bin_name:expr, $region:expr) => {{
         let cit = $crate::CollectionIndexType::Default;
-        let region = as_geo!(String::from($region));
+        let region = $crate::Value::GeoJSON($region.to_string());
         $crate::query::Filter::new(
             $bin_name,
             cit,
@@ -163,7 +165,7 @@ macro_rules! as_within_region {
         )
     }};
     ($bin_name:expr, $region:expr, $cit:expr) => {{
-        let region = as_geo!(String::from($region));
+        let region = $crate::Value::GeoJSON($region.to_string());
         $crate::query::Filter::new(
             $bin_name,
             $cit,
@@ -176,52 +178,39 @@ macro_rules! as_within_region {
 
 /// Create geospatial "points within radius" filter for queries. For queries on a collection index
 /// the collection index type must be specified.
+///
+/// The circle is given either as a `geo::AeroCircle` or by the latitude and longitude of its center
+/// and its radius in meters.
 #[macro_export]
 macro_rules! as_within_radius {
+    ($bin_name:expr, $circle:expr) => {{
+        as_within_region!($bin_name, $circle)
+    }};
+    ($bin_name:expr, $circle:expr, $cit:expr) => {{
+        as_within_region!($bin_name, $circle, $cit)
+    }};
     ($bin_name:expr, $lat:expr, $lng:expr, $radius:expr) => {{
         let cit = $crate::CollectionIndexType::Default;
-        let lat = as_val!($lat as f64);
-        let lng = as_val!($lng as f64);
-        let radius = as_val!($radius as f64);
-        let geo_json = format!(
-            "{{ \"type\": \"Aeroircle\", \"coordinates\": [[{:.8}, {:.8}], {}] }}",
-            lng, lat, radius
-        );
-        let geo_json = as_geo!(geo_json);
-        $crate::query::Filter::new(
-            $bin_name,
-            cit,
-            geo_json.particle_type(),
-            geo_json.clone(),
-            geo_json.clone(),
-        )
+        as_within_radius!($bin_name, $lat, $lng, $radius, cit)
     }};
     ($bin_name:expr, $lat:expr, $lng:expr, $radius:expr, $cit:expr) => {{
-        let lat = as_val!($lat as f64);
-        let lng = as_val!($lng as f64);
-        let radius = as_val!($radius as f64);
         let geo_json = format!(
-            "{{ \"type\": \"Aeroircle\", \"coordinates\": [[{:.8}, {:.8}], {}] }}",
-            lng, lat, radius
+            "{{\"type\":\"AeroCircle\",\"coordinates\":[[{},{}],{}]}}",
+            $lng as f64, $lat as f64, $radius as f64
         );
-        let geo_json = as_geo!(geo_json);
-        $crate::query::Filter::new(
-            $bin_name,
-            $cit,
-            geo_json.particle_type(),
-            geo_json.clone(),
-            geo_json.clone(),
-        )
+        as_within_region!($bin_name, geo_json, $cit)
     }};
 }
 
 /// Create geospatial "regions containing point" filter for queries. For queries on a collection
 /// index the collection index type must be specified.
+///
+/// The point can be a `GeoJSON` string or a `geo::Point`.
 #[macro_export]
 macro_rules! as_regions_containing_point {
     ($bin_name:expr, $point:expr) => {{
         let cit = $crate::CollectionIndexType::Default;
-        let point = as_geo!(String::from($point));
+        let point = $crate::Value::GeoJSON($point.to_string());
         $crate::query::Filter::new(
             $bin_name,
             cit,
@@ -231,7 +220,7 @@ macro_rules! as_regions_containing_point {
         )
     }};
     ($bin_name:expr, $point:expr, $cit:expr) => {{
-        let point = as_geo!(String::from($point));
+        let point = $crate::Value::GeoJSON($point.to_string());
         $crate::query::Filter::new(
             $bin_name,
             $cit,
@@ -244,7 +233,11 @@ macro_rules! as_regions_containing_point {
 
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
+
     use super::CollectionIndexType;
+    use crate::geo::{AeroCircle, Point, Polygon};
+    use crate::Value;
 
     #[test]
     fn geo_filter_macros() {
@@ -266,4 +259,29 @@ mod tests {
         let geo_filter = as_within_radius!("bin1", 1, 3, 7, CollectionIndexType::List);
         assert_eq!(geo_filter.bin_name, "bin1");
     }
+
+    #[test]
+    fn typed_geo_filters() {
+        let point = Point::new(3.0, 1.0).unwrap();
+        let circle = AeroCircle::new(point, 7.0).unwrap();
+        let polygon = Polygon::new(vec![
+            point,
+            Point::new(4.0, 1.0).unwrap(),
+            Point::new(4.0, 2.0).unwrap(),
+            point,
+        ])
+        .unwrap();
+
+        let geo_filter = as_within_radius!("bin1", 1, 3, 7);
+        assert_eq!(geo_filter.begin, Value::from(circle));
+        let geo_filter = as_within_radius!("bin1", circle);
+        assert_eq!(geo_filter.begin, Value::from(circle));
+        let geo_filter = as_within_radius!("bin1", circle, CollectionIndexType::List);
+        assert_eq!(geo_filter.end, Value::from(circle));
+
+        let geo_filter = as_within_region!("bin1", &polygon);
+        assert_eq!(geo_filter.begin, Value::from(&polygon));
+        let geo_filter = as_regions_containing_point!("bin1", point);
+        assert_eq!(Point::try_from(geo_filter.begin).unwrap(), point);
+    }
 }
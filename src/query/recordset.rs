@@ -21,7 +21,7 @@ use std::thread;
 use crossbeam_queue::SegQueue;
 use rand::Rng;
 
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use crate::Record;
 
 /// Virtual collection of records retrieved through queries and scans. During a query/scan,
@@ -75,6 +75,20 @@ impl Recordset {
         Some(record)
     }
 
+    /// Hands an error to the consumer, waiting for room on the queue while the record set is
+    /// still active.
+    #[doc(hidden)]
+    pub fn push_error(&self, err: Error) {
+        let mut result = Err(err);
+        while let Some(returned) = self.push(result) {
+            if !self.is_active() {
+                break;
+            }
+            result = returned;
+            thread::yield_now();
+        }
+    }
+
     /// Returns the task ID for the scan/query.
     pub fn task_id(&self) -> u64 {
         self.task_id.load(Ordering::Relaxed) as u64
@@ -14,12 +14,14 @@
 // the License.
 
 #[cfg(feature = "serialization")]
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use crate::errors::{Error, ErrorKind, Result};
 use crate::Key;
 use crate::Value;
 
@@ -30,7 +32,7 @@ lazy_static! {
 
 /// Container object for a database record.
 #[derive(Debug)]
-#[cfg_attr(feature = "serialization", derive(Serialize))]
+#[cfg_attr(feature = "serialization", derive(Serialize, Deserialize))]
 pub struct Record {
     /// Record key. When reading a record from the database, the key is not set in the returned
     /// Record struct.
@@ -63,6 +65,51 @@ impl Record {
         }
     }
 
+    /// Returns the value of the named bin, converted into the requested type. Missing bins are
+    /// treated as `nil`, i.e. they can be read as `Option<T>`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidType` error naming the bin if the value cannot be converted, or an
+    /// `InvalidArgument` error naming the bin if a number is out of range for the requested type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// use aerospike::{as_val, Record};
+    ///
+    /// # let mut bins = HashMap::new();
+    /// # bins.insert("name".to_string(), as_val!("Alice"));
+    /// # bins.insert("age".to_string(), as_val!(32));
+    /// # let record = Record::new(None, bins, 1, 0);
+    /// let name: String = record.get("name").unwrap();
+    /// let age = record.get::<u8>("age").unwrap();
+    /// let email: Option<String> = record.get("email").unwrap();
+    /// assert!(record.get::<i64>("name").is_err());
+    /// ```
+    pub fn get<T>(&self, bin: &str) -> Result<T>
+    where
+        T: TryFrom<Value>,
+        Error: From<T::Error>,
+    {
+        let val = self.bins.get(bin).cloned().unwrap_or(Value::Nil);
+        match T::try_from(val).map_err(Error::from) {
+            Err(Error(ErrorKind::InvalidType(None, expected, actual), _)) => bail!(
+                ErrorKind::InvalidType(Some(bin.to_string()), expected, actual)
+            ),
+            Err(Error(ErrorKind::InvalidArgument(details), _)) => bail!(
+                ErrorKind::InvalidArgument(format!("Bin '{}': {}", bin, details))
+            ),
+            result => result,
+        }
+    }
+
+    // Raw expiration, in seconds since the Citrusleaf epoch.
+    pub(crate) const fn expiration(&self) -> u32 {
+        self.expiration
+    }
+
     /// Returns the remaining time-to-live (TTL, a.k.a. expiration time) for the record or `None`
     /// if the record never expires.
     pub fn time_to_live(&self) -> Option<Duration> {
@@ -81,8 +128,41 @@ impl Record {
     }
 }
 
+#[cfg(feature = "serialization")]
+impl Record {
+    /// Convert the record's bins into any deserializable type, e.g. a struct with one field per
+    /// bin. Bins are deserialized as by `serialization::from_value`; missing bins can be mapped
+    /// to `Option` fields. Requires the `serialization` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::collections::HashMap;
+    /// use aerospike::{as_val, Record};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     age: u32,
+    ///     email: Option<String>,
+    /// }
+    ///
+    /// # let mut bins = HashMap::new();
+    /// # bins.insert("name".to_string(), as_val!("Alice"));
+    /// # bins.insert("age".to_string(), as_val!(32));
+    /// # let record = Record::new(None, bins, 1, 0);
+    /// let user: User = record.deserialize().unwrap();
+    /// assert_eq!(user.age, 32);
+    /// assert_eq!(user.email, None);
+    /// ```
+    pub fn deserialize<'de, T: Deserialize<'de>>(&'de self) -> Result<T> {
+        crate::serialization::from_bins(&self.bins)
+    }
+}
+
 impl fmt::Display for Record {
-    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "key: {:?}", self.key)?;
         write!(f, ", bins: {{")?;
         for (i, (k, v)) in self.bins.iter().enumerate() {
@@ -103,6 +183,8 @@ impl fmt::Display for Record {
 #[cfg(test)]
 mod tests {
     use super::{Record, CITRUSLEAF_EPOCH};
+    use crate::errors::ErrorKind;
+    use crate::ParticleType;
     use std::collections::HashMap;
     use std::time::{Duration, SystemTime};
 
@@ -130,4 +212,41 @@ mod tests {
         let record = Record::new(None, HashMap::new(), 0, 0);
         assert_eq!(record.time_to_live(), None);
     }
+
+    #[test]
+    fn get() {
+        let mut bins = HashMap::new();
+        bins.insert("int".to_string(), as_val!(1));
+        bins.insert("str".to_string(), as_val!("a"));
+        bins.insert("list".to_string(), as_list!(1, 2));
+        let record = Record::new(None, bins, 0, 0);
+
+        assert_eq!(record.get::<i64>("int").unwrap(), 1);
+        assert_eq!(record.get::<String>("str").unwrap(), "a");
+        assert_eq!(record.get::<Vec<u8>>("list").unwrap(), vec![1, 2]);
+        assert_eq!(record.get::<Option<i64>>("missing").unwrap(), None);
+
+        match record.get::<i64>("str").unwrap_err().0 {
+            ErrorKind::InvalidType(bin, expected, actual) => {
+                assert_eq!(bin.as_deref(), Some("str"));
+                assert_eq!(expected, "i64");
+                assert_eq!(actual, ParticleType::STRING);
+            }
+            err => panic!("unexpected error {:?}", err),
+        }
+        let err = record.get::<i64>("missing").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid type for bin 'missing': expected i64, found NULL"
+        );
+
+        let mut bins = HashMap::new();
+        bins.insert("big".to_string(), as_val!(300));
+        let record = Record::new(None, bins, 0, 0);
+        let err = record.get::<u8>("big").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: Bin 'big': 300 is out of range for u8"
+        );
+    }
 }
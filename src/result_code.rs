@@ -98,6 +98,14 @@ pub enum ResultCode {
     /// Enterprise-only feature not supported by the community edition
     EnterpriseOnly,
 
+    /// The operation cannot be applied to the current bin value on the server, e.g. an expression
+    /// write denied by its `ExpWriteFlags`.
+    OpNotApplicable,
+
+    /// The command was not applied because the record did not pass the policy's filter
+    /// expression.
+    FilteredOut,
+
     /// There are no more records left for query.
     QueryEnd,
 
@@ -241,6 +249,8 @@ impl ResultCode {
             23 => ResultCode::ElementNotFound,
             24 => ResultCode::ElementExists,
             25 => ResultCode::EnterpriseOnly,
+            26 => ResultCode::OpNotApplicable,
+            27 => ResultCode::FilteredOut,
             50 => ResultCode::QueryEnd,
             51 => ResultCode::SecurityNotSupported,
             52 => ResultCode::SecurityNotEnabled,
@@ -314,6 +324,8 @@ impl ResultCode {
             ResultCode::EnterpriseOnly => {
                 String::from("Enterprise-only feature not supported by community edition")
             }
+            ResultCode::OpNotApplicable => String::from("Operation not applicable"),
+            ResultCode::FilteredOut => String::from("Transaction filtered out"),
             ResultCode::QueryEnd => String::from("Query end"),
             ResultCode::SecurityNotSupported => String::from("Security not supported"),
             ResultCode::SecurityNotEnabled => String::from("Security not enabled"),
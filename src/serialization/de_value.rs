@@ -0,0 +1,272 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+
+use serde::de::value::BorrowedStrDeserializer;
+use serde::de::{
+    self, Deserialize, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::{FloatValue, Value};
+
+/// Convert a `Value` into any deserializable type, borrowing strings and blobs from the value
+/// where possible.
+///
+/// This is the inverse of `to_value`: maps can be deserialized into structs or maps, lists into
+/// sequences and tuples, and `Value::Nil` into `None` or unit types. Enums are expected in
+/// serde's externally tagged representation, i.e. as the variant name for unit variants and as a
+/// map with the variant name as the single key otherwise.
+pub fn from_value<'de, T: Deserialize<'de>>(value: &'de Value) -> Result<T> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+// Deserializes the bins of a record as a map, keyed by bin name.
+pub(crate) fn from_bins<'de, T: Deserialize<'de>>(bins: &'de HashMap<String, Value>) -> Result<T> {
+    T::deserialize(BinsDeserializer(bins))
+}
+
+struct ValueDeserializer<'de>(&'de Value);
+
+// Map keys; bin names for records, values for all other maps.
+trait MapKey<'de> {
+    type Deserializer: de::Deserializer<'de, Error = Error>;
+
+    fn into_deserializer(self) -> Self::Deserializer;
+}
+
+impl<'de> MapKey<'de> for &'de Value {
+    type Deserializer = ValueDeserializer<'de>;
+
+    fn into_deserializer(self) -> ValueDeserializer<'de> {
+        ValueDeserializer(self)
+    }
+}
+
+impl<'de> MapKey<'de> for &'de str {
+    type Deserializer = BorrowedStrDeserializer<'de, Error>;
+
+    fn into_deserializer(self) -> BorrowedStrDeserializer<'de, Error> {
+        BorrowedStrDeserializer::new(self)
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Nil => visitor.visit_unit(),
+            Value::Bool(b) => visitor.visit_bool(*b),
+            Value::Int(i) => visitor.visit_i64(*i),
+            Value::UInt(u) => visitor.visit_u64(*u),
+            Value::Float(FloatValue::F64(bits)) => visitor.visit_f64(f64::from_bits(*bits)),
+            Value::Float(FloatValue::F32(bits)) => visitor.visit_f32(f32::from_bits(*bits)),
+            Value::String(s) | Value::GeoJSON(s) => visitor.visit_borrowed_str(s),
+            Value::Blob(b) | Value::HLL(b) => visitor.visit_borrowed_bytes(b),
+            Value::List(l) => visitor.visit_seq(SeqDeserializer(l.iter())),
+            Value::HashMap(m) => visitor.visit_map(MapDeserializer::new(m.iter())),
+            Value::OrderedMap(m) => {
+                visitor.visit_map(MapDeserializer::new(m.iter().map(|(k, v)| (k, v))))
+            }
+            Value::Infinity | Value::Wildcard => bail!(ErrorKind::InvalidArgument(format!(
+                "Cannot deserialize {}",
+                self.0
+            ))),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Value::Nil => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            Value::String(_) => visitor.visit_enum(EnumDeserializer {
+                variant: self.0,
+                value: None,
+            }),
+            Value::HashMap(m) if m.len() == 1 => {
+                let (variant, value) = m.iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value: Some(value),
+                })
+            }
+            Value::OrderedMap(m) if m.len() == 1 => visitor.visit_enum(EnumDeserializer {
+                variant: &m[0].0,
+                value: Some(&m[0].1),
+            }),
+            _ => bail!(ErrorKind::InvalidArgument(format!(
+                "Cannot deserialize {} as enum {}",
+                self.0, name
+            ))),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct SeqDeserializer<'de>(std::slice::Iter<'de, Value>);
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct MapDeserializer<'de, I> {
+    entries: I,
+    value: Option<&'de Value>,
+}
+
+impl<'de, I> MapDeserializer<'de, I> {
+    const fn new(entries: I) -> Self {
+        MapDeserializer {
+            entries,
+            value: None,
+        }
+    }
+}
+
+impl<'de, I, K> MapAccess<'de> for MapDeserializer<'de, I>
+where
+    I: Iterator<Item = (K, &'de Value)>,
+    K: MapKey<'de>,
+{
+    type Error = Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<Option<S::Value>> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value> {
+        match self.value.take() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)),
+            None => bail!(ErrorKind::InvalidArgument(
+                "Map value requested before key".to_string()
+            )),
+        }
+    }
+}
+
+static NIL: Value = Value::Nil;
+
+struct EnumDeserializer<'de> {
+    variant: &'de Value,
+    value: Option<&'de Value>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self)> {
+        let variant = seed.deserialize(ValueDeserializer(self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for EnumDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.value {
+            None | Some(Value::Nil) => Ok(()),
+            Some(value) => bail!(ErrorKind::InvalidArgument(format!(
+                "Expected unit variant, found {}",
+                value
+            ))),
+        }
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<S::Value> {
+        seed.deserialize(ValueDeserializer(self.value.unwrap_or(&NIL)))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(ValueDeserializer(self.value.unwrap_or(&NIL)), visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(ValueDeserializer(self.value.unwrap_or(&NIL)), visitor)
+    }
+}
+
+struct BinsDeserializer<'de>(&'de HashMap<String, Value>);
+
+impl<'de> de::Deserializer<'de> for BinsDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(MapDeserializer::new(
+            self.0.iter().map(|(name, value)| (name.as_str(), value)),
+        ))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -0,0 +1,546 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Serde integration. Requires the `serialization` feature.
+//!
+//! `Value`, `Record`, `Key` and `Bins` implement serde's `Serialize` and `Deserialize` traits
+//! directly, mapping values onto the serde data model: lists become sequences, maps become maps,
+//! etc. That representation is convenient for exporting data, but it cannot distinguish e.g. a
+//! `GeoJSON` value from a `String`, or a `HashMap` from an `OrderedMap`.
+//!
+//! Wrapping a value or record in `Tagged` instead serializes every value together with its
+//! type, so that it deserializes to the exact same value. Use this to cache records or pass
+//! them between processes.
+//!
+//! In the other direction, `to_bins` and `to_value` convert any serializable type into bins and
+//! values, and `Record::deserialize` and `from_value` convert records and values into any
+//! deserializable type. This maps application types to records without implementing the
+//! `entity` traits.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aerospike::serialization::Tagged;
+//! use aerospike::Value;
+//!
+//! let value = Value::GeoJSON(r#"{"type":"Point","coordinates":[0,0]}"#.to_string());
+//! let json = serde_json::to_string(&Tagged(value.clone())).unwrap();
+//! let Tagged(decoded) = serde_json::from_str::<Tagged<Value>>(&json).unwrap();
+//! assert_eq!(decoded, value);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::result::Result as StdResult;
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::{FloatValue, Key, Record, Value};
+
+mod de_value;
+mod ser_value;
+
+pub(crate) use self::de_value::from_bins;
+pub use self::de_value::from_value;
+pub use self::ser_value::{to_bins, to_value};
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ErrorKind::InvalidArgument(msg.to_string()).into()
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ErrorKind::InvalidArgument(msg.to_string()).into()
+    }
+}
+
+/// Wrapper that (de-)serializes values in tagged mode, i.e. with the type of every value
+/// preserved. Implemented for `Value` and `Record`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tagged<T>(pub T);
+
+// Externally tagged representation of a value.
+#[derive(Serialize, Deserialize)]
+enum TaggedValue {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Float32(f32),
+    String(String),
+    Blob(Vec<u8>),
+    List(Vec<TaggedValue>),
+    HashMap(Vec<(TaggedValue, TaggedValue)>),
+    OrderedMap(Vec<(TaggedValue, TaggedValue)>),
+    GeoJSON(String),
+    HLL(Vec<u8>),
+    Infinity,
+    Wildcard,
+}
+
+fn pairs<'a, I>(entries: I) -> Vec<(TaggedValue, TaggedValue)>
+where
+    I: Iterator<Item = (&'a Value, &'a Value)>,
+{
+    entries
+        .map(|(k, v)| (TaggedValue::from(k), TaggedValue::from(v)))
+        .collect()
+}
+
+impl From<&Value> for TaggedValue {
+    fn from(value: &Value) -> Self {
+        match value {
+            Value::Nil => TaggedValue::Nil,
+            Value::Bool(b) => TaggedValue::Bool(*b),
+            Value::Int(i) => TaggedValue::Int(*i),
+            Value::UInt(u) => TaggedValue::UInt(*u),
+            Value::Float(FloatValue::F64(bits)) => TaggedValue::Float(f64::from_bits(*bits)),
+            Value::Float(FloatValue::F32(bits)) => TaggedValue::Float32(f32::from_bits(*bits)),
+            Value::String(s) => TaggedValue::String(s.clone()),
+            Value::Blob(b) => TaggedValue::Blob(b.clone()),
+            Value::List(l) => TaggedValue::List(l.iter().map(TaggedValue::from).collect()),
+            Value::HashMap(m) => TaggedValue::HashMap(pairs(m.iter())),
+            Value::OrderedMap(m) => TaggedValue::OrderedMap(pairs(m.iter().map(|(k, v)| (k, v)))),
+            Value::GeoJSON(s) => TaggedValue::GeoJSON(s.clone()),
+            Value::HLL(b) => TaggedValue::HLL(b.clone()),
+            Value::Infinity => TaggedValue::Infinity,
+            Value::Wildcard => TaggedValue::Wildcard,
+        }
+    }
+}
+
+impl TaggedValue {
+    // Fails for map keys that are maps, which cannot be hashed.
+    fn into_value(self) -> Result<Value> {
+        let value = match self {
+            TaggedValue::Nil => Value::Nil,
+            TaggedValue::Bool(b) => Value::Bool(b),
+            TaggedValue::Int(i) => Value::Int(i),
+            TaggedValue::UInt(u) => Value::UInt(u),
+            TaggedValue::Float(f) => Value::from(f),
+            TaggedValue::Float32(f) => Value::from(f),
+            TaggedValue::String(s) => Value::String(s),
+            TaggedValue::Blob(b) => Value::Blob(b),
+            TaggedValue::List(l) => Value::List(
+                l.into_iter()
+                    .map(TaggedValue::into_value)
+                    .collect::<Result<_>>()?,
+            ),
+            TaggedValue::HashMap(m) => {
+                let mut map = HashMap::with_capacity(m.len());
+                for (k, v) in m {
+                    let k = k.into_value()?;
+                    if !k.is_valid_map_key() {
+                        bail!(ErrorKind::InvalidArgument(format!(
+                            "Invalid map key: {}",
+                            k
+                        )));
+                    }
+                    map.insert(k, v.into_value()?);
+                }
+                Value::HashMap(map)
+            }
+            TaggedValue::OrderedMap(m) => Value::OrderedMap(
+                m.into_iter()
+                    .map(|(k, v)| Ok((k.into_value()?, v.into_value()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            TaggedValue::GeoJSON(s) => Value::GeoJSON(s),
+            TaggedValue::HLL(b) => Value::HLL(b),
+            TaggedValue::Infinity => Value::Infinity,
+            TaggedValue::Wildcard => Value::Wildcard,
+        };
+        Ok(value)
+    }
+}
+
+impl Serialize for Tagged<Value> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        TaggedValue::from(&self.0).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tagged<Value> {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = TaggedValue::deserialize(deserializer)?;
+        value.into_value().map(Tagged).map_err(de::Error::custom)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct TaggedRecord {
+    key: Option<Key>,
+    bins: HashMap<String, TaggedValue>,
+    generation: u32,
+    expiration: u32,
+}
+
+impl Serialize for Tagged<Record> {
+    fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let record = &self.0;
+        TaggedRecord {
+            key: record.key.clone(),
+            bins: record
+                .bins
+                .iter()
+                .map(|(name, value)| (name.clone(), TaggedValue::from(value)))
+                .collect(),
+            generation: record.generation,
+            expiration: record.expiration(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Tagged<Record> {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let record = TaggedRecord::deserialize(deserializer)?;
+        let bins = record
+            .bins
+            .into_iter()
+            .map(|(name, value)| Ok((name, value.into_value()?)))
+            .collect::<Result<_>>()
+            .map_err(de::Error::custom)?;
+        Ok(Tagged(Record::new(
+            record.key,
+            bins,
+            record.generation,
+            record.expiration,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::{Deserialize, Serialize};
+
+    use super::{from_value, to_bins, to_value, Tagged};
+    use crate::{Bin, Bins, Key, Record, Value};
+
+    fn values() -> Vec<Value> {
+        vec![
+            Value::Nil,
+            Value::Bool(true),
+            Value::Int(-42),
+            Value::UInt(u64::max_value()),
+            Value::from(1.5f64),
+            Value::from(2.5f32),
+            Value::from("string"),
+            Value::Blob(vec![1, 2, 3]),
+            Value::GeoJSON(r#"{"type":"Point","coordinates":[1,2]}"#.to_string()),
+            Value::HLL(vec![0, 1, 2]),
+            as_list!(1, "two", as_list!(3.0)),
+            as_map!("a" => 1, 2 => as_list!(3)),
+            Value::OrderedMap(vec![(as_val!("b"), as_val!(1)), (as_val!("a"), as_val!(2))]),
+            Value::Infinity,
+            Value::Wildcard,
+        ]
+    }
+
+    fn record() -> Record {
+        let mut bins = HashMap::new();
+        for (i, value) in values().into_iter().enumerate() {
+            bins.insert(format!("bin{}", i), value);
+        }
+        Record::new(Some(as_key!("test", "test", 1)), bins, 3, 123_456)
+    }
+
+    #[test]
+    fn tagged_json() {
+        for value in values() {
+            let json = serde_json::to_string(&Tagged(value.clone())).unwrap();
+            let decoded: Tagged<Value> = serde_json::from_str(&json).unwrap();
+            assert_eq!(decoded.0, value, "{}", json);
+        }
+
+        let record = record();
+        let json = serde_json::to_string(&Tagged(record)).unwrap();
+        let Tagged(decoded) = serde_json::from_str::<Tagged<Record>>(&json).unwrap();
+        let record = self::record();
+        assert_eq!(decoded.key, record.key);
+        assert_eq!(decoded.bins, record.bins);
+        assert_eq!(decoded.generation, 3);
+        assert_eq!(decoded.expiration(), 123_456);
+    }
+
+    #[test]
+    fn tagged_binary() {
+        for value in values() {
+            let bytes = serde_cbor::to_vec(&Tagged(value.clone())).unwrap();
+            let decoded: Tagged<Value> = serde_cbor::from_slice(&bytes).unwrap();
+            assert_eq!(decoded.0, value);
+        }
+
+        let bytes = serde_cbor::to_vec(&Tagged(record())).unwrap();
+        let Tagged(decoded) = serde_cbor::from_slice::<Tagged<Record>>(&bytes).unwrap();
+        assert_eq!(decoded.bins, record().bins);
+        assert_eq!(decoded.expiration(), 123_456);
+    }
+
+    #[test]
+    fn untagged() {
+        let value = as_list!(1, "two", u64::max_value(), as_map!("a" => as_list!(true)));
+        let json = serde_json::to_string(&value).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&json).unwrap(), value);
+        assert_eq!(serde_json::from_str::<Value>("null").unwrap(), Value::Nil);
+        assert_eq!(
+            serde_json::from_str::<Value>("1.5").unwrap(),
+            Value::from(1.5)
+        );
+
+        let blob = Value::Blob(vec![1, 2, 3]);
+        let bytes = serde_cbor::to_vec(&blob).unwrap();
+        assert_eq!(serde_cbor::from_slice::<Value>(&bytes).unwrap(), blob);
+
+        let mut bins = HashMap::new();
+        bins.insert("a".to_string(), as_val!("b"));
+        let record = Record::new(Some(as_key!("test", "test", 1)), bins, 2, 100);
+        let json = serde_json::to_string(&record).unwrap();
+        let decoded: Record = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.key, record.key);
+        assert_eq!(decoded.bins, record.bins);
+        assert_eq!(decoded.generation, 2);
+        assert_eq!(decoded.expiration(), 100);
+    }
+
+    #[test]
+    fn bins() {
+        for bins in vec![
+            Bins::All,
+            Bins::None,
+            Bins::Some(vec!["a".to_string(), "b".to_string()]),
+        ] {
+            let json = serde_json::to_string(&bins).unwrap();
+            assert_eq!(serde_json::from_str::<Bins>(&json).unwrap(), bins);
+            let bytes = serde_cbor::to_vec(&bins).unwrap();
+            assert_eq!(serde_cbor::from_slice::<Bins>(&bytes).unwrap(), bins);
+        }
+    }
+
+    #[test]
+    fn keys() {
+        for key in vec![
+            as_key!("test", "test", 1),
+            as_key!("test", "test", "one"),
+            as_key!("test", "test", vec![1u8, 2, 3]),
+        ] {
+            let json = serde_json::to_string(&key).unwrap();
+            assert_eq!(serde_json::from_str::<Key>(&json).unwrap(), key);
+            let bytes = serde_cbor::to_vec(&key).unwrap();
+            assert_eq!(serde_cbor::from_slice::<Key>(&bytes).unwrap(), key);
+        }
+
+        let key = as_key!("test", "test", 1);
+        let hex = hex::encode(key.digest);
+        let json = format!(
+            r#"{{"namespace":"test","set_name":"test","digest":"{}"}}"#,
+            hex
+        );
+        let decoded: Key = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.digest, key.digest);
+        assert_eq!(decoded.user_key, None);
+
+        let json = format!(
+            r#"{{"namespace":"test","set_name":"test","digest":"{}"}}"#,
+            base64::encode(&key.digest)
+        );
+        let decoded: Key = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.digest, key.digest);
+
+        // digest computed from the user key
+        let json = r#"{"namespace":"test","set_name":"test","user_key":1}"#;
+        assert_eq!(serde_json::from_str::<Key>(json).unwrap(), key);
+
+        let json = r#"{"namespace":"test","set_name":"test","digest":"abcd"}"#;
+        assert!(serde_json::from_str::<Key>(json).is_err());
+        let json = r#"{"namespace":"test","set_name":"test"}"#;
+        assert!(serde_json::from_str::<Key>(json).is_err());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Status {
+        Active,
+        Suspended { reason: String },
+        Score(f64),
+        Pair(i32, i32),
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Address {
+        city: String,
+        zip: Option<u32>,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        name: String,
+        age: u8,
+        balance: f64,
+        tags: Vec<String>,
+        address: Address,
+        nickname: Option<String>,
+        status: Status,
+        history: Vec<Status>,
+        scores: HashMap<String, i64>,
+        big: u64,
+    }
+
+    fn user() -> User {
+        let mut scores = HashMap::new();
+        scores.insert("math".to_string(), 90);
+        User {
+            name: "Alice".to_string(),
+            age: 32,
+            balance: 10.5,
+            tags: vec!["admin".to_string()],
+            address: Address {
+                city: "Berlin".to_string(),
+                zip: None,
+            },
+            nickname: None,
+            status: Status::Suspended {
+                reason: "spam".to_string(),
+            },
+            history: vec![Status::Active, Status::Score(1.5), Status::Pair(1, 2)],
+            scores,
+            big: u64::max_value(),
+        }
+    }
+
+    #[test]
+    fn struct_to_bins() {
+        let bins = to_bins(&user()).unwrap();
+        let names: Vec<&str> = bins.iter().map(Bin::name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "name", "age", "balance", "tags", "address", "nickname", "status", "history",
+                "scores", "big"
+            ]
+        );
+        assert_eq!(bins[1].value, as_val!(32));
+        assert_eq!(bins[3].value, as_list!("admin"));
+        assert_eq!(
+            bins[4].value,
+            as_map!("city" => "Berlin", "zip" => Value::Nil)
+        );
+        assert_eq!(bins[5].value, Value::Nil);
+        assert_eq!(
+            bins[6].value,
+            as_map!("Suspended" => as_map!("reason" => "spam"))
+        );
+        assert_eq!(
+            bins[7].value,
+            as_list!(
+                "Active",
+                as_map!("Score" => 1.5),
+                as_map!("Pair" => as_list!(1, 2))
+            )
+        );
+        assert_eq!(bins[9].value, Value::UInt(u64::max_value()));
+
+        assert!(to_bins(&42).is_err());
+        assert!(to_bins(&vec![1, 2]).is_err());
+        assert!(to_bins(&user().scores).is_err());
+    }
+
+    #[test]
+    fn record_to_struct() {
+        let bins = to_bins(&user())
+            .unwrap()
+            .into_iter()
+            .map(|bin| (bin.name.to_string(), bin.value))
+            .collect();
+        let record = Record::new(None, bins, 1, 0);
+        assert_eq!(record.deserialize::<User>().unwrap(), user());
+
+        // missing bins map to `None`, surplus bins are ignored
+        let mut bins = HashMap::new();
+        bins.insert("city".to_string(), as_val!("Paris"));
+        bins.insert("other".to_string(), as_val!(1));
+        let record = Record::new(None, bins, 1, 0);
+        assert_eq!(
+            record.deserialize::<Address>().unwrap(),
+            Address {
+                city: "Paris".to_string(),
+                zip: None
+            }
+        );
+
+        // mismatched bin types are reported as errors
+        let mut bins = HashMap::new();
+        bins.insert("city".to_string(), as_val!(1));
+        let record = Record::new(None, bins, 1, 0);
+        assert!(record.deserialize::<Address>().is_err());
+
+        // strings are borrowed from the record
+        #[derive(Deserialize)]
+        struct Borrowed<'a> {
+            city: &'a str,
+        }
+        let mut bins = HashMap::new();
+        bins.insert("city".to_string(), as_val!("Rome"));
+        let record = Record::new(None, bins, 1, 0);
+        assert_eq!(record.deserialize::<Borrowed>().unwrap().city, "Rome");
+    }
+
+    #[test]
+    fn value_round_trip() {
+        let value = to_value(&user()).unwrap();
+        assert_eq!(from_value::<User>(&value).unwrap(), user());
+
+        let value = to_value(&(1, "two", Some(3.0))).unwrap();
+        assert_eq!(value, as_list!(1, "two", 3.0));
+        assert_eq!(
+            from_value::<(i32, String, Option<f64>)>(&value).unwrap(),
+            (1, "two".to_string(), Some(3.0))
+        );
+
+        assert!(from_value::<u8>(&as_val!(256)).is_err());
+        assert!(from_value::<Status>(&as_val!("Unknown")).is_err());
+        assert!(from_value::<i64>(&Value::Infinity).is_err());
+    }
+
+    #[test]
+    fn map_keys_rejected() {
+        let mut map = BTreeMap::new();
+        map.insert(BTreeMap::from([(1, 2)]), 3);
+        assert!(to_value(&map).is_err());
+
+        // A tagged hash map whose key is itself a hash map.
+        let int = serde_json::to_string(&Tagged(as_val!(1))).unwrap();
+        let json = serde_json::to_string(&Tagged(as_map!(1 => 1))).unwrap();
+        let nested = json.replacen(&int, &json, 1);
+        assert!(serde_json::from_str::<Tagged<Value>>(&nested).is_err());
+    }
+}
@@ -0,0 +1,552 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::errors::{Error, ErrorKind, Result};
+use crate::{Bin, Value};
+
+/// Convert any serializable type into a `Value`.
+///
+/// Structs and maps become `Value::HashMap`s, sequences and tuples become `Value::List`s, `None`
+/// and unit types become `Value::Nil` and byte buffers serialized as bytes (e.g. using
+/// `serde_bytes`) become `Value::Blob`s. Enums use serde's default, externally tagged
+/// representation: unit variants are serialized as their name, all other variants as a map with
+/// the variant name as the single key.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+    value.serialize(ValueSerializer)
+}
+
+/// Convert a serializable struct into a list of bins, one per struct field.
+///
+/// Field values are converted as by `to_value`. Fields set to `None` are written as `nil` bins,
+/// which removes the bin when the record is updated. Only structs can be converted to bins, as
+/// bin names must be known at compile time; this excludes e.g. structs using
+/// `#[serde(flatten)]`.
+///
+/// # Examples
+///
+/// ```rust
+/// use aerospike::{to_bins, Value};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+///     tags: Vec<String>,
+/// }
+///
+/// let user = User { name: "Alice".to_string(), age: 32, tags: vec!["admin".to_string()] };
+/// let bins = to_bins(&user).unwrap();
+/// assert_eq!(bins[0].name, "name");
+/// assert_eq!(bins[1].value, Value::from(32));
+/// ```
+pub fn to_bins<T: Serialize + ?Sized>(value: &T) -> Result<Vec<Bin<'static>>> {
+    value.serialize(BinsSerializer)
+}
+
+fn unsupported<T>(what: &str) -> Result<T> {
+    bail!(ErrorKind::InvalidArgument(format!(
+        "Cannot convert {} to bins; only structs are supported",
+        what
+    )))
+}
+
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeVariant<SerializeList>;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeVariant<SerializeMap>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        if v > i64::max_value() as u64 {
+            Ok(Value::UInt(v))
+        } else {
+            Ok(Value::Int(v as i64))
+        }
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::from(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Blob(v.to_vec()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::Nil)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::from(variant))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Value> {
+        Ok(variant_map(variant, to_value(value)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeList>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_seq(Some(len))?,
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<SerializeMap> {
+        Ok(SerializeMap {
+            map: HashMap::with_capacity(len.unwrap_or(0)),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeVariant<SerializeMap>> {
+        Ok(SerializeVariant {
+            variant,
+            inner: self.serialize_map(Some(len))?,
+        })
+    }
+}
+
+struct SerializeList(Vec<Value>);
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::List(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeMap {
+    map: HashMap<Value, Value>,
+    key: Option<Value>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = to_value(key)?;
+        if !key.is_valid_map_key() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Invalid map key: {}",
+                key
+            )));
+        }
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().unwrap_or(Value::Nil);
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::HashMap(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.map.insert(Value::from(key), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+// Wraps the value of a tuple or struct variant in a map keyed by the variant name.
+struct SerializeVariant<S> {
+    variant: &'static str,
+    inner: S,
+}
+
+fn variant_map(variant: &'static str, value: Value) -> Value {
+    let mut map = HashMap::with_capacity(1);
+    map.insert(Value::from(variant), value);
+    Value::HashMap(map)
+}
+
+impl ser::SerializeTupleVariant for SerializeVariant<SerializeList> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.inner, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(variant_map(self.variant, Value::List(self.inner.0)))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeVariant<SerializeMap> {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(variant_map(self.variant, Value::HashMap(self.inner.map)))
+    }
+}
+
+// Serializes the fields of a struct into bins.
+struct BinsSerializer;
+
+impl ser::Serializer for BinsSerializer {
+    type Ok = Vec<Bin<'static>>;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<Self::Ok, Error>;
+    type SerializeTuple = Impossible<Self::Ok, Error>;
+    type SerializeTupleStruct = Impossible<Self::Ok, Error>;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = Impossible<Self::Ok, Error>;
+    type SerializeStruct = SerializeBins;
+    type SerializeStructVariant = Impossible<Self::Ok, Error>;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        unsupported("bool")
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        unsupported("integer")
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        unsupported("float")
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        unsupported("float")
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        unsupported("char")
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
+        unsupported("string")
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        unsupported("bytes")
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        unsupported("none")
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        unsupported("unit")
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        unsupported(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        unsupported(name)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        unsupported(name)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        unsupported("sequence")
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        unsupported("tuple")
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        unsupported(name)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        unsupported(name)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        unsupported("map")
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeBins> {
+        Ok(SerializeBins(Vec::with_capacity(len)))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        unsupported(name)
+    }
+}
+
+struct SerializeBins(Vec<Bin<'static>>);
+
+impl ser::SerializeStruct for SerializeBins {
+    type Ok = Vec<Bin<'static>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.0.push(Bin::try_new(key, to_value(value)?)?);
+        Ok(())
+    }
+
+    fn skip_field(&mut self, _key: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.0)
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Point-in-time statistics about the client's view of the cluster.
+//!
+//! All counters are maintained with relaxed atomics on the hot path, so taking a snapshot with
+//! `Client::stats()` is cheap and can be done frequently, e.g. once per second by a metrics
+//! scraper. Counters are cumulative since the node was added to the cluster; compute rates by
+//! taking the difference between two snapshots.
+
+use std::time::Duration;
+
+/// Snapshot of the connection pool and command counters of a single cluster node.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    /// Name of the node.
+    pub name: String,
+
+    /// Address (`host:port`) of the node.
+    pub address: String,
+
+    /// Number of connections currently open to the node, both idle and in use.
+    pub open_connections: usize,
+
+    /// Number of open connections currently waiting in the pool.
+    pub idle_connections: usize,
+
+    /// Number of open connections currently checked out of the pool by a command.
+    pub in_use_connections: usize,
+
+    /// Total number of connections successfully opened to the node.
+    pub connections_opened: usize,
+
+    /// Total number of connections closed, either because they went idle, failed or the pool
+    /// was full.
+    pub connections_closed: usize,
+
+    /// Total number of attempts to open a connection that failed.
+    pub connections_failed: usize,
+
+    /// Total number of connection requests rejected because the pool had reached
+    /// `ClientPolicy::max_conns_per_node` (`ErrorKind::NoMoreConnections`).
+    pub connection_rejections: usize,
+
+    /// Total number of command attempts sent to the node, including retries.
+    pub commands: usize,
+
+    /// Total number of command attempts that failed, including timeouts. Results that are normal
+    /// outcomes of a command, i.e. "key not found", "key exists", generation mismatches and
+    /// records filtered out by an expression, are not counted as failures.
+    pub command_errors: usize,
+
+    /// Total number of command attempts that failed because of a socket timeout.
+    pub command_timeouts: usize,
+}
+
+/// Snapshot of the client's statistics for the whole cluster.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClusterStats {
+    /// Statistics for each active node in the cluster.
+    pub nodes: Vec<NodeStats>,
+
+    /// Total number of cluster tend cycles.
+    pub tend_count: usize,
+
+    /// Total number of cluster tend cycles that returned an error.
+    pub tend_errors: usize,
+
+    /// Duration of the most recent cluster tend cycle.
+    pub last_tend_duration: Duration,
+
+    /// Accumulated duration of all cluster tend cycles.
+    pub total_tend_duration: Duration,
+}
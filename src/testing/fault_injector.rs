@@ -0,0 +1,289 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use parking_lot::Mutex;
+
+use crate::net::transport::Transport;
+use crate::net::Host;
+
+const AS_MSG_TYPE: u8 = 3;
+
+// offset of the result code in a response: proto header + 5 bytes into the message header
+const RESULT_CODE_OFFSET: usize = 8 + 5;
+
+/// A fault injected into the response to a command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Delay the response by the given duration.
+    Latency(Duration),
+
+    /// Reset the connection after the given number of response bytes have been received.
+    DropConnection(usize),
+
+    /// Close the connection after the given number of response bytes have been received, so the
+    /// client sees a truncated frame.
+    TruncateFrame(usize),
+
+    /// Stop delivering the response after the given number of bytes have been received. Reads
+    /// block until the connection's read timeout expires and then fail with a timeout error, like
+    /// they would on a stalled socket. Without a read timeout, reads block forever.
+    StallRead(usize),
+
+    /// Replace the result code of the response (of the first record, for batch, scan and query
+    /// commands) with the given code.
+    ResultCode(u8),
+}
+
+#[derive(Debug)]
+struct Rule {
+    fault: Fault,
+    remaining: Option<usize>,
+}
+
+#[derive(Debug, Default)]
+struct NodeFaults {
+    rules: Vec<Rule>,
+    injected: usize,
+}
+
+/// Injects network faults into the connections of a client, to test how commands handle
+/// timeouts, retries and failover. Requires the `testing` feature.
+///
+/// Faults are configured per node address (`host:port`, as used by the client to connect to the
+/// node) and apply to the responses to record commands (single record, batch, scan and query
+/// commands); info commands used for cluster tending are not affected. When several faults are
+/// configured for a node, each response gets the first one. Register the injector with
+/// `ClientPolicy::fault_injector`; it only applies to connections opened afterwards.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use aerospike::testing::{Fault, FaultInjector, MockServer};
+/// use aerospike::{as_bin, as_key, Client, ClientPolicy, ErrorKind, ResultCode, WritePolicy};
+///
+/// let server = MockServer::start(1).unwrap();
+/// let injector = Arc::new(FaultInjector::new());
+/// let mut policy = ClientPolicy::default();
+/// policy.fault_injector = Some(injector.clone());
+/// let client = Client::new(&policy, &server.hosts()).unwrap();
+///
+/// injector.inject_times(server.address(0), Fault::ResultCode(9), 1);
+/// let key = as_key!("test", "test", 1);
+/// let err = client.put(&WritePolicy::default(), &key, &[as_bin!("a", 1)]).unwrap_err();
+/// match err.kind() {
+///     ErrorKind::ServerError(ResultCode::Timeout) => (),
+///     _ => panic!("unexpected error: {}", err),
+/// }
+/// assert_eq!(injector.injected(server.address(0)), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    nodes: Mutex<HashMap<String, NodeFaults>>,
+}
+
+impl FaultInjector {
+    /// Create a fault injector without any faults configured.
+    pub fn new() -> Self {
+        FaultInjector::default()
+    }
+
+    /// Inject a fault into the responses to all subsequent commands sent to a node.
+    pub fn inject(&self, address: &str, fault: Fault) {
+        self.add_rule(address, fault, None);
+    }
+
+    /// Inject a fault into the responses to the next `count` commands sent to a node.
+    pub fn inject_times(&self, address: &str, fault: Fault, count: usize) {
+        if count > 0 {
+            self.add_rule(address, fault, Some(count));
+        }
+    }
+
+    /// Remove all faults configured for a node.
+    pub fn clear(&self, address: &str) {
+        if let Some(node) = self.nodes.lock().get_mut(address) {
+            node.rules.clear();
+        }
+    }
+
+    /// Remove the faults configured for all nodes.
+    pub fn clear_all(&self) {
+        for node in self.nodes.lock().values_mut() {
+            node.rules.clear();
+        }
+    }
+
+    /// Number of faults injected into the responses of a node so far.
+    pub fn injected(&self, address: &str) -> usize {
+        self.nodes
+            .lock()
+            .get(address)
+            .map_or(0, |node| node.injected)
+    }
+
+    fn add_rule(&self, address: &str, fault: Fault, remaining: Option<usize>) {
+        self.nodes
+            .lock()
+            .entry(address.to_owned())
+            .or_default()
+            .rules
+            .push(Rule { fault, remaining });
+    }
+
+    fn next_fault(&self, address: &str) -> Option<Fault> {
+        let mut nodes = self.nodes.lock();
+        let node = nodes.get_mut(address)?;
+        let rule = node.rules.first_mut()?;
+        let fault = rule.fault.clone();
+        if let Some(ref mut remaining) = rule.remaining {
+            *remaining -= 1;
+            if *remaining == 0 {
+                node.rules.remove(0);
+            }
+        }
+        node.injected += 1;
+        Some(fault)
+    }
+
+    pub(crate) fn transport(self: Arc<Self>, host: &Host, stream: TcpStream) -> FaultyTransport {
+        FaultyTransport {
+            injector: self,
+            address: host.to_string(),
+            stream,
+            request_remaining: 0,
+            fault: None,
+            received: 0,
+        }
+    }
+}
+
+// TCP stream that applies the injector's faults to the responses to record commands.
+#[derive(Debug)]
+pub struct FaultyTransport {
+    injector: Arc<FaultInjector>,
+    address: String,
+    stream: TcpStream,
+
+    // bytes of the current request that have not been written yet
+    request_remaining: usize,
+
+    // fault to apply to the current response, and the number of response bytes received so far
+    fault: Option<Fault>,
+    received: usize,
+}
+
+impl FaultyTransport {
+    fn receive(&mut self, buf: &mut [u8], limit: usize) -> io::Result<usize> {
+        let len = cmp::min(buf.len(), limit.saturating_sub(self.received));
+        let n = self.stream.read(&mut buf[..len])?;
+        self.received += n;
+        Ok(n)
+    }
+
+    fn stall(&self) -> io::Error {
+        match self.stream.read_timeout().unwrap_or(None) {
+            Some(timeout) => thread::sleep(timeout),
+            None => loop {
+                thread::park();
+            },
+        }
+        io::Error::new(io::ErrorKind::WouldBlock, "Read stalled by fault injector")
+    }
+}
+
+impl Read for FaultyTransport {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let fault = match self.fault {
+            Some(ref fault) => fault.clone(),
+            None => return self.stream.read(buf),
+        };
+
+        match fault {
+            Fault::Latency(delay) => {
+                if self.received == 0 {
+                    thread::sleep(delay);
+                }
+                self.receive(buf, usize::max_value())
+            }
+            Fault::DropConnection(after) if self.received >= after => {
+                let _ = self.stream.shutdown(Shutdown::Both);
+                Err(io::Error::new(
+                    io::ErrorKind::ConnectionReset,
+                    "Connection dropped by fault injector",
+                ))
+            }
+            Fault::TruncateFrame(after) if self.received >= after => {
+                let _ = self.stream.shutdown(Shutdown::Both);
+                Ok(0)
+            }
+            Fault::StallRead(after) if self.received >= after => Err(self.stall()),
+            Fault::DropConnection(after)
+            | Fault::TruncateFrame(after)
+            | Fault::StallRead(after) => self.receive(buf, after),
+            Fault::ResultCode(code) => {
+                let start = self.received;
+                let n = self.receive(buf, usize::max_value())?;
+                if (start..self.received).contains(&RESULT_CODE_OFFSET) {
+                    buf[RESULT_CODE_OFFSET - start] = code;
+                }
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl Write for FaultyTransport {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // a new request starts; pick the fault for its response
+        if self.request_remaining == 0 && buf.len() >= 8 {
+            let size = NetworkEndian::read_u64(buf) & 0xFFFF_FFFF_FFFF;
+            self.request_remaining = 8 + size as usize;
+            self.received = 0;
+            self.fault = if buf[1] == AS_MSG_TYPE {
+                self.injector.next_fault(&self.address)
+            } else {
+                None
+            };
+        }
+
+        let n = self.stream.write(buf)?;
+        self.request_remaining = self.request_remaining.saturating_sub(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Transport for FaultyTransport {
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        Transport::shutdown(&self.stream);
+    }
+}
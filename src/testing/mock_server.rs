@@ -0,0 +1,427 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+use std::io::{ErrorKind as IoErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use parking_lot::{Mutex, RwLock};
+
+use crate::cluster::node::PARTITIONS;
+use crate::cluster::partition::Partition;
+use crate::errors::{ErrorKind, Result};
+use crate::testing::store::Store;
+use crate::Key;
+
+const PROTO_VERSION: u8 = 2;
+const INFO_MSG_TYPE: u8 = 1;
+const AS_MSG_TYPE: u8 = 3;
+
+/// Configuration of a `MockServer`.
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// Number of server nodes to simulate. Each node listens on its own local port.
+    pub nodes: usize,
+
+    /// Namespaces served by the cluster. Commands against any other namespace fail with
+    /// `ResultCode::InvalidNamespace`.
+    pub namespaces: Vec<String>,
+
+    /// Cluster name returned by the `cluster-name` info command.
+    pub cluster_name: Option<String>,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        MockConfig {
+            nodes: 1,
+            namespaces: vec!["test".to_string()],
+            cluster_name: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MockNode {
+    name: String,
+    address: String,
+    running: AtomicBool,
+    partition_generation: AtomicUsize,
+    commands: AtomicUsize,
+    // open connections by connection id, to close them when the node is stopped
+    connections: Mutex<HashMap<usize, TcpStream>>,
+    next_connection: AtomicUsize,
+}
+
+impl MockNode {
+    fn stop(&self) {
+        if self.running.swap(false, Ordering::SeqCst) {
+            // wake up the accept loop so it notices that the node was stopped
+            let _ = TcpStream::connect(&self.address);
+            for (_, conn) in self.connections.lock().drain() {
+                let _ = conn.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: MockConfig,
+    nodes: Vec<Arc<MockNode>>,
+    // index of the owning node of each partition, by namespace
+    owners: RwLock<HashMap<String, Vec<usize>>>,
+    store: Store,
+}
+
+/// In-process mock of an Aerospike cluster for tests.
+///
+/// All nodes share a single record store, so a command is served correctly even if it is sent
+/// to a node that does not own the record's partition; `MockServer::commands` can be used to
+/// verify that commands were routed to the owning node. Initially partition `i` is owned by node
+/// `i % nodes`. The server is stopped when it is dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    shared: Arc<Shared>,
+}
+
+impl MockServer {
+    /// Start a mock cluster with the given number of nodes serving the `test` namespace.
+    pub fn start(nodes: usize) -> Result<Self> {
+        MockServer::start_with(MockConfig {
+            nodes,
+            ..MockConfig::default()
+        })
+    }
+
+    /// Start a mock cluster with the given configuration.
+    pub fn start_with(config: MockConfig) -> Result<Self> {
+        if config.nodes == 0 {
+            bail!(ErrorKind::InvalidArgument(
+                "Mock cluster needs at least one node".to_string()
+            ));
+        }
+
+        let mut listeners = Vec::with_capacity(config.nodes);
+        let mut nodes = Vec::with_capacity(config.nodes);
+        for idx in 0..config.nodes {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            nodes.push(Arc::new(MockNode {
+                name: format!("BB9{:013X}", idx + 1),
+                address: listener.local_addr()?.to_string(),
+                running: AtomicBool::new(true),
+                partition_generation: AtomicUsize::new(1),
+                commands: AtomicUsize::new(0),
+                connections: Mutex::new(HashMap::new()),
+                next_connection: AtomicUsize::new(0),
+            }));
+            listeners.push(listener);
+        }
+
+        let owners = config
+            .namespaces
+            .iter()
+            .map(|ns| {
+                (
+                    ns.clone(),
+                    (0..PARTITIONS).map(|pid| pid % config.nodes).collect(),
+                )
+            })
+            .collect();
+
+        let shared = Arc::new(Shared {
+            config,
+            nodes,
+            owners: RwLock::new(owners),
+            store: Store::new(),
+        });
+
+        for (idx, listener) in listeners.into_iter().enumerate() {
+            let shared = shared.clone();
+            thread::spawn(move || Shared::accept(&shared, idx, &listener));
+        }
+
+        Ok(MockServer { shared })
+    }
+
+    /// Comma separated list of the addresses of all running nodes, suitable for `Client::new`.
+    pub fn hosts(&self) -> String {
+        self.shared
+            .nodes
+            .iter()
+            .filter(|node| node.running.load(Ordering::SeqCst))
+            .map(|node| node.address.clone())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Address (`host:port`) of a node.
+    pub fn address(&self, node: usize) -> &str {
+        &self.shared.nodes[node].address
+    }
+
+    /// Names of all nodes, in node index order.
+    pub fn node_names(&self) -> Vec<String> {
+        self.shared
+            .nodes
+            .iter()
+            .map(|node| node.name.clone())
+            .collect()
+    }
+
+    /// Number of record commands (single record, batch and scan) served by a node.
+    pub fn commands(&self, node: usize) -> usize {
+        self.shared.nodes[node].commands.load(Ordering::SeqCst)
+    }
+
+    /// Index of the node that owns a partition.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the namespace is not served by the mock cluster or the partition ID is out of
+    /// range.
+    pub fn partition_owner(&self, namespace: &str, partition_id: usize) -> usize {
+        assert!(
+            partition_id < PARTITIONS,
+            "Invalid partition ID {}",
+            partition_id
+        );
+        let owners = self.shared.owners.read();
+        let owners = owners
+            .get(namespace)
+            .unwrap_or_else(|| panic!("Namespace {} is not served by the mock cluster", namespace));
+        owners[partition_id]
+    }
+
+    /// Index of the node that owns the partition of a key.
+    pub fn owner_of(&self, key: &Key) -> usize {
+        let partition = Partition::new_by_key(key);
+        self.partition_owner(partition.namespace, partition.partition_id)
+    }
+
+    /// Move a partition to another node. The partition generation of all nodes is increased, so
+    /// the client picks up the new partition map on its next cluster tend.
+    pub fn set_partition_owner(&self, namespace: &str, partition_id: usize, node: usize) {
+        assert!(
+            node < self.shared.nodes.len(),
+            "Invalid node index {}",
+            node
+        );
+        if let Some(owners) = self.shared.owners.write().get_mut(namespace) {
+            owners[partition_id] = node;
+        }
+        for node in &self.shared.nodes {
+            node.partition_generation.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Stop a node. Open connections to the node are closed, new connections are refused and the
+    /// node is no longer listed by the `services` info command of the other nodes.
+    pub fn stop_node(&self, node: usize) {
+        self.shared.nodes[node].stop();
+    }
+
+    /// Number of live records in a namespace.
+    pub fn record_count(&self, namespace: &str) -> usize {
+        self.shared.store.len(namespace)
+    }
+
+    /// Remove all records.
+    pub fn clear(&self) {
+        self.shared.store.clear();
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        for node in &self.shared.nodes {
+            node.stop();
+        }
+    }
+}
+
+impl Shared {
+    fn accept(shared: &Arc<Shared>, idx: usize, listener: &TcpListener) {
+        let node = &shared.nodes[idx];
+        for stream in listener.incoming() {
+            if !node.running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("Mock node {}: {}", node.name, err);
+                    continue;
+                }
+            };
+            let id = node.next_connection.fetch_add(1, Ordering::SeqCst);
+            if let Ok(clone) = stream.try_clone() {
+                node.connections.lock().insert(id, clone);
+            }
+
+            let shared = shared.clone();
+            thread::spawn(move || {
+                if let Err(err) = shared.serve(idx, stream) {
+                    debug!("Mock node connection closed: {}", err);
+                }
+                shared.nodes[idx].connections.lock().remove(&id);
+            });
+        }
+    }
+
+    fn serve(&self, idx: usize, mut stream: TcpStream) -> Result<()> {
+        let node = &self.nodes[idx];
+        let mut header = [0u8; 8];
+        loop {
+            if let Err(err) = stream.read_exact(&mut header) {
+                if err.kind() == IoErrorKind::UnexpectedEof {
+                    return Ok(());
+                }
+                bail!(err);
+            }
+
+            let size = (NetworkEndian::read_u64(&header) & 0xFFFF_FFFF_FFFF) as usize;
+            let mut body = vec![0; size];
+            stream.read_exact(&mut body)?;
+            if !node.running.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+
+            let response = match header[1] {
+                INFO_MSG_TYPE => proto_message(INFO_MSG_TYPE, self.info(idx, &body)?.as_bytes()),
+                AS_MSG_TYPE => {
+                    node.commands.fetch_add(1, Ordering::SeqCst);
+                    let body = self.store.handle(&body, &self.config.namespaces)?;
+                    proto_message(AS_MSG_TYPE, &body)
+                }
+                msg_type => bail!(ErrorKind::BadResponse(format!(
+                    "Unsupported message type: {}",
+                    msg_type
+                ))),
+            };
+            stream.write_all(&response)?;
+        }
+    }
+
+    fn info(&self, idx: usize, body: &[u8]) -> Result<String> {
+        let node = &self.nodes[idx];
+        let mut response = String::new();
+        for name in std::str::from_utf8(body)?.split('\n') {
+            if name.is_empty() {
+                continue;
+            }
+
+            let value = match name {
+                "node" => node.name.clone(),
+                "cluster-name" => self.config.cluster_name.clone().unwrap_or_default(),
+                "features" => "float;batch-index;geo".to_string(),
+                "build" | "version" => "5.6.0.0".to_string(),
+                "namespaces" => self.config.namespaces.join(";"),
+                "partition-generation" => {
+                    node.partition_generation.load(Ordering::SeqCst).to_string()
+                }
+                "services" | "services-alternate" => self
+                    .nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, peer)| i != idx && peer.running.load(Ordering::SeqCst))
+                    .map(|(_, peer)| peer.address.clone())
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                "replicas-master" => self.replicas(idx),
+                cmd if cmd.starts_with("truncate:") => {
+                    self.truncate(&cmd["truncate:".len()..]);
+                    "ok".to_string()
+                }
+                _ => String::new(),
+            };
+            response.push_str(name);
+            response.push('\t');
+            response.push_str(&value);
+            response.push('\n');
+        }
+        Ok(response)
+    }
+
+    // <ns>:<base64-encoded partition bitmap>;<ns>:<base64-encoded partition bitmap>; ...
+    fn replicas(&self, idx: usize) -> String {
+        let owners = self.owners.read();
+        let mut replicas = Vec::with_capacity(owners.len());
+        for ns in &self.config.namespaces {
+            let mut bitmap = vec![0u8; PARTITIONS / 8];
+            for (pid, &owner) in owners[ns].iter().enumerate() {
+                if owner == idx {
+                    bitmap[pid >> 3] |= 0x80 >> (pid & 7);
+                }
+            }
+            replicas.push(format!("{}:{}", ns, base64::encode(&bitmap)));
+        }
+        replicas.join(";")
+    }
+
+    fn truncate(&self, params: &str) {
+        let mut namespace = "";
+        let mut set_name = "";
+        for param in params.split(';') {
+            let mut kv = param.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some("namespace"), Some(ns)) => namespace = ns,
+                (Some("set"), Some(set)) => set_name = set,
+                _ => (),
+            }
+        }
+        self.store.truncate(namespace, set_name);
+    }
+}
+
+// Prepends the protocol header to a message body.
+fn proto_message(msg_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut msg = vec![0u8; 8];
+    NetworkEndian::write_u64(
+        &mut msg,
+        body.len() as u64 | (u64::from(PROTO_VERSION) << 56) | (u64::from(msg_type) << 48),
+    );
+    msg.extend_from_slice(body);
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockServer;
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn closed_connections() {
+        let server = MockServer::start(1).unwrap();
+        for _ in 0..10 {
+            drop(TcpStream::connect(server.address(0)).unwrap());
+        }
+        let node = &server.shared.nodes[0];
+        for _ in 0..100 {
+            if node.connections.lock().is_empty() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("{} closed connections kept", node.connections.lock().len());
+    }
+}
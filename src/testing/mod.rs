@@ -0,0 +1,82 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+//! Utilities to test the client and applications using it without a live Aerospike cluster.
+//! Requires the `testing` feature.
+//!
+//! `MockServer` runs one or more in-process server nodes that speak the Aerospike wire protocol
+//! on local ports. It supports the info commands used for cluster tending, single record reads,
+//! writes, deletes and simple operate commands, batch reads and scans. Partition ownership can
+//! be changed at runtime to test cluster tending and command routing deterministically.
+//!
+//! `FaultInjector` injects latency, dropped connections, truncated frames, stalled reads and
+//! server result codes into the responses received by a client, to test timeout, retry and
+//! failover handling.
+//!
+//! `Recorder` captures the frames exchanged between a client and a cluster to a file and
+//! `ReplayServer` serves the captured responses back, to run protocol parsing regression tests
+//! offline against traffic captured from real servers.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use aerospike::testing::MockServer;
+//! use aerospike::{as_bin, as_key, Bins, Client, ClientPolicy, ReadPolicy, WritePolicy};
+//!
+//! let server = MockServer::start(3).unwrap();
+//! let client = Client::new(&ClientPolicy::default(), &server.hosts()).unwrap();
+//!
+//! let key = as_key!("test", "test", 1);
+//! client.put(&WritePolicy::default(), &key, &[as_bin!("a", 1)]).unwrap();
+//! let record = client.get(&ReadPolicy::default(), &key, Bins::All).unwrap();
+//! assert_eq!(record.generation, 1);
+//! assert_eq!(server.commands(server.owner_of(&key)), 2);
+//! ```
+
+use std::net::TcpStream;
+
+use crate::net::transport::Transport;
+use crate::net::Host;
+use crate::policy::ClientPolicy;
+
+mod fault_injector;
+mod mock_server;
+mod recording;
+mod replay_server;
+mod store;
+
+pub use self::fault_injector::{Fault, FaultInjector};
+pub use self::mock_server::{MockConfig, MockServer};
+pub use self::recording::{read_capture, Exchange, Recorder};
+pub use self::replay_server::ReplayServer;
+
+// Wraps a new connection's stream with the fault injector and recorder configured on the policy.
+pub(crate) fn transport(
+    host: &Host,
+    stream: TcpStream,
+    policy: &ClientPolicy,
+) -> Box<dyn Transport> {
+    let mut transport: Box<dyn Transport> = match policy.fault_injector {
+        Some(ref injector) => Box::new(injector.clone().transport(host, stream)),
+        None => Box::new(stream),
+    };
+    if let Some(ref recorder) = policy.recorder {
+        transport = Box::new(recording::RecordingConnection::new(
+            recorder.clone(),
+            transport,
+        ));
+    }
+    transport
+}
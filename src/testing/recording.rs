@@ -0,0 +1,258 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use byteorder::{ByteOrder, NetworkEndian, ReadBytesExt, WriteBytesExt};
+use parking_lot::Mutex;
+
+use crate::commands::field_type::FieldType;
+use crate::errors::{ErrorKind, Result};
+use crate::net::transport::Transport;
+
+const MAGIC: &[u8; 4] = b"ASRC";
+const FORMAT_VERSION: u8 = 1;
+
+// entry types of the capture file
+const REQUEST: u8 = 1;
+const RESPONSE: u8 = 2;
+
+const PROTO_HEADER_SIZE: usize = 8;
+const AS_MSG_TYPE: u8 = 3;
+// offsets into the message header, which follows the proto header
+const TIMEOUT_OFFSET: usize = PROTO_HEADER_SIZE + 14;
+const FIELD_COUNT_OFFSET: usize = PROTO_HEADER_SIZE + 18;
+const FIELDS_OFFSET: usize = PROTO_HEADER_SIZE + 22;
+
+/// A request frame sent by the client together with the response frames it received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exchange {
+    /// Request, incl. the protocol header.
+    pub request: Vec<u8>,
+
+    /// Response frames, incl. their protocol headers, in the order they were received.
+    pub response: Vec<u8>,
+}
+
+impl Exchange {
+    /// Fingerprint identifying the request. Parts of the request that differ between otherwise
+    /// identical commands, i.e. the transaction timeout and the task id of scans and queries, are
+    /// zeroed.
+    pub fn fingerprint(&self) -> Vec<u8> {
+        fingerprint(&self.request)
+    }
+}
+
+pub(crate) fn fingerprint(request: &[u8]) -> Vec<u8> {
+    let mut fingerprint = request.to_vec();
+    if fingerprint.len() < FIELDS_OFFSET || fingerprint[1] != AS_MSG_TYPE {
+        return fingerprint;
+    }
+
+    for byte in &mut fingerprint[TIMEOUT_OFFSET..TIMEOUT_OFFSET + 4] {
+        *byte = 0;
+    }
+    let field_count = NetworkEndian::read_u16(&fingerprint[FIELD_COUNT_OFFSET..]);
+    let mut offset = FIELDS_OFFSET;
+    for _ in 0..field_count {
+        if offset + 5 > fingerprint.len() {
+            break;
+        }
+        let size = NetworkEndian::read_u32(&fingerprint[offset..]) as usize;
+        let end = (offset + 4 + size).min(fingerprint.len());
+        if fingerprint[offset + 4] == FieldType::TranId as u8 {
+            for byte in &mut fingerprint[offset + 5..end] {
+                *byte = 0;
+            }
+        }
+        offset = end;
+    }
+    fingerprint
+}
+
+/// Captures the request and response frames exchanged between a client and the cluster to a
+/// file, which can be served by a `ReplayServer` later on. Requires the `testing` feature.
+///
+/// Register the recorder with `ClientPolicy::recorder`; all connections opened by the client
+/// afterwards are recorded. Frames are written to the file as soon as they are sent or received.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use aerospike::testing::Recorder;
+/// use aerospike::{Client, ClientPolicy};
+///
+/// let mut policy = ClientPolicy::default();
+/// policy.recorder = Some(Arc::new(Recorder::create("session.rec").unwrap()));
+/// let client = Client::new(&policy, &"localhost:3000").unwrap();
+/// // ... run the commands to capture
+/// ```
+#[derive(Debug)]
+pub struct Recorder {
+    file: Mutex<BufWriter<File>>,
+    next_connection: AtomicUsize,
+}
+
+impl Recorder {
+    /// Create a capture file, replacing any existing file at the given path.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_u8(FORMAT_VERSION)?;
+        file.flush()?;
+        Ok(Recorder {
+            file: Mutex::new(file),
+            next_connection: AtomicUsize::new(0),
+        })
+    }
+
+    fn record(&self, entry_type: u8, connection: usize, bytes: &[u8]) {
+        if let Err(err) = self.write_entry(entry_type, connection, bytes) {
+            warn!("Failed to record frame: {}", err);
+        }
+    }
+
+    // <entry type><connection id><length><bytes>
+    fn write_entry(&self, entry_type: u8, connection: usize, bytes: &[u8]) -> io::Result<()> {
+        let mut file = self.file.lock();
+        file.write_u8(entry_type)?;
+        file.write_u32::<NetworkEndian>(connection as u32)?;
+        file.write_u32::<NetworkEndian>(bytes.len() as u32)?;
+        file.write_all(bytes)?;
+        file.flush()
+    }
+}
+
+/// Read all exchanges from a capture file written by a `Recorder`, in the order in which the
+/// requests were sent.
+pub fn read_capture<P: AsRef<Path>>(path: P) -> Result<Vec<Exchange>> {
+    let mut file = BufReader::new(File::open(path)?);
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    if &magic != MAGIC || file.read_u8()? != FORMAT_VERSION {
+        bail!(ErrorKind::InvalidArgument(
+            "Not a capture file, or unsupported format version".to_string()
+        ));
+    }
+
+    let mut exchanges: Vec<Exchange> = vec![];
+    // index of the current exchange of each connection
+    let mut current: HashMap<u32, usize> = HashMap::new();
+    loop {
+        let entry_type = match file.read_u8() {
+            Ok(entry_type) => entry_type,
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => bail!(err),
+        };
+        let connection = file.read_u32::<NetworkEndian>()?;
+        let mut bytes = vec![0; file.read_u32::<NetworkEndian>()? as usize];
+        file.read_exact(&mut bytes)?;
+
+        match entry_type {
+            REQUEST => {
+                current.insert(connection, exchanges.len());
+                exchanges.push(Exchange {
+                    request: bytes,
+                    response: vec![],
+                });
+            }
+            RESPONSE => match current.get(&connection) {
+                Some(&idx) => exchanges[idx].response.extend_from_slice(&bytes),
+                None => bail!(ErrorKind::BadResponse(
+                    "Response without request in capture file".to_string()
+                )),
+            },
+            _ => bail!(ErrorKind::BadResponse(format!(
+                "Invalid entry type {} in capture file",
+                entry_type
+            ))),
+        }
+    }
+    Ok(exchanges)
+}
+
+// Transport that copies all frames sent and received to a recorder.
+#[derive(Debug)]
+pub struct RecordingConnection {
+    recorder: Arc<Recorder>,
+    inner: Box<dyn Transport>,
+    id: usize,
+
+    // bytes of the current request that have not been written yet
+    request_remaining: usize,
+    request: Vec<u8>,
+}
+
+impl RecordingConnection {
+    pub fn new(recorder: Arc<Recorder>, inner: Box<dyn Transport>) -> Self {
+        let id = recorder.next_connection.fetch_add(1, Ordering::Relaxed);
+        RecordingConnection {
+            recorder,
+            inner,
+            id,
+            request_remaining: 0,
+            request: vec![],
+        }
+    }
+}
+
+impl Read for RecordingConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.recorder.record(RESPONSE, self.id, &buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+impl Write for RecordingConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.request_remaining == 0 && buf.len() >= PROTO_HEADER_SIZE {
+            let size = NetworkEndian::read_u64(buf) & 0xFFFF_FFFF_FFFF;
+            self.request_remaining = PROTO_HEADER_SIZE + size as usize;
+        }
+
+        let n = self.inner.write(buf)?;
+        self.request.extend_from_slice(&buf[..n]);
+        self.request_remaining = self.request_remaining.saturating_sub(n);
+        if self.request_remaining == 0 {
+            self.recorder.record(REQUEST, self.id, &self.request);
+            self.request.clear();
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Transport for RecordingConnection {
+    fn set_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+}
@@ -0,0 +1,191 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind as IoErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+use byteorder::{ByteOrder, NetworkEndian};
+use parking_lot::Mutex;
+
+use crate::errors::{ErrorKind, Result};
+use crate::testing::recording::{self, Exchange};
+
+#[derive(Debug)]
+struct Shared {
+    address: String,
+    running: AtomicBool,
+    // recorded responses by request fingerprint, in recording order
+    responses: Mutex<HashMap<Vec<u8>, VecDeque<Vec<u8>>>>,
+    unmatched: AtomicUsize,
+    // open connections by connection id, to close them when the server is stopped
+    connections: Mutex<HashMap<usize, TcpStream>>,
+    next_connection: AtomicUsize,
+}
+
+/// Serves the responses captured by a `Recorder`, so that tests can run offline against real
+/// server traffic. Requires the `testing` feature.
+///
+/// Each request is matched to the recorded requests by its fingerprint (see
+/// `Exchange::fingerprint`). If a request was recorded several times, the recorded responses are
+/// served in order; the last one is repeated once all others have been served, so that periodic
+/// cluster tend requests can be answered indefinitely. Requests that were not recorded are
+/// counted and answered by closing the connection.
+///
+/// All exchanges are served from a single local port, so captures of sessions against a single
+/// node replay faithfully; the client will fail to connect to other nodes listed in a
+/// multi-node capture. The server is stopped when it is dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use aerospike::testing::ReplayServer;
+/// use aerospike::{as_key, Bins, Client, ClientPolicy, ReadPolicy};
+///
+/// let server = ReplayServer::start("tests/captures/get.rec").unwrap();
+/// let client = Client::new(&ClientPolicy::default(), &server.hosts()).unwrap();
+/// client.get(&ReadPolicy::default(), &as_key!("test", "test", 1), Bins::All).unwrap();
+/// assert_eq!(server.unmatched(), 0);
+/// ```
+#[derive(Debug)]
+pub struct ReplayServer {
+    shared: Arc<Shared>,
+}
+
+impl ReplayServer {
+    /// Start a server replaying a capture file written by a `Recorder`.
+    pub fn start<P: AsRef<Path>>(path: P) -> Result<Self> {
+        ReplayServer::with_exchanges(recording::read_capture(path)?)
+    }
+
+    /// Start a server replaying the given exchanges.
+    pub fn with_exchanges(exchanges: Vec<Exchange>) -> Result<Self> {
+        let mut responses: HashMap<Vec<u8>, VecDeque<Vec<u8>>> = HashMap::new();
+        for exchange in exchanges {
+            responses
+                .entry(exchange.fingerprint())
+                .or_default()
+                .push_back(exchange.response);
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let shared = Arc::new(Shared {
+            address: listener.local_addr()?.to_string(),
+            running: AtomicBool::new(true),
+            responses: Mutex::new(responses),
+            unmatched: AtomicUsize::new(0),
+            connections: Mutex::new(HashMap::new()),
+            next_connection: AtomicUsize::new(0),
+        });
+
+        let accept_shared = shared.clone();
+        thread::spawn(move || Shared::accept(&accept_shared, &listener));
+        Ok(ReplayServer { shared })
+    }
+
+    /// Address (`host:port`) of the server, suitable for `Client::new`.
+    pub fn hosts(&self) -> String {
+        self.shared.address.clone()
+    }
+
+    /// Number of requests received that were not found in the capture.
+    pub fn unmatched(&self) -> usize {
+        self.shared.unmatched.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        if self.shared.running.swap(false, Ordering::SeqCst) {
+            // wake up the accept loop so it notices that the server was stopped
+            let _ = TcpStream::connect(&self.shared.address);
+            for (_, conn) in self.shared.connections.lock().drain() {
+                let _ = conn.shutdown(Shutdown::Both);
+            }
+        }
+    }
+}
+
+impl Shared {
+    fn accept(shared: &Arc<Shared>, listener: &TcpListener) {
+        for stream in listener.incoming() {
+            if !shared.running.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("Replay server: {}", err);
+                    continue;
+                }
+            };
+            let id = shared.next_connection.fetch_add(1, Ordering::SeqCst);
+            if let Ok(clone) = stream.try_clone() {
+                shared.connections.lock().insert(id, clone);
+            }
+
+            let shared = shared.clone();
+            thread::spawn(move || {
+                if let Err(err) = shared.serve(stream) {
+                    debug!("Replay server connection closed: {}", err);
+                }
+                shared.connections.lock().remove(&id);
+            });
+        }
+    }
+
+    fn serve(&self, mut stream: TcpStream) -> Result<()> {
+        loop {
+            let mut request = vec![0u8; 8];
+            if let Err(err) = stream.read_exact(&mut request) {
+                if err.kind() == IoErrorKind::UnexpectedEof {
+                    return Ok(());
+                }
+                bail!(err);
+            }
+
+            let size = (NetworkEndian::read_u64(&request) & 0xFFFF_FFFF_FFFF) as usize;
+            request.resize(8 + size, 0);
+            stream.read_exact(&mut request[8..])?;
+
+            let response = match self.response(&request) {
+                Some(response) => response,
+                None => {
+                    self.unmatched.fetch_add(1, Ordering::SeqCst);
+                    let _ = stream.shutdown(Shutdown::Both);
+                    bail!(ErrorKind::BadResponse(
+                        "Request not found in capture".to_string()
+                    ));
+                }
+            };
+            stream.write_all(&response)?;
+        }
+    }
+
+    fn response(&self, request: &[u8]) -> Option<Vec<u8>> {
+        let mut responses = self.responses.lock();
+        let queue = responses.get_mut(&recording::fingerprint(request))?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
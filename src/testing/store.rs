@@ -0,0 +1,810 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Record store of the mock server and the handling of AS_MSG requests against it.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+
+use byteorder::{ByteOrder, LittleEndian, NetworkEndian};
+use parking_lot::Mutex;
+
+use crate::cluster::node::PARTITIONS;
+use crate::commands::buffer::{self, Buffer};
+use crate::commands::field_type::FieldType;
+use crate::errors::{ErrorKind, Result};
+use crate::expressions::FilterExpression;
+use crate::operations::exp::ExpWriteFlags;
+use crate::operations::OperationType;
+use crate::record::CITRUSLEAF_EPOCH;
+use crate::value::bytes_to_particle;
+use crate::{Key, Record, Value};
+
+// Result codes, see `ResultCode`.
+const OK: u8 = 0;
+const KEY_NOT_FOUND_ERROR: u8 = 2;
+const GENERATION_ERROR: u8 = 3;
+const PARAMETER_ERROR: u8 = 4;
+const KEY_EXISTS_ERROR: u8 = 5;
+const BIN_EXISTS_ERROR: u8 = 6;
+const BIN_TYPE_ERROR: u8 = 12;
+const UNSUPPORTED_FEATURE: u8 = 16;
+const BIN_NOT_FOUND: u8 = 17;
+const INVALID_NAMESPACE: u8 = 20;
+const OP_NOT_APPLICABLE: u8 = 26;
+
+// Record TTLs with a special meaning, see `Expiration`.
+const NEVER_EXPIRE: u32 = 0xFFFF_FFFF;
+const DONT_UPDATE: u32 = 0xFFFF_FFFE;
+
+const MSG_HEADER_SIZE: usize = buffer::MSG_REMAINING_HEADER_SIZE as usize;
+
+type Digest = [u8; 20];
+
+#[derive(Debug, Clone)]
+struct MockRecord {
+    set_name: String,
+    // particle type and bytes of the user key, if it was sent with the write
+    user_key: Option<Vec<u8>>,
+    bins: BTreeMap<String, Value>,
+    generation: u32,
+    // seconds since the citrusleaf epoch; 0 if the record never expires
+    void_time: u32,
+}
+
+impl MockRecord {
+    fn is_expired(&self, now: u32) -> bool {
+        self.void_time != 0 && self.void_time <= now
+    }
+}
+
+#[derive(Debug)]
+struct Field {
+    field_type: u8,
+    data: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Op {
+    op_type: u8,
+    particle_type: u8,
+    name: String,
+    data: Vec<u8>,
+}
+
+impl Op {
+    fn value(&self) -> Result<Value> {
+        decode_value(self.particle_type, &self.data)
+    }
+}
+
+#[derive(Debug)]
+struct Request {
+    info1: u8,
+    info2: u8,
+    info3: u8,
+    generation: u32,
+    expiration: u32,
+    fields: Vec<Field>,
+    ops: Vec<Op>,
+}
+
+impl Request {
+    fn parse(body: &[u8]) -> Result<Self> {
+        if body.len() < MSG_HEADER_SIZE {
+            bail!(ErrorKind::BadResponse(
+                "Truncated message header".to_string()
+            ));
+        }
+
+        let mut reader = Reader::new(body);
+        reader.skip(1)?;
+        let info1 = reader.u8()?;
+        let info2 = reader.u8()?;
+        let info3 = reader.u8()?;
+        reader.skip(2)?;
+        let generation = reader.u32()?;
+        let expiration = reader.u32()?;
+        reader.skip(4)?;
+        let field_count = reader.u16()?;
+        let op_count = reader.u16()?;
+
+        let mut fields = Vec::with_capacity(field_count as usize);
+        for _ in 0..field_count {
+            fields.push(reader.field()?);
+        }
+        let mut ops = Vec::with_capacity(op_count as usize);
+        for _ in 0..op_count {
+            ops.push(reader.op()?);
+        }
+
+        Ok(Request {
+            info1,
+            info2,
+            info3,
+            generation,
+            expiration,
+            fields,
+            ops,
+        })
+    }
+
+    fn field(&self, field_type: FieldType) -> Option<&[u8]> {
+        let field_type = field_type as u8;
+        self.fields
+            .iter()
+            .find(|field| field.field_type == field_type)
+            .map(|field| field.data.as_slice())
+    }
+
+    fn string_field(&self, field_type: FieldType) -> Result<String> {
+        Ok(std::str::from_utf8(self.field(field_type).unwrap_or_default())?.to_owned())
+    }
+}
+
+// Bounds-checked reader over a request message.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Reader { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8]> {
+        if self.offset + count > self.data.len() {
+            bail!(ErrorKind::BadResponse("Truncated message".to_string()));
+        }
+        let bytes = &self.data[self.offset..self.offset + count];
+        self.offset += count;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, count: usize) -> Result<()> {
+        self.bytes(count).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(NetworkEndian::read_u16(self.bytes(2)?))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(NetworkEndian::read_u32(self.bytes(4)?))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        Ok(std::str::from_utf8(self.bytes(len)?)?.to_owned())
+    }
+
+    fn field(&mut self) -> Result<Field> {
+        let size = self.u32()? as usize;
+        if size == 0 {
+            bail!(ErrorKind::BadResponse("Invalid field size".to_string()));
+        }
+        let field_type = self.u8()?;
+        let data = self.bytes(size - 1)?.to_vec();
+        Ok(Field { field_type, data })
+    }
+
+    fn op(&mut self) -> Result<Op> {
+        let size = self.u32()? as usize;
+        let op_type = self.u8()?;
+        let particle_type = self.u8()?;
+        self.skip(1)?;
+        let name_len = self.u8()? as usize;
+        if size < 4 + name_len {
+            bail!(ErrorKind::BadResponse("Invalid operation size".to_string()));
+        }
+        let name = self.string(name_len)?;
+        let data = self.bytes(size - 4 - name_len)?.to_vec();
+        Ok(Op {
+            op_type,
+            particle_type,
+            name,
+            data,
+        })
+    }
+}
+
+// Builder for the response to a request, without the protocol header.
+#[derive(Default)]
+struct Response {
+    buf: Vec<u8>,
+}
+
+impl Response {
+    fn header(
+        &mut self,
+        info3: u8,
+        result_code: u8,
+        record: Option<&MockRecord>,
+        batch_index: u32,
+        field_count: u16,
+        op_count: u16,
+    ) {
+        self.buf.push(MSG_HEADER_SIZE as u8);
+        self.buf.extend_from_slice(&[0, 0, info3, 0, result_code]);
+        let (generation, void_time) = record.map_or((0, 0), |rec| (rec.generation, rec.void_time));
+        self.u32(generation);
+        self.u32(void_time);
+        self.u32(batch_index);
+        self.u16(field_count);
+        self.u16(op_count);
+    }
+
+    fn result(result_code: u8) -> Self {
+        let mut response = Response::default();
+        response.header(0, result_code, None, 0, 0, 0);
+        response
+    }
+
+    fn last(&mut self) {
+        self.header(buffer::INFO3_LAST, OK, None, 0, 0, 0);
+    }
+
+    fn u16(&mut self, val: u16) {
+        let mut bytes = [0; 2];
+        NetworkEndian::write_u16(&mut bytes, val);
+        self.buf.extend_from_slice(&bytes);
+    }
+
+    fn u32(&mut self, val: u32) {
+        let mut bytes = [0; 4];
+        NetworkEndian::write_u32(&mut bytes, val);
+        self.buf.extend_from_slice(&bytes);
+    }
+
+    fn field(&mut self, field_type: FieldType, data: &[u8]) {
+        self.u32(data.len() as u32 + 1);
+        self.buf.push(field_type as u8);
+        self.buf.extend_from_slice(data);
+    }
+
+    fn bin(&mut self, name: &str, value: &Value) -> Result<()> {
+        let data = encode_value(value)?;
+        self.u32((4 + name.len() + data.len()) as u32);
+        self.buf.push(OperationType::Read as u8);
+        self.buf.push(value.particle_type() as u8);
+        self.buf.push(0);
+        self.buf.push(name.len() as u8);
+        self.buf.extend_from_slice(name.as_bytes());
+        self.buf.extend_from_slice(&data);
+        Ok(())
+    }
+
+    // Writes a record with its key fields, as returned by batch and scan commands.
+    fn record(
+        &mut self,
+        namespace: &str,
+        digest: &Digest,
+        record: &MockRecord,
+        batch_index: u32,
+        bins: &[(&str, &Value)],
+    ) -> Result<()> {
+        let mut field_count = 2;
+        if !record.set_name.is_empty() {
+            field_count += 1;
+        }
+        if record.user_key.is_some() {
+            field_count += 1;
+        }
+
+        self.header(
+            0,
+            OK,
+            Some(record),
+            batch_index,
+            field_count,
+            bins.len() as u16,
+        );
+        self.field(FieldType::Namespace, namespace.as_bytes());
+        if !record.set_name.is_empty() {
+            self.field(FieldType::Table, record.set_name.as_bytes());
+        }
+        self.field(FieldType::DigestRipe, digest);
+        if let Some(ref user_key) = record.user_key {
+            self.field(FieldType::Key, user_key);
+        }
+        for &(name, value) in bins {
+            self.bin(name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Store {
+    records: Mutex<HashMap<(String, Digest), MockRecord>>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Store::default()
+    }
+
+    pub fn len(&self, namespace: &str) -> usize {
+        let now = now();
+        self.records
+            .lock()
+            .iter()
+            .filter(|&((ns, _), rec)| ns == namespace && !rec.is_expired(now))
+            .count()
+    }
+
+    pub fn clear(&self) {
+        self.records.lock().clear();
+    }
+
+    pub fn truncate(&self, namespace: &str, set_name: &str) {
+        self.records.lock().retain(|(ns, _), rec| {
+            ns != namespace || (!set_name.is_empty() && rec.set_name != set_name)
+        });
+    }
+
+    // Executes an AS_MSG request and returns the response message.
+    pub fn handle(&self, body: &[u8], namespaces: &[String]) -> Result<Vec<u8>> {
+        let request = Request::parse(body)?;
+        let response = if request.info1 & buffer::INFO1_BATCH != 0 {
+            self.batch(&request, namespaces)?
+        } else if request.field(FieldType::DigestRipe).is_some() {
+            self.single(&request, namespaces)?
+        } else {
+            self.scan(&request, namespaces)?
+        };
+        Ok(response.buf)
+    }
+
+    fn single(&self, request: &Request, namespaces: &[String]) -> Result<Response> {
+        let namespace = request.string_field(FieldType::Namespace)?;
+        if !namespaces.contains(&namespace) {
+            return Ok(Response::result(INVALID_NAMESPACE));
+        }
+        if request.field(FieldType::FilterExp).is_some()
+            || request.field(FieldType::UdfPackageName).is_some()
+        {
+            return Ok(Response::result(UNSUPPORTED_FEATURE));
+        }
+
+        let mut digest: Digest = [0; 20];
+        match request.field(FieldType::DigestRipe) {
+            Some(bytes) if bytes.len() == digest.len() => digest.copy_from_slice(bytes),
+            _ => return Ok(Response::result(PARAMETER_ERROR)),
+        }
+
+        let now = now();
+        let mut records = self.records.lock();
+        let id = (namespace, digest);
+        if records.get(&id).map_or(false, |rec| rec.is_expired(now)) {
+            records.remove(&id);
+        }
+        let existing = records.get(&id);
+
+        if request.info2 & buffer::INFO2_DELETE != 0 {
+            if let Some(rc) = check_generation(request, existing) {
+                return Ok(Response::result(rc));
+            }
+            let rc = records.remove(&id).map_or(KEY_NOT_FOUND_ERROR, |_| OK);
+            return Ok(Response::result(rc));
+        }
+
+        if request.info2 & buffer::INFO2_WRITE == 0 {
+            return read(request, &id, existing);
+        }
+
+        if let Some(rc) = check_generation(request, existing) {
+            return Ok(Response::result(rc));
+        }
+        if existing.is_some() && request.info2 & buffer::INFO2_CREATE_ONLY != 0 {
+            return Ok(Response::result(KEY_EXISTS_ERROR));
+        }
+        if existing.is_none()
+            && request.info3 & (buffer::INFO3_UPDATE_ONLY | buffer::INFO3_REPLACE_ONLY) != 0
+        {
+            return Ok(Response::result(KEY_NOT_FOUND_ERROR));
+        }
+
+        let replace =
+            request.info3 & (buffer::INFO3_CREATE_OR_REPLACE | buffer::INFO3_REPLACE_ONLY) != 0;
+        let mut record = match existing {
+            Some(rec) if !replace => rec.clone(),
+            Some(rec) => MockRecord {
+                bins: BTreeMap::new(),
+                ..rec.clone()
+            },
+            None => MockRecord {
+                set_name: request.string_field(FieldType::Table)?,
+                user_key: None,
+                bins: BTreeMap::new(),
+                generation: 0,
+                void_time: 0,
+            },
+        };
+        if let Some(user_key) = request.field(FieldType::Key) {
+            record.user_key = Some(user_key.to_vec());
+        }
+
+        let respond_all = request.info2 & buffer::INFO2_RESPOND_ALL_OPS != 0;
+        let mut results: Vec<(String, Value)> = vec![];
+        let mut deleted = false;
+        for op in &request.ops {
+            match op.op_type {
+                t if t == OperationType::Write as u8 => {
+                    let value = op.value()?;
+                    if value.is_nil() {
+                        record.bins.remove(&op.name);
+                    } else {
+                        record.bins.insert(op.name.clone(), value);
+                    }
+                }
+                t if t == OperationType::Incr as u8 => {
+                    let sum = match (record.bins.get(&op.name), op.value()?) {
+                        (None, value) => value,
+                        (Some(Value::Int(a)), Value::Int(b)) => Value::Int(a.wrapping_add(b)),
+                        (Some(Value::Float(a)), Value::Float(b)) => {
+                            Value::from(f64::from(*a) + f64::from(b))
+                        }
+                        _ => return Ok(Response::result(BIN_TYPE_ERROR)),
+                    };
+                    record.bins.insert(op.name.clone(), sum);
+                }
+                t if t == OperationType::Append as u8 || t == OperationType::Prepend as u8 => {
+                    let append = t == OperationType::Append as u8;
+                    let joined = match (record.bins.get(&op.name), op.value()?) {
+                        (None, value) => value,
+                        (Some(Value::String(a)), Value::String(b)) if append => {
+                            Value::String(format!("{}{}", a, b))
+                        }
+                        (Some(Value::String(a)), Value::String(b)) => {
+                            Value::String(format!("{}{}", b, a))
+                        }
+                        (Some(Value::Blob(a)), Value::Blob(b)) => {
+                            let (head, tail) = if append { (a, &b) } else { (&b, a) };
+                            Value::Blob(head.iter().chain(tail.iter()).cloned().collect())
+                        }
+                        _ => return Ok(Response::result(BIN_TYPE_ERROR)),
+                    };
+                    record.bins.insert(op.name.clone(), joined);
+                }
+                t if t == OperationType::Touch as u8 => {
+                    if existing.is_none() {
+                        return Ok(Response::result(KEY_NOT_FOUND_ERROR));
+                    }
+                }
+                t if t == OperationType::Delete as u8 => {
+                    record.bins.clear();
+                    deleted = true;
+                }
+                t if t == OperationType::Read as u8 => {
+                    if op.name.is_empty() {
+                        results.extend(record.bins.iter().map(|(n, v)| (n.clone(), v.clone())));
+                    } else if let Some(value) = record.bins.get(&op.name) {
+                        results.push((op.name.clone(), value.clone()));
+                    } else if respond_all {
+                        results.push((op.name.clone(), Value::Nil));
+                    }
+                    continue;
+                }
+                t if t == OperationType::ExpRead as u8 => {
+                    let value = match eval_exp(op, &id, &record) {
+                        Ok((_, value)) => value.unwrap_or(Value::Nil),
+                        Err(rc) => return Ok(Response::result(rc)),
+                    };
+                    results.push((op.name.clone(), value));
+                    continue;
+                }
+                t if t == OperationType::ExpWrite as u8 => {
+                    let (flags, value) = match eval_exp(op, &id, &record) {
+                        Ok((flags, Some(value))) => (flags, value),
+                        Ok((_, None)) => continue,
+                        Err(rc) => return Ok(Response::result(rc)),
+                    };
+                    let exists = record.bins.contains_key(&op.name);
+                    let denied = if exists && flags & ExpWriteFlags::CreateOnly as u8 != 0 {
+                        Some(BIN_EXISTS_ERROR)
+                    } else if !exists && flags & ExpWriteFlags::UpdateOnly as u8 != 0 {
+                        Some(BIN_NOT_FOUND)
+                    } else if value.is_nil() && flags & ExpWriteFlags::AllowDelete as u8 == 0 {
+                        Some(OP_NOT_APPLICABLE)
+                    } else {
+                        None
+                    };
+                    match denied {
+                        Some(_) if flags & ExpWriteFlags::PolicyNoFail as u8 != 0 => (),
+                        Some(rc) => return Ok(Response::result(rc)),
+                        None if value.is_nil() => {
+                            record.bins.remove(&op.name);
+                        }
+                        None => {
+                            record.bins.insert(op.name.clone(), value);
+                        }
+                    }
+                }
+                _ => return Ok(Response::result(UNSUPPORTED_FEATURE)),
+            }
+            if respond_all {
+                results.push((op.name.clone(), Value::Nil));
+            }
+        }
+
+        record.generation += 1;
+        record.void_time = match request.expiration {
+            0 | NEVER_EXPIRE => 0,
+            DONT_UPDATE => record.void_time,
+            ttl => now + ttl,
+        };
+
+        let mut response = Response::default();
+        response.header(0, OK, Some(&record), 0, 0, results.len() as u16);
+        for (name, value) in &results {
+            response.bin(name, value)?;
+        }
+
+        // the server removes records without bins
+        if deleted || record.bins.is_empty() {
+            records.remove(&id);
+        } else {
+            records.insert(id, record);
+        }
+        Ok(response)
+    }
+
+    fn batch(&self, request: &Request, namespaces: &[String]) -> Result<Response> {
+        let data = match request
+            .field(FieldType::BatchIndex)
+            .or_else(|| request.field(FieldType::BatchIndexWithSet))
+        {
+            Some(data) => data,
+            None => return Ok(Response::result(PARAMETER_ERROR)),
+        };
+        if request.field(FieldType::FilterExp).is_some() {
+            return Ok(Response::result(UNSUPPORTED_FEATURE));
+        }
+
+        let now = now();
+        let records = self.records.lock();
+        let mut response = Response::default();
+        let mut reader = Reader::new(data);
+        let count = reader.u32()?;
+        reader.skip(1)?; // allow inline
+
+        let mut namespace = String::new();
+        let mut info1 = 0;
+        let mut bin_names: Vec<String> = vec![];
+        for _ in 0..count {
+            let batch_index = reader.u32()?;
+            let mut digest: Digest = [0; 20];
+            digest.copy_from_slice(reader.bytes(20)?);
+
+            // unless the entry repeats the previous entry's namespace and bins
+            if reader.u8()? == 0 {
+                info1 = reader.u8()?;
+                let field_count = reader.u16()?;
+                let op_count = reader.u16()?;
+                for _ in 0..field_count {
+                    let field = reader.field()?;
+                    if field.field_type == FieldType::Namespace as u8 {
+                        namespace = String::from_utf8_lossy(&field.data).into_owned();
+                    }
+                }
+                bin_names.clear();
+                for _ in 0..op_count {
+                    bin_names.push(reader.op()?.name);
+                }
+            }
+
+            if !namespaces.contains(&namespace) {
+                response.header(0, INVALID_NAMESPACE, None, batch_index, 0, 0);
+                continue;
+            }
+            match records.get(&(namespace.clone(), digest)) {
+                Some(rec) if !rec.is_expired(now) => {
+                    let bins = select_bins(rec, info1, &bin_names);
+                    response.record(&namespace, &digest, rec, batch_index, &bins)?;
+                }
+                _ => response.header(0, KEY_NOT_FOUND_ERROR, None, batch_index, 0, 0),
+            }
+        }
+        response.last();
+        Ok(response)
+    }
+
+    fn scan(&self, request: &Request, namespaces: &[String]) -> Result<Response> {
+        let namespace = request.string_field(FieldType::Namespace)?;
+        if !namespaces.contains(&namespace) {
+            let mut response = Response::default();
+            response.header(buffer::INFO3_LAST, INVALID_NAMESPACE, None, 0, 0, 0);
+            return Ok(response);
+        }
+        if request.field(FieldType::IndexRange).is_some()
+            || request.field(FieldType::FilterExp).is_some()
+            || request.field(FieldType::UdfPackageName).is_some()
+        {
+            let mut response = Response::default();
+            response.header(buffer::INFO3_LAST, UNSUPPORTED_FEATURE, None, 0, 0, 0);
+            return Ok(response);
+        }
+
+        let set_name = request.string_field(FieldType::Table)?;
+        let mut partitions = vec![false; PARTITIONS];
+        match request.field(FieldType::PIDArray) {
+            Some(pids) => {
+                for pid in pids.chunks_exact(2) {
+                    partitions[LittleEndian::read_u16(pid) as usize % PARTITIONS] = true;
+                }
+            }
+            None => partitions = vec![true; PARTITIONS],
+        }
+        let bin_names: Vec<String> = request.ops.iter().map(|op| op.name.clone()).collect();
+
+        let now = now();
+        let records = self.records.lock();
+        let mut matches: Vec<(&Digest, &MockRecord)> = records
+            .iter()
+            .filter(|&((ns, digest), rec)| {
+                *ns == namespace
+                    && (set_name.is_empty() || rec.set_name == set_name)
+                    && partitions[partition_id(digest)]
+                    && !rec.is_expired(now)
+            })
+            .map(|((_, digest), rec)| (digest, rec))
+            .collect();
+        matches.sort_by_key(|&(digest, _)| *digest);
+
+        let mut response = Response::default();
+        for (digest, rec) in matches {
+            let bins = select_bins(rec, request.info1, &bin_names);
+            response.record(&namespace, digest, rec, 0, &bins)?;
+        }
+        response.last();
+        Ok(response)
+    }
+}
+
+fn read(
+    request: &Request,
+    id: &(String, Digest),
+    existing: Option<&MockRecord>,
+) -> Result<Response> {
+    let record = match existing {
+        Some(record) => record,
+        None => return Ok(Response::result(KEY_NOT_FOUND_ERROR)),
+    };
+
+    let mut bin_names = Vec::with_capacity(request.ops.len());
+    let mut exp_results = vec![];
+    for op in &request.ops {
+        match op.op_type {
+            t if t == OperationType::Read as u8 => bin_names.push(op.name.clone()),
+            t if t == OperationType::ExpRead as u8 => match eval_exp(op, id, record) {
+                Ok((_, value)) => exp_results.push((op.name.clone(), value.unwrap_or(Value::Nil))),
+                Err(rc) => return Ok(Response::result(rc)),
+            },
+            _ => return Ok(Response::result(UNSUPPORTED_FEATURE)),
+        }
+    }
+
+    // a request with only expression reads does not read any bins
+    let mut bins = if bin_names.is_empty() && !exp_results.is_empty() {
+        vec![]
+    } else {
+        select_bins(record, request.info1, &bin_names)
+    };
+    bins.extend(
+        exp_results
+            .iter()
+            .map(|(name, value)| (name.as_str(), value)),
+    );
+    let mut response = Response::default();
+    response.header(0, OK, Some(record), 0, 0, bins.len() as u16);
+    for (name, value) in bins {
+        response.bin(name, value)?;
+    }
+    Ok(response)
+}
+
+// Bins to return for a read, depending on the read attributes of the request.
+fn select_bins<'a>(
+    record: &'a MockRecord,
+    info1: u8,
+    bin_names: &'a [String],
+) -> Vec<(&'a str, &'a Value)> {
+    if info1 & buffer::INFO1_NOBINDATA != 0 {
+        vec![]
+    } else if info1 & buffer::INFO1_GET_ALL != 0 || bin_names.is_empty() {
+        record
+            .bins
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+            .collect()
+    } else {
+        bin_names
+            .iter()
+            .filter_map(|name| record.bins.get(name).map(|value| (name.as_str(), value)))
+            .collect()
+    }
+}
+
+// Evaluate the expression of an expression read or write op, packed as `[exp, flags]`, against
+// the record. Returns the flags and the result, which is `None` if the evaluation failed and the
+// `EvalNoFail` flag is set, or the result code to fail the request with.
+fn eval_exp(
+    op: &Op,
+    id: &(String, Digest),
+    record: &MockRecord,
+) -> std::result::Result<(u8, Option<Value>), u8> {
+    let (flags, exp) = match op.data.split_last() {
+        Some((&flags, exp)) if flags < 0x80 && exp.first() == Some(&0x92) => (flags, &exp[1..]),
+        _ => return Err(PARAMETER_ERROR),
+    };
+    let exp = FilterExpression::from_bytes(exp).map_err(|_| PARAMETER_ERROR)?;
+    let key = Key {
+        namespace: id.0.clone(),
+        set_name: record.set_name.clone(),
+        user_key: None,
+        digest: id.1,
+    };
+    let bins = record
+        .bins
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    let rec = Record::new(None, bins, record.generation, record.void_time);
+    match exp.evaluate(&rec, &key) {
+        Ok(value) => Ok((flags, Some(value))),
+        Err(_) if flags & ExpWriteFlags::EvalNoFail as u8 != 0 => Ok((flags, None)),
+        Err(_) => Err(OP_NOT_APPLICABLE),
+    }
+}
+
+fn check_generation(request: &Request, existing: Option<&MockRecord>) -> Option<u8> {
+    let generation = existing.map_or(0, |rec| rec.generation);
+    if request.info2 & buffer::INFO2_GENERATION != 0 && request.generation != generation {
+        return Some(GENERATION_ERROR);
+    }
+    if request.info2 & buffer::INFO2_GENERATION_GT != 0 && request.generation <= generation {
+        return Some(GENERATION_ERROR);
+    }
+    None
+}
+
+fn partition_id(digest: &Digest) -> usize {
+    LittleEndian::read_u32(&digest[0..4]) as usize & (PARTITIONS - 1)
+}
+
+// Seconds since the citrusleaf epoch.
+fn now() -> u32 {
+    SystemTime::now()
+        .duration_since(*CITRUSLEAF_EPOCH)
+        .map_or(0, |d| d.as_secs() as u32)
+}
+
+fn encode_value(value: &Value) -> Result<Vec<u8>> {
+    let mut buf = Buffer::new(0);
+    buf.resize_buffer(value.estimate_size()?)?;
+    value.write_to(&mut buf)?;
+    Ok(buf.data_buffer)
+}
+
+fn decode_value(particle_type: u8, data: &[u8]) -> Result<Value> {
+    let mut buf = Buffer::new(0);
+    buf.data_buffer = data.to_vec();
+    bytes_to_particle(particle_type, &mut buf, data.len())
+}
@@ -0,0 +1,132 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Spans for commands and cluster tending. With the `tracing` feature disabled all functions in
+// this module are no-ops and `Span` is a zero-sized stand-in for `tracing::Span`.
+
+use crate::cluster::Node;
+use crate::commands::CommandType;
+use crate::errors::Result;
+use crate::policy::Policy;
+
+#[cfg(feature = "tracing")]
+pub use tracing::Span;
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug, Clone, Default)]
+pub struct Span;
+
+#[cfg(not(feature = "tracing"))]
+#[derive(Debug)]
+pub struct Entered;
+
+#[cfg(not(feature = "tracing"))]
+impl Span {
+    pub const fn current() -> Self {
+        Span
+    }
+
+    pub const fn enter(&self) -> Entered {
+        Entered
+    }
+}
+
+// Span of a single client command, e.g. a `get`, or of one node's share of a multi-node command
+// such as a batch, scan or query. The attempt, size and result fields are recorded as the command
+// progresses.
+#[cfg(feature = "tracing")]
+pub fn command_span(command: CommandType, namespace: &str, set: &str, policy: &dyn Policy) -> Span {
+    tracing::info_span!(
+        "aerospike.command",
+        command = command.as_str(),
+        namespace = namespace,
+        set = set,
+        timeout_ms = policy.timeout().map_or(0, |t| t.as_millis() as u64),
+        max_retries = policy.max_retries().map_or(0, |r| r as u64),
+        node = tracing::field::Empty,
+        iteration = tracing::field::Empty,
+        bytes_sent = tracing::field::Empty,
+        bytes_received = tracing::field::Empty,
+        result_code = tracing::field::Empty,
+    )
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn command_span(_: CommandType, _: &str, _: &str, _: &dyn Policy) -> Span {
+    Span
+}
+
+// Span of a single cluster tend cycle.
+#[cfg(feature = "tracing")]
+pub fn tend_span() -> Span {
+    tracing::debug_span!("aerospike.tend", nodes = tracing::field::Empty)
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn tend_span() -> Span {
+    Span
+}
+
+// Span of the refresh of a single node during a tend cycle.
+#[cfg(feature = "tracing")]
+pub fn refresh_span(node: &Node) -> Span {
+    tracing::debug_span!("aerospike.tend.refresh", node = node.name())
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn refresh_span(_: &Node) -> Span {
+    Span
+}
+
+// Records the node and iteration of a command attempt in the current span.
+#[cfg(feature = "tracing")]
+pub fn record_attempt(node: &Node, iteration: usize) {
+    let span = Span::current();
+    span.record("node", &node.name());
+    span.record("iteration", &(iteration as u64));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn record_attempt(_: &Node, _: usize) {}
+
+// Records the number of bytes sent and received by a command attempt in the current span.
+#[cfg(feature = "tracing")]
+pub fn record_bytes(sent: usize, received: usize) {
+    let span = Span::current();
+    span.record("bytes_sent", &(sent as u64));
+    span.record("bytes_received", &(received as u64));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn record_bytes(_: usize, _: usize) {}
+
+// Records the outcome of a command in the current span.
+#[cfg(feature = "tracing")]
+pub fn record_result<T>(result: &Result<T>) {
+    let label = crate::commands::result_label(result);
+    Span::current().record("result_code", &label.as_str());
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn record_result<T>(_: &Result<T>) {}
+
+// Records the number of nodes in the cluster in the current tend span.
+#[cfg(feature = "tracing")]
+pub fn record_nodes(count: usize) {
+    Span::current().record("nodes", &(count as u64));
+}
+
+#[cfg(not(feature = "tracing"))]
+pub const fn record_nodes(_: usize) {}
@@ -13,10 +13,13 @@
 // License for the specific language governing permissions and limitations under
 // the License.
 
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
 use std::fmt;
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasher, Hash, Hasher};
 use std::result::Result as StdResult;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{f32, f64};
 
 use byteorder::{ByteOrder, NetworkEndian};
@@ -28,13 +31,18 @@ use std::vec::Vec;
 
 use crate::commands::buffer::Buffer;
 use crate::commands::ParticleType;
-use crate::errors::Result;
+use crate::errors::{Error, ErrorKind, Result};
 use crate::msgpack::{decoder, encoder};
 
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
+
+#[cfg(feature = "serialization")]
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
 #[cfg(feature = "serialization")]
 use serde::ser::{SerializeMap, SerializeSeq};
 #[cfg(feature = "serialization")]
-use serde::{Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// Container for floating point bin values stored in the Aerospike database.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -185,7 +193,8 @@ pub enum Value {
 
     /// Map data type where the map entries are sorted based key ordering (K-ordered maps) and may
     /// have an additional value-order index depending the namespace configuration (KV-ordered
-    /// maps).
+    /// maps). Ordered maps are written to the server as K-ordered maps, with the entries sorted by
+    /// key. Use `Value::from(BTreeMap<Value, Value>)` to build one with sorted inserts.
     OrderedMap(Vec<(Value, Value)>),
 
     /// GeoJSON data type are JSON formatted strings to encode geospatial information.
@@ -193,6 +202,16 @@ pub enum Value {
 
     /// HLL value
     HLL(Vec<u8>),
+
+    /// Value that sorts after all other values. Can be used as the end of an open-ended range in
+    /// CDT range operations and expressions, e.g. `get_by_value_range(bin, begin, Infinity, ...)`.
+    /// It is not a valid bin value or record key.
+    Infinity,
+
+    /// Value that matches any other value. Can be used in list values of CDT value operations and
+    /// expressions to match list-of-tuples elements, e.g. `["key", Wildcard]`. It is not a valid
+    /// bin value or record key.
+    Wildcard,
 }
 
 #[allow(clippy::derive_hash_xor_eq)]
@@ -212,22 +231,155 @@ impl Hash for Value {
             Value::List(ref val) => val.hash(state),
             Value::HashMap(_) => panic!("HashMaps cannot be used as map keys."),
             Value::OrderedMap(_) => panic!("OrderedMaps cannot be used as map keys."),
+            Value::Infinity => "INF".hash(state),
+            Value::Wildcard => "*".hash(state),
         }
     }
 }
 
+impl Ord for FloatValue {
+    /// Floats are compared by value, with NaN sorting after all other numbers. Equal values are
+    /// ordered by precision (`F32` first) and then by their bit pattern, to stay consistent with
+    /// `Eq`.
+    fn cmp(&self, other: &FloatValue) -> Ordering {
+        let as_f64 = |val: &FloatValue| match *val {
+            FloatValue::F32(val) => f64::from(f32::from_bits(val)),
+            FloatValue::F64(val) => f64::from_bits(val),
+        };
+        let (a, b) = (as_f64(self), as_f64(other));
+        a.partial_cmp(&b)
+            .unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+            .then_with(|| match (*self, *other) {
+                (FloatValue::F32(a), FloatValue::F32(b)) => a.cmp(&b),
+                (FloatValue::F64(a), FloatValue::F64(b)) => a.cmp(&b),
+                (FloatValue::F32(_), FloatValue::F64(_)) => Ordering::Less,
+                (FloatValue::F64(_), FloatValue::F32(_)) => Ordering::Greater,
+            })
+    }
+}
+
+impl PartialOrd for FloatValue {
+    fn partial_cmp(&self, other: &FloatValue) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Value {
+    // Rank of the value's type in the server's CDT collation order.
+    const fn collation_rank(&self) -> u8 {
+        match *self {
+            Value::Nil => 0,
+            Value::Bool(_) => 1,
+            Value::Int(_) | Value::UInt(_) => 2,
+            Value::String(_) => 3,
+            Value::List(_) => 4,
+            Value::HashMap(_) | Value::OrderedMap(_) => 5,
+            Value::Blob(_) | Value::HLL(_) => 6,
+            Value::Float(_) => 7,
+            Value::GeoJSON(_) => 8,
+            Value::Wildcard => 9,
+            Value::Infinity => 10,
+        }
+    }
+
+    // Map entries in the order the server compares them: sorted by key.
+    pub(crate) fn sorted_entries(&self) -> Vec<(&Value, &Value)> {
+        let mut entries: Vec<(&Value, &Value)> = match *self {
+            Value::HashMap(ref map) => map.iter().collect(),
+            Value::OrderedMap(ref map) => map.iter().map(|(k, v)| (k, v)).collect(),
+            _ => vec![],
+        };
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+}
+
+/// Values are ordered the way the server collates CDT elements: first by type, in the order
+/// `Nil < Bool < Int < String < List < Map < Blob < Float < GeoJSON < Wildcard < Infinity`, and
+/// then by value within each type:
+///
+/// * `Int` and `UInt` values are compared numerically.
+/// * Strings, blobs and GeoJSON strings are compared byte-wise, shorter values first on a common
+///   prefix.
+/// * Lists are compared element by element, shorter lists first on a common prefix.
+/// * Maps are compared by their number of entries first, then entry by entry in key order.
+///
+/// Values that the server considers equal but that are not `==` on the client, e.g. `Int(1)` and
+/// `UInt(1)`, or a `HashMap` and an `OrderedMap` with the same entries, are ordered by variant so
+/// that the ordering stays consistent with `Eq`. Note that the server treats `Wildcard` as equal
+/// to any value when matching; it is given its own position here so that it can be sorted.
+impl Ord for Value {
+    fn cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (&Value::Bool(ref a), &Value::Bool(ref b)) => a.cmp(b),
+            (&Value::Int(ref a), &Value::Int(ref b)) => a.cmp(b),
+            (&Value::UInt(ref a), &Value::UInt(ref b)) => a.cmp(b),
+            (&Value::Int(a), &Value::UInt(b)) => {
+                if a < 0 {
+                    Ordering::Less
+                } else {
+                    (a as u64).cmp(&b).then(Ordering::Less)
+                }
+            }
+            (&Value::UInt(_), &Value::Int(_)) => other.cmp(self).reverse(),
+            (&Value::String(ref a), &Value::String(ref b))
+            | (&Value::GeoJSON(ref a), &Value::GeoJSON(ref b)) => a.cmp(b),
+            (&Value::List(ref a), &Value::List(ref b)) => a.cmp(b),
+            (&Value::Blob(ref a), &Value::Blob(ref b))
+            | (&Value::HLL(ref a), &Value::HLL(ref b)) => a.cmp(b),
+            (&Value::Blob(_), &Value::HLL(_)) => Ordering::Less,
+            (&Value::HLL(_), &Value::Blob(_)) => Ordering::Greater,
+            (&Value::Float(ref a), &Value::Float(ref b)) => a.cmp(b),
+            (&Value::HashMap(ref a), &Value::HashMap(ref b)) => a
+                .len()
+                .cmp(&b.len())
+                .then_with(|| self.sorted_entries().cmp(&other.sorted_entries())),
+            (&Value::OrderedMap(ref a), &Value::OrderedMap(ref b)) => a
+                .len()
+                .cmp(&b.len())
+                .then_with(|| self.sorted_entries().cmp(&other.sorted_entries()))
+                .then_with(|| a.cmp(b)),
+            (&Value::HashMap(ref a), &Value::OrderedMap(ref b)) => a
+                .len()
+                .cmp(&b.len())
+                .then_with(|| self.sorted_entries().cmp(&other.sorted_entries()))
+                .then(Ordering::Less),
+            (&Value::OrderedMap(_), &Value::HashMap(_)) => other.cmp(self).reverse(),
+            _ => self.collation_rank().cmp(&other.collation_rank()),
+        }
+    }
+}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Value) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl Value {
     /// Returns true if this value is the empty value (nil).
     pub const fn is_nil(&self) -> bool {
         matches!(*self, Value::Nil)
     }
 
-    /// Return the particle type for the value used in the wire protocol.
+    /// Returns false for values that cannot be used as `HashMap` keys, since hashing them would
+    /// panic: maps, and lists containing maps.
+    pub(crate) fn is_valid_map_key(&self) -> bool {
+        match *self {
+            Value::HashMap(_) | Value::OrderedMap(_) => false,
+            Value::List(ref list) => list.iter().all(Value::is_valid_map_key),
+            _ => true,
+        }
+    }
+
+    /// Return the particle type for the value used in the wire protocol. `Infinity` and
+    /// `Wildcard` have no particle type and return `ParticleType::NULL`; they are rejected with an
+    /// `InvalidArgument` error when sent as a bin value.
     /// For internal use only.
     #[doc(hidden)]
     pub fn particle_type(&self) -> ParticleType {
         match *self {
-            Value::Nil => ParticleType::NULL,
+            Value::Nil | Value::Infinity | Value::Wildcard => ParticleType::NULL,
             Value::Int(_) | Value::Bool(_) => ParticleType::INTEGER,
             Value::UInt(_) => panic!(
                 "Aerospike does not support u64 natively on server-side. Use casting to \
@@ -238,7 +390,7 @@ impl Value {
             Value::Blob(_) => ParticleType::BLOB,
             Value::List(_) => ParticleType::LIST,
             Value::HashMap(_) => ParticleType::MAP,
-            Value::OrderedMap(_) => panic!("The library never passes ordered maps to the server."),
+            Value::OrderedMap(_) => ParticleType::MAP,
             Value::GeoJSON(_) => ParticleType::GEOJSON,
             Value::HLL(_) => ParticleType::HLL,
         }
@@ -257,6 +409,8 @@ impl Value {
             Value::List(ref val) => format!("{:?}", val),
             Value::HashMap(ref val) => format!("{:?}", val),
             Value::OrderedMap(ref val) => format!("{:?}", val),
+            Value::Infinity => "INF".to_string(),
+            Value::Wildcard => "*".to_string(),
         }
     }
 
@@ -273,10 +427,15 @@ impl Value {
             ),
             Value::String(ref s) => Ok(s.len()),
             Value::Blob(ref b) => Ok(b.len()),
-            Value::List(_) | Value::HashMap(_) => encoder::pack_value(&mut None, self),
-            Value::OrderedMap(_) => panic!("The library never passes ordered maps to the server."),
+            Value::List(_) | Value::HashMap(_) | Value::OrderedMap(_) => {
+                encoder::pack_value(&mut None, self)
+            }
             Value::GeoJSON(ref s) => Ok(1 + 2 + s.len()), // flags + ncells + jsonstr
             Value::HLL(ref h) => Ok(h.len()),
+            Value::Infinity | Value::Wildcard => bail!(ErrorKind::InvalidArgument(format!(
+                "{} is not a valid bin value",
+                self
+            ))),
         }
     }
 
@@ -295,9 +454,14 @@ impl Value {
             Value::Float(ref val) => buf.write_f64(f64::from(val)),
             Value::String(ref val) => buf.write_str(val),
             Value::Blob(ref val) | Value::HLL(ref val) => buf.write_bytes(val),
-            Value::List(_) | Value::HashMap(_) => encoder::pack_value(&mut Some(buf), self),
-            Value::OrderedMap(_) => panic!("The library never passes ordered maps to the server."),
+            Value::List(_) | Value::HashMap(_) | Value::OrderedMap(_) => {
+                encoder::pack_value(&mut Some(buf), self)
+            }
             Value::GeoJSON(ref val) => buf.write_geo(val),
+            Value::Infinity | Value::Wildcard => bail!(ErrorKind::InvalidArgument(format!(
+                "{} is not a valid bin value",
+                self
+            ))),
         }
     }
 
@@ -320,7 +484,10 @@ impl Value {
                 h.input(val);
                 Ok(())
             }
-            _ => panic!("Data type is not supported as Key value."),
+            _ => bail!(ErrorKind::InvalidArgument(format!(
+                "{} is not a valid key value",
+                self
+            ))),
         }
     }
 }
@@ -367,6 +534,12 @@ impl From<HashMap<Value, Value>> for Value {
     }
 }
 
+impl From<BTreeMap<Value, Value>> for Value {
+    fn from(val: BTreeMap<Value, Value>) -> Value {
+        Value::OrderedMap(val.into_iter().collect())
+    }
+}
+
 impl From<f32> for Value {
     fn from(val: f32) -> Value {
         Value::Float(FloatValue::from(val))
@@ -715,9 +888,9 @@ impl<'a> From<&'a Option<Vec<u8>>> for Value {
     }
 }
 
-impl From<Value> for i8 {
-    fn from(val: Value) -> i8 {
-        match val {
+impl<'a> From<&'a Value> for i8 {
+    fn from(val: &'a Value) -> i8 {
+        match *val {
             Value::Int(val) => val as i8,
             Value::UInt(val) => val as i8,
             _ => panic!("Value is not an integer to convert."),
@@ -725,9 +898,9 @@ impl From<Value> for i8 {
     }
 }
 
-impl From<Value> for u8 {
-    fn from(val: Value) -> u8 {
-        match val {
+impl<'a> From<&'a Value> for u8 {
+    fn from(val: &'a Value) -> u8 {
+        match *val {
             Value::Int(val) => val as u8,
             Value::UInt(val) => val as u8,
             _ => panic!("Value is not an integer to convert."),
@@ -735,9 +908,9 @@ impl From<Value> for u8 {
     }
 }
 
-impl From<Value> for i16 {
-    fn from(val: Value) -> i16 {
-        match val {
+impl<'a> From<&'a Value> for i16 {
+    fn from(val: &'a Value) -> i16 {
+        match *val {
             Value::Int(val) => val as i16,
             Value::UInt(val) => val as i16,
             _ => panic!("Value is not an integer to convert."),
@@ -745,9 +918,9 @@ impl From<Value> for i16 {
     }
 }
 
-impl From<Value> for u16 {
-    fn from(val: Value) -> u16 {
-        match val {
+impl<'a> From<&'a Value> for u16 {
+    fn from(val: &'a Value) -> u16 {
+        match *val {
             Value::Int(val) => val as u16,
             Value::UInt(val) => val as u16,
             _ => panic!("Value is not an integer to convert."),
@@ -755,9 +928,9 @@ impl From<Value> for u16 {
     }
 }
 
-impl From<Value> for i32 {
-    fn from(val: Value) -> i32 {
-        match val {
+impl<'a> From<&'a Value> for i32 {
+    fn from(val: &'a Value) -> i32 {
+        match *val {
             Value::Int(val) => val as i32,
             Value::UInt(val) => val as i32,
             _ => panic!("Value is not an integer to convert."),
@@ -765,9 +938,9 @@ impl From<Value> for i32 {
     }
 }
 
-impl From<Value> for u32 {
-    fn from(val: Value) -> u32 {
-        match val {
+impl<'a> From<&'a Value> for u32 {
+    fn from(val: &'a Value) -> u32 {
+        match *val {
             Value::Int(val) => val as u32,
             Value::UInt(val) => val as u32,
             _ => panic!("Value is not an integer to convert."),
@@ -775,9 +948,9 @@ impl From<Value> for u32 {
     }
 }
 
-impl From<Value> for i64 {
-    fn from(val: Value) -> i64 {
-        match val {
+impl<'a> From<&'a Value> for i64 {
+    fn from(val: &'a Value) -> i64 {
+        match *val {
             Value::Int(val) => val,
             Value::UInt(val) => val as i64,
             _ => panic!("Value is not an integer to convert."),
@@ -785,19 +958,19 @@ impl From<Value> for i64 {
     }
 }
 
-impl From<Value> for u64 {
-    fn from(val: Value) -> u64 {
-        match val {
+impl<'a> From<&'a Value> for u64 {
+    fn from(val: &'a Value) -> u64 {
+        match *val {
             Value::Int(val) => val as u64,
-            Value::UInt(val) => val,
+            Value::UInt(val) => val as u64,
             _ => panic!("Value is not an integer to convert."),
         }
     }
 }
 
-impl From<Value> for isize {
-    fn from(val: Value) -> isize {
-        match val {
+impl<'a> From<&'a Value> for isize {
+    fn from(val: &'a Value) -> isize {
+        match *val {
             Value::Int(val) => val as isize,
             Value::UInt(val) => val as isize,
             _ => panic!("Value is not an integer to convert."),
@@ -805,9 +978,9 @@ impl From<Value> for isize {
     }
 }
 
-impl From<Value> for usize {
-    fn from(val: Value) -> usize {
-        match val {
+impl<'a> From<&'a Value> for usize {
+    fn from(val: &'a Value) -> usize {
+        match *val {
             Value::Int(val) => val as usize,
             Value::UInt(val) => val as usize,
             _ => panic!("Value is not an integer to convert."),
@@ -815,72 +988,72 @@ impl From<Value> for usize {
     }
 }
 
-impl From<Value> for bool {
-    fn from(val: Value) -> bool {
-        match val {
+impl<'a> From<&'a Value> for bool {
+    fn from(val: &'a Value) -> bool {
+        match *val {
             Value::Bool(val) => val,
-            _ => panic!("Value is not an integer to convert."),
+            _ => panic!("Value is not a bool to convert."),
         }
     }
 }
 
-impl From<Value> for String {
-    fn from(val: Value) -> String {
+impl<'a> From<&'a Value> for String {
+    fn from(val: &'a Value) -> String {
         match val {
-            Value::String(val) | Value::GeoJSON(val) => val,
-            _ => panic!("Value is not an integer to convert."),
+            Value::String(val) | Value::GeoJSON(val) => val.clone(),
+            _ => panic!("Value is not a string to convert."),
         }
     }
 }
 
-impl From<Value> for Vec<Value> {
-    fn from(val: Value) -> Vec<Value> {
+impl<'a> From<&'a Value> for Vec<Value> {
+    fn from(val: &'a Value) -> Vec<Value> {
         match val {
-            Value::List(val) => val,
-            _ => panic!("Value is not an integer to convert."),
+            Value::List(val) => val.clone(),
+            _ => panic!("Value is not a list to convert."),
         }
     }
 }
 
-impl From<Value> for Vec<u8> {
-    fn from(val: Value) -> Vec<u8> {
+impl<'a> From<&'a Value> for Vec<u8> {
+    fn from(val: &'a Value) -> Vec<u8> {
         match val {
-            Value::Blob(val) => val,
-            _ => panic!("Value is not an integer to convert."),
+            Value::Blob(val) => val.clone(),
+            _ => panic!("Value is not a blob to convert."),
         }
     }
 }
 
-impl From<Value> for FloatValue {
-    fn from(val: Value) -> FloatValue {
+impl<'a> From<&'a Value> for FloatValue {
+    fn from(val: &'a Value) -> FloatValue {
         match val {
-            Value::Float(val) => val,
-            _ => panic!("Value is not an integer to convert."),
+            Value::Float(val) => *val,
+            _ => panic!("Value is not a Float to convert."),
         }
     }
 }
 
-impl From<Value> for f32 {
-    fn from(val: Value) -> f32 {
+impl<'a> From<&'a Value> for f32 {
+    fn from(val: &'a Value) -> f32 {
         match val {
             Value::Float(val) => f32::from(val),
-            _ => panic!("Value is not an integer to convert."),
+            _ => panic!("Value is not a Float to convert."),
         }
     }
 }
 
-impl From<Value> for f64 {
-    fn from(val: Value) -> f64 {
+impl<'a> From<&'a Value> for f64 {
+    fn from(val: &'a Value) -> f64 {
         match val {
             Value::Float(val) => f64::from(val),
-            _ => panic!("Value is not an integer to convert."),
+            _ => panic!("Value is not a float to convert."),
         }
     }
 }
 
-impl From<Value> for Option<i8> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<i8> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as i8),
             Value::UInt(val) => Some(val as i8),
             _ => None,
@@ -888,9 +1061,9 @@ impl From<Value> for Option<i8> {
     }
 }
 
-impl From<Value> for Option<u8> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<u8> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as u8),
             Value::UInt(val) => Some(val as u8),
             _ => None,
@@ -898,9 +1071,9 @@ impl From<Value> for Option<u8> {
     }
 }
 
-impl From<Value> for Option<i16> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<i16> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as i16),
             Value::UInt(val) => Some(val as i16),
             _ => None,
@@ -908,9 +1081,9 @@ impl From<Value> for Option<i16> {
     }
 }
 
-impl From<Value> for Option<u16> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<u16> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as u16),
             Value::UInt(val) => Some(val as u16),
             _ => None,
@@ -918,9 +1091,9 @@ impl From<Value> for Option<u16> {
     }
 }
 
-impl From<Value> for Option<i32> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<i32> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as i32),
             Value::UInt(val) => Some(val as i32),
             _ => None,
@@ -928,9 +1101,9 @@ impl From<Value> for Option<i32> {
     }
 }
 
-impl From<Value> for Option<u32> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<u32> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as u32),
             Value::UInt(val) => Some(val as u32),
             _ => None,
@@ -938,9 +1111,9 @@ impl From<Value> for Option<u32> {
     }
 }
 
-impl From<Value> for Option<i64> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<i64> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val),
             Value::UInt(val) => Some(val as i64),
             _ => None,
@@ -948,9 +1121,9 @@ impl From<Value> for Option<i64> {
     }
 }
 
-impl From<Value> for Option<u64> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<u64> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as u64),
             Value::UInt(val) => Some(val),
             _ => None,
@@ -958,9 +1131,9 @@ impl From<Value> for Option<u64> {
     }
 }
 
-impl From<Value> for Option<isize> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<isize> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as isize),
             Value::UInt(val) => Some(val as isize),
             _ => None,
@@ -968,9 +1141,9 @@ impl From<Value> for Option<isize> {
     }
 }
 
-impl From<Value> for Option<usize> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<usize> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Int(val) => Some(val as usize),
             Value::UInt(val) => Some(val as usize),
             _ => None,
@@ -978,53 +1151,53 @@ impl From<Value> for Option<usize> {
     }
 }
 
-impl From<Value> for Option<bool> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<bool> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Bool(val) => Some(val),
             _ => None,
         }
     }
 }
 
-impl From<Value> for Option<String> {
-    fn from(val: Value) -> Self {
+impl<'a> From<&'a Value> for Option<String> {
+    fn from(val: &'a Value) -> Self {
         match val {
-            Value::String(val) | Value::GeoJSON(val) => Some(val),
+            Value::String(val) | Value::GeoJSON(val) => Some(val.clone()),
             _ => None,
         }
     }
 }
 
-impl From<Value> for Option<Vec<u8>> {
-    fn from(val: Value) -> Self {
+impl<'a> From<&'a Value> for Option<Vec<u8>> {
+    fn from(val: &'a Value) -> Self {
         match val {
-            Value::Blob(val) => Some(val),
+            Value::Blob(val) => Some(val.clone()),
             _ => None,
         }
     }
 }
 
-impl From<Value> for Option<Vec<Value>> {
-    fn from(val: Value) -> Self {
+impl<'a> From<&'a Value> for Option<Vec<Value>> {
+    fn from(val: &'a Value) -> Self {
         match val {
-            Value::List(val) => Some(val),
+            Value::List(val) => Some(val.clone()),
             _ => None,
         }
     }
 }
 
-impl From<Value> for Option<FloatValue> {
-    fn from(val: Value) -> Self {
-        match val {
+impl<'a> From<&'a Value> for Option<FloatValue> {
+    fn from(val: &'a Value) -> Self {
+        match *val {
             Value::Float(val) => Some(val),
             _ => None,
         }
     }
 }
 
-impl From<Value> for Option<f32> {
-    fn from(val: Value) -> Self {
+impl<'a> From<&'a Value> for Option<f32> {
+    fn from(val: &'a Value) -> Self {
         match val {
             Value::Float(val) => Some(f32::from(val)),
             _ => None,
@@ -1032,8 +1205,8 @@ impl From<Value> for Option<f32> {
     }
 }
 
-impl From<Value> for Option<f64> {
-    fn from(val: Value) -> Self {
+impl<'a> From<&'a Value> for Option<f64> {
+    fn from(val: &'a Value) -> Self {
         match val {
             Value::Float(val) => Some(f64::from(val)),
             _ => None,
@@ -1041,394 +1214,341 @@ impl From<Value> for Option<f64> {
     }
 }
 
-impl<'a> From<&'a Value> for i8 {
-    fn from(val: &'a Value) -> i8 {
-        match *val {
-            Value::Int(val) => val as i8,
-            Value::UInt(val) => val as i8,
-            _ => panic!("Value is not an integer to convert."),
-        }
+impl From<Vec<String>> for Value {
+    fn from(val: Vec<String>) -> Self {
+        Value::List(val.into_iter().map(Value::from).collect())
     }
 }
 
-impl<'a> From<&'a Value> for u8 {
-    fn from(val: &'a Value) -> u8 {
-        match *val {
-            Value::Int(val) => val as u8,
-            Value::UInt(val) => val as u8,
-            _ => panic!("Value is not an integer to convert."),
-        }
+impl<'a> From<&'a Vec<String>> for Value {
+    fn from(val: &'a Vec<String>) -> Self {
+        Value::List(val.iter().map(Value::from).collect())
     }
 }
 
-impl<'a> From<&'a Value> for i16 {
-    fn from(val: &'a Value) -> i16 {
-        match *val {
-            Value::Int(val) => val as i16,
-            Value::UInt(val) => val as i16,
-            _ => panic!("Value is not an integer to convert."),
-        }
+impl From<Option<Vec<String>>> for Value {
+    fn from(val: Option<Vec<String>>) -> Self {
+        val.map_or(Value::Nil, |val| {
+            Value::List(val.into_iter().map(Value::from).collect())
+        })
     }
 }
 
-impl<'a> From<&'a Value> for u16 {
-    fn from(val: &'a Value) -> u16 {
-        match *val {
-            Value::Int(val) => val as u16,
-            Value::UInt(val) => val as u16,
-            _ => panic!("Value is not an integer to convert."),
+impl<'a> From<&'a Option<Vec<String>>> for Value {
+    fn from(val: &'a Option<Vec<String>>) -> Self {
+        match val {
+            Some(val) => Value::List(val.iter().map(Value::from).collect()),
+            _ => Value::Nil,
         }
     }
 }
 
-impl<'a> From<&'a Value> for i32 {
-    fn from(val: &'a Value) -> i32 {
-        match *val {
-            Value::Int(val) => val as i32,
-            Value::UInt(val) => val as i32,
-            _ => panic!("Value is not an integer to convert."),
+impl<'a> From<&'a Value> for Vec<String> {
+    fn from(val: &'a Value) -> Self {
+        match val {
+            Value::List(val) => val.iter().map(String::from).collect(),
+            _ => panic!("Value is not a string list to convert."),
         }
     }
 }
 
-impl<'a> From<&'a Value> for u32 {
-    fn from(val: &'a Value) -> u32 {
-        match *val {
-            Value::Int(val) => val as u32,
-            Value::UInt(val) => val as u32,
-            _ => panic!("Value is not an integer to convert."),
+impl<'a> From<&'a Value> for Option<Vec<String>> {
+    fn from(val: &'a Value) -> Self {
+        match val {
+            Value::List(val) => Some(val.iter().map(String::from).collect()),
+            _ => None,
         }
     }
 }
 
-impl<'a> From<&'a Value> for i64 {
-    fn from(val: &'a Value) -> i64 {
-        match *val {
-            Value::Int(val) => val,
-            Value::UInt(val) => val as i64,
-            _ => panic!("Value is not an integer to convert."),
+// Error for a value that cannot be converted into the expected type.
+pub(crate) fn invalid_type(expected: &str, val: &Value) -> Error {
+    let actual = match val {
+        Value::UInt(_) => ParticleType::INTEGER,
+        Value::OrderedMap(_) => ParticleType::MAP,
+        Value::Infinity | Value::Wildcard => {
+            return ErrorKind::InvalidArgument(format!("Cannot convert {} to {}", val, expected))
+                .into()
         }
-    }
+        val => val.particle_type(),
+    };
+    ErrorKind::InvalidType(None, expected.to_string(), actual).into()
+}
+
+macro_rules! try_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl TryFrom<Value> for $t {
+                type Error = Error;
+
+                fn try_from(val: Value) -> Result<$t> {
+                    let converted = match val {
+                        Value::Int(i) => <$t>::try_from(i).ok(),
+                        Value::UInt(u) => <$t>::try_from(u).ok(),
+                        _ => return Err(invalid_type(stringify!($t), &val)),
+                    };
+                    converted.ok_or_else(|| {
+                        ErrorKind::InvalidArgument(format!(
+                            "{} is out of range for {}",
+                            val,
+                            stringify!($t)
+                        ))
+                        .into()
+                    })
+                }
+            }
+        )*
+    };
 }
 
-impl<'a> From<&'a Value> for u64 {
-    fn from(val: &'a Value) -> u64 {
-        match *val {
-            Value::Int(val) => val as u64,
-            Value::UInt(val) => val as u64,
-            _ => panic!("Value is not an integer to convert."),
+try_from_int!(i8, u8, i16, u16, i32, u32, i64, u64, isize, usize);
+
+impl TryFrom<Value> for bool {
+    type Error = Error;
+
+    fn try_from(val: Value) -> Result<bool> {
+        match val {
+            Value::Bool(val) => Ok(val),
+            _ => Err(invalid_type("bool", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for isize {
-    fn from(val: &'a Value) -> isize {
-        match *val {
-            Value::Int(val) => val as isize,
-            Value::UInt(val) => val as isize,
-            _ => panic!("Value is not an integer to convert."),
+impl TryFrom<Value> for String {
+    type Error = Error;
+
+    fn try_from(val: Value) -> Result<String> {
+        match val {
+            Value::String(val) | Value::GeoJSON(val) => Ok(val),
+            _ => Err(invalid_type("String", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for usize {
-    fn from(val: &'a Value) -> usize {
-        match *val {
-            Value::Int(val) => val as usize,
-            Value::UInt(val) => val as usize,
-            _ => panic!("Value is not an integer to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for bool {
-    fn from(val: &'a Value) -> bool {
-        match *val {
-            Value::Bool(val) => val,
-            _ => panic!("Value is not a bool to convert."),
-        }
-    }
-}
+impl TryFrom<Value> for FloatValue {
+    type Error = Error;
 
-impl<'a> From<&'a Value> for String {
-    fn from(val: &'a Value) -> String {
+    fn try_from(val: Value) -> Result<FloatValue> {
         match val {
-            Value::String(val) | Value::GeoJSON(val) => val.clone(),
-            _ => panic!("Value is not a string to convert."),
+            Value::Float(val) => Ok(val),
+            _ => Err(invalid_type("float", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Vec<Value> {
-    fn from(val: &'a Value) -> Vec<Value> {
-        match val {
-            Value::List(val) => val.clone(),
-            _ => panic!("Value is not a list to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for Vec<u8> {
-    fn from(val: &'a Value) -> Vec<u8> {
-        match val {
-            Value::Blob(val) => val.clone(),
-            _ => panic!("Value is not a blob to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for FloatValue {
-    fn from(val: &'a Value) -> FloatValue {
-        match val {
-            Value::Float(val) => *val,
-            _ => panic!("Value is not a Float to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for f32 {
-    fn from(val: &'a Value) -> f32 {
-        match val {
-            Value::Float(val) => f32::from(val),
-            _ => panic!("Value is not a Float to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for f64 {
-    fn from(val: &'a Value) -> f64 {
-        match val {
-            Value::Float(val) => f64::from(val),
-            _ => panic!("Value is not a float to convert."),
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for Option<i8> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as i8),
-            Value::UInt(val) => Some(val as i8),
-            _ => None,
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for Option<u8> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as u8),
-            Value::UInt(val) => Some(val as u8),
-            _ => None,
-        }
-    }
-}
-
-impl<'a> From<&'a Value> for Option<i16> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as i16),
-            Value::UInt(val) => Some(val as i16),
-            _ => None,
-        }
-    }
-}
+impl TryFrom<Value> for f32 {
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<u16> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as u16),
-            Value::UInt(val) => Some(val as u16),
-            _ => None,
-        }
+    fn try_from(val: Value) -> Result<f32> {
+        FloatValue::try_from(val).map(f32::from)
     }
 }
 
-impl<'a> From<&'a Value> for Option<i32> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as i32),
-            Value::UInt(val) => Some(val as i32),
-            _ => None,
-        }
-    }
-}
+impl TryFrom<Value> for f64 {
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<u32> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as u32),
-            Value::UInt(val) => Some(val as u32),
-            _ => None,
-        }
+    fn try_from(val: Value) -> Result<f64> {
+        FloatValue::try_from(val).map(f64::from)
     }
 }
 
-impl<'a> From<&'a Value> for Option<i64> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val),
-            Value::UInt(val) => Some(val as i64),
-            _ => None,
-        }
-    }
-}
+/// Converts lists element by element. Blobs are converted byte by byte, e.g. into a `Vec<u8>`.
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value>,
+    Error: From<T::Error>,
+{
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<u64> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as u64),
-            Value::UInt(val) => Some(val),
-            _ => None,
+    fn try_from(val: Value) -> Result<Vec<T>> {
+        match val {
+            Value::List(list) => list.into_iter().map(|val| Ok(T::try_from(val)?)).collect(),
+            Value::Blob(bytes) => bytes
+                .into_iter()
+                .map(|byte| Ok(T::try_from(Value::from(byte))?))
+                .collect(),
+            _ => Err(invalid_type("list", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Option<isize> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as isize),
-            Value::UInt(val) => Some(val as isize),
-            _ => None,
-        }
-    }
-}
+/// Converts both unordered and ordered maps.
+impl<K, V, S> TryFrom<Value> for HashMap<K, V, S>
+where
+    K: TryFrom<Value> + Eq + Hash,
+    V: TryFrom<Value>,
+    S: BuildHasher + Default,
+    Error: From<K::Error> + From<V::Error>,
+{
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<usize> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Int(val) => Some(val as usize),
-            Value::UInt(val) => Some(val as usize),
-            _ => None,
+    fn try_from(val: Value) -> Result<HashMap<K, V, S>> {
+        match val {
+            Value::HashMap(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((K::try_from(k)?, V::try_from(v)?)))
+                .collect(),
+            Value::OrderedMap(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((K::try_from(k)?, V::try_from(v)?)))
+                .collect(),
+            _ => Err(invalid_type("map", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Option<bool> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Bool(val) => Some(val),
-            _ => None,
-        }
-    }
-}
+/// Converts both unordered and ordered maps.
+impl<K, V> TryFrom<Value> for BTreeMap<K, V>
+where
+    K: TryFrom<Value> + Ord,
+    V: TryFrom<Value>,
+    Error: From<K::Error> + From<V::Error>,
+{
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<String> {
-    fn from(val: &'a Value) -> Self {
+    fn try_from(val: Value) -> Result<BTreeMap<K, V>> {
         match val {
-            Value::String(val) | Value::GeoJSON(val) => Some(val.clone()),
-            _ => None,
+            Value::HashMap(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((K::try_from(k)?, V::try_from(v)?)))
+                .collect(),
+            Value::OrderedMap(map) => map
+                .into_iter()
+                .map(|(k, v)| Ok((K::try_from(k)?, V::try_from(v)?)))
+                .collect(),
+            _ => Err(invalid_type("map", &val)),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Option<Vec<u8>> {
-    fn from(val: &'a Value) -> Self {
-        match val {
-            Value::Blob(val) => Some(val.clone()),
-            _ => None,
-        }
-    }
-}
+/// Converts `Value::Nil` into `None`.
+impl<T> TryFrom<Value> for Option<T>
+where
+    T: TryFrom<Value, Error = Error>,
+{
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<Vec<Value>> {
-    fn from(val: &'a Value) -> Self {
+    fn try_from(val: Value) -> Result<Option<T>> {
         match val {
-            Value::List(val) => Some(val.clone()),
-            _ => None,
+            Value::Nil => Ok(None),
+            val => T::try_from(val).map(Some),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Option<FloatValue> {
-    fn from(val: &'a Value) -> Self {
-        match *val {
-            Value::Float(val) => Some(val),
-            _ => None,
-        }
+/// Points in time are stored as integers, in nanoseconds since the Unix epoch, like the record
+/// metadata returned by the `last_update` expression. Times outside of the range of `i64`
+/// nanoseconds (approx. the years 1678 to 2262) are saturated.
+impl From<SystemTime> for Value {
+    fn from(val: SystemTime) -> Value {
+        let nanos = match val.duration_since(UNIX_EPOCH) {
+            Ok(since) => i64::try_from(since.as_nanos()).unwrap_or(i64::max_value()),
+            Err(err) => i64::try_from(err.duration().as_nanos())
+                .map(|nanos| -nanos)
+                .unwrap_or(i64::min_value()),
+        };
+        Value::Int(nanos)
     }
 }
 
-impl<'a> From<&'a Value> for Option<f32> {
-    fn from(val: &'a Value) -> Self {
-        match val {
-            Value::Float(val) => Some(f32::from(val)),
-            _ => None,
-        }
-    }
-}
+impl TryFrom<Value> for SystemTime {
+    type Error = Error;
 
-impl<'a> From<&'a Value> for Option<f64> {
-    fn from(val: &'a Value) -> Self {
+    fn try_from(val: Value) -> Result<SystemTime> {
         match val {
-            Value::Float(val) => Some(f64::from(val)),
-            _ => None,
+            Value::Int(nanos) if nanos >= 0 => Ok(UNIX_EPOCH + Duration::from_nanos(nanos as u64)),
+            Value::Int(nanos) => Ok(UNIX_EPOCH - Duration::from_nanos(nanos.wrapping_neg() as u64)),
+            _ => Err(invalid_type("SystemTime", &val)),
         }
     }
 }
 
-impl From<Vec<String>> for Value {
-    fn from(val: Vec<String>) -> Self {
-        Value::List(val.into_iter().map(Value::from).collect())
-    }
-}
-
-impl<'a> From<&'a Vec<String>> for Value {
-    fn from(val: &'a Vec<String>) -> Self {
-        Value::List(val.iter().map(Value::from).collect())
+/// Stored as integers, in nanoseconds since the Unix epoch; see `From<SystemTime>`. Requires the
+/// `chrono` feature.
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for Value {
+    fn from(val: DateTime<Utc>) -> Value {
+        let nanos = val
+            .timestamp()
+            .saturating_mul(1_000_000_000)
+            .saturating_add(i64::from(val.timestamp_subsec_nanos()));
+        Value::Int(nanos)
     }
 }
 
-impl From<Option<Vec<String>>> for Value {
-    fn from(val: Option<Vec<String>>) -> Self {
-        val.map_or(
-            Value::Nil, 
-            |val| Value::List(val.into_iter().map(Value::from).collect())
-        )
-    }
-}
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for DateTime<Utc> {
+    type Error = Error;
 
-impl<'a> From<&'a Option<Vec<String>>> for Value {
-    fn from(val: &'a Option<Vec<String>>) -> Self {
+    fn try_from(val: Value) -> Result<DateTime<Utc>> {
         match val {
-            Some(val) => Value::List(val.iter().map(Value::from).collect()),
-            _ => Value::Nil,
+            Value::Int(nanos) => Ok(Utc.timestamp_nanos(nanos)),
+            _ => Err(invalid_type("DateTime", &val)),
         }
     }
 }
 
-impl From<Value> for Vec<String> {
-    fn from(val: Value) -> Self {
+/// JSON documents map to nested lists and maps with string keys; integers that do not fit an
+/// `i64` become `UInt`. Requires the `serde_json` feature.
+#[cfg(feature = "serde_json")]
+impl From<serde_json::Value> for Value {
+    fn from(val: serde_json::Value) -> Value {
         match val {
-            Value::List(val) => val.into_iter().map(String::from).collect(),
-            _ => panic!("Value is not a string list to convert."),
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(val) => Value::Bool(val),
+            serde_json::Value::Number(val) => match (val.as_i64(), val.as_u64()) {
+                (Some(val), _) => Value::Int(val),
+                (None, Some(val)) => Value::UInt(val),
+                (None, None) => Value::from(val.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(val) => Value::String(val),
+            serde_json::Value::Array(list) => {
+                Value::List(list.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(map) => Value::HashMap(
+                map.into_iter()
+                    .map(|(k, v)| (Value::String(k), Value::from(v)))
+                    .collect(),
+            ),
         }
     }
 }
 
-impl<'a> From<&'a Value> for Vec<String> {
-    fn from(val: &'a Value) -> Self {
-        match val {
-            Value::List(val) => val.iter().map(String::from).collect(),
-            _ => panic!("Value is not a string list to convert."),
-        }
-    }
-}
+/// Fails for values without a JSON representation: blobs, GeoJSON and HLL values, non-finite
+/// floats and maps with keys other than strings. Requires the `serde_json` feature.
+#[cfg(feature = "serde_json")]
+impl TryFrom<Value> for serde_json::Value {
+    type Error = Error;
 
-impl From<Value> for Option<Vec<String>> {
-    fn from(val: Value) -> Self {
-        match val {
-            Value::List(val) => Some(val.into_iter().map(String::from).collect()),
-            _ => None,
+    fn try_from(val: Value) -> Result<serde_json::Value> {
+        fn object(entries: Vec<(Value, Value)>) -> Result<serde_json::Value> {
+            let mut map = serde_json::Map::with_capacity(entries.len());
+            for (k, v) in entries {
+                match k {
+                    Value::String(k) => map.insert(k, serde_json::Value::try_from(v)?),
+                    k => return Err(invalid_type("string map key", &k)),
+                };
+            }
+            Ok(serde_json::Value::Object(map))
         }
-    }
-}
 
-impl<'a> From<&'a Value> for Option<Vec<String>> {
-    fn from(val: &'a Value) -> Self {
         match val {
-            Value::List(val) => Some(val.iter().map(String::from).collect()),
-            _ => None,
+            Value::Nil => Ok(serde_json::Value::Null),
+            Value::Bool(val) => Ok(serde_json::Value::Bool(val)),
+            Value::Int(val) => Ok(serde_json::Value::from(val)),
+            Value::UInt(val) => Ok(serde_json::Value::from(val)),
+            Value::Float(val) => serde_json::Number::from_f64(f64::from(val))
+                .map(serde_json::Value::Number)
+                .ok_or_else(|| invalid_type("finite float", &Value::Float(val))),
+            Value::String(val) => Ok(serde_json::Value::String(val)),
+            Value::List(list) => list
+                .into_iter()
+                .map(serde_json::Value::try_from)
+                .collect::<Result<_>>()
+                .map(serde_json::Value::Array),
+            Value::HashMap(map) => object(map.into_iter().collect()),
+            Value::OrderedMap(entries) => object(entries),
+            _ => Err(invalid_type("JSON value", &val)),
         }
     }
 }
@@ -1633,13 +1753,137 @@ impl Serialize for Value {
                 map.end()
             }
             Value::HLL(b) => serializer.serialize_bytes(&b[..]),
+            Value::Infinity => serializer.serialize_unit_variant("Value", 12, "Infinity"),
+            Value::Wildcard => serializer.serialize_unit_variant("Value", 13, "Wildcard"),
+        }
+    }
+}
+
+// Deserializes values from the data model of self-describing formats. Blobs are only recovered
+// from formats that distinguish byte strings, and the particle types that have no counterpart in
+// the serde data model (`GeoJSON`, `HLL`, `OrderedMap`, floats, etc.) are not preserved; use
+// `serialization::Tagged` to round-trip values without losing their type.
+#[cfg(feature = "serialization")]
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+#[cfg(feature = "serialization")]
+struct ValueVisitor;
+
+#[cfg(feature = "serialization")]
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an Aerospike value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> StdResult<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> StdResult<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> StdResult<Value, E> {
+        if v > i64::max_value() as u64 {
+            Ok(Value::UInt(v))
+        } else {
+            Ok(Value::Int(v as i64))
+        }
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> StdResult<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> StdResult<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> StdResult<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> StdResult<Value, E> {
+        Ok(Value::Blob(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> StdResult<Value, E> {
+        Ok(Value::Blob(v))
+    }
+
+    fn visit_none<E: de::Error>(self) -> StdResult<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> StdResult<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> StdResult<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> StdResult<Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut list = Vec::with_capacity(cautious_capacity(seq.size_hint()));
+        while let Some(elem) = seq.next_element()? {
+            list.push(elem);
+        }
+        Ok(Value::List(list))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> StdResult<Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut entries = HashMap::with_capacity(cautious_capacity(map.size_hint()));
+        while let Some((key, value)) = map.next_entry::<Value, Value>()? {
+            if !key.is_valid_map_key() {
+                return Err(de::Error::custom(format!("invalid map key: {}", key)));
+            }
+            entries.insert(key, value);
         }
+        Ok(Value::HashMap(entries))
     }
 }
 
+// Binary formats report the length read from the input as size hint; do not trust it for more
+// than a modest preallocation.
+#[cfg(feature = "serialization")]
+fn cautious_capacity(size_hint: Option<usize>) -> usize {
+    size_hint.unwrap_or(0).min(4096)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Value;
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{FloatValue, Value};
+    use crate::errors::{Error, ErrorKind};
+    use crate::ParticleType;
+
+    fn invalid_type(err: Error) -> Option<(String, ParticleType)> {
+        match err.0 {
+            ErrorKind::InvalidType(None, expected, actual) => Some((expected, actual)),
+            _ => None,
+        }
+    }
 
     #[test]
     fn as_string() {
@@ -1657,6 +1901,280 @@ mod tests {
         );
     }
 
+    #[test]
+    fn infinity_and_wildcard() {
+        use crate::commands::buffer::Buffer;
+        use crate::msgpack::{decoder, encoder};
+
+        assert_eq!(Value::Infinity.as_string(), "INF");
+        assert_eq!(Value::Wildcard.as_string(), "*");
+        assert!(Value::Infinity.estimate_size().is_err());
+        assert!(Value::Wildcard.estimate_size().is_err());
+        assert_eq!(Value::Infinity.particle_type(), ParticleType::NULL);
+        let filter = crate::as_range!("bin", Value::Wildcard, Value::Infinity);
+        assert!(filter.estimate_size().is_err());
+
+        let list = as_list!("key", Value::Wildcard, Value::Infinity);
+        let mut buf = Buffer::new(1024);
+        let size = encoder::pack_value(&mut None, &list).unwrap();
+        buf.resize_buffer(size).unwrap();
+        encoder::pack_value(&mut Some(&mut buf), &list).unwrap();
+        assert_eq!(&buf.data_buffer[6..], &[0xd4, 0xff, 0x00, 0xd4, 0xff, 0x01]);
+
+        buf.reset_offset().unwrap();
+        assert_eq!(decoder::unpack_value_list(&mut buf).unwrap(), list);
+    }
+
+    #[test]
+    fn collation_order() {
+        let sorted = vec![
+            Value::Nil,
+            as_val!(false),
+            as_val!(true),
+            as_val!(-1),
+            Value::UInt(0),
+            as_val!(1),
+            Value::UInt(1),
+            Value::UInt(u64::max_value()),
+            as_val!(""),
+            as_val!("a"),
+            as_val!("ab"),
+            as_val!("b"),
+            as_list!(),
+            as_list!(1, 2),
+            as_list!(1, 2, 0),
+            as_list!(2),
+            as_map!(),
+            as_map!("z" => 1),
+            as_map!("a" => 1, "b" => 1),
+            as_map!("a" => 2, "b" => 1),
+            as_blob!(vec![]),
+            as_blob!(vec![1]),
+            Value::HLL(vec![0]),
+            as_val!(f64::NEG_INFINITY),
+            as_val!(-0.5),
+            as_val!(1.5f32),
+            as_val!(1.5),
+            as_val!(f64::NAN),
+            as_geo!("{}"),
+            Value::Wildcard,
+            Value::Infinity,
+        ];
+
+        let mut values = sorted.clone();
+        values.reverse();
+        values.sort();
+        assert_eq!(values, sorted);
+
+        for (i, val) in sorted.iter().enumerate() {
+            assert_eq!(sorted.binary_search(val), Ok(i));
+        }
+    }
+
+    #[test]
+    fn map_order() {
+        use std::cmp::Ordering;
+        use std::collections::BTreeMap;
+
+        let mut btree = BTreeMap::new();
+        btree.insert(as_val!("b"), as_val!(2));
+        btree.insert(as_val!(1), as_val!(1));
+        btree.insert(as_val!("a"), as_val!(3));
+        let ordered = Value::from(btree);
+        assert_eq!(
+            ordered,
+            Value::OrderedMap(vec![
+                (as_val!(1), as_val!(1)),
+                (as_val!("a"), as_val!(3)),
+                (as_val!("b"), as_val!(2)),
+            ])
+        );
+
+        let map = as_map!("b" => 2, 1 => 1, "a" => 3);
+        assert_eq!(map.cmp(&map.clone()), Ordering::Equal);
+        assert_eq!(map.cmp(&ordered), Ordering::Less);
+        assert_eq!(ordered.cmp(&map), Ordering::Greater);
+        assert_eq!(ordered.particle_type(), ParticleType::MAP);
+        assert_eq!(
+            BTreeMap::<String, i64>::try_from(as_map!("b" => 2, "a" => 1))
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            vec![("a".to_string(), 1), ("b".to_string(), 2)]
+        );
+    }
+
+    #[test]
+    fn map_encoding() {
+        use crate::commands::buffer::Buffer;
+        use crate::msgpack::{decoder, encoder};
+
+        fn encode(val: &Value) -> Vec<u8> {
+            let mut buf = Buffer::new(1024);
+            let size = encoder::pack_value(&mut None, val).unwrap();
+            buf.resize_buffer(size).unwrap();
+            encoder::pack_value(&mut Some(&mut buf), val).unwrap();
+            buf.data_buffer
+        }
+
+        let map = as_map!(1 => 0);
+        assert_eq!(encode(&map), vec![0x81, 1, 0]);
+
+        // ordered maps are written as K-ordered maps, whatever the order of their entries
+        let ordered = Value::OrderedMap(vec![(as_val!(2), as_val!(0)), (as_val!(1), as_val!(0))]);
+        let bytes = encode(&ordered);
+        assert_eq!(bytes, vec![0x83, 0xc7, 0, 1, 0xc0, 1, 0, 2, 0]);
+        assert_eq!(ordered.estimate_size().unwrap(), bytes.len());
+
+        let mut buf = Buffer::new(1024);
+        buf.data_buffer = bytes;
+        assert_eq!(
+            decoder::unpack_value_map(&mut buf).unwrap(),
+            as_map!(1 => 0, 2 => 0)
+        );
+    }
+
+    #[test]
+    fn try_from() {
+        assert_eq!(i64::try_from(as_val!(-1)).unwrap(), -1);
+        assert_eq!(u8::try_from(as_val!(255)).unwrap(), 255);
+        assert_eq!(
+            u64::try_from(Value::UInt(u64::max_value())).unwrap(),
+            u64::max_value()
+        );
+        assert!(bool::try_from(as_val!(true)).unwrap());
+        assert_eq!(f64::try_from(as_val!(1.5)).unwrap(), 1.5);
+        assert_eq!(f32::try_from(as_val!(1.5f32)).unwrap(), 1.5);
+        assert_eq!(
+            FloatValue::try_from(as_val!(1.5)).unwrap(),
+            FloatValue::from(1.5)
+        );
+        assert_eq!(String::try_from(as_val!("str")).unwrap(), "str");
+        assert_eq!(String::try_from(as_geo!("{}")).unwrap(), "{}");
+        assert_eq!(
+            Vec::<u8>::try_from(as_blob!(vec![1, 2])).unwrap(),
+            vec![1, 2]
+        );
+        assert_eq!(
+            Vec::<String>::try_from(as_list!("a", "b")).unwrap(),
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            Vec::<Value>::try_from(as_list!(1, "b")).unwrap(),
+            vec![as_val!(1), as_val!("b")]
+        );
+        assert_eq!(
+            Vec::<Vec<i32>>::try_from(as_list!(as_list!(1), as_list!())).unwrap(),
+            vec![vec![1], vec![]]
+        );
+
+        let map: HashMap<String, i64> = HashMap::try_from(as_map!("a" => 1)).unwrap();
+        assert_eq!(map["a"], 1);
+        let ordered = Value::OrderedMap(vec![(as_val!(1), as_val!(true))]);
+        let map: HashMap<u8, bool> = HashMap::try_from(ordered).unwrap();
+        assert!(map[&1]);
+
+        assert_eq!(Option::<i64>::try_from(Value::Nil).unwrap(), None);
+        assert_eq!(Option::<i64>::try_from(as_val!(1)).unwrap(), Some(1));
+        assert!(Option::<i64>::try_from(as_val!("1")).is_err());
+    }
+
+    #[test]
+    fn try_from_errors() {
+        let err = i64::try_from(as_val!("1")).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("i64".to_string(), ParticleType::STRING))
+        );
+        let err = String::try_from(as_list!()).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("String".to_string(), ParticleType::LIST))
+        );
+        let err = Vec::<i64>::try_from(as_list!(1, 2.5)).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("i64".to_string(), ParticleType::FLOAT))
+        );
+        let err = HashMap::<String, i64>::try_from(Value::Nil).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("map".to_string(), ParticleType::NULL))
+        );
+        let err = bool::try_from(Value::UInt(1)).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("bool".to_string(), ParticleType::INTEGER))
+        );
+
+        // out of range
+        assert!(u8::try_from(as_val!(256)).is_err());
+        assert!(u32::try_from(as_val!(-1)).is_err());
+        assert!(i64::try_from(Value::UInt(u64::max_value())).is_err());
+        assert!(i64::try_from(Value::Infinity).is_err());
+    }
+
+    #[test]
+    fn system_time() {
+        for time in &[
+            UNIX_EPOCH,
+            UNIX_EPOCH + Duration::new(1_600_000_000, 123_456_789),
+            UNIX_EPOCH - Duration::new(1_000, 1),
+        ] {
+            let value = Value::from(*time);
+            assert_eq!(SystemTime::try_from(value).unwrap(), *time);
+        }
+        assert_eq!(
+            Value::from(UNIX_EPOCH + Duration::from_millis(1)),
+            as_val!(1_000_000)
+        );
+        assert!(SystemTime::try_from(as_val!("now")).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let time = Utc.timestamp_nanos(1_600_000_000_123_456_789);
+        assert_eq!(Value::from(time), as_val!(1_600_000_000_123_456_789i64));
+        assert_eq!(DateTime::<Utc>::try_from(Value::from(time)).unwrap(), time);
+        let time = Utc.timestamp_nanos(-1_500_000_000);
+        assert_eq!(DateTime::<Utc>::try_from(Value::from(time)).unwrap(), time);
+    }
+
+    #[test]
+    #[cfg(feature = "serde_json")]
+    fn json() {
+        let doc: serde_json::Value = serde_json::from_str(
+            r#"{"name": "Alice", "tags": ["a", 1, 2.5, true, null], "big": 18446744073709551615}"#,
+        )
+        .unwrap();
+        let val = Value::from(doc.clone());
+        assert_eq!(
+            val,
+            as_map!(
+                "name" => "Alice",
+                "tags" => as_list!("a", 1, 2.5, true, Value::Nil),
+                "big" => Value::UInt(u64::max_value())
+            )
+        );
+        assert_eq!(serde_json::Value::try_from(val).unwrap(), doc);
+
+        let ordered = Value::OrderedMap(vec![(as_val!("a"), as_val!(1))]);
+        assert_eq!(
+            serde_json::Value::try_from(ordered).unwrap(),
+            serde_json::json!({"a": 1})
+        );
+        let err = serde_json::Value::try_from(as_map!(1 => "one")).unwrap_err();
+        assert_eq!(
+            invalid_type(err),
+            Some(("string map key".to_string(), ParticleType::INTEGER))
+        );
+        assert!(serde_json::Value::try_from(as_blob!(vec![1u8])).is_err());
+        assert!(serde_json::Value::try_from(as_val!(f64::NAN)).is_err());
+    }
+
     #[test]
     fn as_geo() {
         let string = String::from(r#"{"type":"Point"}"#);
@@ -1681,4 +2199,21 @@ mod tests {
         // We only check for the len of the String because HashMap serialization does not keep the key order. Comparing like the list above is not possible.
         assert_eq!(json.unwrap().len(), 48, "Map Serialization failed");
     }
+
+    #[test]
+    #[cfg(feature = "serialization")]
+    fn deserialize_map_keys() {
+        // {{1: 2}: 3} and [{1: 2}]: 3 in CBOR
+        for bytes in &[
+            &[0xa1, 0xa1, 0x01, 0x02, 0x03][..],
+            &[0xa1, 0x81, 0xa1, 0x01, 0x02, 0x03],
+        ] {
+            let err = serde_cbor::from_slice::<Value>(bytes).unwrap_err();
+            assert!(err.to_string().contains("invalid map key"), "{}", err);
+        }
+
+        // a map header claiming 2^32 - 1 entries
+        let bytes = [0xba, 0xff, 0xff, 0xff, 0xff, 0x01, 0x02];
+        assert!(serde_cbor::from_slice::<Value>(&bytes).is_err());
+    }
 }
@@ -18,7 +18,12 @@ extern crate env_logger;
 extern crate lazy_static;
 extern crate rand;
 
-use aerospike::Client;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use aerospike::{
+    as_key, Bins, Client, CommandContext, CommandType, ErrorKind, Interceptor, ReadPolicy, Result,
+};
 
 mod common;
 
@@ -64,3 +69,87 @@ fn close() {
         assert!(false, "Failed to close client");
     }
 }
+
+#[test]
+fn stats() {
+    let client = Client::new(common::client_policy(), &common::hosts()).unwrap();
+    let key = as_key!(common::namespace(), "test", common::rand_str(10));
+    let _ = client.get(&ReadPolicy::default(), &key, Bins::None);
+
+    let stats = client.stats();
+    assert!(stats.tend_count > 0);
+    assert_eq!(stats.nodes.len(), client.nodes().len());
+    let commands: usize = stats.nodes.iter().map(|node| node.commands).sum();
+    assert!(commands >= 1);
+    for node in &stats.nodes {
+        assert_eq!(
+            node.open_connections,
+            node.idle_connections + node.in_use_connections
+        );
+        assert!(node.connections_opened >= node.open_connections);
+    }
+
+    client.close().unwrap();
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn metrics_text() {
+    let client = Client::new(common::client_policy(), &common::hosts()).unwrap();
+    let key = as_key!(common::namespace(), "test", common::rand_str(10));
+    let _ = client.get(&ReadPolicy::default(), &key, Bins::None);
+
+    let text = client.metrics_text();
+    assert!(text.contains("# TYPE aerospike_client_command_duration_seconds histogram"));
+    assert!(text.contains(&format!(
+        "command=\"get\",namespace=\"{}\"",
+        common::namespace()
+    )));
+    assert!(text.ends_with("# EOF\n"));
+
+    client.close().unwrap();
+}
+
+#[derive(Debug, Default)]
+struct CountingInterceptor {
+    before: AtomicUsize,
+    after: AtomicUsize,
+}
+
+impl Interceptor for CountingInterceptor {
+    fn before_command(&self, ctx: &mut CommandContext) -> Result<()> {
+        self.before.fetch_add(1, Ordering::Relaxed);
+        if ctx.namespace() == "restricted" {
+            return Err(ErrorKind::InvalidArgument("Namespace not allowed".to_string()).into());
+        }
+        Ok(())
+    }
+
+    fn after_command(&self, ctx: &CommandContext, _result: &Result<()>) {
+        assert_eq!(ctx.command(), CommandType::Get);
+        assert!(ctx.key().is_some());
+        self.after.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn interceptors() {
+    let interceptor = Arc::new(CountingInterceptor::default());
+    let policy = &mut common::client_policy().clone();
+    policy.interceptors.push(interceptor.clone());
+    let client = Client::new(policy, &common::hosts()).unwrap();
+
+    let key = as_key!(common::namespace(), "test", common::rand_str(10));
+    let _ = client.get(&ReadPolicy::default(), &key, Bins::None);
+    assert_eq!(interceptor.before.load(Ordering::Relaxed), 1);
+    assert_eq!(interceptor.after.load(Ordering::Relaxed), 1);
+
+    let key = as_key!("restricted", "test", common::rand_str(10));
+    let err = client
+        .get(&ReadPolicy::default(), &key, Bins::None)
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::InvalidArgument(_)));
+    assert_eq!(interceptor.after.load(Ordering::Relaxed), 1);
+
+    client.close().unwrap();
+}
@@ -0,0 +1,204 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Error handling tests using fault injection against the in-process mock cluster.
+#![cfg(feature = "testing")]
+
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use aerospike::testing::{Fault, FaultInjector, MockServer};
+use aerospike::{
+    as_bin, as_key, BatchPolicy, BatchRead, Bins, Client, ClientPolicy, Error, ErrorKind, Key,
+    ReadPolicy, ResultCode, ScanPolicy, WritePolicy,
+};
+
+struct Setup {
+    server: MockServer,
+    injector: Arc<FaultInjector>,
+    client: Client,
+    key: Key,
+}
+
+impl Setup {
+    fn new() -> Self {
+        let server = MockServer::start(1).unwrap();
+        let injector = Arc::new(FaultInjector::new());
+        let mut policy = ClientPolicy::default();
+        policy.fault_injector = Some(injector.clone());
+        let client = Client::new(&policy, &server.hosts()).unwrap();
+
+        let key = as_key!("test", "test", 1);
+        client
+            .put(&WritePolicy::default(), &key, &[as_bin!("a", 1)])
+            .unwrap();
+        Setup {
+            server,
+            injector,
+            client,
+            key,
+        }
+    }
+
+    fn inject_once(&self, fault: Fault) {
+        self.injector.inject_times(self.server.address(0), fault, 1);
+    }
+
+    fn get(&self) -> aerospike::Result<()> {
+        self.get_with(&ReadPolicy::default())
+    }
+
+    fn get_with(&self, policy: &ReadPolicy) -> aerospike::Result<()> {
+        self.client.get(policy, &self.key, Bins::All).map(|_| ())
+    }
+}
+
+fn io_error(err: &Error) -> Option<io::ErrorKind> {
+    match err.kind() {
+        ErrorKind::Io(err) => Some(err.kind()),
+        _ => None,
+    }
+}
+
+fn server_error(err: &Error) -> Option<ResultCode> {
+    match err.kind() {
+        ErrorKind::ServerError(rc) => Some(*rc),
+        _ => None,
+    }
+}
+
+#[test]
+fn latency() {
+    let setup = Setup::new();
+    setup.inject_once(Fault::Latency(Duration::from_millis(100)));
+
+    let start = Instant::now();
+    setup.get().unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    assert_eq!(setup.injector.injected(setup.server.address(0)), 1);
+}
+
+#[test]
+fn dropped_connection() {
+    let setup = Setup::new();
+    setup.inject_once(Fault::DropConnection(10));
+
+    let err = setup.get().unwrap_err();
+    assert_eq!(io_error(&err), Some(io::ErrorKind::ConnectionReset));
+
+    // the broken connection is discarded
+    setup.get().unwrap();
+}
+
+#[test]
+fn truncated_frame() {
+    let setup = Setup::new();
+    setup.inject_once(Fault::TruncateFrame(20));
+
+    let err = setup.get().unwrap_err();
+    assert_eq!(io_error(&err), Some(io::ErrorKind::UnexpectedEof));
+    setup.get().unwrap();
+}
+
+#[test]
+fn stalled_read() {
+    let setup = Setup::new();
+    setup.inject_once(Fault::StallRead(0));
+
+    let mut policy = ReadPolicy::default();
+    policy.timeout = Some(Duration::from_millis(100));
+    let start = Instant::now();
+    let err = setup.get_with(&policy).unwrap_err();
+    assert_eq!(io_error(&err), Some(io::ErrorKind::WouldBlock));
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    setup.get_with(&policy).unwrap();
+}
+
+#[test]
+fn result_code() {
+    let setup = Setup::new();
+    setup.inject_once(Fault::ResultCode(9));
+
+    let err = setup.get().unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::Timeout));
+    setup.get().unwrap();
+}
+
+#[test]
+fn persistent_faults() {
+    let setup = Setup::new();
+    let address = setup.server.address(0);
+    setup.injector.inject(address, Fault::ResultCode(9));
+
+    for _ in 0..3 {
+        assert!(setup.get().is_err());
+    }
+    assert_eq!(setup.injector.injected(address), 3);
+
+    setup.injector.clear(address);
+    setup.get().unwrap();
+}
+
+#[test]
+fn batch_read() {
+    let setup = Setup::new();
+    let bins = Bins::All;
+    let batch = || vec![BatchRead::new(setup.key.clone(), &bins)];
+
+    setup.inject_once(Fault::ResultCode(9));
+    let err = setup
+        .client
+        .batch_get(&BatchPolicy::default(), batch())
+        .err()
+        .unwrap();
+    assert_eq!(server_error(&err), Some(ResultCode::Timeout));
+
+    setup.inject_once(Fault::DropConnection(30));
+    let err = setup
+        .client
+        .batch_get(&BatchPolicy::default(), batch())
+        .err()
+        .unwrap();
+    assert_eq!(io_error(&err), Some(io::ErrorKind::ConnectionReset));
+
+    let results = setup
+        .client
+        .batch_get(&BatchPolicy::default(), batch())
+        .unwrap();
+    assert!(results[0].record.is_some());
+}
+
+#[test]
+fn scan() {
+    let setup = Setup::new();
+
+    for fault in &[Fault::ResultCode(9), Fault::DropConnection(0)] {
+        setup.inject_once(fault.clone());
+        let rs = setup
+            .client
+            .scan(&ScanPolicy::default(), "test", "test", Bins::All)
+            .unwrap();
+        let results: Vec<_> = (&*rs).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    let rs = setup
+        .client
+        .scan(&ScanPolicy::default(), "test", "test", Bins::All)
+        .unwrap();
+    assert_eq!((&*rs).map(Result::unwrap).count(), 1);
+}
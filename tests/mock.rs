@@ -0,0 +1,469 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Tests against the in-process mock cluster; these do not need a live server.
+#![cfg(feature = "testing")]
+
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use aerospike::expressions::{int_bin, int_val, num_add, num_mul};
+use aerospike::operations;
+use aerospike::operations::exp::ExpWriteFlags;
+use aerospike::testing::{MockConfig, MockServer};
+use aerospike::{
+    as_bin, as_key, as_list, as_val, BatchPolicy, BatchRead, Bin, Bins, Client, ClientPolicy,
+    CommandContext, ErrorKind, Expiration, GenerationPolicy, Interceptor, OwnedBin, ReadPolicy,
+    RecordExistsAction, ResultCode, ScanPolicy, WritePolicy,
+};
+
+fn client(server: &MockServer) -> Client {
+    let mut policy = ClientPolicy::default();
+    policy.tend_interval = Duration::from_millis(10);
+    Client::new(&policy, &server.hosts()).unwrap()
+}
+
+fn server_error(err: &aerospike::Error) -> Option<ResultCode> {
+    match err.kind() {
+        ErrorKind::ServerError(rc) => Some(*rc),
+        _ => None,
+    }
+}
+
+#[test]
+fn crud() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let rpolicy = ReadPolicy::default();
+    let key = as_key!("test", "test", "crud");
+
+    client
+        .put(&wpolicy, &key, &[as_bin!("s", "a"), as_bin!("i", 1)])
+        .unwrap();
+    client.append(&wpolicy, &key, &[as_bin!("s", "b")]).unwrap();
+    client.add(&wpolicy, &key, &[as_bin!("i", 2)]).unwrap();
+
+    let record = client.get(&rpolicy, &key, Bins::All).unwrap();
+    assert_eq!(record.bins.get("s"), Some(&as_val!("ab")));
+    assert_eq!(record.bins.get("i"), Some(&as_val!(3)));
+    assert_eq!(record.generation, 3);
+    assert_eq!(record.time_to_live(), None);
+
+    let record = client.get(&rpolicy, &key, ["i"]).unwrap();
+    assert_eq!(record.bins.len(), 1);
+
+    assert!(client.exists(&wpolicy, &key).unwrap());
+    assert!(client.delete(&wpolicy, &key).unwrap());
+    assert!(!client.delete(&wpolicy, &key).unwrap());
+    assert!(!client.exists(&wpolicy, &key).unwrap());
+
+    let err = client.get(&rpolicy, &key, Bins::All).unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::KeyNotFoundError));
+}
+
+#[test]
+fn owned_bins() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "owned_bins");
+
+    let bins: Vec<OwnedBin> = (0..3)
+        .map(|i| Bin::new(format!("bin{}", i), as_val!(i)))
+        .collect();
+    client.put(&wpolicy, &key, &bins).unwrap();
+    let record = client.get(&ReadPolicy::default(), &key, Bins::All).unwrap();
+    assert_eq!(record.bins.get("bin2"), Some(&as_val!(2)));
+
+    // bins built from the public fields skip the check in `Bin::new`
+    let bin = Bin {
+        name: "a_very_long_bin_name".into(),
+        value: as_val!(1),
+    };
+    let err = client.put(&wpolicy, &key, &[bin]).unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::InvalidArgument(_)));
+}
+
+#[test]
+fn write_policies() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let key = as_key!("test", "test", "policies");
+    let bins = [as_bin!("a", 1)];
+
+    let mut wpolicy = WritePolicy::default();
+    wpolicy.record_exists_action = RecordExistsAction::UpdateOnly;
+    let err = client.put(&wpolicy, &key, &bins).unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::KeyNotFoundError));
+
+    wpolicy.record_exists_action = RecordExistsAction::CreateOnly;
+    wpolicy.expiration = Expiration::Seconds(100);
+    client.put(&wpolicy, &key, &bins).unwrap();
+    let err = client.put(&wpolicy, &key, &bins).unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::KeyExistsError));
+
+    let record = client
+        .get(&ReadPolicy::default(), &key, Bins::None)
+        .unwrap();
+    let ttl = record.time_to_live().unwrap();
+    assert!(ttl <= Duration::from_secs(100) && ttl > Duration::from_secs(90));
+
+    let mut wpolicy = WritePolicy::default();
+    wpolicy.generation_policy = GenerationPolicy::ExpectGenEqual;
+    wpolicy.generation = 5;
+    let err = client.put(&wpolicy, &key, &bins).unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::GenerationError));
+    wpolicy.generation = 1;
+    client.put(&wpolicy, &key, &bins).unwrap();
+}
+
+#[test]
+fn operate() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "operate");
+
+    let a = as_bin!("a", 1);
+    let b = as_bin!("b", as_list!(1, "two"));
+    let ops = [
+        operations::put(&a),
+        operations::put(&b),
+        operations::add(&a),
+        operations::get_bin("a"),
+    ];
+    let record = client.operate(&wpolicy, &key, &ops).unwrap();
+    assert_eq!(record.bins.get("a"), Some(&as_val!(2)));
+    assert_eq!(record.bins.len(), 1);
+
+    let record = client
+        .operate(&wpolicy, &key, &[operations::touch(), operations::get()])
+        .unwrap();
+    assert_eq!(record.bins.get("b"), Some(&as_list!(1, "two")));
+    assert_eq!(record.generation, 2);
+}
+
+#[test]
+fn operate_typed() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "operate_typed");
+
+    let a = as_bin!("a", 1);
+    let ops = [
+        operations::put(&a),
+        operations::add(&a),
+        operations::get_bin("a"),
+        operations::get_bin("b"),
+    ];
+    let result = client.operate_typed(&wpolicy, &key, &ops).unwrap();
+    assert_eq!(result.results.len(), ops.len());
+    assert!(result.results[0].value.is_nil());
+    assert_eq!(result.results[2].bin, "a");
+    assert_eq!(result.results[2].as_int().unwrap(), 2);
+    assert!(result.results[3].value.is_nil());
+    assert_eq!(result.record.generation, 1);
+
+    let err = client
+        .operate_typed(&wpolicy, &key, &[operations::get()])
+        .unwrap_err();
+    match err.kind() {
+        ErrorKind::InvalidArgument(_) => (),
+        kind => panic!("unexpected error: {:?}", kind),
+    }
+}
+
+#[test]
+fn expression_helpers() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "expressions");
+    let bins = [as_bin!("a", 1), as_bin!("b", 2), as_bin!("age", 20)];
+    client.put(&wpolicy, &key, &bins).unwrap();
+
+    let total = || num_add(vec![int_bin("a".to_string()), int_bin("b".to_string())]);
+    let exps = [
+        ("total", total()),
+        (
+            "months",
+            num_mul(vec![int_bin("age".to_string()), int_val(12)]),
+        ),
+    ];
+    let results = client.get_with_expressions(&wpolicy, &key, &exps).unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results["total"], as_val!(3));
+    assert_eq!(results["months"], as_val!(240));
+
+    let exps = [("total", total()), ("total", int_val(1))];
+    match client.get_with_expressions(&wpolicy, &key, &exps) {
+        Err(err) => match err.kind() {
+            ErrorKind::InvalidArgument(_) => (),
+            kind => panic!("unexpected error: {:?}", kind),
+        },
+        Ok(_) => panic!("duplicate expression name accepted"),
+    }
+
+    client
+        .put_computed(&wpolicy, &key, &[("sum", total())], ExpWriteFlags::Default)
+        .unwrap();
+    let record = client.get(&ReadPolicy::default(), &key, ["sum"]).unwrap();
+    assert_eq!(record.bins["sum"], as_val!(3));
+
+    let err = client
+        .put_computed(
+            &wpolicy,
+            &key,
+            &[("sum", int_val(0))],
+            ExpWriteFlags::CreateOnly,
+        )
+        .unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::BinExistsError));
+    let err = client
+        .put_computed(
+            &wpolicy,
+            &key,
+            &[("new", int_val(0))],
+            ExpWriteFlags::UpdateOnly,
+        )
+        .unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::BinNotFound));
+    let record = client.get(&ReadPolicy::default(), &key, Bins::All).unwrap();
+    assert_eq!(record.bins["sum"], as_val!(3));
+    assert!(!record.bins.contains_key("new"));
+}
+
+#[test]
+fn command_error_stats() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "errors");
+
+    client.put(&wpolicy, &key, &[as_bin!("a", "str")]).unwrap();
+    let err = client.add(&wpolicy, &key, &[as_bin!("a", 1)]).unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::BinTypeError));
+    let missing = as_key!("test", "test", "missing");
+    let err = client
+        .get(&ReadPolicy::default(), &missing, Bins::All)
+        .unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::KeyNotFoundError));
+
+    let mut gen_policy = WritePolicy::default();
+    gen_policy.generation_policy = GenerationPolicy::ExpectGenEqual;
+    gen_policy.generation = 99;
+    let err = client
+        .put(&gen_policy, &key, &[as_bin!("a", 1)])
+        .unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::GenerationError));
+
+    // only the bin type error counts; misses and generation mismatches are normal outcomes
+    let stats = client.stats();
+    assert_eq!(stats.nodes[0].commands, 4);
+    assert_eq!(stats.nodes[0].command_errors, 1);
+    assert_eq!(stats.nodes[0].command_timeouts, 0);
+}
+
+#[test]
+#[should_panic(expected = "Namespace other is not served by the mock cluster")]
+fn partition_owner_unknown_namespace() {
+    let server = MockServer::start(1).unwrap();
+    server.partition_owner("other", 0);
+}
+
+#[test]
+fn routing() {
+    let server = MockServer::start(3).unwrap();
+    let client = client(&server);
+    assert_eq!(client.nodes().len(), 3);
+
+    let wpolicy = WritePolicy::default();
+    let mut expected = [0; 3];
+    for i in 0..30 {
+        let key = as_key!("test", "test", i);
+        client.put(&wpolicy, &key, &[as_bin!("i", i)]).unwrap();
+        expected[server.owner_of(&key)] += 1;
+    }
+    for (node, &count) in expected.iter().enumerate() {
+        assert_eq!(server.commands(node), count);
+    }
+    assert_eq!(server.record_count("test"), 30);
+}
+
+#[test]
+fn partition_migration() {
+    let server = MockServer::start(2).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let key = as_key!("test", "test", "migrate");
+
+    let owner = server.owner_of(&key);
+    let other = 1 - owner;
+    client.put(&wpolicy, &key, &[as_bin!("a", 1)]).unwrap();
+    assert_eq!(server.commands(owner), 1);
+
+    let digest = &key.digest;
+    let partition_id =
+        (u32::from_le_bytes([digest[0], digest[1], digest[2], digest[3]]) & 4095) as usize;
+    server.set_partition_owner("test", partition_id, other);
+    assert_eq!(server.owner_of(&key), other);
+
+    // wait for the client to pick up the new partition map
+    thread::sleep(Duration::from_millis(200));
+    client.put(&wpolicy, &key, &[as_bin!("a", 2)]).unwrap();
+    assert_eq!(server.commands(other), 1);
+}
+
+#[test]
+#[cfg(feature = "metrics")]
+fn metrics_node_label() {
+    let server = MockServer::start(2).unwrap();
+    let client = client(&server);
+    let key = as_key!("test", "test", "metrics");
+    client
+        .put(&WritePolicy::default(), &key, &[as_bin!("a", 1)])
+        .unwrap();
+
+    let node = &server.node_names()[server.owner_of(&key)];
+    let labels = format!(
+        "command=\"put\",namespace=\"test\",node=\"{}\",result=\"Ok\"",
+        node
+    );
+    assert!(client.metrics_text().contains(&labels));
+}
+
+#[test]
+fn batch_get() {
+    let server = MockServer::start(2).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    let bins = Bins::All;
+
+    let mut batch = vec![];
+    for i in 0..10 {
+        let key = as_key!("test", "test", i);
+        if i % 2 == 0 {
+            client.put(&wpolicy, &key, &[as_bin!("i", i)]).unwrap();
+        }
+        batch.push(BatchRead::new(key, &bins));
+    }
+
+    let results = client.batch_get(&BatchPolicy::default(), batch).unwrap();
+    for (i, result) in results.iter().enumerate() {
+        match result.record {
+            Some(ref record) => {
+                assert_eq!(i % 2, 0);
+                assert_eq!(record.bins.get("i"), Some(&as_val!(i as i64)));
+            }
+            None => assert_eq!(i % 2, 1),
+        }
+    }
+}
+
+#[test]
+fn scan() {
+    let config = MockConfig {
+        nodes: 3,
+        ..MockConfig::default()
+    };
+    let server = MockServer::start_with(config).unwrap();
+    let client = client(&server);
+    let wpolicy = WritePolicy::default();
+    for i in 0..100 {
+        let set_name = if i < 60 { "a" } else { "b" };
+        let key = as_key!("test", set_name, i);
+        client.put(&wpolicy, &key, &[as_bin!("i", i)]).unwrap();
+    }
+
+    let rs = client
+        .scan(&ScanPolicy::default(), "test", "a", Bins::All)
+        .unwrap();
+    let count = (&*rs).map(Result::unwrap).count();
+    assert_eq!(count, 60);
+
+    let rs = client
+        .scan(&ScanPolicy::default(), "test", "", Bins::None)
+        .unwrap();
+    for record in &*rs {
+        assert!(record.unwrap().bins.is_empty());
+    }
+}
+
+#[test]
+fn invalid_namespace() {
+    let server = MockServer::start(1).unwrap();
+    let client = client(&server);
+    let key = as_key!("other", "test", 1);
+    let err = client
+        .put(&WritePolicy::default(), &key, &[as_bin!("a", 1)])
+        .unwrap_err();
+    assert_eq!(server_error(&err), Some(ResultCode::InvalidNamespace));
+}
+
+#[derive(Debug)]
+struct Tenants;
+
+impl Interceptor for Tenants {
+    fn before_command(&self, ctx: &mut CommandContext) -> aerospike::Result<()> {
+        if ctx.namespace() == "tenant" {
+            ctx.set_namespace("test");
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn interceptor_namespace_rewrite() {
+    let server = MockServer::start(2).unwrap();
+    let mut policy = ClientPolicy::default();
+    policy.tend_interval = Duration::from_millis(10);
+    policy.interceptors.push(Arc::new(Tenants));
+    let client = Client::new(&policy, &server.hosts()).unwrap();
+
+    let key = as_key!("tenant", "test", 1);
+    client
+        .put(&WritePolicy::default(), &key, &[as_bin!("a", 1)])
+        .unwrap();
+    let record = client.get(&ReadPolicy::default(), &key, Bins::All).unwrap();
+    assert_eq!(record.bins.get("a"), Some(&as_val!(1)));
+    assert_eq!(server.record_count("test"), 1);
+
+    let plain = Client::new(&ClientPolicy::default(), &server.hosts()).unwrap();
+    let key = as_key!("test", "test", 1);
+    let record = plain.get(&ReadPolicy::default(), &key, Bins::All).unwrap();
+    assert_eq!(record.bins.get("a"), Some(&as_val!(1)));
+
+    let err = client
+        .scan(&ScanPolicy::default(), "tenant", "test", Bins::All)
+        .and_then(|rs| (&*rs).next().unwrap().map(drop))
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::InvalidArgument(_)));
+}
+
+#[test]
+fn scan_errors_on_full_queue() {
+    let server = MockServer::start(3).unwrap();
+    let client = client(&server);
+    let mut policy = ScanPolicy::default();
+    policy.record_queue_size = 1;
+
+    let rs = client.scan(&policy, "other", "test", Bins::All).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    let errors = (&*rs).filter(Result::is_err).count();
+    assert_eq!(errors, 3);
+}
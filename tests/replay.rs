@@ -0,0 +1,125 @@
+// Copyright 2015-2020 Aerospike, Inc.
+//
+// Portions may be licensed to Aerospike, Inc. under one or more contributor
+// license agreements.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not
+// use this file except in compliance with the License. You may obtain a copy of
+// the License at http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS, WITHOUT
+// WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the
+// License for the specific language governing permissions and limitations under
+// the License.
+
+// Record a session against the in-process mock cluster and replay it offline.
+#![cfg(feature = "testing")]
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+
+use aerospike::testing::{read_capture, MockServer, Recorder, ReplayServer};
+use aerospike::{
+    as_bin, as_key, as_list, as_map, as_val, operations, BatchPolicy, BatchRead, Bins, Client,
+    ClientPolicy, ReadPolicy, Record, ScanPolicy, WritePolicy,
+};
+
+// Runs a fixed sequence of commands and returns the records read.
+fn session(client: &Client) -> Vec<Option<Record>> {
+    let wpolicy = WritePolicy::default();
+    let rpolicy = ReadPolicy::default();
+    let key = as_key!("test", "test", "replay");
+    let mut records = vec![];
+
+    records.push(client.get(&rpolicy, &key, Bins::All).ok());
+    let bins = [
+        as_bin!("i", 1),
+        as_bin!("s", "string"),
+        as_bin!("f", 1.5),
+        as_bin!("l", as_list!(1, "two", 3.0)),
+        as_bin!("m", as_map!("k" => 1)),
+    ];
+    client.put(&wpolicy, &key, &bins).unwrap();
+    records.push(client.get(&rpolicy, &key, Bins::All).ok());
+
+    let i = as_bin!("i", 2);
+    let ops = [operations::add(&i), operations::get()];
+    records.push(client.operate(&wpolicy, &key, &ops).ok());
+
+    let all = Bins::All;
+    let batch = vec![
+        BatchRead::new(key.clone(), &all),
+        BatchRead::new(as_key!("test", "test", "missing"), &all),
+    ];
+    let results = client.batch_get(&BatchPolicy::default(), batch).unwrap();
+    records.extend(results.into_iter().map(|batch_read| batch_read.record));
+
+    let rs = client
+        .scan(&ScanPolicy::default(), "test", "test", Bins::All)
+        .unwrap();
+    records.extend((&*rs).map(Result::ok));
+    records
+}
+
+fn capture_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("aerospike-{}-{}.rec", name, process::id()))
+}
+
+#[test]
+fn record_and_replay() {
+    let path = capture_path("session");
+
+    let recorded = {
+        let server = MockServer::start(1).unwrap();
+        let mut policy = ClientPolicy::default();
+        policy.recorder = Some(Arc::new(Recorder::create(&path).unwrap()));
+        let client = Client::new(&policy, &server.hosts()).unwrap();
+        session(&client)
+    };
+    assert!(recorded[0].is_none());
+    assert!(recorded[1].is_some());
+
+    let exchanges = read_capture(&path).unwrap();
+    assert!(exchanges.len() > recorded.len());
+
+    let server = ReplayServer::start(&path).unwrap();
+    let client = Client::new(&ClientPolicy::default(), &server.hosts()).unwrap();
+    let replayed = session(&client);
+    assert_eq!(server.unmatched(), 0);
+
+    assert_eq!(recorded.len(), replayed.len());
+    for (recorded, replayed) in recorded.iter().zip(replayed.iter()) {
+        match (recorded, replayed) {
+            (Some(recorded), Some(replayed)) => {
+                assert_eq!(recorded.bins, replayed.bins);
+                assert_eq!(recorded.generation, replayed.generation);
+            }
+            (None, None) => (),
+            _ => panic!("replayed {:?}, recorded {:?}", replayed, recorded),
+        }
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn unmatched_request() {
+    let path = capture_path("unmatched");
+    {
+        let server = MockServer::start(1).unwrap();
+        let mut policy = ClientPolicy::default();
+        policy.recorder = Some(Arc::new(Recorder::create(&path).unwrap()));
+        let _client = Client::new(&policy, &server.hosts()).unwrap();
+    }
+
+    let server = ReplayServer::start(&path).unwrap();
+    let client = Client::new(&ClientPolicy::default(), &server.hosts()).unwrap();
+    let key = as_key!("test", "test", 1);
+    assert!(client.get(&ReadPolicy::default(), &key, Bins::All).is_err());
+    assert_eq!(server.unmatched(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -88,7 +88,7 @@ fn query_single_consumer() {
         match res {
             Ok(rec) => {
                 count += 1;
-                let v: i64 = rec.bins["bin"].clone().into();
+                let v: i64 = rec.get("bin").unwrap();
                 assert!(v >= 0);
                 assert!(v < 10);
             }
@@ -150,7 +150,7 @@ fn query_multi_consumer() {
                 match res {
                     Ok(rec) => {
                         count.fetch_add(1, Ordering::Relaxed);
-                        let v: i64 = rec.bins["bin"].clone().into();
+                        let v: i64 = rec.get("bin").unwrap();
                         assert!(v >= 0);
                         assert!(v < 10);
                     }